@@ -1,7 +1,6 @@
 use cw1_whitelist::whitelist::{WhiteListContract, WhiteListExecute, WhiteListQuery};
 use schemars::JsonSchema;
 use std::fmt;
-use std::ops::{AddAssign, Sub};
 
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
@@ -19,7 +18,7 @@ use cw1_whitelist::{
 };
 use cw2::{get_contract_version, set_contract_version};
 use cw_storage_plus::Bound;
-use cw_utils::Expiration;
+use cw_utils::{Expiration, NativeBalance};
 use semver::Version;
 
 use crate::error::ContractError;
@@ -114,7 +113,9 @@ where
                         );
 
                         // Decrease allowance
-                        allowance.balance = allowance.balance.sub(amount.clone())?;
+                        allowance.balance = allowance
+                            .balance
+                            .checked_sub(&NativeBalance(amount.clone()))?;
                         Ok(allowance)
                     })?;
                 }
@@ -208,7 +209,7 @@ where
             return Err(ContractError::SettingExpiredAllowance(prev_expires));
         }
 
-        allowance.balance.add_assign(amount.clone());
+        allowance.balance = allowance.balance.checked_add(&amount.clone().into())?;
         Ok(allowance)
     })?;
 
@@ -368,9 +369,9 @@ fn can_execute(deps: Deps, env: Env, sender: String, msg: CosmosMsg) -> StdResul
             // now we check if there is enough allowance for this message
             let allowance = ALLOWANCES.may_load(deps.storage, &sender)?;
             match allowance {
-                // if there is an allowance, we subtract the requested amount to ensure it is covered (error on underflow)
+                // if there is an allowance, it must cover the requested amount
                 Some(allow) => {
-                    Ok(!allow.expires.is_expired(&env.block) && allow.balance.sub(amount).is_ok())
+                    Ok(!allow.expires.is_expired(&env.block) && allow.balance.has_all(&NativeBalance(amount)))
                 }
                 None => Ok(false),
             }
@@ -481,7 +482,6 @@ mod tests {
 
     use cw1_whitelist::msg::AdminListResponse;
     use cw2::{get_contract_version, ContractVersion};
-    use cw_utils::NativeBalance;
 
     use crate::state::Permissions;
 
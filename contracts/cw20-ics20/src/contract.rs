@@ -2,12 +2,11 @@
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
     from_binary, to_binary, Addr, Binary, Deps, DepsMut, Env, IbcMsg, IbcQuery, MessageInfo, Order,
-    PortIdResponse, Response, StdError, StdResult,
+    PortIdResponse, Response, StdResult,
 };
-use semver::Version;
 
-use cw2::{get_contract_version, set_contract_version};
-use cw20::{Cw20Coin, Cw20ReceiveMsg};
+use cw2::set_contract_version;
+use cw20::{Asset, AssetInfo, Cw20ReceiveMsg};
 use cw_storage_plus::Bound;
 
 use crate::amount::Amount;
@@ -20,9 +19,9 @@ use crate::msg::{
 };
 use crate::state::{
     increase_channel_balance, AllowInfo, Config, ADMIN, ALLOW_LIST, CHANNEL_INFO, CHANNEL_STATE,
-    CONFIG,
+    CONFIG, PAUSE, TRANSFER_ACTION,
 };
-use cw_utils::{maybe_addr, nonpayable, one_coin};
+use cw_utils::{maybe_addr, nonpayable, Migrations};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cw20-ics20";
@@ -66,14 +65,26 @@ pub fn execute(
     match msg {
         ExecuteMsg::Receive(msg) => execute_receive(deps, env, info, msg),
         ExecuteMsg::Transfer(msg) => {
-            let coin = one_coin(&info)?;
-            execute_transfer(deps, env, msg, Amount::Native(coin), info.sender)
+            let coin = Asset::from_funds(&info)?;
+            execute_transfer(deps, env, msg, coin, info.sender)
         }
         ExecuteMsg::Allow(allow) => execute_allow(deps, env, info, allow),
         ExecuteMsg::UpdateAdmin { admin } => {
             let admin = deps.api.addr_validate(&admin)?;
             Ok(ADMIN.execute_update_admin(deps, info, Some(admin))?)
         }
+        ExecuteMsg::Pause { action, expires } => {
+            if let Some(action) = action.as_deref().filter(|a| *a != TRANSFER_ACTION) {
+                return Err(ContractError::UnknownPauseAction {
+                    action: action.to_string(),
+                    expected: TRANSFER_ACTION.to_string(),
+                });
+            }
+            Ok(PAUSE.execute_pause(&ADMIN, deps, env, info, action, expires)?)
+        }
+        ExecuteMsg::Unpause { action } => {
+            Ok(PAUSE.execute_unpause(&ADMIN, deps, env, info, action)?)
+        }
     }
 }
 
@@ -86,10 +97,7 @@ pub fn execute_receive(
     nonpayable(&info)?;
 
     let msg: TransferMsg = from_binary(&wrapper.msg)?;
-    let amount = Amount::Cw20(Cw20Coin {
-        address: info.sender.to_string(),
-        amount: wrapper.amount,
-    });
+    let amount = Asset::from_cw20_receive(&info, &wrapper);
     let api = deps.api;
     execute_transfer(deps, env, msg, amount, api.addr_validate(&wrapper.sender)?)
 }
@@ -98,10 +106,11 @@ pub fn execute_transfer(
     deps: DepsMut,
     env: Env,
     msg: TransferMsg,
-    amount: Amount,
+    amount: Asset,
     sender: Addr,
 ) -> Result<Response, ContractError> {
-    if amount.is_empty() {
+    PAUSE.assert_not_paused(deps.storage, &env.block, TRANSFER_ACTION)?;
+    if amount.is_zero() {
         return Err(ContractError::NoFunds {});
     }
     // ensure the requested channel is registered
@@ -110,13 +119,12 @@ pub fn execute_transfer(
     }
     let config = CONFIG.load(deps.storage)?;
 
-    // if cw20 token, validate and ensure it is whitelisted, or we set default gas limit
-    if let Amount::Cw20(coin) = &amount {
-        let addr = deps.api.addr_validate(&coin.address)?;
+    // if cw20 token, ensure it is whitelisted, or we set default gas limit
+    if let AssetInfo::Cw20(addr) = &amount.info {
         // if limit is set, then we always allow cw20
         if config.default_gas_limit.is_none() {
             ALLOW_LIST
-                .may_load(deps.storage, &addr)?
+                .may_load(deps.storage, addr)?
                 .ok_or(ContractError::NotOnAllowList)?;
         }
     };
@@ -130,18 +138,14 @@ pub fn execute_transfer(
     let timeout = env.block.time.plus_seconds(timeout_delta);
 
     // build ics20 packet
-    let packet = Ics20Packet::new(
-        amount.amount(),
-        amount.denom(),
-        sender.as_ref(),
-        &msg.remote_address,
-    );
+    let denom = amount.info.to_string();
+    let packet = Ics20Packet::new(amount.amount, &denom, sender.as_ref(), &msg.remote_address);
     packet.validate()?;
 
     // Update the balance now (optimistically) like ibctransfer modules.
     // In on_packet_failure (ack with error message or a timeout), we reduce the balance appropriately.
     // This means the channel works fine if success acks are not relayed.
-    increase_channel_balance(deps.storage, &msg.channel, &amount.denom(), amount.amount())?;
+    increase_channel_balance(deps.storage, &msg.channel, &denom, amount.amount)?;
 
     // prepare ibc message
     let msg = IbcMsg::SendPacket {
@@ -203,51 +207,19 @@ pub fn execute_allow(
 }
 
 const MIGRATE_MIN_VERSION: &str = "0.11.1";
-const MIGRATE_VERSION_2: &str = "0.12.0-alpha1";
-// the new functionality starts in 0.13.1, this is the last release that needs to be migrated to v3
-const MIGRATE_VERSION_3: &str = "0.13.0";
+// anything older than 0.12.0-alpha2 uses the v1 format
+const MIGRATE_VERSION_2: &str = "0.12.0-alpha2";
+// the new functionality starts in 0.13.1, anything older needs to be migrated to v3
+const MIGRATE_VERSION_3: &str = "0.13.1";
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn migrate(mut deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
-    let version: Version = CONTRACT_VERSION.parse().map_err(from_semver)?;
-    let stored = get_contract_version(deps.storage)?;
-    let storage_version: Version = stored.version.parse().map_err(from_semver)?;
-
-    // First, ensure we are working from an equal or older version of this contract
-    // wrong type
-    if CONTRACT_NAME != stored.contract {
-        return Err(ContractError::CannotMigrate {
-            previous_contract: stored.contract,
-        });
-    }
-    // existing one is newer
-    if storage_version > version {
-        return Err(ContractError::CannotMigrateVersion {
-            previous_version: stored.version,
-        });
-    }
-
-    // Then, run the proper migration
-    if storage_version < MIGRATE_MIN_VERSION.parse().map_err(from_semver)? {
-        return Err(ContractError::CannotMigrateVersion {
-            previous_version: stored.version,
-        });
-    }
-    // run the v1->v2 converstion if we are v1 style
-    if storage_version <= MIGRATE_VERSION_2.parse().map_err(from_semver)? {
-        let old_config = v1::CONFIG.load(deps.storage)?;
-        ADMIN.set(deps.branch(), Some(old_config.gov_contract))?;
-        let config = Config {
-            default_timeout: old_config.default_timeout,
-            default_gas_limit: None,
-        };
-        CONFIG.save(deps.storage, &config)?;
-    }
-    // run the v2->v3 converstion if we are v2 style
-    if storage_version <= MIGRATE_VERSION_3.parse().map_err(from_semver)? {
-        v2::update_balances(deps.branch(), &env)?;
-    }
-    // otherwise no migration (yet) - add them here
+    // ensures we are working from an equal or older version of this contract,
+    // then runs the proper migrations and updates the version
+    Migrations::new()
+        .add_step(MIGRATE_MIN_VERSION, MIGRATE_VERSION_2, v1::migrate_config)
+        .add_step(MIGRATE_VERSION_2, MIGRATE_VERSION_3, v2::update_balances)
+        .run(deps.branch(), &env, CONTRACT_NAME, CONTRACT_VERSION)?;
 
     // always allow setting the default gas limit via MigrateMsg, even if same version
     // (Note this doesn't allow unsetting it now)
@@ -258,20 +230,11 @@ pub fn migrate(mut deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response,
         })?;
     }
 
-    // we don't need to save anything if migrating from the same version
-    if storage_version < version {
-        set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-    }
-
     Ok(Response::new())
 }
 
-fn from_semver(err: semver::Error) -> StdError {
-    StdError::generic_err(format!("Semver: {}", err))
-}
-
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Port {} => to_binary(&query_port(deps)?),
         QueryMsg::ListChannels {} => to_binary(&query_list(deps)?),
@@ -282,6 +245,7 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
             to_binary(&list_allowed(deps, start_after, limit)?)
         }
         QueryMsg::Admin {} => to_binary(&ADMIN.query_admin(deps)?),
+        QueryMsg::Paused {} => to_binary(&PAUSE.query_paused(deps, env)?),
     }
 }
 
@@ -307,11 +271,10 @@ pub fn query_channel(deps: Deps, id: String) -> StdResult<ChannelResponse> {
         .prefix(&id)
         .range(deps.storage, None, None, Order::Ascending)
         .map(|r| {
-            r.map(|(denom, v)| {
-                let outstanding = Amount::from_parts(denom.clone(), v.outstanding);
-                let total = Amount::from_parts(denom, v.total_sent);
-                (outstanding, total)
-            })
+            let (denom, v) = r?;
+            let outstanding = Amount::from_parts(denom.clone(), v.outstanding);
+            let total = Amount::from_parts(denom, v.total_sent);
+            Ok((outstanding, total))
         })
         .collect::<StdResult<Vec<_>>>()?;
     // we want (Vec<outstanding>, Vec<total>)
@@ -384,9 +347,11 @@ mod test {
 
     use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
     use cosmwasm_std::{coin, coins, CosmosMsg, IbcMsg, StdError, Uint128};
+    use cw_utils::MigrationError;
 
     use crate::state::ChannelState;
-    use cw_utils::PaymentError;
+    use cw_controllers::{AdminError, PausableError, PausedResponse};
+    use cw_utils::{Expiration, PaymentError};
 
     #[test]
     fn setup_and_query() {
@@ -482,6 +447,91 @@ mod test {
         );
     }
 
+    #[test]
+    fn paused_transfers() {
+        let send_channel = "channel-5";
+        let cw20_addr = "my-token";
+        let mut deps = setup(&[send_channel], &[(cw20_addr, 123456)]);
+
+        let transfer = TransferMsg {
+            channel: send_channel.to_string(),
+            remote_address: "foreign-address".to_string(),
+            timeout: None,
+        };
+        let native = ExecuteMsg::Transfer(transfer.clone());
+        let cw20 = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "my-account".into(),
+            amount: Uint128::new(888777666),
+            msg: to_binary(&transfer).unwrap(),
+        });
+
+        // only the admin can pause
+        let pause = ExecuteMsg::Pause {
+            action: Some(TRANSFER_ACTION.to_string()),
+            expires: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("foobar", &[]),
+            pause.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Pausable(AdminError::NotAdmin {}.into()));
+        execute(deps.as_mut(), mock_env(), mock_info("gov", &[]), pause).unwrap();
+
+        // both native and cw20 transfers are halted
+        let paused: ContractError = PausableError::ActionPaused {
+            action: TRANSFER_ACTION.to_string(),
+        }
+        .into();
+        let info = mock_info("foobar", &coins(1234567, "ucosm"));
+        let err = execute(deps.as_mut(), mock_env(), info, native.clone()).unwrap_err();
+        assert_eq!(err, paused);
+        let info = mock_info(cw20_addr, &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, cw20.clone()).unwrap_err();
+        assert_eq!(err, paused);
+        let res: PausedResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Paused {}).unwrap()).unwrap();
+        assert_eq!(res.actions.len(), 1);
+
+        let unpause = ExecuteMsg::Unpause {
+            action: Some(TRANSFER_ACTION.to_string()),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("gov", &[]), unpause).unwrap();
+        let info = mock_info("foobar", &coins(1234567, "ucosm"));
+        execute(deps.as_mut(), mock_env(), info, native.clone()).unwrap();
+
+        // nothing else can be paused
+        let pause = ExecuteMsg::Pause {
+            action: Some("receive".to_string()),
+            expires: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("gov", &[]), pause).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::UnknownPauseAction {
+                action: "receive".to_string(),
+                expected: TRANSFER_ACTION.to_string(),
+            }
+        );
+
+        // pause everything for a while
+        let mut env = mock_env();
+        let pause = ExecuteMsg::Pause {
+            action: None,
+            expires: Some(Expiration::AtHeight(env.block.height + 100)),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("gov", &[]), pause).unwrap();
+        let info = mock_info(cw20_addr, &[]);
+        let err = execute(deps.as_mut(), env.clone(), info, cw20.clone()).unwrap_err();
+        assert_eq!(err, PausableError::Paused {}.into());
+
+        env.block.height += 100;
+        let info = mock_info(cw20_addr, &[]);
+        execute(deps.as_mut(), env, info, cw20).unwrap();
+    }
+
     #[test]
     fn proper_checks_on_execute_cw20() {
         let send_channel = "channel-15";
@@ -577,7 +627,7 @@ mod test {
         deps.querier
             .update_balance(MOCK_CONTRACT_ADDR, coins(50000, native));
         // pretend this is an old contract - set version explicitly
-        set_contract_version(deps.as_mut().storage, CONTRACT_NAME, MIGRATE_VERSION_3).unwrap();
+        set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "0.13.0").unwrap();
 
         // channel state a bit lower (some in-flight acks)
         let state = ChannelState {
@@ -608,4 +658,49 @@ mod test {
         let config = query_config(deps.as_ref()).unwrap();
         assert_eq!(config.default_gas_limit, Some(123456));
     }
+
+    #[test]
+    fn v1_migration_works() {
+        let mut deps = setup(&[], &[]);
+        // pretend this is a v1 contract - rewrite config and version
+        let old_config = v1::Config {
+            default_timeout: 3600,
+            gov_contract: Addr::unchecked("old_gov"),
+        };
+        v1::CONFIG.save(deps.as_mut().storage, &old_config).unwrap();
+        set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "0.11.1").unwrap();
+
+        migrate(
+            deps.as_mut(),
+            mock_env(),
+            MigrateMsg {
+                default_gas_limit: None,
+            },
+        )
+        .unwrap();
+
+        let config = query_config(deps.as_ref()).unwrap();
+        assert_eq!(config.default_timeout, 3600);
+        assert_eq!(config.default_gas_limit, None);
+        assert_eq!(config.gov_contract, "old_gov".to_string());
+        let stored = cw2::get_contract_version(&deps.storage).unwrap();
+        assert_eq!(stored.version, CONTRACT_VERSION.to_string());
+
+        // versions older than the first migration step are not supported
+        set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "0.10.0").unwrap();
+        let err = migrate(
+            deps.as_mut(),
+            mock_env(),
+            MigrateMsg {
+                default_gas_limit: None,
+            },
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Migration(MigrationError::MissingStep {
+                version: "0.10.0".to_string()
+            })
+        );
+    }
 }
@@ -3,8 +3,8 @@ use std::string::FromUtf8Error;
 use thiserror::Error;
 
 use cosmwasm_std::StdError;
-use cw_controllers::AdminError;
-use cw_utils::PaymentError;
+use cw_controllers::{AdminError, PausableError};
+use cw_utils::{MigrationError, PaymentError};
 
 /// Never is a placeholder to ensure we don't return any errors
 #[derive(Error, Debug)]
@@ -21,6 +21,12 @@ pub enum ContractError {
     #[error("{0}")]
     Admin(#[from] AdminError),
 
+    #[error("{0}")]
+    Pausable(#[from] PausableError),
+
+    #[error("Only the {expected} action can be paused, got {action}")]
+    UnknownPauseAction { action: String, expected: String },
+
     #[error("Channel doesn't exist: {id}")]
     NoSuchChannel { id: String },
 
@@ -51,8 +57,8 @@ pub enum ContractError {
     #[error("Cannot migrate from different contract type: {previous_contract}")]
     CannotMigrate { previous_contract: String },
 
-    #[error("Cannot migrate from unsupported version: {previous_version}")]
-    CannotMigrateVersion { previous_version: String },
+    #[error("{0}")]
+    Migration(#[from] MigrationError),
 
     #[error("Got a submessage reply with unknown id: {id}")]
     UnknownReplyId { id: u64 },
@@ -2,19 +2,18 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use cosmwasm_std::{
-    attr, entry_point, from_binary, to_binary, BankMsg, Binary, CosmosMsg, Deps, DepsMut, Env,
-    IbcBasicResponse, IbcChannel, IbcChannelCloseMsg, IbcChannelConnectMsg, IbcChannelOpenMsg,
-    IbcEndpoint, IbcOrder, IbcPacket, IbcPacketAckMsg, IbcPacketReceiveMsg, IbcPacketTimeoutMsg,
-    IbcReceiveResponse, Reply, Response, SubMsg, SubMsgResult, Uint128, WasmMsg,
+    attr, entry_point, from_binary, to_binary, Binary, Deps, DepsMut, Env, IbcBasicResponse,
+    IbcChannel, IbcChannelCloseMsg, IbcChannelConnectMsg, IbcChannelOpenMsg, IbcEndpoint, IbcOrder,
+    IbcPacket, IbcPacketAckMsg, IbcPacketReceiveMsg, IbcPacketTimeoutMsg, IbcReceiveResponse,
+    Reply, Response, SubMsg, SubMsgResult, Uint128,
 };
 
-use crate::amount::Amount;
 use crate::error::{ContractError, Never};
 use crate::state::{
     reduce_channel_balance, undo_reduce_channel_balance, ChannelInfo, ReplyArgs, ALLOW_LIST,
     CHANNEL_INFO, CONFIG, REPLY_ARGS,
 };
-use cw20::Cw20ExecuteMsg;
+use cw20::{Asset, AssetInfo, AssetInfoUnchecked};
 
 pub const ICS20_VERSION: &str = "ics20-1";
 pub const ICS20_ORDERING: IbcOrder = IbcOrder::Unordered;
@@ -249,9 +248,10 @@ fn do_ibc_packet_receive(
     };
     REPLY_ARGS.save(deps.storage, &reply_args)?;
 
-    let to_send = Amount::from_parts(denom.to_string(), msg.amount);
+    let info = AssetInfoUnchecked::from(denom).check(deps.api)?;
+    let to_send = Asset::new(info, msg.amount);
     let gas_limit = check_gas_limit(deps.as_ref(), &to_send)?;
-    let send = send_amount(to_send, msg.receiver.clone());
+    let send = to_send.into_transfer_msg(msg.receiver.clone())?;
     let mut submsg = SubMsg::reply_on_error(send, RECEIVE_ID);
     submsg.gas_limit = gas_limit;
 
@@ -268,12 +268,11 @@ fn do_ibc_packet_receive(
     Ok(res)
 }
 
-fn check_gas_limit(deps: Deps, amount: &Amount) -> Result<Option<u64>, ContractError> {
-    match amount {
-        Amount::Cw20(coin) => {
+fn check_gas_limit(deps: Deps, amount: &Asset) -> Result<Option<u64>, ContractError> {
+    match &amount.info {
+        AssetInfo::Cw20(addr) => {
             // if cw20 token, use the registered gas limit, or error if not whitelisted
-            let addr = deps.api.addr_validate(&coin.address)?;
-            let allowed = ALLOW_LIST.may_load(deps.storage, &addr)?;
+            let allowed = ALLOW_LIST.may_load(deps.storage, addr)?;
             match allowed {
                 Some(allow) => Ok(allow.gas_limit),
                 None => match CONFIG.load(deps.storage)?.default_gas_limit {
@@ -343,9 +342,10 @@ fn on_packet_failure(
     // undo the balance update on failure (as we pre-emptively added it on send)
     reduce_channel_balance(deps.storage, &packet.src.channel_id, &msg.denom, msg.amount)?;
 
-    let to_send = Amount::from_parts(msg.denom.clone(), msg.amount);
+    let info = AssetInfoUnchecked::from(msg.denom.as_str()).check(deps.api)?;
+    let to_send = Asset::new(info, msg.amount);
     let gas_limit = check_gas_limit(deps.as_ref(), &to_send)?;
-    let send = send_amount(to_send, msg.sender.clone());
+    let send = to_send.into_transfer_msg(msg.sender.clone())?;
     let mut submsg = SubMsg::reply_on_error(send, ACK_FAILURE_ID);
     submsg.gas_limit = gas_limit;
 
@@ -363,38 +363,20 @@ fn on_packet_failure(
     Ok(res)
 }
 
-fn send_amount(amount: Amount, recipient: String) -> CosmosMsg {
-    match amount {
-        Amount::Native(coin) => BankMsg::Send {
-            to_address: recipient,
-            amount: vec![coin],
-        }
-        .into(),
-        Amount::Cw20(coin) => {
-            let msg = Cw20ExecuteMsg::Transfer {
-                recipient,
-                amount: coin.amount,
-            };
-            WasmMsg::Execute {
-                contract_addr: coin.address,
-                msg: to_binary(&msg).unwrap(),
-                funds: vec![],
-            }
-            .into()
-        }
-    }
-}
-
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::test_helpers::*;
 
+    use crate::amount::Amount;
+
     use crate::contract::{execute, migrate, query_channel};
     use crate::msg::{ExecuteMsg, MigrateMsg, TransferMsg};
     use cosmwasm_std::testing::{mock_env, mock_info};
-    use cosmwasm_std::{coins, to_vec, IbcEndpoint, IbcMsg, IbcTimeout, Timestamp};
-    use cw20::Cw20ReceiveMsg;
+    use cosmwasm_std::{
+        coins, to_vec, Addr, BankMsg, IbcEndpoint, IbcMsg, IbcTimeout, Timestamp, WasmMsg,
+    };
+    use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
 
     #[test]
     fn check_ack_json() {
@@ -634,12 +616,20 @@ mod test {
         let mut deps = setup(&[send_channel], &[(allowed, allowed_gas)]);
 
         // allow list will get proper gas
-        let limit = check_gas_limit(deps.as_ref(), &Amount::cw20(500, allowed)).unwrap();
+        let limit = check_gas_limit(
+            deps.as_ref(),
+            &Asset::cw20(Addr::unchecked(allowed), 500u128),
+        )
+        .unwrap();
         assert_eq!(limit, Some(allowed_gas));
 
         // non-allow list will error
         let random = "tokenz";
-        check_gas_limit(deps.as_ref(), &Amount::cw20(500, random)).unwrap_err();
+        check_gas_limit(
+            deps.as_ref(),
+            &Asset::cw20(Addr::unchecked(random), 500u128),
+        )
+        .unwrap_err();
 
         // add default_gas_limit
         let def_limit = 54321;
@@ -653,11 +643,19 @@ mod test {
         .unwrap();
 
         // allow list still gets proper gas
-        let limit = check_gas_limit(deps.as_ref(), &Amount::cw20(500, allowed)).unwrap();
+        let limit = check_gas_limit(
+            deps.as_ref(),
+            &Asset::cw20(Addr::unchecked(allowed), 500u128),
+        )
+        .unwrap();
         assert_eq!(limit, Some(allowed_gas));
 
         // non-allow list will now get default
-        let limit = check_gas_limit(deps.as_ref(), &Amount::cw20(500, random)).unwrap();
+        let limit = check_gas_limit(
+            deps.as_ref(),
+            &Asset::cw20(Addr::unchecked(random), 500u128),
+        )
+        .unwrap();
         assert_eq!(limit, Some(def_limit));
     }
 }
@@ -3,8 +3,12 @@ pub mod v1 {
     use schemars::JsonSchema;
     use serde::{Deserialize, Serialize};
 
-    use cosmwasm_std::Addr;
+    use cosmwasm_std::{Addr, DepsMut, Env};
     use cw_storage_plus::Item;
+    use cw_utils::MigrationProgress;
+
+    use crate::state::{self, ADMIN};
+    use crate::ContractError;
 
     #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
     pub struct Config {
@@ -13,22 +17,40 @@ pub mod v1 {
     }
 
     pub const CONFIG: Item<Config> = Item::new("ics20_config");
+
+    /// the gov contract becomes the admin, and the config gets the (empty) default gas limit
+    pub fn migrate_config(
+        mut deps: DepsMut,
+        _env: &Env,
+    ) -> Result<MigrationProgress, ContractError> {
+        let old_config = CONFIG.load(deps.storage)?;
+        ADMIN.set(deps.branch(), Some(old_config.gov_contract))?;
+        let config = state::Config {
+            default_timeout: old_config.default_timeout,
+            default_gas_limit: None,
+        };
+        state::CONFIG.save(deps.storage, &config)?;
+        Ok(MigrationProgress::Done)
+    }
 }
 
 // v2 format is anything older than 0.13.1 when we only updated the internal balances on success ack
 pub mod v2 {
-    use crate::amount::Amount;
     use crate::state::{ChannelState, CHANNEL_INFO, CHANNEL_STATE};
     use crate::ContractError;
-    use cosmwasm_std::{to_binary, Addr, DepsMut, Env, Order, StdResult, WasmQuery};
-    use cw20::{BalanceResponse, Cw20QueryMsg};
+    use cosmwasm_std::{Addr, DepsMut, Env, Order, StdResult};
+    use cw20::AssetInfoUnchecked;
+    use cw_utils::MigrationProgress;
 
-    pub fn update_balances(mut deps: DepsMut, env: &Env) -> Result<(), ContractError> {
+    pub fn update_balances(
+        mut deps: DepsMut,
+        env: &Env,
+    ) -> Result<MigrationProgress, ContractError> {
         let channels = CHANNEL_INFO
             .keys(deps.storage, None, None, Order::Ascending)
             .collect::<StdResult<Vec<_>>>()?;
         match channels.len() {
-            0 => Ok(()),
+            0 => Ok(MigrationProgress::Done),
             1 => {
                 let channel = &channels[0];
                 let addr = &env.contract.address;
@@ -39,7 +61,7 @@ pub mod v2 {
                 for (denom, state) in states.into_iter() {
                     update_denom(deps.branch(), addr, channel, denom, state)?;
                 }
-                Ok(())
+                Ok(MigrationProgress::Done)
             }
             _ => Err(ContractError::CannotMigrate {
                 previous_contract: "multiple channels open".into(),
@@ -55,22 +77,9 @@ pub mod v2 {
         mut state: ChannelState,
     ) -> StdResult<()> {
         // handle this for both native and cw20
-        let balance = match Amount::from_parts(denom.clone(), state.outstanding) {
-            Amount::Native(coin) => deps.querier.query_balance(contract, coin.denom)?.amount,
-            Amount::Cw20(coin) => {
-                // FIXME: we should be able to do this with the following line, but QuerierWrapper doesn't play
-                // with the Querier generics
-                // `Cw20Contract(contract.clone()).balance(&deps.querier, contract)?`
-                let query = WasmQuery::Smart {
-                    contract_addr: coin.address,
-                    msg: to_binary(&Cw20QueryMsg::Balance {
-                        address: contract.into(),
-                    })?,
-                };
-                let res: BalanceResponse = deps.querier.query(&query.into())?;
-                res.balance
-            }
-        };
+        let balance = AssetInfoUnchecked::from(denom.as_str())
+            .check(deps.api)?
+            .query_balance(&deps.querier, contract)?;
 
         // this checks if we have received some coins that are "in flight" and not yet accounted in the state
         let diff = balance - state.outstanding;
@@ -2,6 +2,7 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use cw20::Cw20ReceiveMsg;
+use cw_utils::Expiration;
 
 use crate::amount::Amount;
 use crate::state::ChannelInfo;
@@ -41,6 +42,15 @@ pub enum ExecuteMsg {
     Allow(AllowMsg),
     /// Change the admin (must be called by current admin)
     UpdateAdmin { admin: String },
+    /// Halt outgoing transfers until unpaused or `expires` (must be called by current admin).
+    /// `action` can only be "transfer"; leaving it empty sets the contract-wide pause, which
+    /// also only halts outgoing transfers for now.
+    Pause {
+        action: Option<String>,
+        expires: Option<Expiration>,
+    },
+    /// Lift a pause set with `Pause` (must be called by current admin)
+    Unpause { action: Option<String> },
 }
 
 /// This is the message we accept via Receive
@@ -70,6 +80,8 @@ pub enum QueryMsg {
     Config {},
     /// Return AdminResponse
     Admin {},
+    /// Return PausedResponse
+    Paused {},
     /// Query if a given cw20 contract is allowed. Returns AllowedResponse
     Allowed { contract: String },
     /// List all allowed cw20 contracts. Returns ListAllowedResponse
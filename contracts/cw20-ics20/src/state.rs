@@ -2,13 +2,19 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use cosmwasm_std::{Addr, IbcEndpoint, StdResult, Storage, Uint128};
-use cw_controllers::Admin;
+use cw_controllers::{Admin, Pausable};
 use cw_storage_plus::{Item, Map};
 
 use crate::ContractError;
 
 pub const ADMIN: Admin = Admin::new("admin");
 
+/// The admin can pause outgoing transfers, as `TRANSFER_ACTION` or as a contract-wide pause.
+/// Incoming packets are never paused, so that refunds keep working.
+pub const PAUSE: Pausable = Pausable::new("pause", "paused_actions");
+
+pub const TRANSFER_ACTION: &str = "transfer";
+
 pub const CONFIG: Item<Config> = Item::new("ics20_config");
 
 // Used to pass info from the ibc_packet_receive to the reply handler
@@ -17,7 +17,9 @@ use cw_utils::{Expiration, ThresholdResponse};
 
 use crate::error::ContractError;
 use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::{next_id, Ballot, Config, Proposal, Votes, BALLOTS, CONFIG, PROPOSALS, VOTERS};
+use crate::state::{
+    Ballot, Config, Proposal, Votes, BALLOTS, CONFIG, PROPOSALS, PROPOSAL_COUNT, VOTERS,
+};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cw3-fixed-multisig";
@@ -114,7 +116,7 @@ pub fn execute_propose(
         total_weight: cfg.total_weight,
     };
     prop.update_status(&env.block);
-    let id = next_id(deps.storage)?;
+    let id = PROPOSAL_COUNT.next(deps.storage)?;
     PROPOSALS.save(deps.storage, id, &prop)?;
 
     // add the first yes vote from voter
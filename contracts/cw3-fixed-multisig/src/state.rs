@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 use cosmwasm_std::{Addr, BlockInfo, CosmosMsg, Decimal, Empty, StdResult, Storage, Uint128};
 
 use cw3::{Status, Vote};
-use cw_storage_plus::{Item, Map};
+use cw_storage_plus::{Item, Map, Sequence};
 use cw_utils::{Duration, Expiration, Threshold};
 
 // we multiply by this when calculating needed_votes in order to round up properly
@@ -71,20 +71,59 @@ impl Proposal {
                     >= votes_needed(self.total_weight - self.votes.abstain, percentage_needed)
             }
             Threshold::ThresholdQuorum { threshold, quorum } => {
-                // we always require the quorum
-                if self.votes.total() < votes_needed(self.total_weight, quorum) {
-                    return false;
-                }
-                if self.expires.is_expired(block) {
-                    // If expired, we compare vote_count against the total number of votes (minus abstain).
-                    let opinions = self.votes.total() - self.votes.abstain;
-                    self.votes.yes >= votes_needed(opinions, threshold)
-                } else {
-                    // If not expired, we must assume all non-votes will be cast against
-                    let possible_opinions = self.total_weight - self.votes.abstain;
-                    self.votes.yes >= votes_needed(possible_opinions, threshold)
-                }
+                self.is_passed_quorum(block, threshold, quorum)
             }
+            Threshold::ThresholdQuorumVeto {
+                threshold,
+                quorum,
+                veto_threshold,
+            } => {
+                self.is_passed_quorum(block, threshold, quorum)
+                    && !self.may_be_vetoed(block, veto_threshold)
+            }
+            Threshold::Supermajority { quorum } => {
+                self.is_passed_quorum(block, Threshold::supermajority(), quorum)
+            }
+        }
+    }
+
+    fn is_passed_quorum(&self, block: &BlockInfo, threshold: Decimal, quorum: Decimal) -> bool {
+        // we always require the quorum
+        if self.votes.total() < votes_needed(self.total_weight, quorum) {
+            return false;
+        }
+        if self.expires.is_expired(block) {
+            // If expired, we compare vote_count against the total number of votes (minus abstain).
+            let opinions = self.votes.total() - self.votes.abstain;
+            self.votes.yes >= votes_needed(opinions, threshold)
+        } else {
+            // If not expired, we must assume all non-votes will be cast against
+            let possible_opinions = self.total_weight - self.votes.abstain;
+            self.votes.yes >= votes_needed(possible_opinions, threshold)
+        }
+    }
+
+    // Returns true if the veto votes reach veto_threshold of the votes cast, or still could
+    // if the voting period is not over
+    fn may_be_vetoed(&self, block: &BlockInfo, veto_threshold: Decimal) -> bool {
+        if self.expires.is_expired(block) {
+            self.votes.veto >= votes_needed(self.votes.total(), veto_threshold)
+        } else {
+            // If not expired, we must assume all non-votes will be cast as veto
+            let possible_vetoes = self.votes.veto + self.total_weight - self.votes.total();
+            possible_vetoes >= votes_needed(self.total_weight, veto_threshold)
+        }
+    }
+
+    // Returns true if the veto votes reach veto_threshold of the votes cast, whatever the
+    // remaining votes
+    fn is_vetoed(&self, block: &BlockInfo, veto_threshold: Decimal) -> bool {
+        if self.expires.is_expired(block) {
+            self.votes.veto >= votes_needed(self.votes.total(), veto_threshold)
+        } else {
+            // If not expired, the remaining votes can only dilute the veto votes down to
+            // a share of the total weight
+            self.votes.veto >= votes_needed(self.total_weight, veto_threshold)
         }
     }
 
@@ -110,19 +149,29 @@ impl Proposal {
             Threshold::ThresholdQuorum {
                 threshold,
                 quorum: _,
-            } => {
-                if self.expires.is_expired(block) {
-                    // If expired, we compare vote_count against the total number of votes (minus abstain).
-                    let opinions = self.votes.total() - self.votes.abstain;
-                    self.votes.no > votes_needed(opinions, Decimal::one() - threshold)
-                } else {
-                    // If not expired, we must assume all non-votes will be cast for
-                    let possible_opinions = self.total_weight - self.votes.abstain;
-                    self.votes.no > votes_needed(possible_opinions, Decimal::one() - threshold)
-                }
+            } => self.is_rejected_quorum(block, threshold),
+            Threshold::ThresholdQuorumVeto {
+                threshold,
+                quorum: _,
+                veto_threshold,
+            } => self.is_vetoed(block, veto_threshold) || self.is_rejected_quorum(block, threshold),
+            Threshold::Supermajority { quorum: _ } => {
+                self.is_rejected_quorum(block, Threshold::supermajority())
             }
         }
     }
+
+    fn is_rejected_quorum(&self, block: &BlockInfo, threshold: Decimal) -> bool {
+        if self.expires.is_expired(block) {
+            // If expired, we compare vote_count against the total number of votes (minus abstain).
+            let opinions = self.votes.total() - self.votes.abstain;
+            self.votes.no > votes_needed(opinions, Decimal::one() - threshold)
+        } else {
+            // If not expired, we must assume all non-votes will be cast for
+            let possible_opinions = self.total_weight - self.votes.abstain;
+            self.votes.no > votes_needed(possible_opinions, Decimal::one() - threshold)
+        }
+    }
 }
 
 // weight of votes for each option
@@ -178,7 +227,8 @@ pub struct Ballot {
 
 // unique items
 pub const CONFIG: Item<Config> = Item::new("config");
-pub const PROPOSAL_COUNT: Item<u64> = Item::new("proposal_count");
+// Stored as the `Item<u64>` it used to be: the last proposal id. Read it with `last`.
+pub const PROPOSAL_COUNT: Sequence = Sequence::new("proposal_count");
 
 // multiple-item map
 pub const BALLOTS: Map<(u64, &Addr), Ballot> = Map::new("votes");
@@ -187,16 +237,25 @@ pub const PROPOSALS: Map<u64, Proposal> = Map::new("proposals");
 // multiple-item maps
 pub const VOTERS: Map<&Addr, u64> = Map::new("voters");
 
+#[deprecated(note = "use `PROPOSAL_COUNT.next` instead")]
 pub fn next_id(store: &mut dyn Storage) -> StdResult<u64> {
-    let id: u64 = PROPOSAL_COUNT.may_load(store)?.unwrap_or_default() + 1;
-    PROPOSAL_COUNT.save(store, &id)?;
-    Ok(id)
+    PROPOSAL_COUNT.next(store)
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use cosmwasm_std::testing::mock_env;
+    use cosmwasm_std::testing::{mock_env, MockStorage};
+
+    #[test]
+    #[allow(deprecated)]
+    fn next_id_uses_proposal_count() {
+        let mut store = MockStorage::new();
+        assert_eq!(next_id(&mut store).unwrap(), 1);
+        assert_eq!(PROPOSAL_COUNT.next(&mut store).unwrap(), 2);
+        assert_eq!(next_id(&mut store).unwrap(), 3);
+        assert_eq!(PROPOSAL_COUNT.last(&store).unwrap(), Some(3));
+    }
 
     #[test]
     fn count_votes() {
@@ -594,4 +653,134 @@ mod test {
         ));
         assert!(check_is_passed(quorum, passes_early, 15, true));
     }
+
+    #[test]
+    fn proposal_vetoed() {
+        let veto = Threshold::ThresholdQuorumVeto {
+            threshold: Decimal::percent(50),
+            quorum: Decimal::percent(40),
+            veto_threshold: Decimal::permille(334),
+        };
+        // passes as ThresholdQuorum, but 35 of 100 cast votes are veto
+        let vetoed = Votes {
+            yes: 60,
+            no: 5,
+            abstain: 0,
+            veto: 35,
+        };
+        assert!(check_is_passed(
+            Threshold::ThresholdQuorum {
+                threshold: Decimal::percent(50),
+                quorum: Decimal::percent(40),
+            },
+            vetoed.clone(),
+            100,
+            true
+        ));
+        assert!(!check_is_passed(veto.clone(), vetoed.clone(), 100, true));
+        assert!(check_is_rejected(veto.clone(), vetoed.clone(), 100, true));
+        // the vetoes already reach 33.4% of the total weight, no need to wait
+        assert!(!check_is_passed(veto.clone(), vetoed.clone(), 100, false));
+        assert!(check_is_rejected(veto.clone(), vetoed.clone(), 100, false));
+        // but if more votes may dilute them, we cannot reject early
+        assert!(!check_is_passed(veto.clone(), vetoed.clone(), 110, false));
+        assert!(!check_is_rejected(veto.clone(), vetoed.clone(), 110, false));
+        // abstain votes count as cast
+        let diluted = Votes {
+            abstain: 10,
+            ..vetoed
+        };
+        assert!(check_is_passed(veto.clone(), diluted.clone(), 110, true));
+        assert!(!check_is_rejected(veto.clone(), diluted, 110, true));
+
+        // 70 yes out of 100 pass early only if the remaining votes cannot veto it
+        let passing = Votes {
+            yes: 70,
+            no: 0,
+            abstain: 0,
+            veto: 0,
+        };
+        // 30 remaining vetoes would stay under 33.4%
+        assert!(check_is_passed(veto.clone(), passing.clone(), 100, false));
+        let passing = Votes {
+            yes: 65,
+            no: 0,
+            abstain: 0,
+            veto: 1,
+        };
+        // 1 + 34 remaining vetoes could reach 33.4%
+        assert!(!check_is_passed(veto.clone(), passing.clone(), 100, false));
+        assert!(!check_is_rejected(
+            veto.clone(),
+            passing.clone(),
+            100,
+            false
+        ));
+        assert!(check_is_passed(veto, passing, 100, true));
+    }
+
+    #[test]
+    fn proposal_supermajority() {
+        let supermajority = Threshold::Supermajority {
+            quorum: Decimal::percent(30),
+        };
+        // 2 of 3 opinions are yes, abstain are ignored
+        let passing = Votes {
+            yes: 20,
+            no: 6,
+            abstain: 5,
+            veto: 4,
+        };
+        assert!(check_is_passed(
+            supermajority.clone(),
+            passing.clone(),
+            100,
+            true
+        ));
+        assert!(!check_is_rejected(
+            supermajority.clone(),
+            passing.clone(),
+            100,
+            true
+        ));
+        // under quorum
+        assert!(!check_is_passed(
+            supermajority.clone(),
+            passing.clone(),
+            120,
+            true
+        ));
+        // remaining votes could still reject it
+        assert!(!check_is_passed(
+            supermajority.clone(),
+            passing.clone(),
+            100,
+            false
+        ));
+        // one vote short of two thirds, and over a third of no votes
+        let failing = Votes {
+            yes: 19,
+            no: 11,
+            abstain: 5,
+            veto: 0,
+        };
+        assert!(!check_is_passed(
+            supermajority.clone(),
+            failing.clone(),
+            100,
+            true
+        ));
+        assert!(check_is_rejected(supermajority.clone(), failing, 100, true));
+
+        // 2 of 3 exactly passes early, with 1 of 3 no it is rejected early
+        assert!(check_is_passed(
+            supermajority.clone(),
+            Votes::yes(2),
+            3,
+            false
+        ));
+        let mut rejecting = Votes::yes(0);
+        rejecting.add_vote(Vote::No, 2);
+        assert!(check_is_rejected(supermajority, rejecting, 3, false));
+    }
 }
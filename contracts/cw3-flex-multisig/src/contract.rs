@@ -12,7 +12,7 @@ use cw3::{
     ProposalListResponse, ProposalResponse, Status, Vote, VoteInfo, VoteListResponse, VoteResponse,
     VoterDetail, VoterListResponse, VoterResponse,
 };
-use cw3_fixed_multisig::state::{next_id, Ballot, Proposal, Votes, BALLOTS, PROPOSALS};
+use cw3_fixed_multisig::state::{Ballot, Proposal, Votes, BALLOTS, PROPOSALS, PROPOSAL_COUNT};
 use cw4::{Cw4Contract, MemberChangedHookMsg, MemberDiff};
 use cw_storage_plus::Bound;
 use cw_utils::{maybe_addr, Expiration, ThresholdResponse};
@@ -122,7 +122,7 @@ pub fn execute_propose(
         total_weight: cfg.group_addr.total_weight(&deps.querier)?,
     };
     prop.update_status(&env.block);
-    let id = next_id(deps.storage)?;
+    let id = PROPOSAL_COUNT.next(deps.storage)?;
     PROPOSALS.save(deps.storage, id, &prop)?;
 
     // add the first yes vote from voter
@@ -1916,4 +1916,77 @@ mod tests {
             .unwrap();
         assert_eq!(prop_status(&app), Status::Passed);
     }
+
+    #[test]
+    fn veto_rejects_proposal_with_enough_yes_votes() {
+        let init_funds = coins(10, "BTC");
+        let mut app = mock_app(&init_funds);
+
+        // 20% vetoes of the 23 total weight (5) reject the proposal
+        let voting_period = Duration::Time(20000);
+        let (flex_addr, _) = setup_test_case(
+            &mut app,
+            Threshold::ThresholdQuorumVeto {
+                threshold: Decimal::percent(51),
+                quorum: Decimal::percent(50),
+                veto_threshold: Decimal::percent(20),
+            },
+            voting_period,
+            init_funds,
+            false,
+            None,
+        );
+
+        // create proposal
+        let proposal = pay_somebody_proposal();
+        let res = app
+            .execute_contract(Addr::unchecked(VOTER5), flex_addr.clone(), &proposal, &[])
+            .unwrap();
+        // Get the proposal id from the logs
+        let proposal_id: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
+        let prop_status = |app: &App| -> Status {
+            let query_prop = QueryMsg::Proposal { proposal_id };
+            let prop: ProposalResponse = app
+                .wrap()
+                .query_wasm_smart(&flex_addr, &query_prop)
+                .unwrap();
+            prop.status
+        };
+
+        // 17 yes would pass a ThresholdQuorum, but the remaining 6 may still veto
+        let yes_vote = ExecuteMsg::Vote {
+            proposal_id,
+            vote: Vote::Yes,
+        };
+        app.execute_contract(Addr::unchecked(VOTER4), flex_addr.clone(), &yes_vote, &[])
+            .unwrap();
+        assert_eq!(prop_status(&app), Status::Open);
+
+        let veto_vote = ExecuteMsg::Vote {
+            proposal_id,
+            vote: Vote::Veto,
+        };
+        app.execute_contract(Addr::unchecked(VOTER3), flex_addr.clone(), &veto_vote, &[])
+            .unwrap();
+        assert_eq!(prop_status(&app), Status::Open);
+
+        // 5 vetoes reject it
+        app.execute_contract(Addr::unchecked(VOTER2), flex_addr.clone(), &veto_vote, &[])
+            .unwrap();
+        assert_eq!(prop_status(&app), Status::Rejected);
+
+        let threshold: ThresholdResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::Threshold {})
+            .unwrap();
+        assert_eq!(
+            threshold,
+            ThresholdResponse::ThresholdQuorumVeto {
+                threshold: Decimal::percent(51),
+                quorum: Decimal::percent(50),
+                veto_threshold: Decimal::percent(20),
+                total_weight: 23,
+            }
+        );
+    }
 }
@@ -5,6 +5,7 @@ use cosmwasm_schema::{export_schema, export_schema_with_title, remove_schemas, s
 
 pub use cw4::{AdminResponse, MemberListResponse, MemberResponse, TotalWeightResponse};
 pub use cw4_group::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+pub use cw_controllers::PendingAdminResponse;
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
@@ -16,6 +17,7 @@ fn main() {
     export_schema_with_title(&schema_for!(ExecuteMsg), &out_dir, "ExecuteMsg");
     export_schema_with_title(&schema_for!(QueryMsg), &out_dir, "QueryMsg");
     export_schema(&schema_for!(AdminResponse), &out_dir);
+    export_schema(&schema_for!(PendingAdminResponse), &out_dir);
     export_schema(&schema_for!(MemberListResponse), &out_dir);
     export_schema(&schema_for!(MemberResponse), &out_dir);
     export_schema(&schema_for!(TotalWeightResponse), &out_dir);
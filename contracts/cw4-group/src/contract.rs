@@ -73,6 +73,15 @@ pub fn execute(
             info,
             admin.map(|admin| api.addr_validate(&admin)).transpose()?,
         )?),
+        ExecuteMsg::ProposeAdmin { admin, expires } => Ok(ADMIN.execute_propose_admin(
+            deps,
+            env,
+            info,
+            api.addr_validate(&admin)?,
+            expires,
+        )?),
+        ExecuteMsg::AcceptAdmin {} => Ok(ADMIN.execute_accept_admin(deps, env, info)?),
+        ExecuteMsg::CancelAdminProposal {} => Ok(ADMIN.execute_cancel_admin_proposal(deps, info)?),
         ExecuteMsg::UpdateMembers { add, remove } => {
             execute_update_members(deps, env, info, add, remove)
         }
@@ -150,7 +159,7 @@ pub fn update_members(
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Member {
             addr,
@@ -161,6 +170,7 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         }
         QueryMsg::TotalWeight {} => to_binary(&query_total_weight(deps)?),
         QueryMsg::Admin {} => to_binary(&ADMIN.query_admin(deps)?),
+        QueryMsg::PendingAdmin {} => to_binary(&ADMIN.query_pending_admin(deps, env)?),
         QueryMsg::Hooks {} => to_binary(&HOOKS.query_hooks(deps)?),
     }
 }
@@ -212,7 +222,7 @@ mod tests {
     use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
     use cosmwasm_std::{from_slice, Api, OwnedDeps, Querier, Storage};
     use cw4::{member_key, TOTAL_KEY};
-    use cw_controllers::{AdminError, HookError};
+    use cw_controllers::{AdminError, HookError, PendingAdminResponse};
 
     const INIT_ADMIN: &str = "juan";
     const USER1: &str = "somebody";
@@ -407,6 +417,73 @@ mod tests {
         assert_users(&deps, None, Some(6), Some(5), None);
     }
 
+    #[test]
+    fn two_step_admin_transfer() {
+        let mut deps = mock_dependencies();
+        do_instantiate(deps.as_mut());
+
+        let propose = ExecuteMsg::ProposeAdmin {
+            admin: USER1.into(),
+            expires: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(USER1, &[]),
+            propose.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, AdminError::NotAdmin {}.into());
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(INIT_ADMIN, &[]),
+            propose,
+        )
+        .unwrap();
+
+        let pending: PendingAdminResponse =
+            from_slice(&query(deps.as_ref(), mock_env(), QueryMsg::PendingAdmin {}).unwrap())
+                .unwrap();
+        assert_eq!(pending.pending_admin, Some(USER1.into()));
+        // still the old admin
+        let admin = ADMIN.query_admin(deps.as_ref()).unwrap();
+        assert_eq!(admin.admin, Some(INIT_ADMIN.into()));
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(USER2, &[]),
+            ExecuteMsg::AcceptAdmin {},
+        )
+        .unwrap_err();
+        assert_eq!(err, AdminError::NotPendingAdmin {}.into());
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(USER1, &[]),
+            ExecuteMsg::AcceptAdmin {},
+        )
+        .unwrap();
+        let admin = ADMIN.query_admin(deps.as_ref()).unwrap();
+        assert_eq!(admin.admin, Some(USER1.into()));
+
+        // the new admin is in charge
+        let update = ExecuteMsg::UpdateMembers {
+            remove: vec![USER2.into()],
+            add: vec![],
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(INIT_ADMIN, &[]),
+            update.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, AdminError::NotAdmin {}.into());
+        execute(deps.as_mut(), mock_env(), mock_info(USER1, &[]), update).unwrap();
+    }
+
     #[test]
     fn add_remove_hooks() {
         // add will over-write and remove have no effect
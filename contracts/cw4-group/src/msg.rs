@@ -2,6 +2,7 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use cw4::Member;
+use cw_utils::Expiration;
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 #[serde(rename_all = "snake_case")]
@@ -15,8 +16,19 @@ pub struct InstantiateMsg {
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
-    /// Change the admin
+    /// Change the admin immediately. Prefer `ProposeAdmin` to hand over to another address,
+    /// a mistyped address here locks the admin out for good.
     UpdateAdmin { admin: Option<String> },
+    /// Propose a new admin, who gets the admin rights once it sends `AcceptAdmin` (before
+    /// `expires`). Must be called by Admin
+    ProposeAdmin {
+        admin: String,
+        expires: Option<Expiration>,
+    },
+    /// Become the admin. Must be called by the pending admin
+    AcceptAdmin {},
+    /// Withdraw the pending admin proposal. Must be called by Admin
+    CancelAdminProposal {},
     /// apply a diff to the existing members.
     /// remove is applied after add, so if an address is in both, it is removed
     UpdateMembers {
@@ -34,6 +46,8 @@ pub enum ExecuteMsg {
 pub enum QueryMsg {
     /// Return AdminResponse
     Admin {},
+    /// Return PendingAdminResponse
+    PendingAdmin {},
     /// Return TotalWeightResponse
     TotalWeight {},
     /// Returns MembersListResponse
@@ -3,7 +3,7 @@ use cw4::TOTAL_KEY;
 use cw_controllers::{Admin, Hooks};
 use cw_storage_plus::{Item, SnapshotMap, Strategy};
 
-pub const ADMIN: Admin = Admin::new("admin");
+pub const ADMIN: Admin = Admin::new_with_pending("admin", "pending_admin");
 pub const HOOKS: Hooks = Hooks::new("cw4-hooks");
 
 pub const TOTAL: Item<u64> = Item::new(TOTAL_KEY);
@@ -5,8 +5,9 @@ use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
 pub use cw4::{AdminResponse, MemberListResponse, MemberResponse, TotalWeightResponse};
 pub use cw4_stake::msg::{
-    ClaimsResponse, ExecuteMsg, InstantiateMsg, QueryMsg, ReceiveMsg, StakedResponse,
+    ClaimsResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, ReceiveMsg, StakedResponse,
 };
+pub use cw_controllers::PendingAdminResponse;
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
@@ -17,9 +18,11 @@ fn main() {
     export_schema(&schema_for!(InstantiateMsg), &out_dir);
     export_schema(&schema_for!(ExecuteMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
+    export_schema(&schema_for!(MigrateMsg), &out_dir);
     export_schema(&schema_for!(ReceiveMsg), &out_dir);
 
     export_schema(&schema_for!(AdminResponse), &out_dir);
+    export_schema(&schema_for!(PendingAdminResponse), &out_dir);
     export_schema(&schema_for!(MemberListResponse), &out_dir);
     export_schema(&schema_for!(MemberResponse), &out_dir);
     export_schema(&schema_for!(TotalWeightResponse), &out_dir);
@@ -1,27 +1,30 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    coins, from_slice, to_binary, Addr, BankMsg, Binary, Deps, DepsMut, Env, MessageInfo, Order,
-    Response, StdResult, Storage, SubMsg, Uint128, WasmMsg,
+    from_slice, to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Order, Response,
+    StdResult, Storage, SubMsg, Uint128,
 };
 
 use cw2::set_contract_version;
-use cw20::{Balance, Cw20CoinVerified, Cw20ExecuteMsg, Cw20ReceiveMsg, Denom};
+use cw20::{Asset, Balance, Cw20CoinVerified, Cw20ReceiveMsg, Denom};
 use cw4::{
     Member, MemberChangedHookMsg, MemberDiff, MemberListResponse, MemberResponse,
     TotalWeightResponse,
 };
 use cw_storage_plus::Bound;
-use cw_utils::{maybe_addr, NativeBalance};
+use cw_utils::{ensure_from_older_version, maybe_addr, MigrationProgress, NativeBalance};
 
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, ReceiveMsg, StakedResponse};
+use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, ReceiveMsg, StakedResponse};
 use crate::state::{Config, ADMIN, CLAIMS, CONFIG, HOOKS, MEMBERS, STAKE, TOTAL};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cw4-stake";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+// addresses whose legacy claims are moved at most by one call
+const MIGRATE_CLAIMS_LIMIT: u32 = 100;
+
 // Note, you can use StdResult in some functions where you do not
 // make use of the custom errors
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -63,6 +66,15 @@ pub fn execute(
         ExecuteMsg::UpdateAdmin { admin } => {
             Ok(ADMIN.execute_update_admin(deps, info, maybe_addr(api, admin)?)?)
         }
+        ExecuteMsg::ProposeAdmin { admin, expires } => Ok(ADMIN.execute_propose_admin(
+            deps,
+            env,
+            info,
+            api.addr_validate(&admin)?,
+            expires,
+        )?),
+        ExecuteMsg::AcceptAdmin {} => Ok(ADMIN.execute_accept_admin(deps, env, info)?),
+        ExecuteMsg::CancelAdminProposal {} => Ok(ADMIN.execute_cancel_admin_proposal(deps, info)?),
         ExecuteMsg::AddHook { addr } => {
             Ok(HOOKS.execute_add_hook(&ADMIN, deps, info, api.addr_validate(&addr)?)?)
         }
@@ -72,6 +84,7 @@ pub fn execute(
         ExecuteMsg::Bond {} => execute_bond(deps, env, Balance::from(info.funds), info.sender),
         ExecuteMsg::Unbond { tokens: amount } => execute_unbond(deps, env, info, amount),
         ExecuteMsg::Claim {} => execute_claim(deps, env, info),
+        ExecuteMsg::MigrateClaims { limit } => execute_migrate_claims(deps, limit),
         ExecuteMsg::Receive(msg) => execute_receive(deps, env, info, msg),
     }
 }
@@ -244,51 +257,58 @@ pub fn execute_claim(
     env: Env,
     info: MessageInfo,
 ) -> Result<Response, ContractError> {
+    // the claims of the sender may not be migrated yet
+    CLAIMS.migrate_legacy_claims_of(deps.storage, &info.sender)?;
     let release = CLAIMS.claim_tokens(deps.storage, &info.sender, &env.block, None)?;
     if release.is_zero() {
         return Err(ContractError::NothingToClaim {});
     }
 
     let config = CONFIG.load(deps.storage)?;
-    let (amount_str, message) = match &config.denom {
-        Denom::Native(denom) => {
-            let amount_str = coin_to_string(release, denom.as_str());
-            let amount = coins(release.u128(), denom);
-            let message = SubMsg::new(BankMsg::Send {
-                to_address: info.sender.to_string(),
-                amount,
-            });
-            (amount_str, message)
-        }
-        Denom::Cw20(addr) => {
-            let amount_str = coin_to_string(release, addr.as_str());
-            let transfer = Cw20ExecuteMsg::Transfer {
-                recipient: info.sender.clone().into(),
-                amount: release,
-            };
-            let message = SubMsg::new(WasmMsg::Execute {
-                contract_addr: addr.into(),
-                msg: to_binary(&transfer)?,
-                funds: vec![],
-            });
-            (amount_str, message)
-        }
+    let amount_str = match &config.denom {
+        Denom::Native(denom) => coin_to_string(release, denom.as_str()),
+        Denom::Cw20(addr) => coin_to_string(release, addr.as_str()),
     };
+    let message = Asset::new(config.denom.into(), release).into_transfer_msg(&info.sender)?;
 
     Ok(Response::new()
-        .add_submessage(message)
+        .add_message(message)
         .add_attribute("action", "claim")
         .add_attribute("tokens", amount_str)
         .add_attribute("sender", info.sender))
 }
 
+/// Continues moving the claims stored by previous versions, if `migrate` could not move them all
+pub fn execute_migrate_claims(
+    deps: DepsMut,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let progress = migrate_claims(deps.storage, limit)?;
+    Ok(Response::new()
+        .add_attribute("action", "migrate_claims")
+        .add_attribute("claims_migration", progress))
+}
+
+// claims used to be stored as a list by address, under `LEGACY_CLAIMS_NAMESPACE`.
+// Does nothing once they were all moved
+fn migrate_claims(storage: &mut dyn Storage, limit: Option<u32>) -> StdResult<&'static str> {
+    let limit = limit
+        .unwrap_or(MIGRATE_CLAIMS_LIMIT)
+        .min(MIGRATE_CLAIMS_LIMIT) as usize;
+    let progress = CLAIMS.migrate_legacy_claims(storage, limit)?;
+    Ok(match progress {
+        MigrationProgress::Done => "done",
+        MigrationProgress::Pending => "pending",
+    })
+}
+
 #[inline]
 fn coin_to_string(amount: Uint128, denom: &str) -> String {
     format!("{} {}", amount, denom)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Member {
             addr,
@@ -298,11 +318,19 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
             to_binary(&list_members(deps, start_after, limit)?)
         }
         QueryMsg::TotalWeight {} => to_binary(&query_total_weight(deps)?),
-        QueryMsg::Claims { address } => {
-            to_binary(&CLAIMS.query_claims(deps, &deps.api.addr_validate(&address)?)?)
-        }
+        QueryMsg::Claims {
+            address,
+            start_after,
+            limit,
+        } => to_binary(&CLAIMS.query_claims(
+            deps,
+            &deps.api.addr_validate(&address)?,
+            start_after,
+            limit,
+        )?),
         QueryMsg::Staked { address } => to_binary(&query_staked(deps, address)?),
         QueryMsg::Admin {} => to_binary(&ADMIN.query_admin(deps)?),
+        QueryMsg::PendingAdmin {} => to_binary(&ADMIN.query_pending_admin(deps, env)?),
         QueryMsg::Hooks {} => to_binary(&HOOKS.query_hooks(deps)?),
     }
 }
@@ -355,18 +383,31 @@ fn list_members(
     Ok(MemberListResponse { members })
 }
 
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    ensure_from_older_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    // moves a batch of legacy claims. If some are left ("pending"), run the migration again
+    // or send `MigrateClaims` until done
+    let progress = migrate_claims(deps.storage, None)?;
+    Ok(Response::new().add_attribute("claims_migration", progress))
+}
+
 #[cfg(test)]
 mod tests {
     use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
     use cosmwasm_std::{
-        coin, from_slice, CosmosMsg, OverflowError, OverflowOperation, StdError, Storage,
+        attr, coin, coins, from_slice, BankMsg, CosmosMsg, OverflowError, OverflowOperation,
+        StdError, Storage, WasmMsg,
     };
-    use cw20::Denom;
+    use cw20::{Cw20ExecuteMsg, Denom};
     use cw4::{member_key, TOTAL_KEY};
     use cw_controllers::{AdminError, Claim, HookError};
-    use cw_utils::Duration;
+    use cw_storage_plus::Map;
+    use cw_utils::{Duration, Expiration};
 
     use crate::error::ContractError;
+    use crate::state::LEGACY_CLAIMS_NAMESPACE;
 
     use super::*;
 
@@ -706,7 +747,113 @@ mod tests {
     }
 
     fn get_claims(deps: Deps, addr: &Addr) -> Vec<Claim> {
-        CLAIMS.query_claims(deps, addr).unwrap().claims
+        CLAIMS.query_claims(deps, addr, None, None).unwrap().claims
+    }
+
+    #[test]
+    fn migrate_moves_legacy_claims() {
+        let mut deps = mock_dependencies();
+        default_instantiate(deps.as_mut());
+        bond(deps.as_mut(), 12_000, 7_500, 4_000, 1);
+
+        // claims stored by the previous version, a list by address
+        let legacy: Map<&Addr, Vec<Claim>> = Map::new(LEGACY_CLAIMS_NAMESPACE);
+        let claims = vec![
+            Claim::new(4_500, Expiration::AtHeight(100)),
+            Claim::new(1_000, Expiration::AtHeight(50)),
+        ];
+        legacy
+            .save(&mut deps.storage, &Addr::unchecked(USER1), &claims)
+            .unwrap();
+
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        assert_eq!(res.attributes, vec![attr("claims_migration", "done")]);
+        assert_eq!(
+            get_claims(deps.as_ref(), &Addr::unchecked(USER1)),
+            vec![claims[1].clone(), claims[0].clone()]
+        );
+        assert_eq!(
+            CLAIMS.total_pending(&deps.storage).unwrap(),
+            Uint128::new(5_500)
+        );
+
+        // they can be claimed once released
+        let mut env = mock_env();
+        env.block.height = 60;
+        let res = execute_claim(deps.as_mut(), env, mock_info(USER1, &[])).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: USER1.into(),
+                amount: coins(1_000, DENOM),
+            })]
+        );
+
+        // running it again changes nothing
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        assert_eq!(
+            get_claims(deps.as_ref(), &Addr::unchecked(USER1)),
+            vec![claims[0].clone()]
+        );
+    }
+
+    #[test]
+    fn migrate_claims_in_batches() {
+        let mut deps = mock_dependencies();
+        default_instantiate(deps.as_mut());
+
+        let legacy: Map<&Addr, Vec<Claim>> = Map::new(LEGACY_CLAIMS_NAMESPACE);
+        let claims = vec![Claim::new(1_000, Expiration::AtHeight(50))];
+        let count = MIGRATE_CLAIMS_LIMIT + 5;
+        for i in 0..count {
+            let addr = Addr::unchecked(format!("user{:04}", i));
+            legacy.save(&mut deps.storage, &addr, &claims).unwrap();
+        }
+
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        assert_eq!(res.attributes, vec![attr("claims_migration", "pending")]);
+        let last = Addr::unchecked(format!("user{:04}", count - 1));
+        // the claims of an address not migrated yet are listed anyway
+        assert_eq!(get_claims(deps.as_ref(), &last), claims);
+
+        // an address not migrated yet can still claim
+        let mut env = mock_env();
+        env.block.height = 60;
+        let res = execute_claim(deps.as_mut(), env, mock_info(last.as_str(), &[])).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: last.to_string(),
+                amount: coins(1_000, DENOM),
+            })]
+        );
+
+        // a zero limit is rejected, as it would never make progress
+        let msg = ExecuteMsg::MigrateClaims { limit: Some(0) };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(USER1, &[]), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Std(StdError::generic_err(
+                "Migration limit must be greater than zero"
+            ))
+        );
+
+        // anyone can move the rest
+        let msg = ExecuteMsg::MigrateClaims { limit: Some(2) };
+        let res = execute(deps.as_mut(), mock_env(), mock_info(USER1, &[]), msg).unwrap();
+        assert_eq!(res.attributes[1], attr("claims_migration", "pending"));
+        let msg = ExecuteMsg::MigrateClaims { limit: None };
+        let res = execute(deps.as_mut(), mock_env(), mock_info(USER1, &[]), msg).unwrap();
+        assert_eq!(res.attributes[1], attr("claims_migration", "done"));
+
+        for i in 0..count - 1 {
+            let addr = Addr::unchecked(format!("user{:04}", i));
+            assert_eq!(get_claims(deps.as_ref(), &addr), claims);
+        }
+        assert_eq!(
+            CLAIMS.total_pending(&deps.storage).unwrap(),
+            Uint128::new(1_000 * (count as u128 - 1))
+        );
     }
 
     #[test]
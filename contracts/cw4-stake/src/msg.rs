@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 
 use cw20::{Cw20ReceiveMsg, Denom};
 pub use cw_controllers::ClaimsResponse;
-use cw_utils::Duration;
+use cw_utils::{Duration, Expiration};
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct InstantiateMsg {
@@ -30,9 +30,24 @@ pub enum ExecuteMsg {
     /// Claim is used to claim your native tokens that you previously "unbonded"
     /// after the contract-defined waiting period (eg. 1 week)
     Claim {},
+    /// Moves the claims stored by a previous version of this contract to the current layout,
+    /// up to `limit` addresses (not zero). Only needed when `migrate` reports the claims
+    /// migration as "pending"; anyone can call it.
+    MigrateClaims { limit: Option<u32> },
 
-    /// Change the admin
+    /// Change the admin immediately. Prefer `ProposeAdmin` to hand over to another address,
+    /// a mistyped address here locks the admin out for good.
     UpdateAdmin { admin: Option<String> },
+    /// Propose a new admin, who gets the admin rights once it sends `AcceptAdmin` (before
+    /// `expires`). Must be called by Admin
+    ProposeAdmin {
+        admin: String,
+        expires: Option<Expiration>,
+    },
+    /// Become the admin. Must be called by the pending admin
+    AcceptAdmin {},
+    /// Withdraw the pending admin proposal. Must be called by Admin
+    CancelAdminProposal {},
     /// Add a new hook to be informed of all membership changes. Must be called by Admin
     AddHook { addr: String },
     /// Remove a hook. Must be called by Admin
@@ -49,12 +64,19 @@ pub enum ReceiveMsg {
     Bond {},
 }
 
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct MigrateMsg {}
+
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
-    /// Claims shows the tokens in process of unbonding for this address
+    /// Claims shows the tokens in process of unbonding for this address, by release date.
+    /// Returns ClaimsResponse
     Claims {
         address: String,
+        /// Release date and id of the last claim received, see `ClaimsResponse.next_start_after`
+        start_after: Option<(Expiration, u64)>,
+        limit: Option<u32>,
     },
     // Show the number of tokens currently staked by this address.
     Staked {
@@ -63,6 +85,8 @@ pub enum QueryMsg {
 
     /// Return AdminResponse
     Admin {},
+    /// Return PendingAdminResponse
+    PendingAdmin {},
     /// Return TotalWeightResponse
     TotalWeight {},
     /// Returns MembersListResponse
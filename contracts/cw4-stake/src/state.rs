@@ -8,7 +8,10 @@ use cw_controllers::{Admin, Claims, Hooks};
 use cw_storage_plus::{Item, Map, SnapshotMap, Strategy};
 use cw_utils::Duration;
 
-pub const CLAIMS: Claims = Claims::new("claims");
+// namespace of the claims stored by previous versions, a list by address
+pub const LEGACY_CLAIMS_NAMESPACE: &str = "claims";
+pub const CLAIMS: Claims =
+    Claims::new_with_legacy("claims_by_release", "claims_total", LEGACY_CLAIMS_NAMESPACE);
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct Config {
@@ -19,7 +22,7 @@ pub struct Config {
     pub unbonding_period: Duration,
 }
 
-pub const ADMIN: Admin = Admin::new("admin");
+pub const ADMIN: Admin = Admin::new_with_pending("admin", "pending_admin");
 pub const HOOKS: Hooks = Hooks::new("cw4-hooks");
 pub const CONFIG: Item<Config> = Item::new("config");
 pub const TOTAL: Item<u64> = Item::new(TOTAL_KEY);
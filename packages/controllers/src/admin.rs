@@ -4,9 +4,11 @@ use std::fmt;
 use thiserror::Error;
 
 use cosmwasm_std::{
-    attr, Addr, CustomQuery, Deps, DepsMut, MessageInfo, Response, StdError, StdResult,
+    attr, Addr, CustomQuery, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult,
+    Storage,
 };
 use cw_storage_plus::Item;
+use cw_utils::Expiration;
 
 // TODO: should the return values end up in utils, so eg. cw4 can import them as well as this module?
 /// Returned from Admin.query_admin()
@@ -15,6 +17,21 @@ pub struct AdminResponse {
     pub admin: Option<String>,
 }
 
+/// Returned from Admin.query_pending_admin()
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PendingAdminResponse {
+    pub pending_admin: Option<String>,
+    /// When the proposal can no longer be accepted, None if there is no pending admin
+    pub expires: Option<Expiration>,
+}
+
+/// An admin transfer waiting to be accepted
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PendingAdmin {
+    pub admin: Addr,
+    pub expires: Expiration,
+}
+
 /// Errors returned from Admin
 #[derive(Error, Debug, PartialEq)]
 pub enum AdminError {
@@ -23,29 +40,71 @@ pub enum AdminError {
 
     #[error("Caller is not admin")]
     NotAdmin {},
+
+    #[error("Caller is not the pending admin")]
+    NotPendingAdmin {},
+
+    #[error("There is no pending admin")]
+    NoPendingAdmin {},
+
+    #[error("Admin proposal expired")]
+    Expired {},
+
+    #[error("Two-step admin transfer is not enabled")]
+    NoPendingStorage {},
 }
 
 // state/logic
-pub struct Admin<'a>(Item<'a, Option<Addr>>);
+pub struct Admin<'a> {
+    admin: Item<'a, Option<Addr>>,
+    pending: Option<Item<'a, PendingAdmin>>,
+}
 
 // this is the core business logic we expose
 impl<'a> Admin<'a> {
+    /// Admin with one-step transfers only. Proposing a new admin fails with `NoPendingStorage`.
     pub const fn new(namespace: &'a str) -> Self {
-        Admin(Item::new(namespace))
+        Admin {
+            admin: Item::new(namespace),
+            pending: None,
+        }
+    }
+
+    /// Admin with two-step transfers, keeping the pending proposal under `pending_namespace`.
+    /// The admin itself stays under `namespace`, so existing contracts can switch to this
+    /// without migrating their state.
+    pub const fn new_with_pending(namespace: &'a str, pending_namespace: &'a str) -> Self {
+        Admin {
+            admin: Item::new(namespace),
+            pending: Some(Item::new(pending_namespace)),
+        }
+    }
+
+    fn may_load_pending(&self, store: &dyn Storage) -> StdResult<Option<PendingAdmin>> {
+        match &self.pending {
+            Some(pending) => pending.may_load(store),
+            None => Ok(None),
+        }
+    }
+
+    fn remove_pending(&self, store: &mut dyn Storage) {
+        if let Some(pending) = &self.pending {
+            pending.remove(store);
+        }
     }
 
     pub fn set<Q: CustomQuery>(&self, deps: DepsMut<Q>, admin: Option<Addr>) -> StdResult<()> {
-        self.0.save(deps.storage, &admin)
+        self.admin.save(deps.storage, &admin)
     }
 
     pub fn get<Q: CustomQuery>(&self, deps: Deps<Q>) -> StdResult<Option<Addr>> {
-        self.0.load(deps.storage)
+        self.admin.load(deps.storage)
     }
 
     /// Returns Ok(true) if this is an admin, Ok(false) if not and an Error if
     /// we hit an error with Api or Storage usage
     pub fn is_admin<Q: CustomQuery>(&self, deps: Deps<Q>, caller: &Addr) -> StdResult<bool> {
-        match self.0.load(deps.storage)? {
+        match self.admin.load(deps.storage)? {
             Some(owner) => Ok(caller == &owner),
             None => Ok(false),
         }
@@ -86,22 +145,158 @@ impl<'a> Admin<'a> {
             attr("sender", info.sender),
         ];
 
+        self.remove_pending(deps.storage);
         self.set(deps, new_admin)?;
 
         Ok(Response::new().add_attributes(attributes))
     }
 
+    /// First step of a two-step admin transfer: the admin proposes a new admin, who then needs
+    /// to accept it before `expires`. This replaces any previous proposal.
+    pub fn execute_propose_admin<C, Q: CustomQuery>(
+        &self,
+        deps: DepsMut<Q>,
+        env: Env,
+        info: MessageInfo,
+        new_admin: Addr,
+        expires: Option<Expiration>,
+    ) -> Result<Response<C>, AdminError>
+    where
+        C: Clone + fmt::Debug + PartialEq + JsonSchema,
+    {
+        self.assert_admin(deps.as_ref(), &info.sender)?;
+        let pending_store = self
+            .pending
+            .as_ref()
+            .ok_or(AdminError::NoPendingStorage {})?;
+        let expires = expires.unwrap_or_default();
+        if expires.is_expired(&env.block) {
+            return Err(AdminError::Expired {});
+        }
+
+        let attributes = vec![
+            attr("action", "propose_admin"),
+            attr("pending_admin", new_admin.as_str()),
+            attr("expires", expires.to_string()),
+            attr("sender", info.sender),
+        ];
+
+        let pending = PendingAdmin {
+            admin: new_admin,
+            expires,
+        };
+        pending_store.save(deps.storage, &pending)?;
+
+        Ok(Response::new().add_attributes(attributes))
+    }
+
+    /// Second step of a two-step admin transfer: the pending admin becomes the admin
+    pub fn execute_accept_admin<C, Q: CustomQuery>(
+        &self,
+        deps: DepsMut<Q>,
+        env: Env,
+        info: MessageInfo,
+    ) -> Result<Response<C>, AdminError>
+    where
+        C: Clone + fmt::Debug + PartialEq + JsonSchema,
+    {
+        let pending = self
+            .may_load_pending(deps.storage)?
+            .ok_or(AdminError::NoPendingAdmin {})?;
+        if pending.admin != info.sender {
+            return Err(AdminError::NotPendingAdmin {});
+        }
+        if pending.expires.is_expired(&env.block) {
+            return Err(AdminError::Expired {});
+        }
+
+        let attributes = vec![
+            attr("action", "accept_admin"),
+            attr("admin", info.sender.as_str()),
+        ];
+
+        self.remove_pending(deps.storage);
+        self.set(deps, Some(pending.admin))?;
+
+        Ok(Response::new().add_attributes(attributes))
+    }
+
+    /// The admin withdraws the pending proposal
+    pub fn execute_cancel_admin_proposal<C, Q: CustomQuery>(
+        &self,
+        deps: DepsMut<Q>,
+        info: MessageInfo,
+    ) -> Result<Response<C>, AdminError>
+    where
+        C: Clone + fmt::Debug + PartialEq + JsonSchema,
+    {
+        self.assert_admin(deps.as_ref(), &info.sender)?;
+        if self.may_load_pending(deps.storage)?.is_none() {
+            return Err(AdminError::NoPendingAdmin {});
+        }
+        self.remove_pending(deps.storage);
+
+        let attributes = vec![
+            attr("action", "cancel_admin_proposal"),
+            attr("sender", info.sender),
+        ];
+        Ok(Response::new().add_attributes(attributes))
+    }
+
+    /// The admin gives up control for good, also dropping any pending proposal
+    pub fn execute_renounce_admin<C, Q: CustomQuery>(
+        &self,
+        deps: DepsMut<Q>,
+        info: MessageInfo,
+    ) -> Result<Response<C>, AdminError>
+    where
+        C: Clone + fmt::Debug + PartialEq + JsonSchema,
+    {
+        self.assert_admin(deps.as_ref(), &info.sender)?;
+
+        let attributes = vec![
+            attr("action", "renounce_admin"),
+            attr("sender", info.sender),
+        ];
+
+        self.remove_pending(deps.storage);
+        self.set(deps, None)?;
+
+        Ok(Response::new().add_attributes(attributes))
+    }
+
     pub fn query_admin<Q: CustomQuery>(&self, deps: Deps<Q>) -> StdResult<AdminResponse> {
         let admin = self.get(deps)?.map(String::from);
         Ok(AdminResponse { admin })
     }
+
+    /// Returns the pending admin, if the proposal did not expire yet
+    pub fn query_pending_admin<Q: CustomQuery>(
+        &self,
+        deps: Deps<Q>,
+        env: Env,
+    ) -> StdResult<PendingAdminResponse> {
+        let pending = self
+            .may_load_pending(deps.storage)?
+            .filter(|pending| !pending.expires.is_expired(&env.block));
+        Ok(match pending {
+            Some(pending) => PendingAdminResponse {
+                pending_admin: Some(pending.admin.into()),
+                expires: Some(pending.expires),
+            },
+            None => PendingAdminResponse {
+                pending_admin: None,
+                expires: None,
+            },
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    use cosmwasm_std::testing::{mock_dependencies, mock_info};
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
     use cosmwasm_std::Empty;
 
     #[test]
@@ -181,4 +376,223 @@ mod tests {
         let res = control.query_admin(deps.as_ref()).unwrap();
         assert_eq!(Some(friend.to_string()), res.admin);
     }
+
+    #[test]
+    fn two_step_transfer() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+
+        let control = Admin::new_with_pending("foo", "foo_pending");
+        let owner = Addr::unchecked("big boss");
+        let friend = Addr::unchecked("buddy");
+        let imposter = Addr::unchecked("imposter");
+        control.set(deps.as_mut(), Some(owner.clone())).unwrap();
+
+        // nothing to accept yet
+        let err = control
+            .execute_accept_admin::<Empty, Empty>(
+                deps.as_mut(),
+                env.clone(),
+                mock_info(friend.as_ref(), &[]),
+            )
+            .unwrap_err();
+        assert_eq!(AdminError::NoPendingAdmin {}, err);
+
+        // only the admin can propose, and not an expired proposal
+        let err = control
+            .execute_propose_admin::<Empty, Empty>(
+                deps.as_mut(),
+                env.clone(),
+                mock_info(imposter.as_ref(), &[]),
+                imposter.clone(),
+                None,
+            )
+            .unwrap_err();
+        assert_eq!(AdminError::NotAdmin {}, err);
+        let err = control
+            .execute_propose_admin::<Empty, Empty>(
+                deps.as_mut(),
+                env.clone(),
+                mock_info(owner.as_ref(), &[]),
+                friend.clone(),
+                Some(Expiration::AtHeight(env.block.height)),
+            )
+            .unwrap_err();
+        assert_eq!(AdminError::Expired {}, err);
+
+        let expires = Expiration::AtHeight(env.block.height + 10);
+        control
+            .execute_propose_admin::<Empty, Empty>(
+                deps.as_mut(),
+                env.clone(),
+                mock_info(owner.as_ref(), &[]),
+                friend.clone(),
+                Some(expires),
+            )
+            .unwrap();
+        let res = control
+            .query_pending_admin(deps.as_ref(), env.clone())
+            .unwrap();
+        assert_eq!(
+            res,
+            PendingAdminResponse {
+                pending_admin: Some(friend.to_string()),
+                expires: Some(expires),
+            }
+        );
+        // the admin does not change until accepted
+        let res = control.query_admin(deps.as_ref()).unwrap();
+        assert_eq!(Some(owner.to_string()), res.admin);
+
+        // only by the pending admin
+        let err = control
+            .execute_accept_admin::<Empty, Empty>(
+                deps.as_mut(),
+                env.clone(),
+                mock_info(imposter.as_ref(), &[]),
+            )
+            .unwrap_err();
+        assert_eq!(AdminError::NotPendingAdmin {}, err);
+
+        // before it expires
+        env.block.height += 10;
+        let err = control
+            .execute_accept_admin::<Empty, Empty>(
+                deps.as_mut(),
+                env.clone(),
+                mock_info(friend.as_ref(), &[]),
+            )
+            .unwrap_err();
+        assert_eq!(AdminError::Expired {}, err);
+        let res = control
+            .query_pending_admin(deps.as_ref(), env.clone())
+            .unwrap();
+        assert_eq!(res.pending_admin, None);
+
+        // propose again without expiration
+        control
+            .execute_propose_admin::<Empty, Empty>(
+                deps.as_mut(),
+                env.clone(),
+                mock_info(owner.as_ref(), &[]),
+                friend.clone(),
+                None,
+            )
+            .unwrap();
+        let res = control
+            .execute_accept_admin::<Empty, Empty>(
+                deps.as_mut(),
+                env.clone(),
+                mock_info(friend.as_ref(), &[]),
+            )
+            .unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![attr("action", "accept_admin"), attr("admin", "buddy")]
+        );
+        assert!(control.is_admin(deps.as_ref(), &friend).unwrap());
+        let res = control
+            .query_pending_admin(deps.as_ref(), env.clone())
+            .unwrap();
+        assert_eq!(res.pending_admin, None);
+
+        // the stored admin is still readable as before
+        let legacy: Item<Option<Addr>> = Item::new("foo");
+        assert_eq!(legacy.load(&deps.storage).unwrap(), Some(friend.clone()));
+
+        // without a pending namespace, only one-step transfers are possible
+        let one_step = Admin::new("foo");
+        let err = one_step
+            .execute_propose_admin::<Empty, Empty>(
+                deps.as_mut(),
+                env.clone(),
+                mock_info(friend.as_ref(), &[]),
+                owner,
+                None,
+            )
+            .unwrap_err();
+        assert_eq!(AdminError::NoPendingStorage {}, err);
+        let res = one_step.query_pending_admin(deps.as_ref(), env).unwrap();
+        assert_eq!(res.pending_admin, None);
+    }
+
+    #[test]
+    fn cancel_and_renounce() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+
+        let control = Admin::new_with_pending("foo", "foo_pending");
+        let owner = Addr::unchecked("big boss");
+        let friend = Addr::unchecked("buddy");
+        control.set(deps.as_mut(), Some(owner.clone())).unwrap();
+
+        let err = control
+            .execute_cancel_admin_proposal::<Empty, Empty>(
+                deps.as_mut(),
+                mock_info(owner.as_ref(), &[]),
+            )
+            .unwrap_err();
+        assert_eq!(AdminError::NoPendingAdmin {}, err);
+
+        control
+            .execute_propose_admin::<Empty, Empty>(
+                deps.as_mut(),
+                env.clone(),
+                mock_info(owner.as_ref(), &[]),
+                friend.clone(),
+                None,
+            )
+            .unwrap();
+        let err = control
+            .execute_cancel_admin_proposal::<Empty, Empty>(
+                deps.as_mut(),
+                mock_info(friend.as_ref(), &[]),
+            )
+            .unwrap_err();
+        assert_eq!(AdminError::NotAdmin {}, err);
+        control
+            .execute_cancel_admin_proposal::<Empty, Empty>(
+                deps.as_mut(),
+                mock_info(owner.as_ref(), &[]),
+            )
+            .unwrap();
+        let err = control
+            .execute_accept_admin::<Empty, Empty>(
+                deps.as_mut(),
+                env.clone(),
+                mock_info(friend.as_ref(), &[]),
+            )
+            .unwrap_err();
+        assert_eq!(AdminError::NoPendingAdmin {}, err);
+
+        // renouncing drops the pending proposal as well
+        control
+            .execute_propose_admin::<Empty, Empty>(
+                deps.as_mut(),
+                env.clone(),
+                mock_info(owner.as_ref(), &[]),
+                friend.clone(),
+                None,
+            )
+            .unwrap();
+        let err = control
+            .execute_renounce_admin::<Empty, Empty>(deps.as_mut(), mock_info(friend.as_ref(), &[]))
+            .unwrap_err();
+        assert_eq!(AdminError::NotAdmin {}, err);
+        let res = control
+            .execute_renounce_admin::<Empty, Empty>(deps.as_mut(), mock_info(owner.as_ref(), &[]))
+            .unwrap();
+        assert_eq!(res.attributes[0], attr("action", "renounce_admin"));
+        let pending: Item<PendingAdmin> = Item::new("foo_pending");
+        assert_eq!(pending.may_load(&deps.storage).unwrap(), None);
+        assert_eq!(control.get(deps.as_ref()).unwrap(), None);
+        let err = control
+            .execute_accept_admin::<Empty, Empty>(
+                deps.as_mut(),
+                env,
+                mock_info(friend.as_ref(), &[]),
+            )
+            .unwrap_err();
+        assert_eq!(AdminError::NoPendingAdmin {}, err);
+    }
 }
@@ -1,14 +1,22 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Addr, BlockInfo, CustomQuery, Deps, StdResult, Storage, Uint128};
-use cw_storage_plus::Map;
-use cw_utils::Expiration;
+use cosmwasm_std::{
+    Addr, BlockInfo, CustomQuery, Deps, Order, StdError, StdResult, Storage, Timestamp, Uint128,
+};
+use cw_storage_plus::{Bound, Item, Map};
+use cw_utils::{Expiration, MigrationProgress};
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
 
 // TODO: pull into utils?
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct ClaimsResponse {
     pub claims: Vec<Claim>,
+    /// Where the next page starts (release date and id of the last claim), if this page is full
+    pub next_start_after: Option<(Expiration, u64)>,
 }
 
 // TODO: pull into utils?
@@ -27,12 +35,78 @@ impl Claim {
     }
 }
 
-// TODO: revisit design (split each claim on own key?)
-pub struct Claims<'a>(Map<'a, &'a Addr, Vec<Claim>>);
+/// Claims tracks the tokens an address can get back once a release date is reached
+/// (e.g. at the end of an unbonding period).
+///
+/// Each claim is stored on its own key, `(address, release date, id)`, so the mature claims of an
+/// address are read with a range, whatever the number of claims still waiting.
+pub struct Claims<'a> {
+    claims: Map<'a, (&'a Addr, Vec<u8>, u64), Claim>,
+    // sum of all the claims not paid yet
+    total_pending: Item<'a, Uint128>,
+    // claims stored by previous versions, a list by address, until they are moved
+    legacy: Option<Map<'a, &'a Addr, Vec<Claim>>>,
+}
+
+// key of a claim within the claims of an address: encoded release date and id
+type ClaimKey = (Vec<u8>, u64);
+
+/// Encodes the release date of a claim as part of its key.
+/// Claims are sorted by kind of expiration first (heights, then times, then never), and by
+/// height or time inside each kind.
+fn release_key(release_at: &Expiration) -> Vec<u8> {
+    let (kind, value) = match release_at {
+        Expiration::AtHeight(height) => (0u8, *height),
+        Expiration::AtTime(time) => (1u8, time.nanos()),
+        Expiration::Never {} => (2u8, 0),
+    };
+    let mut key = vec![kind];
+    key.extend_from_slice(&value.to_be_bytes());
+    key
+}
+
+/// Adds the legacy claims of an address to its current ones, sorted by key, with the ids
+/// `move_legacy_claims` gives them
+fn merge_legacy_claims(
+    mut claims: Vec<(ClaimKey, Claim)>,
+    legacy: Vec<Claim>,
+) -> Vec<(ClaimKey, Claim)> {
+    for claim in legacy {
+        let release = release_key(&claim.release_at);
+        let id = claims
+            .iter()
+            .filter(|((key, _), _)| *key == release)
+            .map(|((_, id), _)| id + 1)
+            .max()
+            .unwrap_or(0);
+        claims.push(((release, id), claim));
+    }
+    claims.sort_by(|(a, _), (b, _)| a.cmp(b));
+    claims
+}
 
 impl<'a> Claims<'a> {
-    pub const fn new(storage_key: &'a str) -> Self {
-        Claims(Map::new(storage_key))
+    pub const fn new(storage_key: &'a str, total_pending_key: &'a str) -> Self {
+        Claims {
+            claims: Map::new(storage_key),
+            total_pending: Item::new(total_pending_key),
+            legacy: None,
+        }
+    }
+
+    /// Like `new`, for a contract that stored claims with previous versions of `Claims`, under
+    /// `legacy_key`. They are moved by `migrate_legacy_claims` to `storage_key`, which must be
+    /// a different namespace.
+    pub const fn new_with_legacy(
+        storage_key: &'a str,
+        total_pending_key: &'a str,
+        legacy_key: &'a str,
+    ) -> Self {
+        Claims {
+            claims: Map::new(storage_key),
+            total_pending: Item::new(total_pending_key),
+            legacy: Some(Map::new(legacy_key)),
+        }
     }
 
     /// This creates a claim, such that the given address can claim an amount of tokens after
@@ -44,17 +118,27 @@ impl<'a> Claims<'a> {
         amount: Uint128,
         release_at: Expiration,
     ) -> StdResult<()> {
+        // claims of an address with the same release date are numbered in order of creation
+        let release = release_key(&release_at);
+        let id = self
+            .claims
+            .prefix((addr, release.clone()))
+            .keys(storage, None, None, Order::Descending)
+            .next()
+            .transpose()?
+            .map_or(0, |id| id + 1);
+
         // add a claim to this user to get their tokens after the unbonding period
-        self.0.update(storage, addr, |old| -> StdResult<_> {
-            let mut claims = old.unwrap_or_default();
-            claims.push(Claim { amount, release_at });
-            Ok(claims)
-        })?;
-        Ok(())
+        self.claims
+            .save(storage, (addr, release, id), &Claim { amount, release_at })?;
+        let total = self.total_pending(storage)?.checked_add(amount)?;
+        self.total_pending.save(storage, &total)
     }
 
-    /// This iterates over all mature claims for the address, and removes them, up to an optional cap.
-    /// it removes the finished claims and returns the total amount of tokens to be released.
+    /// This iterates over all mature claims for the address, from the oldest one, and removes
+    /// them, up to an optional cap. If the cap is reached in the middle of a claim, that claim
+    /// is paid partially and keeps the rest.
+    /// It returns the total amount of tokens to be released.
     pub fn claim_tokens(
         &self,
         storage: &mut dyn Storage,
@@ -63,36 +147,188 @@ impl<'a> Claims<'a> {
         cap: Option<Uint128>,
     ) -> StdResult<Uint128> {
         let mut to_send = Uint128::zero();
-        self.0.update(storage, addr, |claim| -> StdResult<_> {
-            let (_send, waiting): (Vec<_>, _) =
-                claim.unwrap_or_default().into_iter().partition(|c| {
-                    // if mature and we can pay fully, then include in _send
-                    if c.release_at.is_expired(block) {
-                        if let Some(limit) = cap {
-                            if to_send + c.amount > limit {
-                                return false;
-                            }
-                        }
-                        // TODO: handle partial paying claims?
-                        to_send += c.amount;
-                        true
-                    } else {
-                        // not to send, leave in waiting and save again
-                        false
-                    }
-                });
-            Ok(waiting)
-        })?;
+        for (key, mut claim) in self.mature_claims(storage, addr, block)? {
+            if let Some(limit) = cap {
+                let left = limit.saturating_sub(to_send);
+                if left.is_zero() {
+                    break;
+                }
+                if claim.amount > left {
+                    to_send += left;
+                    claim.amount -= left;
+                    self.claims.save(storage, (addr, key.0, key.1), &claim)?;
+                    break;
+                }
+            }
+            to_send += claim.amount;
+            self.claims.remove(storage, (addr, key.0, key.1));
+        }
+
+        if !to_send.is_zero() {
+            let total = self.total_pending(storage)?.checked_sub(to_send)?;
+            self.total_pending.save(storage, &total)?;
+        }
         Ok(to_send)
     }
 
+    /// Returns the claims of the address that can be released at the given block, oldest first
+    fn mature_claims(
+        &self,
+        storage: &dyn Storage,
+        addr: &Addr,
+        block: &BlockInfo,
+    ) -> StdResult<Vec<(ClaimKey, Claim)>> {
+        // one range per kind of expiration, from the earliest date to the current one
+        let ranges = [
+            (Expiration::AtHeight(0), Expiration::AtHeight(block.height)),
+            (
+                Expiration::AtTime(Timestamp::from_nanos(0)),
+                Expiration::AtTime(block.time),
+            ),
+        ];
+        let mut mature = vec![];
+        for (earliest, latest) in ranges.iter() {
+            let min = Bound::inclusive((release_key(earliest), 0));
+            let max = Bound::inclusive((release_key(latest), u64::MAX));
+            for item in
+                self.claims
+                    .sub_prefix(addr)
+                    .range(storage, Some(min), Some(max), Order::Ascending)
+            {
+                mature.push(item?);
+            }
+        }
+        Ok(mature)
+    }
+
+    /// Returns the sum of the claims of all addresses, not released yet
+    pub fn total_pending(&self, storage: &dyn Storage) -> StdResult<Uint128> {
+        Ok(self.total_pending.may_load(storage)?.unwrap_or_default())
+    }
+
+    /// Lists the claims of the address, by release date, and by order of creation for the same
+    /// release date. The next page starts after `start_after`, the release date and id of the
+    /// last claim received, as returned in `next_start_after`.
+    /// Legacy claims not migrated yet are listed as they will be once moved.
     pub fn query_claims<Q: CustomQuery>(
         &self,
         deps: Deps<Q>,
         address: &Addr,
+        start_after: Option<(Expiration, u64)>,
+        limit: Option<u32>,
     ) -> StdResult<ClaimsResponse> {
-        let claims = self.0.may_load(deps.storage, address)?.unwrap_or_default();
-        Ok(ClaimsResponse { claims })
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = start_after.map(|(release_at, id)| (release_key(&release_at), id));
+
+        let legacy = match &self.legacy {
+            Some(legacy) => legacy.may_load(deps.storage, address)?,
+            None => None,
+        };
+        let page = match legacy {
+            None => self
+                .claims
+                .sub_prefix(address)
+                .range(
+                    deps.storage,
+                    start.map(Bound::exclusive),
+                    None,
+                    Order::Ascending,
+                )
+                .take(limit)
+                .collect::<StdResult<Vec<_>>>()?,
+            // not migrated yet: list the claims as they will be once moved
+            Some(legacy) => {
+                let claims = self
+                    .claims
+                    .sub_prefix(address)
+                    .range(deps.storage, None, None, Order::Ascending)
+                    .collect::<StdResult<Vec<_>>>()?;
+                merge_legacy_claims(claims, legacy)
+                    .into_iter()
+                    .filter(|(key, _)| match &start {
+                        Some(start) => key > start,
+                        None => true,
+                    })
+                    .take(limit)
+                    .collect()
+            }
+        };
+        let next_start_after = match page.last() {
+            Some(((_, id), claim)) if page.len() == limit => Some((claim.release_at, *id)),
+            _ => None,
+        };
+        let claims = page.into_iter().map(|(_, claim)| claim).collect();
+        Ok(ClaimsResponse {
+            claims,
+            next_start_after,
+        })
+    }
+
+    /// Moves the claims stored by previous versions of `Claims` (see `new_with_legacy`) to the
+    /// current layout, `limit` addresses at a time.
+    /// Returns `MigrationProgress::Pending` while some addresses are left: call it again
+    /// (e.g. from a later migration, or an execute message) to continue. Meanwhile, use
+    /// `migrate_legacy_claims_of` before handling the claims of an address.
+    /// `limit` must not be zero.
+    pub fn migrate_legacy_claims(
+        &self,
+        storage: &mut dyn Storage,
+        limit: usize,
+    ) -> StdResult<MigrationProgress> {
+        if limit == 0 {
+            return Err(StdError::generic_err(
+                "Migration limit must be greater than zero",
+            ));
+        }
+        let legacy = match &self.legacy {
+            Some(legacy) => legacy,
+            None => return Ok(MigrationProgress::Done),
+        };
+        let batch = legacy
+            .range(storage, None, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let done = batch.len() < limit;
+        for (addr, claims) in batch {
+            self.move_legacy_claims(storage, legacy, &addr, claims)?;
+        }
+
+        if done {
+            Ok(MigrationProgress::Done)
+        } else {
+            Ok(MigrationProgress::Pending)
+        }
+    }
+
+    /// Moves the legacy claims of one address (see `migrate_legacy_claims`), if there are any
+    pub fn migrate_legacy_claims_of(
+        &self,
+        storage: &mut dyn Storage,
+        addr: &Addr,
+    ) -> StdResult<()> {
+        let legacy = match &self.legacy {
+            Some(legacy) => legacy,
+            None => return Ok(()),
+        };
+        match legacy.may_load(storage, addr)? {
+            Some(claims) => self.move_legacy_claims(storage, legacy, addr, claims),
+            None => Ok(()),
+        }
+    }
+
+    fn move_legacy_claims(
+        &self,
+        storage: &mut dyn Storage,
+        legacy: &Map<&Addr, Vec<Claim>>,
+        addr: &Addr,
+        claims: Vec<Claim>,
+    ) -> StdResult<()> {
+        legacy.remove(storage, addr);
+        for claim in claims {
+            self.create_claim(storage, addr, claim.amount, claim.release_at)?;
+        }
+        Ok(())
     }
 }
 
@@ -107,6 +343,17 @@ mod test {
     const TEST_AMOUNT: u128 = 1000u128;
     const TEST_EXPIRATION: Expiration = Expiration::AtHeight(10);
 
+    fn load_claims(claims: &Claims, storage: &dyn Storage, addr: &str) -> Vec<Claim> {
+        let addr = Addr::unchecked(addr);
+        claims
+            .claims
+            .sub_prefix(&addr)
+            .range(storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(_, claim)| claim))
+            .collect::<StdResult<_>>()
+            .unwrap()
+    }
+
     #[test]
     fn can_create_claim() {
         let claim = Claim::new(TEST_AMOUNT, TEST_EXPIRATION);
@@ -117,11 +364,11 @@ mod test {
     #[test]
     fn can_create_claims() {
         let deps = mock_dependencies();
-        let claims = Claims::new("claims");
+        let claims = Claims::new("claims", "claims_total");
         // Assert that claims creates a map and there are no keys in the map.
         assert_eq!(
             claims
-                .0
+                .claims
                 .range_raw(&deps.storage, None, None, Order::Ascending)
                 .collect::<StdResult<Vec<_>>>()
                 .unwrap()
@@ -133,7 +380,7 @@ mod test {
     #[test]
     fn check_create_claim_updates_map() {
         let mut deps = mock_dependencies();
-        let claims = Claims::new("claims");
+        let claims = Claims::new("claims", "claims_total");
 
         claims
             .create_claim(
@@ -145,10 +392,7 @@ mod test {
             .unwrap();
 
         // Assert that claims creates a map and there is one claim for the address.
-        let saved_claims = claims
-            .0
-            .load(deps.as_mut().storage, &Addr::unchecked("addr"))
-            .unwrap();
+        let saved_claims = load_claims(&claims, &deps.storage, "addr");
         assert_eq!(saved_claims.len(), 1);
         assert_eq!(saved_claims[0].amount, TEST_AMOUNT.into());
         assert_eq!(saved_claims[0].release_at, TEST_EXPIRATION);
@@ -164,10 +408,7 @@ mod test {
             .unwrap();
 
         // Assert that both claims exist for the address.
-        let saved_claims = claims
-            .0
-            .load(deps.as_mut().storage, &Addr::unchecked("addr"))
-            .unwrap();
+        let saved_claims = load_claims(&claims, &deps.storage, "addr");
         assert_eq!(saved_claims.len(), 2);
         assert_eq!(saved_claims[0].amount, TEST_AMOUNT.into());
         assert_eq!(saved_claims[0].release_at, TEST_EXPIRATION);
@@ -185,15 +426,9 @@ mod test {
             .unwrap();
 
         // Assert that both claims exist for the address.
-        let saved_claims = claims
-            .0
-            .load(deps.as_mut().storage, &Addr::unchecked("addr"))
-            .unwrap();
+        let saved_claims = load_claims(&claims, &deps.storage, "addr");
 
-        let saved_claims_addr2 = claims
-            .0
-            .load(deps.as_mut().storage, &Addr::unchecked("addr2"))
-            .unwrap();
+        let saved_claims_addr2 = load_claims(&claims, &deps.storage, "addr2");
         assert_eq!(saved_claims.len(), 2);
         assert_eq!(saved_claims_addr2.len(), 1);
     }
@@ -201,7 +436,7 @@ mod test {
     #[test]
     fn test_claim_tokens_with_no_claims() {
         let mut deps = mock_dependencies();
-        let claims = Claims::new("claims");
+        let claims = Claims::new("claims", "claims_total");
 
         let amount = claims
             .claim_tokens(
@@ -211,10 +446,7 @@ mod test {
                 None,
             )
             .unwrap();
-        let saved_claims = claims
-            .0
-            .load(deps.as_mut().storage, &Addr::unchecked("addr"))
-            .unwrap();
+        let saved_claims = load_claims(&claims, &deps.storage, "addr");
 
         assert_eq!(amount, Uint128::zero());
         assert_eq!(saved_claims.len(), 0);
@@ -223,7 +455,7 @@ mod test {
     #[test]
     fn test_claim_tokens_with_no_released_claims() {
         let mut deps = mock_dependencies();
-        let claims = Claims::new("claims");
+        let claims = Claims::new("claims", "claims_total");
 
         claims
             .create_claim(
@@ -255,10 +487,7 @@ mod test {
             )
             .unwrap();
 
-        let saved_claims = claims
-            .0
-            .load(deps.as_mut().storage, &Addr::unchecked("addr"))
-            .unwrap();
+        let saved_claims = load_claims(&claims, &deps.storage, "addr");
 
         assert_eq!(amount, Uint128::zero());
         assert_eq!(saved_claims.len(), 2);
@@ -271,7 +500,7 @@ mod test {
     #[test]
     fn test_claim_tokens_with_one_released_claim() {
         let mut deps = mock_dependencies();
-        let claims = Claims::new("claims");
+        let claims = Claims::new("claims", "claims_total");
 
         claims
             .create_claim(
@@ -303,10 +532,7 @@ mod test {
             )
             .unwrap();
 
-        let saved_claims = claims
-            .0
-            .load(deps.as_mut().storage, &Addr::unchecked("addr"))
-            .unwrap();
+        let saved_claims = load_claims(&claims, &deps.storage, "addr");
 
         assert_eq!(amount, TEST_AMOUNT.into());
         assert_eq!(saved_claims.len(), 1);
@@ -317,7 +543,7 @@ mod test {
     #[test]
     fn test_claim_tokens_with_all_released_claims() {
         let mut deps = mock_dependencies();
-        let claims = Claims::new("claims");
+        let claims = Claims::new("claims", "claims_total");
 
         claims
             .create_claim(
@@ -349,10 +575,7 @@ mod test {
             )
             .unwrap();
 
-        let saved_claims = claims
-            .0
-            .load(deps.as_mut().storage, &Addr::unchecked("addr"))
-            .unwrap();
+        let saved_claims = load_claims(&claims, &deps.storage, "addr");
 
         assert_eq!(amount, (TEST_AMOUNT + TEST_AMOUNT + 100).into());
         assert_eq!(saved_claims.len(), 0);
@@ -361,7 +584,7 @@ mod test {
     #[test]
     fn test_claim_tokens_with_zero_cap() {
         let mut deps = mock_dependencies();
-        let claims = Claims::new("claims");
+        let claims = Claims::new("claims", "claims_total");
 
         claims
             .create_claim(
@@ -393,10 +616,7 @@ mod test {
             )
             .unwrap();
 
-        let saved_claims = claims
-            .0
-            .load(deps.as_mut().storage, &Addr::unchecked("addr"))
-            .unwrap();
+        let saved_claims = load_claims(&claims, &deps.storage, "addr");
 
         assert_eq!(amount, Uint128::zero());
         assert_eq!(saved_claims.len(), 2);
@@ -409,7 +629,7 @@ mod test {
     #[test]
     fn test_claim_tokens_with_cap_greater_than_pending_claims() {
         let mut deps = mock_dependencies();
-        let claims = Claims::new("claims");
+        let claims = Claims::new("claims", "claims_total");
 
         claims
             .create_claim(
@@ -441,19 +661,16 @@ mod test {
             )
             .unwrap();
 
-        let saved_claims = claims
-            .0
-            .load(deps.as_mut().storage, &Addr::unchecked("addr"))
-            .unwrap();
+        let saved_claims = load_claims(&claims, &deps.storage, "addr");
 
         assert_eq!(amount, (TEST_AMOUNT + TEST_AMOUNT + 100).into());
         assert_eq!(saved_claims.len(), 0);
     }
 
     #[test]
-    fn test_claim_tokens_with_cap_only_one_claim_released() {
+    fn test_claim_tokens_with_cap_pays_last_claim_partially() {
         let mut deps = mock_dependencies();
-        let claims = Claims::new("claims");
+        let claims = Claims::new("claims", "claims_total");
 
         claims
             .create_claim(
@@ -475,7 +692,7 @@ mod test {
 
         let mut env = mock_env();
         env.block.height = 1000;
-        // the address has two claims and the first one can be released
+        // the oldest claim is paid fully, and the other one up to the cap
         let amount = claims
             .claim_tokens(
                 deps.as_mut().storage,
@@ -484,21 +701,22 @@ mod test {
                 Some((TEST_AMOUNT + 50).into()),
             )
             .unwrap();
-        assert_eq!(amount, (TEST_AMOUNT).into());
+        assert_eq!(amount, (TEST_AMOUNT + 50).into());
 
-        let saved_claims = claims
-            .0
-            .load(deps.as_mut().storage, &Addr::unchecked("addr"))
-            .unwrap();
+        let saved_claims = load_claims(&claims, &deps.storage, "addr");
         assert_eq!(saved_claims.len(), 1);
-        assert_eq!(saved_claims[0].amount, (TEST_AMOUNT + 100).into());
+        assert_eq!(saved_claims[0].amount, (TEST_AMOUNT + 50).into());
         assert_eq!(saved_claims[0].release_at, Expiration::AtHeight(10));
+        assert_eq!(
+            claims.total_pending(&deps.storage).unwrap(),
+            Uint128::from(TEST_AMOUNT + 50)
+        );
     }
 
     #[test]
-    fn test_claim_tokens_with_cap_too_low_no_claims_released() {
+    fn test_claim_tokens_with_cap_lower_than_first_claim() {
         let mut deps = mock_dependencies();
-        let claims = Claims::new("claims");
+        let claims = Claims::new("claims", "claims_total");
 
         claims
             .create_claim(
@@ -520,7 +738,7 @@ mod test {
 
         let mut env = mock_env();
         env.block.height = 1000;
-        // the address has two claims and the first one can be released
+        // only the oldest claim is paid, partially
         let amount = claims
             .claim_tokens(
                 deps.as_mut().storage,
@@ -529,23 +747,20 @@ mod test {
                 Some((TEST_AMOUNT - 50).into()),
             )
             .unwrap();
-        assert_eq!(amount, Uint128::zero());
+        assert_eq!(amount, (TEST_AMOUNT - 50).into());
 
-        let saved_claims = claims
-            .0
-            .load(deps.as_mut().storage, &Addr::unchecked("addr"))
-            .unwrap();
+        let saved_claims = load_claims(&claims, &deps.storage, "addr");
         assert_eq!(saved_claims.len(), 2);
-        assert_eq!(saved_claims[0].amount, (TEST_AMOUNT + 100).into());
-        assert_eq!(saved_claims[0].release_at, Expiration::AtHeight(10));
-        assert_eq!(saved_claims[1].amount, (TEST_AMOUNT).into());
-        assert_eq!(saved_claims[1].release_at, Expiration::AtHeight(5));
+        assert_eq!(saved_claims[0].amount, 50u128.into());
+        assert_eq!(saved_claims[0].release_at, Expiration::AtHeight(5));
+        assert_eq!(saved_claims[1].amount, (TEST_AMOUNT + 100).into());
+        assert_eq!(saved_claims[1].release_at, Expiration::AtHeight(10));
     }
 
     #[test]
     fn test_query_claims_returns_correct_claims() {
         let mut deps = mock_dependencies();
-        let claims = Claims::new("claims");
+        let claims = Claims::new("claims", "claims_total");
 
         claims
             .create_claim(
@@ -557,19 +772,16 @@ mod test {
             .unwrap();
 
         let queried_claims = claims
-            .query_claims(deps.as_ref(), &Addr::unchecked("addr"))
-            .unwrap();
-        let saved_claims = claims
-            .0
-            .load(deps.as_mut().storage, &Addr::unchecked("addr"))
+            .query_claims(deps.as_ref(), &Addr::unchecked("addr"), None, None)
             .unwrap();
+        let saved_claims = load_claims(&claims, &deps.storage, "addr");
         assert_eq!(queried_claims.claims, saved_claims);
     }
 
     #[test]
     fn test_query_claims_returns_empty_for_non_existent_user() {
         let mut deps = mock_dependencies();
-        let claims = Claims::new("claims");
+        let claims = Claims::new("claims", "claims_total");
 
         claims
             .create_claim(
@@ -581,9 +793,314 @@ mod test {
             .unwrap();
 
         let queried_claims = claims
-            .query_claims(deps.as_ref(), &Addr::unchecked("addr2"))
+            .query_claims(deps.as_ref(), &Addr::unchecked("addr2"), None, None)
             .unwrap();
 
         assert_eq!(queried_claims.claims.len(), 0);
     }
+
+    #[test]
+    fn test_claim_tokens_by_height_and_time() {
+        let mut deps = mock_dependencies();
+        let claims = Claims::new("claims", "claims_total");
+        let env = mock_env();
+        let addr = Addr::unchecked("addr");
+
+        let releases = [
+            Expiration::AtTime(env.block.time.plus_seconds(10)),
+            Expiration::AtHeight(env.block.height),
+            Expiration::Never {},
+            Expiration::AtTime(env.block.time),
+            Expiration::AtHeight(env.block.height + 1),
+            Expiration::AtHeight(env.block.height),
+        ];
+        for (i, release_at) in releases.iter().enumerate() {
+            claims
+                .create_claim(
+                    deps.as_mut().storage,
+                    &addr,
+                    Uint128::new(10u128.pow(i as u32)),
+                    *release_at,
+                )
+                .unwrap();
+        }
+        assert_eq!(
+            claims.total_pending(&deps.storage).unwrap(),
+            Uint128::new(111111)
+        );
+
+        // both claims at the current height (with the same key but for the id), and the one at
+        // the current time
+        let amount = claims
+            .claim_tokens(deps.as_mut().storage, &addr, &env.block, None)
+            .unwrap();
+        assert_eq!(amount, Uint128::new(101010));
+        assert_eq!(
+            claims.total_pending(&deps.storage).unwrap(),
+            Uint128::new(10101)
+        );
+        assert_eq!(
+            load_claims(&claims, &deps.storage, "addr"),
+            vec![
+                Claim::new(10000, Expiration::AtHeight(env.block.height + 1)),
+                Claim::new(1, Expiration::AtTime(env.block.time.plus_seconds(10))),
+                Claim::new(100, Expiration::Never {}),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_query_claims_paginates() {
+        let mut deps = mock_dependencies();
+        let claims = Claims::new("claims", "claims_total");
+        let addr = Addr::unchecked("addr");
+
+        for (height, amount) in [(10, 1), (20, 2), (20, 3), (30, 4), (40, 5)] {
+            claims
+                .create_claim(
+                    deps.as_mut().storage,
+                    &addr,
+                    Uint128::new(amount),
+                    Expiration::AtHeight(height),
+                )
+                .unwrap();
+        }
+
+        // the limit is strict, even within claims released at the same height
+        let page = claims
+            .query_claims(deps.as_ref(), &addr, None, Some(2))
+            .unwrap();
+        assert_eq!(
+            page.claims,
+            vec![
+                Claim::new(1, Expiration::AtHeight(10)),
+                Claim::new(2, Expiration::AtHeight(20)),
+            ]
+        );
+        assert_eq!(page.next_start_after, Some((Expiration::AtHeight(20), 0)));
+
+        let page = claims
+            .query_claims(deps.as_ref(), &addr, page.next_start_after, Some(2))
+            .unwrap();
+        assert_eq!(
+            page.claims,
+            vec![
+                Claim::new(3, Expiration::AtHeight(20)),
+                Claim::new(4, Expiration::AtHeight(30)),
+            ]
+        );
+        assert_eq!(page.next_start_after, Some((Expiration::AtHeight(30), 0)));
+
+        let page = claims
+            .query_claims(deps.as_ref(), &addr, page.next_start_after, Some(2))
+            .unwrap();
+        assert_eq!(page.claims, vec![Claim::new(5, Expiration::AtHeight(40))]);
+        assert_eq!(page.next_start_after, None);
+
+        let page = claims
+            .query_claims(
+                deps.as_ref(),
+                &addr,
+                Some((Expiration::AtHeight(40), 0)),
+                None,
+            )
+            .unwrap();
+        assert_eq!(page.claims, vec![]);
+        assert_eq!(page.next_start_after, None);
+    }
+
+    #[test]
+    fn test_query_claims_includes_legacy_claims() {
+        let mut deps = mock_dependencies();
+        let claims = Claims::new_with_legacy("claims_by_release", "claims_total", "claims");
+        let legacy: Map<&Addr, Vec<Claim>> = Map::new("claims");
+        let addr = Addr::unchecked("addr");
+
+        legacy
+            .save(
+                deps.as_mut().storage,
+                &addr,
+                &vec![
+                    Claim::new(1, Expiration::AtHeight(30)),
+                    Claim::new(2, Expiration::AtHeight(10)),
+                    Claim::new(3, Expiration::AtHeight(20)),
+                ],
+            )
+            .unwrap();
+        // a claim created after the upgrade, before the migration of the address
+        claims
+            .create_claim(
+                deps.as_mut().storage,
+                &addr,
+                Uint128::new(4),
+                Expiration::AtHeight(20),
+            )
+            .unwrap();
+
+        let query_all = |deps: Deps| {
+            let first = claims.query_claims(deps, &addr, None, Some(2)).unwrap();
+            let second = claims
+                .query_claims(deps, &addr, first.next_start_after, Some(2))
+                .unwrap();
+            let last = claims
+                .query_claims(deps, &addr, second.next_start_after, Some(2))
+                .unwrap();
+            assert_eq!(last.claims, vec![]);
+            [first.claims, second.claims].concat()
+        };
+        let expected = vec![
+            Claim::new(2, Expiration::AtHeight(10)),
+            Claim::new(4, Expiration::AtHeight(20)),
+            Claim::new(3, Expiration::AtHeight(20)),
+            Claim::new(1, Expiration::AtHeight(30)),
+        ];
+        assert_eq!(query_all(deps.as_ref()), expected);
+
+        // the same once migrated
+        claims
+            .migrate_legacy_claims_of(deps.as_mut().storage, &addr)
+            .unwrap();
+        assert_eq!(query_all(deps.as_ref()), expected);
+    }
+
+    #[test]
+    fn test_migrate_legacy_claims() {
+        let mut deps = mock_dependencies();
+        let claims = Claims::new_with_legacy("claims_by_release", "claims_total", "claims");
+        let legacy: Map<&Addr, Vec<Claim>> = Map::new("claims");
+
+        let addrs = [Addr::unchecked("addr1"), Addr::unchecked("addr2")];
+        for addr in addrs.iter() {
+            legacy
+                .save(
+                    deps.as_mut().storage,
+                    addr,
+                    &vec![
+                        Claim::new(TEST_AMOUNT, Expiration::AtHeight(20)),
+                        Claim::new(TEST_AMOUNT + 100, Expiration::AtHeight(10)),
+                    ],
+                )
+                .unwrap();
+        }
+        // a claim created after the upgrade, before the migration is complete
+        claims
+            .create_claim(
+                deps.as_mut().storage,
+                &addrs[0],
+                TEST_AMOUNT.into(),
+                Expiration::AtHeight(30),
+            )
+            .unwrap();
+
+        // the claims created in the new layout are kept
+        let progress = claims
+            .migrate_legacy_claims(deps.as_mut().storage, 1)
+            .unwrap();
+        assert_eq!(progress, MigrationProgress::Pending);
+        assert_eq!(load_claims(&claims, &deps.storage, "addr1").len(), 3);
+        assert_eq!(load_claims(&claims, &deps.storage, "addr2").len(), 0);
+
+        let progress = claims
+            .migrate_legacy_claims(deps.as_mut().storage, 10)
+            .unwrap();
+        assert_eq!(progress, MigrationProgress::Done);
+        assert_eq!(
+            load_claims(&claims, &deps.storage, "addr2"),
+            vec![
+                Claim::new(TEST_AMOUNT + 100, Expiration::AtHeight(10)),
+                Claim::new(TEST_AMOUNT, Expiration::AtHeight(20)),
+            ]
+        );
+        assert_eq!(
+            claims.total_pending(&deps.storage).unwrap(),
+            Uint128::new(5 * TEST_AMOUNT + 200)
+        );
+        // kept apart from the claims
+        let total: Item<Uint128> = Item::new("claims_total");
+        assert_eq!(
+            total.load(&deps.storage).unwrap(),
+            Uint128::new(5 * TEST_AMOUNT + 200)
+        );
+        for addr in addrs.iter() {
+            assert_eq!(legacy.may_load(&deps.storage, addr).unwrap(), None);
+        }
+
+        // nothing left to migrate
+        let progress = claims
+            .migrate_legacy_claims(deps.as_mut().storage, 10)
+            .unwrap();
+        assert_eq!(progress, MigrationProgress::Done);
+
+        // a zero limit would never make progress
+        let err = claims
+            .migrate_legacy_claims(deps.as_mut().storage, 0)
+            .unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("Migration limit must be greater than zero")
+        );
+    }
+
+    #[test]
+    fn test_migrate_legacy_claims_of() {
+        let mut deps = mock_dependencies();
+        let claims = Claims::new_with_legacy("claims_by_release", "claims_total", "claims");
+        let legacy: Map<&Addr, Vec<Claim>> = Map::new("claims");
+
+        let addrs = [Addr::unchecked("addr1"), Addr::unchecked("addr2")];
+        for addr in addrs.iter() {
+            legacy
+                .save(
+                    deps.as_mut().storage,
+                    addr,
+                    &vec![Claim::new(TEST_AMOUNT, Expiration::AtHeight(20))],
+                )
+                .unwrap();
+        }
+
+        claims
+            .migrate_legacy_claims_of(deps.as_mut().storage, &addrs[1])
+            .unwrap();
+        assert_eq!(load_claims(&claims, &deps.storage, "addr1"), vec![]);
+        assert_eq!(
+            load_claims(&claims, &deps.storage, "addr2"),
+            vec![Claim::new(TEST_AMOUNT, Expiration::AtHeight(20))]
+        );
+        assert_eq!(legacy.may_load(&deps.storage, &addrs[1]).unwrap(), None);
+
+        // the others are left for migrate_legacy_claims, and this does nothing again
+        claims
+            .migrate_legacy_claims_of(deps.as_mut().storage, &addrs[1])
+            .unwrap();
+        let progress = claims
+            .migrate_legacy_claims(deps.as_mut().storage, 10)
+            .unwrap();
+        assert_eq!(progress, MigrationProgress::Done);
+        assert_eq!(load_claims(&claims, &deps.storage, "addr1").len(), 1);
+        assert_eq!(load_claims(&claims, &deps.storage, "addr2").len(), 1);
+        assert_eq!(
+            claims.total_pending(&deps.storage).unwrap(),
+            Uint128::new(2 * TEST_AMOUNT)
+        );
+    }
+
+    #[test]
+    fn test_migrate_without_legacy_claims() {
+        let mut deps = mock_dependencies();
+        let claims = Claims::new("claims", "claims_total");
+        claims
+            .create_claim(
+                deps.as_mut().storage,
+                &Addr::unchecked("addr1"),
+                TEST_AMOUNT.into(),
+                TEST_EXPIRATION,
+            )
+            .unwrap();
+
+        let progress = claims
+            .migrate_legacy_claims(deps.as_mut().storage, 10)
+            .unwrap();
+        assert_eq!(progress, MigrationProgress::Done);
+        assert_eq!(load_claims(&claims, &deps.storage, "addr1").len(), 1);
+    }
 }
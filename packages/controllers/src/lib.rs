@@ -1,7 +1,11 @@
 mod admin;
 mod claim;
 mod hooks;
+mod pausable;
+mod roles;
 
-pub use admin::{Admin, AdminError, AdminResponse};
+pub use admin::{Admin, AdminError, AdminResponse, PendingAdmin, PendingAdminResponse};
 pub use claim::{Claim, Claims, ClaimsResponse};
 pub use hooks::{HookError, Hooks};
+pub use pausable::{Pausable, PausableError, PausedAction, PausedResponse};
+pub use roles::{HasRoleResponse, RoleAdminResponse, RoleMembersResponse, Roles, RolesError};
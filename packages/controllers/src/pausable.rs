@@ -0,0 +1,389 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use thiserror::Error;
+
+use cosmwasm_std::{
+    attr, BlockInfo, CustomQuery, Deps, DepsMut, Env, MessageInfo, Order, Response, StdError,
+    StdResult, Storage,
+};
+use cw_storage_plus::{Item, Map};
+use cw_utils::Expiration;
+
+use crate::admin::{Admin, AdminError};
+
+/// Returned from Pausable.query_paused(). Only lists the pauses still in effect.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PausedResponse {
+    /// Set if the whole contract is paused, until then
+    pub paused: Option<Expiration>,
+    pub actions: Vec<PausedAction>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PausedAction {
+    pub action: String,
+    /// The action is unpaused automatically once expired
+    pub expires: Expiration,
+}
+
+/// Errors returned from Pausable
+#[derive(Error, Debug, PartialEq)]
+pub enum PausableError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    Admin(#[from] AdminError),
+
+    #[error("Contract is paused")]
+    Paused {},
+
+    #[error("Action {action} is paused")]
+    ActionPaused { action: String },
+
+    #[error("Not paused")]
+    NotPaused {},
+
+    #[error("Cannot pause with an expiration already passed")]
+    Expired {},
+}
+
+/// Pausable is a circuit breaker: the whole contract, or some of its actions, can be paused in
+/// an emergency, either until unpaused or until an expiration.
+///
+/// The contract calls `assert_not_paused` with the name of the action at the start of each
+/// handler it wants to be able to halt. The execute helpers are guarded by an `Admin`; to
+/// guard them by a role instead, check it with `Roles::assert_role` and call `pause` /
+/// `unpause` directly.
+pub struct Pausable<'a> {
+    // pause of the whole contract
+    paused: Item<'a, Expiration>,
+    // pauses of single actions
+    actions: Map<'a, &'a str, Expiration>,
+}
+
+impl<'a> Pausable<'a> {
+    pub const fn new(namespace: &'a str, actions_namespace: &'a str) -> Self {
+        Pausable {
+            paused: Item::new(namespace),
+            actions: Map::new(actions_namespace),
+        }
+    }
+
+    /// Pauses the given action, or the whole contract if it is None, until `expires`
+    /// (`Expiration::Never` to pause until unpaused). Replaces any previous expiration.
+    pub fn pause(
+        &self,
+        storage: &mut dyn Storage,
+        action: Option<&str>,
+        expires: Expiration,
+    ) -> StdResult<()> {
+        match action {
+            Some(action) => self.actions.save(storage, action, &expires),
+            None => self.paused.save(storage, &expires),
+        }
+    }
+
+    /// Lifts the pause of the given action, or of the whole contract if it is None.
+    /// Pausing the whole contract does not change the pauses of single actions.
+    pub fn unpause(&self, storage: &mut dyn Storage, action: Option<&str>) {
+        match action {
+            Some(action) => self.actions.remove(storage, action),
+            None => self.paused.remove(storage),
+        }
+    }
+
+    /// Returns true if the given action, or the whole contract if it is None, is paused
+    pub fn is_paused(
+        &self,
+        storage: &dyn Storage,
+        block: &BlockInfo,
+        action: Option<&str>,
+    ) -> StdResult<bool> {
+        let expires = match action {
+            Some(action) => self.actions.may_load(storage, action)?,
+            None => self.paused.may_load(storage)?,
+        };
+        Ok(matches!(expires, Some(expires) if !expires.is_expired(block)))
+    }
+
+    /// Returns an error if the whole contract, or the given action, is paused.
+    /// Helper for a nice one-line check at the start of a handler.
+    pub fn assert_not_paused(
+        &self,
+        storage: &dyn Storage,
+        block: &BlockInfo,
+        action: &str,
+    ) -> Result<(), PausableError> {
+        if self.is_paused(storage, block, None)? {
+            return Err(PausableError::Paused {});
+        }
+        if self.is_paused(storage, block, Some(action))? {
+            return Err(PausableError::ActionPaused {
+                action: action.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    pub fn execute_pause<C, Q: CustomQuery>(
+        &self,
+        admin: &Admin,
+        deps: DepsMut<Q>,
+        env: Env,
+        info: MessageInfo,
+        action: Option<String>,
+        expires: Option<Expiration>,
+    ) -> Result<Response<C>, PausableError>
+    where
+        C: Clone + fmt::Debug + PartialEq + JsonSchema,
+    {
+        admin.assert_admin(deps.as_ref(), &info.sender)?;
+        let expires = expires.unwrap_or_default();
+        if expires.is_expired(&env.block) {
+            return Err(PausableError::Expired {});
+        }
+
+        self.pause(deps.storage, action.as_deref(), expires)?;
+
+        let attributes = vec![
+            attr("action", "pause"),
+            attr("paused", action.unwrap_or_else(|| "all".to_string())),
+            attr("expires", expires.to_string()),
+            attr("sender", info.sender),
+        ];
+        Ok(Response::new().add_attributes(attributes))
+    }
+
+    pub fn execute_unpause<C, Q: CustomQuery>(
+        &self,
+        admin: &Admin,
+        deps: DepsMut<Q>,
+        env: Env,
+        info: MessageInfo,
+        action: Option<String>,
+    ) -> Result<Response<C>, PausableError>
+    where
+        C: Clone + fmt::Debug + PartialEq + JsonSchema,
+    {
+        admin.assert_admin(deps.as_ref(), &info.sender)?;
+        if !self.is_paused(deps.storage, &env.block, action.as_deref())? {
+            return Err(PausableError::NotPaused {});
+        }
+
+        self.unpause(deps.storage, action.as_deref());
+
+        let attributes = vec![
+            attr("action", "unpause"),
+            attr("unpaused", action.unwrap_or_else(|| "all".to_string())),
+            attr("sender", info.sender),
+        ];
+        Ok(Response::new().add_attributes(attributes))
+    }
+
+    pub fn query_paused<Q: CustomQuery>(
+        &self,
+        deps: Deps<Q>,
+        env: Env,
+    ) -> StdResult<PausedResponse> {
+        let paused = self
+            .paused
+            .may_load(deps.storage)?
+            .filter(|expires| !expires.is_expired(&env.block));
+        let actions = self
+            .actions
+            .range(deps.storage, None, None, Order::Ascending)
+            .filter(|item| match item {
+                Ok((_, expires)) => !expires.is_expired(&env.block),
+                Err(_) => true,
+            })
+            .map(|item| item.map(|(action, expires)| PausedAction { action, expires }))
+            .collect::<StdResult<_>>()?;
+        Ok(PausedResponse { paused, actions })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{Addr, Empty};
+
+    const ADMIN: Admin = Admin::new("admin");
+    const PAUSE: Pausable = Pausable::new("pause", "paused_actions");
+
+    #[test]
+    fn pause_actions() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+
+        PAUSE
+            .assert_not_paused(&deps.storage, &env.block, "transfer")
+            .unwrap();
+
+        PAUSE
+            .pause(&mut deps.storage, Some("transfer"), Expiration::Never {})
+            .unwrap();
+        let err = PAUSE
+            .assert_not_paused(&deps.storage, &env.block, "transfer")
+            .unwrap_err();
+        assert_eq!(
+            err,
+            PausableError::ActionPaused {
+                action: "transfer".to_string()
+            }
+        );
+        PAUSE
+            .assert_not_paused(&deps.storage, &env.block, "mint")
+            .unwrap();
+        // single actions are kept apart from the pause of the whole contract
+        let actions: Map<&str, Expiration> = Map::new("paused_actions");
+        assert!(actions.has(&deps.storage, "transfer"));
+        assert!(!PAUSE.is_paused(&deps.storage, &env.block, None).unwrap());
+
+        // pausing everything covers all actions
+        PAUSE
+            .pause(
+                &mut deps.storage,
+                None,
+                Expiration::AtHeight(env.block.height + 10),
+            )
+            .unwrap();
+        let err = PAUSE
+            .assert_not_paused(&deps.storage, &env.block, "mint")
+            .unwrap_err();
+        assert_eq!(err, PausableError::Paused {});
+
+        // until it expires
+        env.block.height += 10;
+        PAUSE
+            .assert_not_paused(&deps.storage, &env.block, "mint")
+            .unwrap();
+        PAUSE
+            .assert_not_paused(&deps.storage, &env.block, "transfer")
+            .unwrap_err();
+
+        PAUSE.unpause(&mut deps.storage, Some("transfer"));
+        PAUSE
+            .assert_not_paused(&deps.storage, &env.block, "transfer")
+            .unwrap();
+    }
+
+    #[test]
+    fn execute_and_query() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let owner = Addr::unchecked("owner");
+        ADMIN.set(deps.as_mut(), Some(owner.clone())).unwrap();
+
+        let res = PAUSE.query_paused(deps.as_ref(), env.clone()).unwrap();
+        assert_eq!(
+            res,
+            PausedResponse {
+                paused: None,
+                actions: vec![]
+            }
+        );
+
+        // only the admin can pause
+        let err = PAUSE
+            .execute_pause::<Empty, Empty>(
+                &ADMIN,
+                deps.as_mut(),
+                env.clone(),
+                mock_info("imposter", &[]),
+                None,
+                None,
+            )
+            .unwrap_err();
+        assert_eq!(err, PausableError::Admin(AdminError::NotAdmin {}));
+        let err = PAUSE
+            .execute_pause::<Empty, Empty>(
+                &ADMIN,
+                deps.as_mut(),
+                env.clone(),
+                mock_info(owner.as_ref(), &[]),
+                None,
+                Some(Expiration::AtHeight(env.block.height)),
+            )
+            .unwrap_err();
+        assert_eq!(err, PausableError::Expired {});
+
+        let res = PAUSE
+            .execute_pause::<Empty, Empty>(
+                &ADMIN,
+                deps.as_mut(),
+                env.clone(),
+                mock_info(owner.as_ref(), &[]),
+                Some("mint".to_string()),
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "pause"),
+                attr("paused", "mint"),
+                attr("expires", "expiration: never"),
+                attr("sender", "owner"),
+            ]
+        );
+        let expires = Expiration::AtHeight(env.block.height + 5);
+        PAUSE
+            .execute_pause::<Empty, Empty>(
+                &ADMIN,
+                deps.as_mut(),
+                env.clone(),
+                mock_info(owner.as_ref(), &[]),
+                None,
+                Some(expires),
+            )
+            .unwrap();
+        PAUSE
+            .pause(
+                &mut deps.storage,
+                Some("burn"),
+                Expiration::AtHeight(env.block.height),
+            )
+            .unwrap();
+
+        // expired pauses are not shown
+        let res = PAUSE.query_paused(deps.as_ref(), env.clone()).unwrap();
+        assert_eq!(
+            res,
+            PausedResponse {
+                paused: Some(expires),
+                actions: vec![PausedAction {
+                    action: "mint".to_string(),
+                    expires: Expiration::Never {}
+                }]
+            }
+        );
+
+        // unpause
+        let err = PAUSE
+            .execute_unpause::<Empty, Empty>(
+                &ADMIN,
+                deps.as_mut(),
+                env.clone(),
+                mock_info(owner.as_ref(), &[]),
+                Some("burn".to_string()),
+            )
+            .unwrap_err();
+        assert_eq!(err, PausableError::NotPaused {});
+        PAUSE
+            .execute_unpause::<Empty, Empty>(
+                &ADMIN,
+                deps.as_mut(),
+                env.clone(),
+                mock_info(owner.as_ref(), &[]),
+                None,
+            )
+            .unwrap();
+        let res = PAUSE.query_paused(deps.as_ref(), env).unwrap();
+        assert_eq!(res.paused, None);
+        assert_eq!(res.actions.len(), 1);
+    }
+}
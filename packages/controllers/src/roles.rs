@@ -0,0 +1,527 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::marker::PhantomData;
+use thiserror::Error;
+
+use cosmwasm_std::{
+    attr, Addr, CustomQuery, Deps, DepsMut, MessageInfo, Order, Response, StdError, StdResult,
+    Storage,
+};
+use cw_storage_plus::{Bound, Map};
+use cw_utils::maybe_addr;
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+/// Returned from Roles.query_has_role()
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct HasRoleResponse {
+    pub has_role: bool,
+}
+
+/// Returned from Roles.query_role_members()
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct RoleMembersResponse {
+    pub members: Vec<String>,
+}
+
+/// Returned from Roles.query_role_admin()
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct RoleAdminResponse {
+    /// The role whose members can grant and revoke the queried role, if any
+    pub admin_role: Option<String>,
+}
+
+/// Errors returned from Roles
+#[derive(Error, Debug, PartialEq)]
+pub enum RolesError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Caller does not have role {role}")]
+    MissingRole { role: String },
+
+    #[error("Role {role} has no admin role, it cannot be granted or revoked")]
+    NoRoleAdmin { role: String },
+
+    #[error("Address already has role {role}")]
+    AlreadyGranted { role: String },
+
+    #[error("Address does not have role {role}")]
+    NotGranted { role: String },
+}
+
+/// Roles gives addresses named roles, like "minter" or "pauser".
+///
+/// Each role can have an admin role, whose members can grant and revoke it (a role can be its
+/// own admin). Roles without an admin role can only be granted by the contract itself, e.g.
+/// on instantiation. `R` is usually an enum of the roles of the contract, `&str` works as well.
+pub struct Roles<'a, R> {
+    members_namespace: &'a str,
+    admins_namespace: &'a str,
+    role_type: PhantomData<R>,
+}
+
+// this is the core business logic we expose
+impl<'a, R> Roles<'a, R>
+where
+    R: AsRef<str>,
+{
+    pub const fn new(members_namespace: &'a str, admins_namespace: &'a str) -> Self {
+        Roles {
+            members_namespace,
+            admins_namespace,
+            role_type: PhantomData,
+        }
+    }
+
+    // the members of each role: (role, address) => ()
+    fn members<'k>(&self) -> Map<'a, (&'k str, &'k Addr), ()> {
+        Map::new(self.members_namespace)
+    }
+
+    // the admin role of each role
+    fn admins<'k>(&self) -> Map<'a, &'k str, String> {
+        Map::new(self.admins_namespace)
+    }
+
+    /// Gives the role to the address, without checking any permission.
+    /// Does nothing if the address already has it.
+    pub fn grant(&self, storage: &mut dyn Storage, role: &R, addr: &Addr) -> StdResult<()> {
+        self.members().save(storage, (role.as_ref(), addr), &())
+    }
+
+    /// Takes the role from the address, without checking any permission.
+    /// Does nothing if the address does not have it.
+    pub fn revoke(&self, storage: &mut dyn Storage, role: &R, addr: &Addr) {
+        self.members().remove(storage, (role.as_ref(), addr))
+    }
+
+    pub fn has_role(&self, storage: &dyn Storage, role: &R, addr: &Addr) -> bool {
+        self.members().has(storage, (role.as_ref(), addr))
+    }
+
+    /// Like has_role but returns RolesError::MissingRole if the caller does not have the role.
+    /// Helper for a nice one-line auth check.
+    pub fn assert_role<Q: CustomQuery>(
+        &self,
+        deps: Deps<Q>,
+        role: &R,
+        caller: &Addr,
+    ) -> Result<(), RolesError> {
+        if !self.has_role(deps.storage, role, caller) {
+            Err(RolesError::MissingRole {
+                role: role.as_ref().to_string(),
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Sets the role whose members can grant and revoke `role`
+    pub fn set_role_admin(
+        &self,
+        storage: &mut dyn Storage,
+        role: &R,
+        admin_role: &R,
+    ) -> StdResult<()> {
+        self.admins()
+            .save(storage, role.as_ref(), &admin_role.as_ref().to_string())
+    }
+
+    /// Returns the name of the admin role of `role`, if any
+    pub fn role_admin(&self, storage: &dyn Storage, role: &R) -> StdResult<Option<String>> {
+        self.admins().may_load(storage, role.as_ref())
+    }
+
+    // the caller must have the admin role of role
+    fn assert_role_admin(
+        &self,
+        storage: &dyn Storage,
+        role: &R,
+        caller: &Addr,
+    ) -> Result<(), RolesError> {
+        let admin_role =
+            self.role_admin(storage, role)?
+                .ok_or_else(|| RolesError::NoRoleAdmin {
+                    role: role.as_ref().to_string(),
+                })?;
+        if !self.members().has(storage, (&admin_role, caller)) {
+            return Err(RolesError::MissingRole { role: admin_role });
+        }
+        Ok(())
+    }
+
+    pub fn execute_grant_role<C, Q: CustomQuery>(
+        &self,
+        deps: DepsMut<Q>,
+        info: MessageInfo,
+        role: R,
+        addr: Addr,
+    ) -> Result<Response<C>, RolesError>
+    where
+        C: Clone + fmt::Debug + PartialEq + JsonSchema,
+    {
+        self.assert_role_admin(deps.storage, &role, &info.sender)?;
+        if self.has_role(deps.storage, &role, &addr) {
+            return Err(RolesError::AlreadyGranted {
+                role: role.as_ref().to_string(),
+            });
+        }
+        self.grant(deps.storage, &role, &addr)?;
+
+        let attributes = vec![
+            attr("action", "grant_role"),
+            attr("role", role.as_ref()),
+            attr("account", addr),
+            attr("sender", info.sender),
+        ];
+        Ok(Response::new().add_attributes(attributes))
+    }
+
+    pub fn execute_revoke_role<C, Q: CustomQuery>(
+        &self,
+        deps: DepsMut<Q>,
+        info: MessageInfo,
+        role: R,
+        addr: Addr,
+    ) -> Result<Response<C>, RolesError>
+    where
+        C: Clone + fmt::Debug + PartialEq + JsonSchema,
+    {
+        self.assert_role_admin(deps.storage, &role, &info.sender)?;
+        if !self.has_role(deps.storage, &role, &addr) {
+            return Err(RolesError::NotGranted {
+                role: role.as_ref().to_string(),
+            });
+        }
+        self.revoke(deps.storage, &role, &addr);
+
+        let attributes = vec![
+            attr("action", "revoke_role"),
+            attr("role", role.as_ref()),
+            attr("account", addr),
+            attr("sender", info.sender),
+        ];
+        Ok(Response::new().add_attributes(attributes))
+    }
+
+    /// The caller gives up one of its roles. Works for roles without admin role as well.
+    pub fn execute_renounce_role<C, Q: CustomQuery>(
+        &self,
+        deps: DepsMut<Q>,
+        info: MessageInfo,
+        role: R,
+    ) -> Result<Response<C>, RolesError>
+    where
+        C: Clone + fmt::Debug + PartialEq + JsonSchema,
+    {
+        if !self.has_role(deps.storage, &role, &info.sender) {
+            return Err(RolesError::NotGranted {
+                role: role.as_ref().to_string(),
+            });
+        }
+        self.revoke(deps.storage, &role, &info.sender);
+
+        let attributes = vec![
+            attr("action", "renounce_role"),
+            attr("role", role.as_ref()),
+            attr("sender", info.sender),
+        ];
+        Ok(Response::new().add_attributes(attributes))
+    }
+
+    pub fn query_has_role<Q: CustomQuery>(
+        &self,
+        deps: Deps<Q>,
+        role: R,
+        addr: String,
+    ) -> StdResult<HasRoleResponse> {
+        let addr = deps.api.addr_validate(&addr)?;
+        Ok(HasRoleResponse {
+            has_role: self.has_role(deps.storage, &role, &addr),
+        })
+    }
+
+    pub fn query_role_admin<Q: CustomQuery>(
+        &self,
+        deps: Deps<Q>,
+        role: R,
+    ) -> StdResult<RoleAdminResponse> {
+        Ok(RoleAdminResponse {
+            admin_role: self.role_admin(deps.storage, &role)?,
+        })
+    }
+
+    /// Lists the members of the role, by address
+    pub fn query_role_members<Q: CustomQuery>(
+        &self,
+        deps: Deps<Q>,
+        role: R,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<RoleMembersResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start_after = maybe_addr(deps.api, start_after)?;
+        let start = start_after.as_ref().map(Bound::exclusive);
+
+        let members = self
+            .members()
+            .prefix(role.as_ref())
+            .keys(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|addr| addr.map(String::from))
+            .collect::<StdResult<_>>()?;
+        Ok(RoleMembersResponse { members })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::testing::{mock_dependencies, mock_info};
+    use cosmwasm_std::Empty;
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    enum Role {
+        Owner,
+        Minter,
+        Pauser,
+    }
+
+    impl AsRef<str> for Role {
+        fn as_ref(&self) -> &str {
+            match self {
+                Role::Owner => "owner",
+                Role::Minter => "minter",
+                Role::Pauser => "pauser",
+            }
+        }
+    }
+
+    const ROLES: Roles<Role> = Roles::new("roles", "role_admins");
+
+    #[test]
+    fn grant_and_revoke() {
+        let mut deps = mock_dependencies();
+        let alice = Addr::unchecked("alice");
+        let bob = Addr::unchecked("bob");
+
+        ROLES
+            .grant(&mut deps.storage, &Role::Minter, &alice)
+            .unwrap();
+        ROLES
+            .grant(&mut deps.storage, &Role::Minter, &alice)
+            .unwrap();
+        ROLES.grant(&mut deps.storage, &Role::Pauser, &bob).unwrap();
+        assert!(ROLES.has_role(&deps.storage, &Role::Minter, &alice));
+        assert!(!ROLES.has_role(&deps.storage, &Role::Pauser, &alice));
+        assert!(!ROLES.has_role(&deps.storage, &Role::Minter, &bob));
+        ROLES
+            .assert_role(deps.as_ref(), &Role::Pauser, &bob)
+            .unwrap();
+        let err = ROLES
+            .assert_role(deps.as_ref(), &Role::Minter, &bob)
+            .unwrap_err();
+        assert_eq!(
+            err,
+            RolesError::MissingRole {
+                role: "minter".to_string()
+            }
+        );
+
+        ROLES.revoke(&mut deps.storage, &Role::Minter, &alice);
+        assert!(!ROLES.has_role(&deps.storage, &Role::Minter, &alice));
+
+        // plain strings work as roles as well
+        let roles: Roles<&str> = Roles::new("roles", "role_admins");
+        assert!(roles.has_role(&deps.storage, &"pauser", &bob));
+    }
+
+    #[test]
+    fn role_admin_hierarchy() {
+        let mut deps = mock_dependencies();
+        let owner = Addr::unchecked("owner");
+        let pauser = Addr::unchecked("pauser");
+        let minter = Addr::unchecked("minter");
+
+        ROLES
+            .grant(&mut deps.storage, &Role::Owner, &owner)
+            .unwrap();
+        ROLES
+            .set_role_admin(&mut deps.storage, &Role::Owner, &Role::Owner)
+            .unwrap();
+        ROLES
+            .set_role_admin(&mut deps.storage, &Role::Minter, &Role::Owner)
+            .unwrap();
+        ROLES
+            .set_role_admin(&mut deps.storage, &Role::Pauser, &Role::Minter)
+            .unwrap();
+        let res = ROLES.query_role_admin(deps.as_ref(), Role::Pauser).unwrap();
+        assert_eq!(res.admin_role, Some("minter".to_string()));
+
+        // owner grants minter
+        let res = ROLES
+            .execute_grant_role::<Empty, Empty>(
+                deps.as_mut(),
+                mock_info(owner.as_ref(), &[]),
+                Role::Minter,
+                minter.clone(),
+            )
+            .unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "grant_role"),
+                attr("role", "minter"),
+                attr("account", "minter"),
+                attr("sender", "owner"),
+            ]
+        );
+        // only once
+        let err = ROLES
+            .execute_grant_role::<Empty, Empty>(
+                deps.as_mut(),
+                mock_info(owner.as_ref(), &[]),
+                Role::Minter,
+                minter.clone(),
+            )
+            .unwrap_err();
+        assert_eq!(
+            err,
+            RolesError::AlreadyGranted {
+                role: "minter".to_string()
+            }
+        );
+
+        // owner is not admin of pauser, minter is
+        let err = ROLES
+            .execute_grant_role::<Empty, Empty>(
+                deps.as_mut(),
+                mock_info(owner.as_ref(), &[]),
+                Role::Pauser,
+                pauser.clone(),
+            )
+            .unwrap_err();
+        assert_eq!(
+            err,
+            RolesError::MissingRole {
+                role: "minter".to_string()
+            }
+        );
+        ROLES
+            .execute_grant_role::<Empty, Empty>(
+                deps.as_mut(),
+                mock_info(minter.as_ref(), &[]),
+                Role::Pauser,
+                pauser.clone(),
+            )
+            .unwrap();
+        let res = ROLES
+            .query_has_role(deps.as_ref(), Role::Pauser, pauser.to_string())
+            .unwrap();
+        assert!(res.has_role);
+
+        // and revokes it
+        let res = ROLES
+            .execute_revoke_role::<Empty, Empty>(
+                deps.as_mut(),
+                mock_info(minter.as_ref(), &[]),
+                Role::Pauser,
+                pauser.clone(),
+            )
+            .unwrap();
+        assert_eq!(res.attributes[0], attr("action", "revoke_role"));
+        assert!(!ROLES.has_role(&deps.storage, &Role::Pauser, &pauser));
+        let err = ROLES
+            .execute_revoke_role::<Empty, Empty>(
+                deps.as_mut(),
+                mock_info(minter.as_ref(), &[]),
+                Role::Pauser,
+                pauser,
+            )
+            .unwrap_err();
+        assert_eq!(
+            err,
+            RolesError::NotGranted {
+                role: "pauser".to_string()
+            }
+        );
+
+        // roles without admin role cannot be granted by anyone
+        let roles: Roles<&str> = Roles::new("roles", "role_admins");
+        let err = roles
+            .execute_grant_role::<Empty, Empty>(
+                deps.as_mut(),
+                mock_info(owner.as_ref(), &[]),
+                "burner",
+                owner,
+            )
+            .unwrap_err();
+        assert_eq!(
+            err,
+            RolesError::NoRoleAdmin {
+                role: "burner".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn renounce_and_list_members() {
+        let mut deps = mock_dependencies();
+        for name in ["dave", "alice", "carol", "bob"] {
+            ROLES
+                .grant(&mut deps.storage, &Role::Minter, &Addr::unchecked(name))
+                .unwrap();
+        }
+        ROLES
+            .grant(&mut deps.storage, &Role::Pauser, &Addr::unchecked("eve"))
+            .unwrap();
+
+        let res = ROLES
+            .query_role_members(deps.as_ref(), Role::Minter, None, Some(3))
+            .unwrap();
+        assert_eq!(res.members, vec!["alice", "bob", "carol"]);
+        let res = ROLES
+            .query_role_members(deps.as_ref(), Role::Minter, Some("carol".to_string()), None)
+            .unwrap();
+        assert_eq!(res.members, vec!["dave"]);
+
+        // renouncing needs no admin role
+        let res = ROLES
+            .execute_renounce_role::<Empty, Empty>(
+                deps.as_mut(),
+                mock_info("bob", &[]),
+                Role::Minter,
+            )
+            .unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "renounce_role"),
+                attr("role", "minter"),
+                attr("sender", "bob"),
+            ]
+        );
+        let err = ROLES
+            .execute_renounce_role::<Empty, Empty>(
+                deps.as_mut(),
+                mock_info("bob", &[]),
+                Role::Minter,
+            )
+            .unwrap_err();
+        assert_eq!(
+            err,
+            RolesError::NotGranted {
+                role: "minter".to_string()
+            }
+        );
+        let res = ROLES
+            .query_role_members(deps.as_ref(), Role::Minter, None, None)
+            .unwrap();
+        assert_eq!(res.members, vec!["alice", "carol", "dave"]);
+    }
+}
@@ -0,0 +1,656 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::fmt;
+use std::iter::FromIterator;
+
+use cosmwasm_std::{
+    to_binary, Addr, Api, BankMsg, Binary, Coin, CosmosMsg, CustomQuery, MessageInfo,
+    QuerierWrapper, StdError, StdResult, Uint128, WasmMsg,
+};
+use cw_utils::{one_coin, PaymentError};
+
+use crate::{
+    Balance, BalanceResponse, Cw20CoinVerified, Cw20ExecuteMsg, Cw20QueryMsg, Cw20ReceiveMsg, Denom,
+};
+
+/// AssetInfoUnchecked is an AssetInfo as received in a message, with an address
+/// which was not validated yet
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetInfoUnchecked {
+    Native(String),
+    Cw20(String),
+}
+
+impl AssetInfoUnchecked {
+    pub fn check(&self, api: &dyn Api) -> StdResult<AssetInfo> {
+        match self {
+            AssetInfoUnchecked::Native(denom) => {
+                if denom.is_empty() {
+                    return Err(StdError::generic_err("Native denom cannot be empty"));
+                }
+                Ok(AssetInfo::Native(denom.clone()))
+            }
+            AssetInfoUnchecked::Cw20(addr) => Ok(AssetInfo::Cw20(api.addr_validate(addr)?)),
+        }
+    }
+}
+
+/// Parses the string an AssetInfo is displayed as: `cw20:<address>` for a cw20 token,
+/// or else the native denom
+impl From<&str> for AssetInfoUnchecked {
+    fn from(denom: &str) -> Self {
+        match denom.strip_prefix("cw20:") {
+            Some(addr) => AssetInfoUnchecked::Cw20(addr.to_string()),
+            None => AssetInfoUnchecked::Native(denom.to_string()),
+        }
+    }
+}
+
+impl From<AssetInfo> for AssetInfoUnchecked {
+    fn from(info: AssetInfo) -> Self {
+        match info {
+            AssetInfo::Native(denom) => AssetInfoUnchecked::Native(denom),
+            AssetInfo::Cw20(addr) => AssetInfoUnchecked::Cw20(addr.into()),
+        }
+    }
+}
+
+/// AssetInfo is either a native denom or a cw20 token
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetInfo {
+    Native(String),
+    Cw20(Addr),
+}
+
+impl AssetInfo {
+    pub fn native(denom: impl Into<String>) -> Self {
+        AssetInfo::Native(denom.into())
+    }
+
+    pub fn cw20(addr: Addr) -> Self {
+        AssetInfo::Cw20(addr)
+    }
+
+    /// Queries how much of this asset the given address holds
+    pub fn query_balance<C: CustomQuery>(
+        &self,
+        querier: &QuerierWrapper<C>,
+        address: impl Into<String>,
+    ) -> StdResult<Uint128> {
+        match self {
+            AssetInfo::Native(denom) => Ok(querier.query_balance(address, denom)?.amount),
+            AssetInfo::Cw20(contract) => {
+                let query = Cw20QueryMsg::Balance {
+                    address: address.into(),
+                };
+                let res: BalanceResponse = querier.query_wasm_smart(contract, &query)?;
+                Ok(res.balance)
+            }
+        }
+    }
+}
+
+/// Displays the denom for native tokens, and `cw20:<address>` for cw20 tokens
+impl fmt::Display for AssetInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AssetInfo::Native(denom) => write!(f, "{}", denom),
+            AssetInfo::Cw20(addr) => write!(f, "cw20:{}", addr),
+        }
+    }
+}
+
+impl From<Denom> for AssetInfo {
+    fn from(denom: Denom) -> Self {
+        match denom {
+            Denom::Native(denom) => AssetInfo::Native(denom),
+            Denom::Cw20(addr) => AssetInfo::Cw20(addr),
+        }
+    }
+}
+
+impl From<AssetInfo> for Denom {
+    fn from(info: AssetInfo) -> Self {
+        match info {
+            AssetInfo::Native(denom) => Denom::Native(denom),
+            AssetInfo::Cw20(addr) => Denom::Cw20(addr),
+        }
+    }
+}
+
+/// AssetUnchecked is an Asset as received in a message, see AssetInfoUnchecked
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AssetUnchecked {
+    pub info: AssetInfoUnchecked,
+    pub amount: Uint128,
+}
+
+impl AssetUnchecked {
+    pub fn check(&self, api: &dyn Api) -> StdResult<Asset> {
+        Ok(Asset {
+            info: self.info.check(api)?,
+            amount: self.amount,
+        })
+    }
+}
+
+/// Asset is an amount of a native or a cw20 token
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Asset {
+    pub info: AssetInfo,
+    pub amount: Uint128,
+}
+
+impl Asset {
+    pub fn new(info: AssetInfo, amount: impl Into<Uint128>) -> Self {
+        Asset {
+            info,
+            amount: amount.into(),
+        }
+    }
+
+    pub fn native(denom: impl Into<String>, amount: impl Into<Uint128>) -> Self {
+        Asset::new(AssetInfo::Native(denom.into()), amount)
+    }
+
+    pub fn cw20(addr: Addr, amount: impl Into<Uint128>) -> Self {
+        Asset::new(AssetInfo::Cw20(addr), amount)
+    }
+
+    /// Returns the one native coin sent with the message, erroring if there are none or more
+    pub fn from_funds(info: &MessageInfo) -> Result<Asset, PaymentError> {
+        Ok(one_coin(info)?.into())
+    }
+
+    /// Returns the cw20 tokens received with a `Receive` message.
+    /// `info` is the one of the `Receive` message, sent by the cw20 contract.
+    pub fn from_cw20_receive(info: &MessageInfo, msg: &Cw20ReceiveMsg) -> Asset {
+        Asset::cw20(info.sender.clone(), msg.amount)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.amount.is_zero()
+    }
+
+    pub fn checked_add(&self, other: &Asset) -> StdResult<Asset> {
+        self.assert_same_info(other)?;
+        Ok(Asset::new(
+            self.info.clone(),
+            self.amount.checked_add(other.amount)?,
+        ))
+    }
+
+    pub fn checked_sub(&self, other: &Asset) -> StdResult<Asset> {
+        self.assert_same_info(other)?;
+        Ok(Asset::new(
+            self.info.clone(),
+            self.amount.checked_sub(other.amount)?,
+        ))
+    }
+
+    /// Creates a message transferring this asset from the contract to `recipient`
+    pub fn into_transfer_msg(self, recipient: impl Into<String>) -> StdResult<CosmosMsg> {
+        match self.info {
+            AssetInfo::Native(denom) => Ok(BankMsg::Send {
+                to_address: recipient.into(),
+                amount: vec![Coin {
+                    denom,
+                    amount: self.amount,
+                }],
+            }
+            .into()),
+            AssetInfo::Cw20(contract) => {
+                let transfer = Cw20ExecuteMsg::Transfer {
+                    recipient: recipient.into(),
+                    amount: self.amount,
+                };
+                Ok(WasmMsg::Execute {
+                    contract_addr: contract.into(),
+                    msg: to_binary(&transfer)?,
+                    funds: vec![],
+                }
+                .into())
+            }
+        }
+    }
+
+    /// Creates a message sending this asset to `contract` along with `msg`.
+    /// Native tokens are attached as funds to `msg`, while cw20 tokens go through
+    /// `Cw20ExecuteMsg::Send`, so `msg` is delivered wrapped in a `Cw20ReceiveMsg`.
+    pub fn into_send_msg(self, contract: impl Into<String>, msg: Binary) -> StdResult<CosmosMsg> {
+        match self.info {
+            AssetInfo::Native(denom) => Ok(WasmMsg::Execute {
+                contract_addr: contract.into(),
+                msg,
+                funds: vec![Coin {
+                    denom,
+                    amount: self.amount,
+                }],
+            }
+            .into()),
+            AssetInfo::Cw20(token) => {
+                let send = Cw20ExecuteMsg::Send {
+                    contract: contract.into(),
+                    amount: self.amount,
+                    msg,
+                };
+                Ok(WasmMsg::Execute {
+                    contract_addr: token.into(),
+                    msg: to_binary(&send)?,
+                    funds: vec![],
+                }
+                .into())
+            }
+        }
+    }
+
+    fn assert_same_info(&self, other: &Asset) -> StdResult<()> {
+        if self.info == other.info {
+            Ok(())
+        } else {
+            Err(StdError::generic_err(format!(
+                "Cannot combine {} with {}",
+                self.info, other.info
+            )))
+        }
+    }
+}
+
+impl fmt::Display for Asset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.amount, self.info)
+    }
+}
+
+impl From<Coin> for Asset {
+    fn from(coin: Coin) -> Self {
+        Asset::native(coin.denom, coin.amount)
+    }
+}
+
+impl From<Cw20CoinVerified> for Asset {
+    fn from(coin: Cw20CoinVerified) -> Self {
+        Asset::cw20(coin.address, coin.amount)
+    }
+}
+
+/// AssetList holds amounts of any number of assets, with at most one entry per asset
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq, JsonSchema)]
+pub struct AssetList(pub Vec<Asset>);
+
+impl AssetList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns all the native coins sent with the message
+    pub fn from_funds(info: &MessageInfo) -> StdResult<AssetList> {
+        info.funds.iter().cloned().map(Asset::from).collect()
+    }
+
+    /// True if there are no assets, or only zero amounts
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(Asset::is_zero)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Asset> {
+        self.0.iter()
+    }
+
+    /// Returns the entry of the given asset, if any
+    pub fn find(&self, info: &AssetInfo) -> Option<&Asset> {
+        self.0.iter().find(|asset| &asset.info == info)
+    }
+
+    /// Returns the amount held of the given asset (zero if there is no entry)
+    pub fn amount_of(&self, info: &AssetInfo) -> Uint128 {
+        self.find(info)
+            .map(|asset| asset.amount)
+            .unwrap_or_default()
+    }
+
+    /// Adds the asset to its entry, or as a new entry
+    pub fn add(&mut self, other: &Asset) -> StdResult<()> {
+        match self.0.iter_mut().find(|asset| asset.info == other.info) {
+            Some(asset) => asset.amount = asset.amount.checked_add(other.amount)?,
+            None => self.0.push(other.clone()),
+        }
+        Ok(())
+    }
+
+    /// Removes the asset from its entry, dropping the entry if it gets to zero.
+    /// Fails if there is not enough of the asset.
+    pub fn deduct(&mut self, other: &Asset) -> StdResult<()> {
+        match self.0.iter().position(|asset| asset.info == other.info) {
+            Some(i) => {
+                let asset = &mut self.0[i];
+                asset.amount = asset.amount.checked_sub(other.amount)?;
+                if asset.amount.is_zero() {
+                    self.0.remove(i);
+                }
+                Ok(())
+            }
+            None if other.is_zero() => Ok(()),
+            None => Err(StdError::generic_err(format!(
+                "Cannot deduct {}, there is none",
+                other.info
+            ))),
+        }
+    }
+
+    /// Creates one message per non zero asset, transferring it from the contract to `recipient`
+    pub fn into_transfer_msgs(self, recipient: impl Into<String>) -> StdResult<Vec<CosmosMsg>> {
+        let recipient = recipient.into();
+        self.0
+            .into_iter()
+            .filter(|asset| !asset.is_zero())
+            .map(|asset| asset.into_transfer_msg(recipient.clone()))
+            .collect()
+    }
+}
+
+/// Merges the entries of the same asset, so it fails if their sum overflows
+impl TryFrom<Vec<Asset>> for AssetList {
+    type Error = StdError;
+
+    fn try_from(assets: Vec<Asset>) -> StdResult<Self> {
+        assets.into_iter().collect()
+    }
+}
+
+impl From<Asset> for AssetList {
+    fn from(asset: Asset) -> Self {
+        AssetList(vec![asset])
+    }
+}
+
+impl TryFrom<Balance> for AssetList {
+    type Error = StdError;
+
+    fn try_from(balance: Balance) -> StdResult<Self> {
+        match balance {
+            Balance::Native(native) => native.into_vec().into_iter().map(Asset::from).collect(),
+            Balance::Cw20(coin) => Ok(Asset::from(coin).into()),
+        }
+    }
+}
+
+/// Collects assets with `AssetList::add`, merging the entries of the same asset
+impl FromIterator<Asset> for StdResult<AssetList> {
+    fn from_iter<I: IntoIterator<Item = Asset>>(iter: I) -> Self {
+        let mut list = AssetList::new();
+        for asset in iter {
+            list.add(&asset)?;
+        }
+        Ok(list)
+    }
+}
+
+impl IntoIterator for AssetList {
+    type Item = Asset;
+    type IntoIter = std::vec::IntoIter<Asset>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use cosmwasm_std::testing::{mock_dependencies, mock_info, MockApi};
+    use cosmwasm_std::{coin, coins, from_binary, from_slice, ContractResult, SystemResult};
+
+    #[test]
+    fn check_asset_info() {
+        let api = MockApi::default();
+        assert_eq!(
+            AssetInfoUnchecked::from("ucosm").check(&api).unwrap(),
+            AssetInfo::native("ucosm")
+        );
+        let info = AssetInfoUnchecked::from("cw20:token").check(&api).unwrap();
+        assert_eq!(info, AssetInfo::cw20(Addr::unchecked("token")));
+        // display round trips
+        assert_eq!(info.to_string(), "cw20:token");
+        assert_eq!(
+            AssetInfoUnchecked::from(info.to_string().as_str()),
+            info.into()
+        );
+
+        AssetInfoUnchecked::Native("".to_string())
+            .check(&api)
+            .unwrap_err();
+        AssetInfoUnchecked::Cw20("".to_string())
+            .check(&api)
+            .unwrap_err();
+
+        let asset = AssetUnchecked {
+            info: AssetInfoUnchecked::Cw20("token".to_string()),
+            amount: Uint128::new(5),
+        };
+        assert_eq!(
+            asset.check(&api).unwrap(),
+            Asset::cw20(Addr::unchecked("token"), 5u128)
+        );
+    }
+
+    #[test]
+    fn asset_arithmetic() {
+        let a = Asset::native("ucosm", 100u128);
+        assert_eq!(
+            a.checked_add(&Asset::native("ucosm", 50u128)).unwrap(),
+            Asset::native("ucosm", 150u128)
+        );
+        assert_eq!(
+            a.checked_sub(&Asset::native("ucosm", 50u128)).unwrap(),
+            Asset::native("ucosm", 50u128)
+        );
+        a.checked_sub(&Asset::native("ucosm", 101u128)).unwrap_err();
+        a.checked_add(&Asset::native("uatom", 1u128)).unwrap_err();
+        a.checked_add(&Asset::cw20(Addr::unchecked("ucosm"), 1u128))
+            .unwrap_err();
+        Asset::native("ucosm", u128::MAX)
+            .checked_add(&Asset::native("ucosm", 1u128))
+            .unwrap_err();
+    }
+
+    #[test]
+    fn transfer_and_send_msgs() {
+        let native = Asset::native("ucosm", 100u128);
+        assert_eq!(
+            native.clone().into_transfer_msg("alice").unwrap(),
+            BankMsg::Send {
+                to_address: "alice".to_string(),
+                amount: coins(100, "ucosm"),
+            }
+            .into()
+        );
+        let msg = Binary::from(b"{}".to_vec());
+        assert_eq!(
+            native.into_send_msg("dex", msg.clone()).unwrap(),
+            WasmMsg::Execute {
+                contract_addr: "dex".to_string(),
+                msg: msg.clone(),
+                funds: coins(100, "ucosm"),
+            }
+            .into()
+        );
+
+        let token = Asset::cw20(Addr::unchecked("token"), 7u128);
+        match token.clone().into_transfer_msg("alice").unwrap() {
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr,
+                msg,
+                funds,
+            }) => {
+                assert_eq!(contract_addr, "token");
+                assert_eq!(funds, vec![]);
+                let parsed: Cw20ExecuteMsg = from_binary(&msg).unwrap();
+                assert_eq!(
+                    parsed,
+                    Cw20ExecuteMsg::Transfer {
+                        recipient: "alice".to_string(),
+                        amount: Uint128::new(7),
+                    }
+                );
+            }
+            m => panic!("Unexpected message: {:?}", m),
+        }
+        match token.into_send_msg("dex", msg.clone()).unwrap() {
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr,
+                msg: send,
+                ..
+            }) => {
+                assert_eq!(contract_addr, "token");
+                let parsed: Cw20ExecuteMsg = from_binary(&send).unwrap();
+                assert_eq!(
+                    parsed,
+                    Cw20ExecuteMsg::Send {
+                        contract: "dex".to_string(),
+                        amount: Uint128::new(7),
+                        msg,
+                    }
+                );
+            }
+            m => panic!("Unexpected message: {:?}", m),
+        }
+    }
+
+    #[test]
+    fn parse_received_assets() {
+        let info = mock_info("alice", &coins(100, "ucosm"));
+        assert_eq!(
+            Asset::from_funds(&info).unwrap(),
+            Asset::native("ucosm", 100u128)
+        );
+        let info = mock_info("alice", &[]);
+        assert_eq!(Asset::from_funds(&info), Err(PaymentError::NoFunds {}));
+        let info = mock_info("alice", &[coin(1, "ucosm"), coin(2, "uatom")]);
+        Asset::from_funds(&info).unwrap_err();
+        let list = AssetList::from_funds(&info).unwrap();
+        assert_eq!(list.amount_of(&AssetInfo::native("uatom")), Uint128::new(2));
+        assert_eq!(list.len(), 2);
+
+        let info = mock_info("token", &[]);
+        let receive = Cw20ReceiveMsg {
+            sender: "alice".to_string(),
+            amount: Uint128::new(7),
+            msg: Binary::default(),
+        };
+        assert_eq!(
+            Asset::from_cw20_receive(&info, &receive),
+            Asset::cw20(Addr::unchecked("token"), 7u128)
+        );
+    }
+
+    #[test]
+    fn asset_list() {
+        let token = AssetInfo::cw20(Addr::unchecked("token"));
+        let mut list = AssetList::new();
+        assert!(list.is_empty());
+        list.add(&Asset::native("ucosm", 100u128)).unwrap();
+        list.add(&Asset::new(token.clone(), 5u128)).unwrap();
+        list.add(&Asset::native("ucosm", 20u128)).unwrap();
+        assert_eq!(list.len(), 2);
+        assert_eq!(
+            list.find(&AssetInfo::native("ucosm")),
+            Some(&Asset::native("ucosm", 120u128))
+        );
+
+        list.deduct(&Asset::new(token.clone(), 5u128)).unwrap();
+        assert_eq!(list.find(&token), None);
+        list.deduct(&Asset::new(token, 1u128)).unwrap_err();
+        list.deduct(&Asset::native("ucosm", 121u128)).unwrap_err();
+        assert_eq!(
+            list.amount_of(&AssetInfo::native("ucosm")),
+            Uint128::new(120)
+        );
+
+        let balance = Balance::from(vec![coin(1, "ucosm"), coin(2, "uatom")]);
+        let list = AssetList::try_from(balance).unwrap();
+        let msgs = list.into_transfer_msgs("bob").unwrap();
+        assert_eq!(msgs.len(), 2);
+    }
+
+    #[test]
+    fn asset_list_merges_duplicates() {
+        let token = AssetInfo::cw20(Addr::unchecked("token"));
+        let assets = vec![
+            Asset::native("ucosm", 100u128),
+            Asset::new(token.clone(), 5u128),
+            Asset::native("ucosm", 20u128),
+        ];
+        let expected = AssetList(vec![
+            Asset::native("ucosm", 120u128),
+            Asset::new(token, 5u128),
+        ]);
+        assert_eq!(AssetList::try_from(assets.clone()).unwrap(), expected);
+        let collected: StdResult<AssetList> = assets.into_iter().collect();
+        assert_eq!(collected.unwrap(), expected);
+
+        let mut list = AssetList::try_from(Balance::from(vec![
+            coin(1, "ucosm"),
+            coin(2, "uatom"),
+            coin(3, "ucosm"),
+        ]))
+        .unwrap();
+        assert_eq!(list.len(), 2);
+        assert_eq!(list.amount_of(&AssetInfo::native("ucosm")), Uint128::new(4));
+        list.deduct(&Asset::native("ucosm", 4u128)).unwrap();
+        assert_eq!(list.find(&AssetInfo::native("ucosm")), None);
+
+        let info = mock_info("alice", &[coin(1, "ucosm"), coin(2, "ucosm")]);
+        let list = AssetList::from_funds(&info).unwrap();
+        assert_eq!(list, AssetList(vec![Asset::native("ucosm", 3u128)]));
+
+        // merged amounts must not overflow
+        let overflow = vec![
+            Asset::native("ucosm", u128::MAX),
+            Asset::native("ucosm", 1u128),
+        ];
+        AssetList::try_from(overflow).unwrap_err();
+    }
+
+    #[test]
+    fn query_balances() {
+        let mut deps = mock_dependencies();
+        deps.querier.update_balance("alice", coins(100, "ucosm"));
+        deps.querier.update_wasm(|query| match query {
+            cosmwasm_std::WasmQuery::Smart { contract_addr, msg } if contract_addr == "token" => {
+                let query: Cw20QueryMsg = from_slice(msg).unwrap();
+                assert_eq!(
+                    query,
+                    Cw20QueryMsg::Balance {
+                        address: "alice".to_string()
+                    }
+                );
+                let res = BalanceResponse {
+                    balance: Uint128::new(7),
+                };
+                SystemResult::Ok(ContractResult::Ok(to_binary(&res).unwrap()))
+            }
+            _ => panic!("Unexpected query"),
+        });
+        let querier = QuerierWrapper::<cosmwasm_std::Empty>::new(&deps.querier);
+
+        let native = AssetInfo::native("ucosm");
+        assert_eq!(
+            native.query_balance(&querier, "alice").unwrap(),
+            Uint128::new(100)
+        );
+        let token = AssetInfo::cw20(Addr::unchecked("token"));
+        assert_eq!(
+            token.query_balance(&querier, "alice").unwrap(),
+            Uint128::new(7)
+        );
+    }
+}
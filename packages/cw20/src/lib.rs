@@ -1,5 +1,6 @@
 pub use cw_utils::Expiration;
 
+pub use crate::asset::{Asset, AssetInfo, AssetInfoUnchecked, AssetList, AssetUnchecked};
 pub use crate::balance::Balance;
 pub use crate::coin::{Cw20Coin, Cw20CoinVerified};
 pub use crate::denom::Denom;
@@ -13,6 +14,7 @@ pub use crate::query::{
 };
 pub use crate::receiver::Cw20ReceiveMsg;
 
+mod asset;
 mod balance;
 mod coin;
 mod denom;
@@ -9,6 +9,7 @@ use cosmwasm_std::{
     Empty, Querier, QuerierResult, QuerierWrapper, QueryRequest, Record, Storage, SystemError,
     SystemResult,
 };
+use cw_storage_plus::StorageInspector;
 use schemars::JsonSchema;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
@@ -571,6 +572,12 @@ where
     pub fn dump_wasm_raw(&self, address: &Addr) -> Vec<Record> {
         self.read_module(|router, _, storage| router.wasm.dump_wasm_raw(storage, address))
     }
+
+    /// This renders the state of a given contract, one record per line, decoding the keys
+    /// and values of the `Item`s and `Map`s registered in the `inspector`
+    pub fn dump_wasm(&self, address: &Addr, inspector: &StorageInspector) -> String {
+        inspector.dump(&self.dump_wasm_raw(address))
+    }
 }
 
 impl<BankT, ApiT, StorageT, CustomT, WasmT, StakingT, DistrT>
@@ -955,7 +962,7 @@ mod test {
 
     use crate::error::Error;
     use crate::test_helpers::contracts::{caller, echo, error, hackatom, payout, reflect};
-    use crate::test_helpers::{CustomMsg, EmptyMsg};
+    use crate::test_helpers::{CustomMsg, EmptyMsg, COUNT};
     use crate::transactions::StorageTransaction;
 
     fn get_balance<BankT, ApiT, StorageT, CustomT, WasmT>(
@@ -1115,6 +1122,35 @@ mod test {
         assert_eq!(funds, coins(18, "eth"));
     }
 
+    #[test]
+    fn dump_wasm_decodes_state() {
+        let owner = Addr::unchecked("owner");
+        let mut app = App::default();
+
+        let code_id = app.store_code(payout::contract());
+        let msg = payout::InstantiateMessage {
+            payout: coin(5, "eth"),
+        };
+        let contract_addr = app
+            .instantiate_contract(code_id, owner, &msg, &[], "Payout", None)
+            .unwrap();
+
+        let inspector = StorageInspector::new().item(&payout::PAYOUT).item(&COUNT);
+        let dump = app.dump_wasm(&contract_addr, &inspector);
+        assert_eq!(
+            dump,
+            "count = 1\npayout = {\"payout\":{\"denom\":\"eth\",\"amount\":\"5\"}}"
+        );
+
+        // records nothing was registered for are shown raw
+        let inspector = StorageInspector::new().item(&COUNT);
+        let dump = app.dump_wasm(&contract_addr, &inspector);
+        assert_eq!(
+            dump.lines().nth(1).unwrap(),
+            r#"? payout = {\"payout\":{\"denom\":\"eth\",\"amount\":\"5\"}}"#
+        );
+    }
+
     #[test]
     fn reflect_success() {
         // set personal balance
@@ -17,4 +17,4 @@ pub enum CustomMsg {
     SetAge { age: u32 },
 }
 
-const COUNT: Item<u32> = Item::new("count");
+pub(crate) const COUNT: Item<u32> = Item::new("count");
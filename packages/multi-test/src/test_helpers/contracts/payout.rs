@@ -31,7 +31,7 @@ pub struct CountResponse {
     pub count: u32,
 }
 
-const PAYOUT: Item<InstantiateMessage> = Item::new("payout");
+pub const PAYOUT: Item<InstantiateMessage> = Item::new("payout");
 
 fn instantiate(
     deps: DepsMut,
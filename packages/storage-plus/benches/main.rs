@@ -1,10 +1,16 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
 
 use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
 use std::mem;
 use std::time::Duration;
 
-use cw_storage_plus::IntKey;
+use cosmwasm_std::testing::MockStorage;
+use cosmwasm_std::{Addr, Binary, Order, Record, StdResult, Storage, Uint128};
+#[cfg(feature = "bin-codec")]
+use cw_storage_plus::BinCodec;
+use cw_storage_plus::{CachedStorage, Codec, IntKey, JsonCodec, Map, RawCodec};
 
 fn bench_signed_int_key(c: &mut Criterion) {
     let mut group = c.benchmark_group("Signed int keys");
@@ -140,6 +146,206 @@ fn bench_unsigned_int_key(c: &mut Criterion) {
     group.finish();
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct Claim {
+    owner: Addr,
+    amount: Uint128,
+    release_at: u64,
+}
+
+fn bench_codecs(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Value codecs");
+
+    let balance = Uint128::new(rand::thread_rng().gen_range(0..u64::MAX) as u128);
+    let claim = Claim {
+        owner: Addr::unchecked("cosmos1qyqszqgpqyqszqgpqyqszqgpqyqszqgp8apuk5"),
+        amount: balance,
+        release_at: 1_650_000_000,
+    };
+    let blob = Binary::from(vec![0xab; 32]);
+
+    // what ends up in storage is what gets charged for, so print the sizes once
+    println!(
+        "Uint128 value size: json {} bytes",
+        JsonCodec::encode(&balance).unwrap().len()
+    );
+    println!(
+        "Claim value size: json {} bytes",
+        JsonCodec::encode(&claim).unwrap().len()
+    );
+    println!(
+        "32 bytes Binary value size: json {} bytes, raw {} bytes",
+        JsonCodec::encode(&blob).unwrap().len(),
+        RawCodec::encode(&blob).unwrap().len()
+    );
+    #[cfg(feature = "bin-codec")]
+    println!(
+        "bin value sizes: Uint128 {} bytes, Claim {} bytes, 32 bytes Binary {} bytes",
+        BinCodec::encode(&balance).unwrap().len(),
+        BinCodec::encode(&claim).unwrap().len(),
+        BinCodec::encode(&blob).unwrap().len()
+    );
+
+    group.bench_function("Uint128 json roundtrip", |b| {
+        b.iter(|| {
+            let data = JsonCodec::encode(black_box(&balance)).unwrap();
+            let value: Uint128 = JsonCodec::decode(&data).unwrap();
+            black_box(value);
+        });
+    });
+
+    #[cfg(feature = "bin-codec")]
+    group.bench_function("Uint128 bin roundtrip", |b| {
+        b.iter(|| {
+            let data = BinCodec::encode(black_box(&balance)).unwrap();
+            let value: Uint128 = BinCodec::decode(&data).unwrap();
+            black_box(value);
+        });
+    });
+
+    group.bench_function("Claim json roundtrip", |b| {
+        b.iter(|| {
+            let data = JsonCodec::encode(black_box(&claim)).unwrap();
+            let value: Claim = JsonCodec::decode(&data).unwrap();
+            black_box(value);
+        });
+    });
+
+    #[cfg(feature = "bin-codec")]
+    group.bench_function("Claim bin roundtrip", |b| {
+        b.iter(|| {
+            let data = BinCodec::encode(black_box(&claim)).unwrap();
+            let value: Claim = BinCodec::decode(&data).unwrap();
+            black_box(value);
+        });
+    });
+
+    group.bench_function("Binary json roundtrip", |b| {
+        b.iter(|| {
+            let data = JsonCodec::encode(black_box(&blob)).unwrap();
+            let value: Binary = JsonCodec::decode(&data).unwrap();
+            black_box(value);
+        });
+    });
+
+    group.bench_function("Binary raw roundtrip", |b| {
+        b.iter(|| {
+            let data = RawCodec::encode(black_box(&blob)).unwrap();
+            let value: Binary = RawCodec::decode(&data).unwrap();
+            black_box(value);
+        });
+    });
+
+    group.finish();
+}
+
+/// Counts the calls made to the storage, which are host calls in a contract
+#[derive(Default)]
+struct CountingStorage {
+    storage: MockStorage,
+    reads: Cell<usize>,
+    writes: usize,
+}
+
+impl Storage for CountingStorage {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.reads.set(self.reads.get() + 1);
+        self.storage.get(key)
+    }
+
+    fn range<'a>(
+        &'a self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'a> {
+        self.reads.set(self.reads.get() + 1);
+        self.storage.range(start, end, order)
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) {
+        self.writes += 1;
+        self.storage.set(key, value)
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        self.writes += 1;
+        self.storage.remove(key)
+    }
+}
+
+const BALANCES: Map<&str, Uint128> = Map::new("balances");
+
+// a batch of transfers going back and forth between a few accounts
+fn batch_transfer(store: &mut dyn Storage) -> StdResult<()> {
+    const ACCOUNTS: [&str; 4] = ["alice", "bob", "carl", "dave"];
+    for i in 0..40 {
+        let from = ACCOUNTS[i % 4];
+        let to = ACCOUNTS[(i + 1) % 4];
+        BALANCES.update(store, from, |b| -> StdResult<_> {
+            Ok(b.unwrap_or_default().checked_sub(Uint128::new(1))?)
+        })?;
+        BALANCES.update(store, to, |b| -> StdResult<_> {
+            Ok(b.unwrap_or_default() + Uint128::new(1))
+        })?;
+    }
+    Ok(())
+}
+
+fn setup_balances() -> CountingStorage {
+    let mut store = CountingStorage::default();
+    for account in ["alice", "bob", "carl", "dave"] {
+        BALANCES
+            .save(&mut store.storage, account, &Uint128::new(1000))
+            .unwrap();
+    }
+    store
+}
+
+fn bench_cached_storage(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Cached storage");
+
+    // host calls are what is expensive in a contract, so print them once
+    let mut store = setup_balances();
+    batch_transfer(&mut store).unwrap();
+    println!(
+        "40 transfers, direct: {} reads, {} writes",
+        store.reads.get(),
+        store.writes
+    );
+    let mut store = setup_balances();
+    let mut cache = CachedStorage::new(&mut store);
+    batch_transfer(&mut cache).unwrap();
+    cache.flush();
+    println!(
+        "40 transfers, cached: {} reads, {} writes",
+        store.reads.get(),
+        store.writes
+    );
+
+    group.bench_function("40 transfers direct", |b| {
+        b.iter_batched_ref(
+            setup_balances,
+            |store| batch_transfer(store).unwrap(),
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.bench_function("40 transfers cached", |b| {
+        b.iter_batched_ref(
+            setup_balances,
+            |store| {
+                let mut cache = CachedStorage::new(store);
+                batch_transfer(&mut cache).unwrap();
+                cache.flush();
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
+    group.finish();
+}
+
 fn make_config() -> Criterion {
     Criterion::default()
         .without_plots()
@@ -158,4 +364,14 @@ criterion_group!(
     config = make_config();
     targets = bench_unsigned_int_key
 );
-criterion_main!(signed_int_key, unsigned_int_key);
+criterion_group!(
+    name = codecs;
+    config = make_config();
+    targets = bench_codecs
+);
+criterion_group!(
+    name = cached_storage;
+    config = make_config();
+    targets = bench_cached_storage
+);
+criterion_main!(signed_int_key, unsigned_int_key, codecs, cached_storage);
@@ -0,0 +1,329 @@
+use std::cell::RefCell;
+#[cfg(feature = "iterator")]
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+#[cfg(feature = "iterator")]
+use std::iter::Peekable;
+#[cfg(feature = "iterator")]
+use std::ops::Bound;
+
+use cosmwasm_std::Storage;
+#[cfg(feature = "iterator")]
+use cosmwasm_std::{Order, Record};
+
+/// A value as known to the cache. `None` means the key is known not to be set.
+struct Cached {
+    value: Option<Vec<u8>>,
+    /// whether it has to be written back to the wrapped storage
+    dirty: bool,
+}
+
+/// CachedStorage wraps a `Storage` and keeps all the values it reads and writes in memory, so
+/// reading or writing the same key repeatedly only costs one read and one write on the wrapped
+/// storage. This is useful in hot paths that go through the same keys many times (e.g. batch
+/// transfers updating the same balances), and works with any `Item` or `Map`:
+///
+/// ```rust
+/// # use cosmwasm_std::testing::MockStorage;
+/// # use cosmwasm_std::{StdResult, Storage};
+/// use cw_storage_plus::{CachedStorage, Map};
+///
+/// const BALANCES: Map<&str, u64> = Map::new("balances");
+///
+/// fn batch_transfer(store: &mut dyn Storage, transfers: &[(&str, &str, u64)]) -> StdResult<()> {
+///     let mut cache = CachedStorage::new(store);
+///     for (from, to, amount) in transfers {
+///         BALANCES.update(&mut cache, from, |b| -> StdResult<_> { Ok(b.unwrap_or_default() - amount) })?;
+///         BALANCES.update(&mut cache, to, |b| -> StdResult<_> { Ok(b.unwrap_or_default() + amount) })?;
+///     }
+///     cache.flush();
+///     Ok(())
+/// }
+/// # let mut store = MockStorage::new();
+/// # BALANCES.save(&mut store, "alice", &10).unwrap();
+/// # batch_transfer(&mut store, &[("alice", "bob", 3), ("bob", "carl", 2)]).unwrap();
+/// # assert_eq!(BALANCES.load(&store, "bob").unwrap(), 1);
+/// ```
+///
+/// Writes only reach the wrapped storage on `flush`. If the cache is dropped without being
+/// flushed (e.g. when returning early on an error), they are discarded.
+///
+/// Ranges go to the wrapped storage, with the pending writes merged in. Their results are not
+/// cached.
+pub struct CachedStorage<'a> {
+    storage: &'a mut dyn Storage,
+    cache: RefCell<BTreeMap<Vec<u8>, Cached>>,
+}
+
+impl<'a> CachedStorage<'a> {
+    pub fn new(storage: &'a mut dyn Storage) -> Self {
+        CachedStorage {
+            storage,
+            cache: RefCell::new(BTreeMap::new()),
+        }
+    }
+
+    /// Number of keys written or removed, which `flush` will write back
+    pub fn pending_writes(&self) -> usize {
+        self.cache
+            .borrow()
+            .values()
+            .filter(|cached| cached.dirty)
+            .count()
+    }
+
+    /// Writes all the changes back to the wrapped storage, once per key
+    pub fn flush(self) {
+        for (key, cached) in self.cache.into_inner() {
+            if !cached.dirty {
+                continue;
+            }
+            match cached.value {
+                Some(value) => self.storage.set(&key, &value),
+                None => self.storage.remove(&key),
+            }
+        }
+    }
+
+    fn write(&mut self, key: &[u8], value: Option<Vec<u8>>) {
+        self.cache
+            .get_mut()
+            .insert(key.to_vec(), Cached { value, dirty: true });
+    }
+}
+
+impl<'a> Storage for CachedStorage<'a> {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        if let Some(cached) = self.cache.borrow().get(key) {
+            return cached.value.clone();
+        }
+        let value = self.storage.get(key);
+        self.cache.borrow_mut().insert(
+            key.to_vec(),
+            Cached {
+                value: value.clone(),
+                dirty: false,
+            },
+        );
+        value
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) {
+        if value.is_empty() {
+            // the wrapped storage would only complain on flush
+            panic!("Value must not be empty in Storage::set, use Storage::remove instead");
+        }
+        self.write(key, Some(value.to_vec()));
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        self.write(key, None);
+    }
+
+    #[cfg(feature = "iterator")]
+    fn range<'b>(
+        &'b self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'b> {
+        // the pending writes in range are copied, so `get` can still update the cache while
+        // the range is being consumed
+        let bounds = (
+            start.map_or(Bound::Unbounded, |x| Bound::Included(x.to_vec())),
+            end.map_or(Bound::Unbounded, |x| Bound::Excluded(x.to_vec())),
+        );
+        let mut pending: Vec<(Vec<u8>, Option<Vec<u8>>)> = match (start, end) {
+            // BTreeMap::range panics if start > end, which is just an empty range
+            (Some(start), Some(end)) if start > end => vec![],
+            _ => self
+                .cache
+                .borrow()
+                .range(bounds)
+                .filter(|(_, cached)| cached.dirty)
+                .map(|(key, cached)| (key.clone(), cached.value.clone()))
+                .collect(),
+        };
+        if let Order::Descending = order {
+            pending.reverse();
+        }
+
+        let base = self.storage.range(start, end, order);
+        Box::new(MergeOverlay::new(pending.into_iter(), base, order))
+    }
+}
+
+/// Merges the pending writes (left) into the records of the wrapped storage (right), both
+/// sorted in the same order
+#[cfg(feature = "iterator")]
+struct MergeOverlay<L, R>
+where
+    L: Iterator<Item = (Vec<u8>, Option<Vec<u8>>)>,
+    R: Iterator<Item = Record>,
+{
+    left: Peekable<L>,
+    right: Peekable<R>,
+    order: Order,
+}
+
+#[cfg(feature = "iterator")]
+impl<L, R> MergeOverlay<L, R>
+where
+    L: Iterator<Item = (Vec<u8>, Option<Vec<u8>>)>,
+    R: Iterator<Item = Record>,
+{
+    fn new(left: L, right: R, order: Order) -> Self {
+        MergeOverlay {
+            left: left.peekable(),
+            right: right.peekable(),
+            order,
+        }
+    }
+}
+
+#[cfg(feature = "iterator")]
+impl<L, R> Iterator for MergeOverlay<L, R>
+where
+    L: Iterator<Item = (Vec<u8>, Option<Vec<u8>>)>,
+    R: Iterator<Item = Record>,
+{
+    type Item = Record;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            // Ordering::Less => take the left side
+            let pick = match (self.left.peek(), self.right.peek()) {
+                (Some((lkey, _)), Some((rkey, _))) => match self.order {
+                    Order::Ascending => lkey.cmp(rkey),
+                    Order::Descending => rkey.cmp(lkey),
+                },
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => return None,
+            };
+            match pick {
+                Ordering::Greater => return self.right.next(),
+                Ordering::Equal => {
+                    // overwritten or removed
+                    self.right.next();
+                }
+                Ordering::Less => {}
+            }
+            if let Some((key, Some(value))) = self.left.next() {
+                return Some((key, value));
+            }
+            // removed key, look further
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use cosmwasm_std::testing::MockStorage;
+
+    use crate::Map;
+
+    const BALANCES: Map<&str, u64> = Map::new("balances");
+
+    #[test]
+    fn reads_and_writes_through_cache() {
+        let mut store = MockStorage::new();
+        store.set(b"foo", b"1");
+        store.set(b"bar", b"2");
+
+        let mut cache = CachedStorage::new(&mut store);
+        assert_eq!(cache.get(b"foo"), Some(b"1".to_vec()));
+        assert_eq!(cache.get(b"baz"), None);
+        cache.set(b"foo", b"3");
+        cache.set(b"baz", b"4");
+        cache.remove(b"bar");
+        assert_eq!(cache.get(b"foo"), Some(b"3".to_vec()));
+        assert_eq!(cache.get(b"bar"), None);
+        assert_eq!(cache.get(b"baz"), Some(b"4".to_vec()));
+        assert_eq!(cache.pending_writes(), 3);
+        cache.flush();
+
+        assert_eq!(store.get(b"foo"), Some(b"3".to_vec()));
+        assert_eq!(store.get(b"bar"), None);
+        assert_eq!(store.get(b"baz"), Some(b"4".to_vec()));
+    }
+
+    #[test]
+    fn dropping_discards_writes() {
+        let mut store = MockStorage::new();
+        BALANCES.save(&mut store, "alice", &10).unwrap();
+        {
+            let mut cache = CachedStorage::new(&mut store);
+            BALANCES.save(&mut cache, "alice", &20).unwrap();
+            BALANCES.save(&mut cache, "bob", &5).unwrap();
+            assert_eq!(BALANCES.load(&cache, "alice").unwrap(), 20);
+        }
+        assert_eq!(BALANCES.load(&store, "alice").unwrap(), 10);
+        assert_eq!(BALANCES.may_load(&store, "bob").unwrap(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn range_merges_pending_writes() {
+        let mut store = MockStorage::new();
+        for (name, amount) in [("alice", 1), ("bob", 2), ("carl", 3), ("dave", 4)] {
+            BALANCES.save(&mut store, name, &amount).unwrap();
+        }
+
+        let mut cache = CachedStorage::new(&mut store);
+        // cached reads do not matter
+        BALANCES.load(&cache, "alice").unwrap();
+        BALANCES.save(&mut cache, "bob", &20).unwrap();
+        BALANCES.remove(&mut cache, "carl");
+        BALANCES.save(&mut cache, "eve", &5).unwrap();
+        BALANCES.save(&mut cache, "abe", &6).unwrap();
+        BALANCES.remove(&mut cache, "zed");
+
+        let all: Vec<_> = BALANCES
+            .range(&cache, None, None, Order::Ascending)
+            .collect::<cosmwasm_std::StdResult<_>>()
+            .unwrap();
+        let expected = vec![
+            ("abe".to_string(), 6),
+            ("alice".to_string(), 1),
+            ("bob".to_string(), 20),
+            ("dave".to_string(), 4),
+            ("eve".to_string(), 5),
+        ];
+        assert_eq!(all, expected);
+
+        let mut reversed: Vec<_> = BALANCES
+            .range(&cache, None, None, Order::Descending)
+            .collect::<cosmwasm_std::StdResult<_>>()
+            .unwrap();
+        reversed.reverse();
+        assert_eq!(reversed, expected);
+
+        // bounds apply to pending writes too
+        let some: Vec<_> = BALANCES
+            .keys(
+                &cache,
+                Some(crate::Bound::inclusive("b")),
+                Some(crate::Bound::exclusive("e")),
+                Order::Ascending,
+            )
+            .collect::<cosmwasm_std::StdResult<_>>()
+            .unwrap();
+        assert_eq!(some, vec!["bob".to_string(), "dave".to_string()]);
+
+        // reading while ranging is fine
+        let mut iter = cache.range(None, None, Order::Ascending);
+        iter.next().unwrap();
+        BALANCES.may_load(&cache, "frank").unwrap();
+        assert_eq!(iter.count(), 4);
+
+        cache.flush();
+        let all: Vec<_> = BALANCES
+            .range(&store, None, None, Order::Ascending)
+            .collect::<cosmwasm_std::StdResult<_>>()
+            .unwrap();
+        assert_eq!(all, expected);
+    }
+}
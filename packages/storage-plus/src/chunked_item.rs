@@ -0,0 +1,424 @@
+use std::convert::TryFrom;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use cosmwasm_std::{Binary, StdError, StdResult, Storage};
+
+use crate::helpers::namespaces_with_key;
+use crate::item::Item;
+
+/// BlobManifest describes the content stored in a `ChunkedItem`.
+/// It is small, and can be loaded (or returned from a query) without reading the data.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BlobManifest {
+    /// Length of the data, in bytes
+    pub len: u64,
+    /// Size of every chunk but the last one
+    pub chunk_size: u32,
+    /// sha256 of the data. `None` after a partial write, until `ChunkedItem::finalize` is called
+    pub hash: Option<Binary>,
+}
+
+impl BlobManifest {
+    /// Number of chunks the data is split in
+    pub fn chunk_count(&self) -> u64 {
+        let full = self.len / self.chunk_size as u64;
+        if self.len > full * self.chunk_size as u64 {
+            full + 1
+        } else {
+            full
+        }
+    }
+}
+
+/// ChunkedItem stores one byte blob, split in chunks of at most `chunk_size` bytes, each under
+/// its own key. Unlike an `Item<Binary>`, it can hold data larger than what is reasonable to
+/// write (or to send in a single message) at once: it can be uploaded piece by piece with
+/// `append` or `write_at`, read partially with `read`, or streamed with `chunks`.
+///
+/// The manifest (length, chunk size and sha256 of the whole data) is stored under the namespace
+/// itself, like an `Item`. Chunk `i` is stored under (namespace, i as big endian u32).
+///
+/// Hashing needs the whole data, so partial writes only clear the hash: call `finalize` once the
+/// upload is complete to compute it (in one pass), and have `load` check the data against it.
+pub struct ChunkedItem<'a> {
+    manifest: Item<'a, BlobManifest>,
+    namespace: &'a [u8],
+    chunk_size: u32,
+}
+
+impl<'a> ChunkedItem<'a> {
+    /// Example:
+    ///
+    /// ```rust
+    /// use cw_storage_plus::ChunkedItem;
+    ///
+    /// const LOGO: ChunkedItem = ChunkedItem::new("logo", 4096);
+    /// ```
+    ///
+    /// `chunk_size` is used for data saved from now on, existing data keeps the chunk size it
+    /// was saved with.
+    pub const fn new(namespace: &'a str, chunk_size: u32) -> Self {
+        ChunkedItem {
+            manifest: Item::new(namespace),
+            namespace: namespace.as_bytes(),
+            chunk_size,
+        }
+    }
+
+    pub fn namespace(&self) -> &'a [u8] {
+        self.namespace
+    }
+
+    /// Returns the manifest of the stored data, or `None` if nothing is stored
+    pub fn manifest(&self, store: &dyn Storage) -> StdResult<Option<BlobManifest>> {
+        self.manifest.may_load(store)
+    }
+
+    /// Replaces the stored data, if any, with `data`
+    pub fn save(&self, store: &mut dyn Storage, data: &[u8]) -> StdResult<BlobManifest> {
+        if self.chunk_size == 0 {
+            return Err(StdError::generic_err(
+                "ChunkedItem chunk size must not be 0",
+            ));
+        }
+        let old_count = self.manifest(store)?.map_or(0, |m| m.chunk_count());
+        let mut count = 0;
+        for chunk in data.chunks(self.chunk_size as usize) {
+            store.set(&self.chunk_key(count)?, chunk);
+            count += 1;
+        }
+        for index in count..old_count {
+            store.remove(&self.chunk_key(index)?);
+        }
+        let manifest = BlobManifest {
+            len: data.len() as u64,
+            chunk_size: self.chunk_size,
+            hash: Some(Binary::from(Sha256::digest(data).as_slice())),
+        };
+        self.manifest.save(store, &manifest)?;
+        Ok(manifest)
+    }
+
+    /// Removes the manifest and all the chunks
+    pub fn remove(&self, store: &mut dyn Storage) -> StdResult<()> {
+        if let Some(manifest) = self.manifest(store)? {
+            for index in 0..manifest.chunk_count() {
+                store.remove(&self.chunk_key(index)?);
+            }
+            self.manifest.remove(store);
+        }
+        Ok(())
+    }
+
+    /// Loads the whole data, checking it against the hash in the manifest (if it was computed,
+    /// see `finalize`). Returns `StdError::NotFound` if nothing is stored.
+    pub fn load(&self, store: &dyn Storage) -> StdResult<Vec<u8>> {
+        self.may_load(store)?
+            .ok_or_else(|| StdError::not_found("ChunkedItem"))
+    }
+
+    /// Like `load`, but returns `Ok(None)` if nothing is stored
+    pub fn may_load(&self, store: &dyn Storage) -> StdResult<Option<Vec<u8>>> {
+        let manifest = match self.manifest(store)? {
+            Some(manifest) => manifest,
+            None => return Ok(None),
+        };
+        let mut data = Vec::with_capacity(manifest.len as usize);
+        for chunk in self.chunks_of(store, &manifest) {
+            data.extend(chunk?);
+        }
+        let hash_matches = match &manifest.hash {
+            Some(hash) => Sha256::digest(&data).as_slice() == hash.as_slice(),
+            None => true,
+        };
+        if data.len() as u64 != manifest.len || !hash_matches {
+            return Err(StdError::generic_err(
+                "ChunkedItem data does not match its manifest",
+            ));
+        }
+        Ok(Some(data))
+    }
+
+    /// Computes the hash of the stored data, after it was written with `append` or `write_at`.
+    /// Returns `StdError::NotFound` if nothing is stored.
+    pub fn finalize(&self, store: &mut dyn Storage) -> StdResult<BlobManifest> {
+        let mut manifest = self
+            .manifest(store)?
+            .ok_or_else(|| StdError::not_found("ChunkedItem"))?;
+        let mut hasher = Sha256::new();
+        for chunk in self.chunks_of(store, &manifest) {
+            hasher.update(chunk?);
+        }
+        manifest.hash = Some(Binary::from(hasher.finalize().as_slice()));
+        self.manifest.save(store, &manifest)?;
+        Ok(manifest)
+    }
+
+    /// Iterates over the chunks of the stored data, reading them one at a time.
+    /// Returns `StdError::NotFound` if nothing is stored.
+    ///
+    /// Chunks are not checked against the hash in the manifest.
+    pub fn chunks<'c>(
+        &self,
+        store: &'c dyn Storage,
+    ) -> StdResult<Box<dyn Iterator<Item = StdResult<Vec<u8>>> + 'c>>
+    where
+        'a: 'c,
+    {
+        let manifest = self
+            .manifest(store)?
+            .ok_or_else(|| StdError::not_found("ChunkedItem"))?;
+        Ok(self.chunks_of(store, &manifest))
+    }
+
+    /// Reads `len` bytes starting at `offset`, only loading the chunks holding them.
+    /// Fails if the range goes past the end of the data.
+    pub fn read(&self, store: &dyn Storage, offset: u64, len: u64) -> StdResult<Vec<u8>> {
+        let manifest = self
+            .manifest(store)?
+            .ok_or_else(|| StdError::not_found("ChunkedItem"))?;
+        let end = offset
+            .checked_add(len)
+            .filter(|end| *end <= manifest.len)
+            .ok_or_else(|| StdError::generic_err("ChunkedItem read out of bounds"))?;
+
+        let chunk_size = manifest.chunk_size as u64;
+        let mut data = Vec::with_capacity(len as usize);
+        let mut pos = offset;
+        while pos < end {
+            let index = pos / chunk_size;
+            let start = index * chunk_size;
+            let chunk = self.load_chunk(store, index)?;
+            let from = (pos - start) as usize;
+            let to = (end.min(start + chunk_size) - start) as usize;
+            let bytes = chunk.get(from..to).ok_or_else(|| {
+                StdError::generic_err("ChunkedItem data does not match its manifest")
+            })?;
+            data.extend_from_slice(bytes);
+            pos = start + to as u64;
+        }
+        Ok(data)
+    }
+
+    /// Overwrites the data from `offset` on with `data`, extending it if needed.
+    /// Only the chunks overlapping with the written range are read and written. The hash in the
+    /// manifest is cleared, call `finalize` to compute it again.
+    ///
+    /// If nothing is stored, `offset` must be 0, and this is the same as `save`.
+    /// Fails if `offset` is past the end of the data, as that would leave a hole.
+    pub fn write_at(
+        &self,
+        store: &mut dyn Storage,
+        offset: u64,
+        data: &[u8],
+    ) -> StdResult<BlobManifest> {
+        let manifest = match self.manifest(store)? {
+            Some(manifest) => manifest,
+            None if offset == 0 => return self.save(store, data),
+            None => return Err(StdError::not_found("ChunkedItem")),
+        };
+        if offset > manifest.len {
+            return Err(StdError::generic_err("ChunkedItem write out of bounds"));
+        }
+
+        let chunk_size = manifest.chunk_size as u64;
+        let end = offset + data.len() as u64;
+        let mut pos = offset;
+        while pos < end {
+            let index = pos / chunk_size;
+            let start = index * chunk_size;
+            let mut chunk = if start < manifest.len {
+                self.load_chunk(store, index)?
+            } else {
+                vec![]
+            };
+            let from = (pos - start) as usize;
+            let to = (end.min(start + chunk_size) - start) as usize;
+            if chunk.len() < to {
+                chunk.resize(to, 0);
+            }
+            let src = (pos - offset) as usize;
+            chunk[from..to].copy_from_slice(&data[src..src + to - from]);
+            store.set(&self.chunk_key(index)?, &chunk);
+            pos = start + to as u64;
+        }
+
+        let manifest = BlobManifest {
+            len: manifest.len.max(end),
+            hash: None,
+            ..manifest
+        };
+        self.manifest.save(store, &manifest)?;
+        Ok(manifest)
+    }
+
+    /// Adds `data` at the end of the stored data (or stores it if there is none).
+    /// As for `write_at`, the hash is cleared.
+    pub fn append(&self, store: &mut dyn Storage, data: &[u8]) -> StdResult<BlobManifest> {
+        let len = self.manifest(store)?.map_or(0, |m| m.len);
+        self.write_at(store, len, data)
+    }
+
+    fn chunks_of<'c>(
+        &self,
+        store: &'c dyn Storage,
+        manifest: &BlobManifest,
+    ) -> Box<dyn Iterator<Item = StdResult<Vec<u8>>> + 'c>
+    where
+        'a: 'c,
+    {
+        let namespace = self.namespace;
+        Box::new((0..manifest.chunk_count()).map(move |index| load_chunk(store, namespace, index)))
+    }
+
+    fn load_chunk(&self, store: &dyn Storage, index: u64) -> StdResult<Vec<u8>> {
+        load_chunk(store, self.namespace, index)
+    }
+
+    fn chunk_key(&self, index: u64) -> StdResult<Vec<u8>> {
+        chunk_key(self.namespace, index)
+    }
+}
+
+fn chunk_key(namespace: &[u8], index: u64) -> StdResult<Vec<u8>> {
+    let index = u32::try_from(index)
+        .map_err(|_| StdError::generic_err("ChunkedItem has too many chunks"))?;
+    Ok(namespaces_with_key(&[namespace], &index.to_be_bytes()))
+}
+
+fn load_chunk(store: &dyn Storage, namespace: &[u8], index: u64) -> StdResult<Vec<u8>> {
+    store
+        .get(&chunk_key(namespace, index)?)
+        .ok_or_else(|| StdError::not_found("ChunkedItem chunk"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use cosmwasm_std::testing::MockStorage;
+
+    const LOGO: ChunkedItem = ChunkedItem::new("logo", 4);
+
+    // no iterator needed, the tests never use more than a few chunks
+    fn stored_chunks(store: &dyn Storage) -> usize {
+        (0..16)
+            .filter(|index| store.get(&chunk_key(b"logo", *index).unwrap()).is_some())
+            .count()
+    }
+
+    #[test]
+    fn save_load_remove() {
+        let mut store = MockStorage::new();
+        assert_eq!(LOGO.may_load(&store).unwrap(), None);
+        assert!(matches!(
+            LOGO.load(&store).unwrap_err(),
+            StdError::NotFound { .. }
+        ));
+
+        let data = b"0123456789".to_vec();
+        let manifest = LOGO.save(&mut store, &data).unwrap();
+        assert_eq!(manifest.len, 10);
+        assert_eq!(manifest.chunk_count(), 3);
+        assert_eq!(
+            manifest.hash.as_ref().unwrap().as_slice(),
+            Sha256::digest(&data).as_slice()
+        );
+        assert_eq!(LOGO.manifest(&store).unwrap(), Some(manifest));
+        assert_eq!(LOGO.load(&store).unwrap(), data);
+        assert_eq!(stored_chunks(&store), 3);
+
+        let chunks: Vec<_> = LOGO
+            .chunks(&store)
+            .unwrap()
+            .collect::<StdResult<_>>()
+            .unwrap();
+        assert_eq!(
+            chunks,
+            vec![b"0123".to_vec(), b"4567".to_vec(), b"89".to_vec()]
+        );
+
+        // saving shorter data drops the chunks not used anymore
+        LOGO.save(&mut store, b"abcde").unwrap();
+        assert_eq!(LOGO.load(&store).unwrap(), b"abcde".to_vec());
+        assert_eq!(stored_chunks(&store), 2);
+
+        // empty data has no chunks, but is still there
+        LOGO.save(&mut store, b"").unwrap();
+        assert_eq!(LOGO.load(&store).unwrap(), Vec::<u8>::new());
+        assert_eq!(stored_chunks(&store), 0);
+
+        LOGO.save(&mut store, &data).unwrap();
+        LOGO.remove(&mut store).unwrap();
+        assert_eq!(LOGO.may_load(&store).unwrap(), None);
+        assert_eq!(stored_chunks(&store), 0);
+        // safe to remove twice
+        LOGO.remove(&mut store).unwrap();
+    }
+
+    #[test]
+    fn partial_reads_and_writes() {
+        let mut store = MockStorage::new();
+        LOGO.save(&mut store, b"0123456789").unwrap();
+
+        assert_eq!(LOGO.read(&store, 0, 10).unwrap(), b"0123456789".to_vec());
+        assert_eq!(LOGO.read(&store, 3, 6).unwrap(), b"345678".to_vec());
+        assert_eq!(LOGO.read(&store, 10, 0).unwrap(), Vec::<u8>::new());
+        LOGO.read(&store, 8, 3).unwrap_err();
+        LOGO.read(&store, 1, u64::MAX).unwrap_err();
+
+        // overwrite across a chunk boundary
+        let manifest = LOGO.write_at(&mut store, 2, b"abcd").unwrap();
+        assert_eq!(manifest.len, 10);
+        assert_eq!(LOGO.load(&store).unwrap(), b"01abcd6789".to_vec());
+
+        // overwrite and extend
+        LOGO.write_at(&mut store, 9, b"xyz").unwrap();
+        assert_eq!(LOGO.load(&store).unwrap(), b"01abcd678xyz".to_vec());
+        assert_eq!(stored_chunks(&store), 3);
+
+        let manifest = LOGO.append(&mut store, b"!").unwrap();
+        assert_eq!(manifest.hash, None);
+        assert_eq!(LOGO.load(&store).unwrap(), b"01abcd678xyz!".to_vec());
+        let manifest = LOGO.finalize(&mut store).unwrap();
+        assert_eq!(
+            manifest.hash.unwrap().as_slice(),
+            Sha256::digest(b"01abcd678xyz!").as_slice()
+        );
+        assert_eq!(LOGO.load(&store).unwrap(), b"01abcd678xyz!".to_vec());
+
+        // no holes
+        LOGO.write_at(&mut store, 14, b"?").unwrap_err();
+    }
+
+    #[test]
+    fn upload_in_pieces() {
+        let mut store = MockStorage::new();
+        let big = ChunkedItem::new("big", 1000);
+        big.write_at(&mut store, 3, b"abc").unwrap_err();
+        big.finalize(&mut store).unwrap_err();
+
+        let data: Vec<u8> = (0..10_000u32).map(|i| (i % 251) as u8).collect();
+        for piece in data.chunks(1500) {
+            big.append(&mut store, piece).unwrap();
+        }
+        let manifest = big.finalize(&mut store).unwrap();
+        assert_eq!(manifest.chunk_count(), 10);
+        assert_eq!(big.load(&store).unwrap(), data);
+        assert_eq!(
+            big.read(&store, 2500, 1000).unwrap(),
+            data[2500..3500].to_vec()
+        );
+
+        // the data is checked against the manifest
+        store.set(&chunk_key(b"big", 4).unwrap(), b"corrupted");
+        let err = big.load(&store).unwrap_err();
+        assert!(matches!(err, StdError::GenericErr { .. }));
+        store.remove(&chunk_key(b"big", 4).unwrap());
+        let err = big.load(&store).unwrap_err();
+        assert!(matches!(err, StdError::NotFound { .. }));
+    }
+}
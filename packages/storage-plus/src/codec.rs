@@ -0,0 +1,159 @@
+#[cfg(feature = "bin-codec")]
+use std::any::type_name;
+
+#[cfg(feature = "bin-codec")]
+use bincode::Options;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+#[cfg(feature = "bin-codec")]
+use cosmwasm_std::StdError;
+use cosmwasm_std::{from_slice, to_vec, Binary, StdResult};
+
+/// Codec defines how the values of an `Item`, `Map` (and the types built on top of them)
+/// are turned into bytes in storage and back.
+///
+/// It is selected through a type parameter, which defaults to `JsonCodec`:
+///
+/// ```rust
+/// use cosmwasm_std::Binary;
+/// use cw_storage_plus::{Item, Map, RawCodec};
+///
+/// const LOGO: Item<Binary, RawCodec> = Item::new("logo").with_codec();
+/// const BLOBS: Map<&str, Vec<u8>, RawCodec> = Map::new("blobs").with_codec();
+/// ```
+///
+/// Note that the codec is not stored along with the data: changing it for existing data requires a
+/// migration.
+pub trait Codec<T> {
+    fn encode(value: &T) -> StdResult<Vec<u8>>;
+    fn decode(data: &[u8]) -> StdResult<T>;
+}
+
+/// JSON, as produced by `cosmwasm_std::to_vec`. This is the default, and the format everything
+/// was stored in before codecs were configurable. It is also what other contracts expect when
+/// doing raw queries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JsonCodec;
+
+impl<T> Codec<T> for JsonCodec
+where
+    T: Serialize + DeserializeOwned,
+{
+    fn encode(value: &T) -> StdResult<Vec<u8>> {
+        to_vec(value)
+    }
+
+    fn decode(data: &[u8]) -> StdResult<T> {
+        from_slice(data)
+    }
+}
+
+/// Compact binary encoding (bincode, with variable length integers). Numbers and byte arrays take
+/// a fraction of their JSON size, and there are no field names to store.
+///
+/// The format is not self-describing, so it is not suited for types relying on
+/// `Deserializer::deserialize_any` (e.g. `#[serde(untagged)]` or `#[serde(flatten)]`), and adding,
+/// removing or reordering fields breaks previously stored values.
+///
+/// Requires the `bin-codec` feature.
+#[cfg(feature = "bin-codec")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BinCodec;
+
+#[cfg(feature = "bin-codec")]
+impl<T> Codec<T> for BinCodec
+where
+    T: Serialize + DeserializeOwned,
+{
+    fn encode(value: &T) -> StdResult<Vec<u8>> {
+        bincode::DefaultOptions::new()
+            .serialize(value)
+            .map_err(|e| StdError::serialize_err(type_name::<T>(), e))
+    }
+
+    fn decode(data: &[u8]) -> StdResult<T> {
+        bincode::DefaultOptions::new()
+            .deserialize(data)
+            .map_err(|e| StdError::parse_err(type_name::<T>(), e))
+    }
+}
+
+/// Stores byte values as they are, without any encoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RawCodec;
+
+impl Codec<Vec<u8>> for RawCodec {
+    fn encode(value: &Vec<u8>) -> StdResult<Vec<u8>> {
+        Ok(value.clone())
+    }
+
+    fn decode(data: &[u8]) -> StdResult<Vec<u8>> {
+        Ok(data.to_vec())
+    }
+}
+
+impl Codec<Binary> for RawCodec {
+    fn encode(value: &Binary) -> StdResult<Vec<u8>> {
+        Ok(value.to_vec())
+    }
+
+    fn decode(data: &[u8]) -> StdResult<Binary> {
+        Ok(Binary::from(data))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use cosmwasm_std::Uint128;
+    use serde::Deserialize;
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Data {
+        pub name: String,
+        pub amount: Uint128,
+        pub tags: Vec<u32>,
+    }
+
+    #[test]
+    fn codecs_roundtrip() {
+        let data = Data {
+            name: "John".to_string(),
+            amount: Uint128::new(1234),
+            tags: vec![1, 200, 70000],
+        };
+
+        let json = JsonCodec::encode(&data).unwrap();
+        let decoded: Data = JsonCodec::decode(&json).unwrap();
+        assert_eq!(decoded, data);
+        #[cfg(feature = "bin-codec")]
+        {
+            let bin = BinCodec::encode(&data).unwrap();
+            let decoded: Data = BinCodec::decode(&bin).unwrap();
+            assert_eq!(decoded, data);
+            assert!(bin.len() < json.len());
+        }
+
+        let raw = b"raw data".to_vec();
+        assert_eq!(RawCodec::encode(&raw).unwrap(), raw);
+        assert_eq!(
+            <RawCodec as Codec<Binary>>::decode(&raw).unwrap(),
+            Binary::from(raw)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "bin-codec")]
+    fn bin_codec_errors() {
+        // trailing bytes are rejected
+        let mut bin = <BinCodec as Codec<u32>>::encode(&7).unwrap();
+        bin.push(0);
+        let err = <BinCodec as Codec<u32>>::decode(&bin).unwrap_err();
+        assert!(matches!(err, StdError::ParseErr { .. }));
+
+        let err = <BinCodec as Codec<String>>::decode(&[5, b'a']).unwrap_err();
+        assert!(matches!(err, StdError::ParseErr { .. }));
+    }
+}
@@ -0,0 +1,412 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+#[cfg(feature = "iterator")]
+use crate::bound::{Bound, PrefixBound};
+#[cfg(feature = "iterator")]
+use crate::de::KeyDeserialize;
+#[cfg(feature = "iterator")]
+use crate::helpers::namespaces_with_key;
+use crate::item::Item;
+use crate::keys::PrimaryKey;
+use crate::map::Map;
+#[cfg(feature = "iterator")]
+use crate::prefix::Prefix;
+use cosmwasm_std::{Addr, CustomQuery, QuerierWrapper, StdError, StdResult, Storage};
+
+/// `CountedMap` works like a `Map`, but also keeps track of the number of entries it holds.
+/// The counter is stored under its own key and is updated on every insertion and removal,
+/// so `count` is a single read instead of a full iteration.
+///
+/// The counter is only kept consistent when all writes go through the `CountedMap`.
+/// Writing to the same namespace with a plain `Map` will make it drift.
+pub struct CountedMap<'a, K, T> {
+    primary: Map<'a, K, T>,
+    counter: Item<'a, u32>,
+}
+
+impl<'a, K, T> CountedMap<'a, K, T> {
+    /// Example:
+    ///
+    /// ```rust
+    /// use cw_storage_plus::CountedMap;
+    ///
+    /// CountedMap::<&str, u64>::new("balance", "balance__count");
+    /// ```
+    pub const fn new(pk_namespace: &'a str, counter_key: &'a str) -> Self {
+        CountedMap {
+            primary: Map::new(pk_namespace),
+            counter: Item::new(counter_key),
+        }
+    }
+
+    pub fn namespace(&self) -> &'a [u8] {
+        self.primary.namespace()
+    }
+}
+
+impl<'a, K, T> CountedMap<'a, K, T>
+where
+    T: Serialize + DeserializeOwned,
+    K: PrimaryKey<'a>,
+{
+    /// Returns the number of entries currently stored in the map
+    pub fn count(&self, store: &dyn Storage) -> StdResult<u32> {
+        Ok(self.counter.may_load(store)?.unwrap_or_default())
+    }
+
+    fn increment(&self, store: &mut dyn Storage) -> StdResult<()> {
+        let count = self
+            .count(store)?
+            .checked_add(1)
+            .ok_or_else(|| StdError::generic_err("CountedMap counter overflow"))?;
+        self.counter.save(store, &count)
+    }
+
+    fn decrement(&self, store: &mut dyn Storage) -> StdResult<()> {
+        let count = self
+            .count(store)?
+            .checked_sub(1)
+            .ok_or_else(|| StdError::generic_err("CountedMap counter underflow"))?;
+        self.counter.save(store, &count)
+    }
+
+    /// save will serialize the value and store it, incrementing the counter if the key was empty
+    pub fn save(&self, store: &mut dyn Storage, k: K, data: &T) -> StdResult<()> {
+        if !self.primary.has(store, k.clone()) {
+            self.increment(store)?;
+        }
+        self.primary.save(store, k, data)
+    }
+
+    /// remove deletes the value at the key, decrementing the counter if there was one.
+    /// Removing a missing key is a no-op.
+    pub fn remove(&self, store: &mut dyn Storage, k: K) -> StdResult<()> {
+        if self.primary.has(store, k.clone()) {
+            self.decrement(store)?;
+            self.primary.remove(store, k);
+        }
+        Ok(())
+    }
+
+    /// load will return an error if no data is set at the given key, or on parse error
+    pub fn load(&self, store: &dyn Storage, k: K) -> StdResult<T> {
+        self.primary.load(store, k)
+    }
+
+    /// may_load will parse the data stored at the key if present, returns Ok(None) if no data there.
+    /// returns an error on issues parsing
+    pub fn may_load(&self, store: &dyn Storage, k: K) -> StdResult<Option<T>> {
+        self.primary.may_load(store, k)
+    }
+
+    /// has returns true or false if any data is at this key, without parsing or interpreting the
+    /// contents.
+    pub fn has(&self, store: &dyn Storage, k: K) -> bool {
+        self.primary.has(store, k)
+    }
+
+    /// Loads the data, perform the specified action, and store the result
+    /// in the database. This is shorthand for some common sequences, which may be useful.
+    ///
+    /// If the data exists, `action(Some(value))` is called. Otherwise `action(None)` is called.
+    pub fn update<A, E>(&self, store: &mut dyn Storage, k: K, action: A) -> Result<T, E>
+    where
+        A: FnOnce(Option<T>) -> Result<T, E>,
+        E: From<StdError>,
+    {
+        let input = self.may_load(store, k.clone())?;
+        let existed = input.is_some();
+        let output = action(input)?;
+        if !existed {
+            self.increment(store)?;
+        }
+        self.primary.save(store, k, &output)?;
+        Ok(output)
+    }
+
+    /// Like `update`, but the action may return `None` to remove the entry.
+    /// The counter is adjusted for all four transitions between present and absent values.
+    ///
+    /// If the data exists, `action(Some(value))` is called. Otherwise `action(None)` is called.
+    pub fn update_or_remove<A, E>(
+        &self,
+        store: &mut dyn Storage,
+        k: K,
+        action: A,
+    ) -> Result<Option<T>, E>
+    where
+        A: FnOnce(Option<T>) -> Result<Option<T>, E>,
+        E: From<StdError>,
+    {
+        let input = self.may_load(store, k.clone())?;
+        let existed = input.is_some();
+        let output = action(input)?;
+        match (&output, existed) {
+            (Some(data), existed) => {
+                if !existed {
+                    self.increment(store)?;
+                }
+                self.primary.save(store, k, data)?;
+            }
+            (None, true) => {
+                self.decrement(store)?;
+                self.primary.remove(store, k);
+            }
+            (None, false) => {}
+        }
+        Ok(output)
+    }
+
+    /// If you import the proper CountedMap from the remote contract, this will let you read the data
+    /// from a remote contract in a type-safe way using WasmQuery::RawQuery
+    pub fn query<Q: CustomQuery>(
+        &self,
+        querier: &QuerierWrapper<Q>,
+        remote_contract: Addr,
+        k: K,
+    ) -> StdResult<Option<T>> {
+        self.primary.query(querier, remote_contract, k)
+    }
+}
+
+#[cfg(feature = "iterator")]
+impl<'a, K, T> CountedMap<'a, K, T>
+where
+    T: Serialize + DeserializeOwned,
+    K: PrimaryKey<'a>,
+{
+    /// Removes all entries from the map and resets the counter.
+    /// This iterates over the whole map, so it is only suitable for small maps.
+    pub fn clear(&self, store: &mut dyn Storage) {
+        let keys: Vec<_> = self
+            .primary
+            .no_prefix_raw()
+            .keys_raw(store, None, None, cosmwasm_std::Order::Ascending)
+            .collect();
+        for key in keys {
+            store.remove(&namespaces_with_key(&[self.primary.namespace()], &key));
+        }
+        self.counter.remove(store);
+    }
+
+    pub fn sub_prefix(&self, p: K::SubPrefix) -> Prefix<K::SuperSuffix, T, K::SuperSuffix> {
+        self.primary.sub_prefix(p)
+    }
+
+    pub fn prefix(&self, p: K::Prefix) -> Prefix<K::Suffix, T, K::Suffix> {
+        self.primary.prefix(p)
+    }
+
+    pub fn range_raw<'c>(
+        &self,
+        store: &'c dyn Storage,
+        min: Option<Bound<'a, K>>,
+        max: Option<Bound<'a, K>>,
+        order: cosmwasm_std::Order,
+    ) -> Box<dyn Iterator<Item = StdResult<cosmwasm_std::Record<T>>> + 'c>
+    where
+        T: 'c,
+    {
+        self.primary.range_raw(store, min, max, order)
+    }
+
+    pub fn keys_raw<'c>(
+        &self,
+        store: &'c dyn Storage,
+        min: Option<Bound<'a, K>>,
+        max: Option<Bound<'a, K>>,
+        order: cosmwasm_std::Order,
+    ) -> Box<dyn Iterator<Item = Vec<u8>> + 'c>
+    where
+        T: 'c,
+    {
+        self.primary.keys_raw(store, min, max, order)
+    }
+}
+
+#[cfg(feature = "iterator")]
+impl<'a, K, T> CountedMap<'a, K, T>
+where
+    T: Serialize + DeserializeOwned,
+    K: PrimaryKey<'a> + KeyDeserialize,
+{
+    /// While `range` over a `prefix` fixes the prefix to one element and iterates over the
+    /// remaining, `prefix_range` accepts bounds for the lowest and highest elements of the
+    /// `Prefix` itself, and iterates over those (inclusively or exclusively, depending on
+    /// `PrefixBound`).
+    pub fn prefix_range<'c>(
+        &self,
+        store: &'c dyn Storage,
+        min: Option<PrefixBound<'a, K::Prefix>>,
+        max: Option<PrefixBound<'a, K::Prefix>>,
+        order: cosmwasm_std::Order,
+    ) -> Box<dyn Iterator<Item = StdResult<(K::Output, T)>> + 'c>
+    where
+        T: 'c,
+        'a: 'c,
+        K: 'c,
+        K::Output: 'static,
+    {
+        self.primary.prefix_range(store, min, max, order)
+    }
+
+    pub fn range<'c>(
+        &self,
+        store: &'c dyn Storage,
+        min: Option<Bound<'a, K>>,
+        max: Option<Bound<'a, K>>,
+        order: cosmwasm_std::Order,
+    ) -> Box<dyn Iterator<Item = StdResult<(K::Output, T)>> + 'c>
+    where
+        T: 'c,
+        K::Output: 'static,
+    {
+        self.primary.range(store, min, max, order)
+    }
+
+    pub fn keys<'c>(
+        &self,
+        store: &'c dyn Storage,
+        min: Option<Bound<'a, K>>,
+        max: Option<Bound<'a, K>>,
+        order: cosmwasm_std::Order,
+    ) -> Box<dyn Iterator<Item = StdResult<K::Output>> + 'c>
+    where
+        T: 'c,
+        K::Output: 'static,
+    {
+        self.primary.keys(store, min, max, order)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde::{Deserialize, Serialize};
+
+    use cosmwasm_std::testing::MockStorage;
+    #[cfg(feature = "iterator")]
+    use cosmwasm_std::Order;
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+    struct Data {
+        pub name: String,
+        pub age: i32,
+    }
+
+    const PEOPLE: CountedMap<&str, Data> = CountedMap::new("people", "people__count");
+
+    fn john() -> Data {
+        Data {
+            name: "John".to_string(),
+            age: 32,
+        }
+    }
+
+    #[test]
+    fn save_and_remove_count() {
+        let mut store = MockStorage::new();
+        assert_eq!(0, PEOPLE.count(&store).unwrap());
+
+        PEOPLE.save(&mut store, "john", &john()).unwrap();
+        assert_eq!(1, PEOPLE.count(&store).unwrap());
+        assert_eq!(john(), PEOPLE.load(&store, "john").unwrap());
+
+        // overwriting does not change the count
+        let older = Data { age: 33, ..john() };
+        PEOPLE.save(&mut store, "john", &older).unwrap();
+        assert_eq!(1, PEOPLE.count(&store).unwrap());
+        assert_eq!(older, PEOPLE.load(&store, "john").unwrap());
+
+        PEOPLE.save(&mut store, "jack", &john()).unwrap();
+        assert_eq!(2, PEOPLE.count(&store).unwrap());
+
+        // removing a missing key does nothing
+        PEOPLE.remove(&mut store, "jim").unwrap();
+        assert_eq!(2, PEOPLE.count(&store).unwrap());
+
+        PEOPLE.remove(&mut store, "john").unwrap();
+        assert_eq!(1, PEOPLE.count(&store).unwrap());
+        assert!(!PEOPLE.has(&store, "john"));
+
+        // removing twice only counts once
+        PEOPLE.remove(&mut store, "john").unwrap();
+        assert_eq!(1, PEOPLE.count(&store).unwrap());
+    }
+
+    #[test]
+    fn update_count() {
+        let mut store = MockStorage::new();
+
+        let add_one = |d: Option<u64>| -> StdResult<u64> { Ok(d.unwrap_or_default() + 1) };
+        const COUNTERS: CountedMap<u8, u64> = CountedMap::new("counters", "counters__count");
+
+        assert_eq!(1, COUNTERS.update(&mut store, 1, add_one).unwrap());
+        assert_eq!(1, COUNTERS.count(&store).unwrap());
+        assert_eq!(2, COUNTERS.update(&mut store, 1, add_one).unwrap());
+        assert_eq!(1, COUNTERS.count(&store).unwrap());
+        assert_eq!(1, COUNTERS.update(&mut store, 2, add_one).unwrap());
+        assert_eq!(2, COUNTERS.count(&store).unwrap());
+
+        // a failing action leaves the count alone
+        let fail = |_: Option<u64>| -> StdResult<u64> { Err(StdError::generic_err("nope")) };
+        COUNTERS.update(&mut store, 3, fail).unwrap_err();
+        assert_eq!(2, COUNTERS.count(&store).unwrap());
+
+        // Some -> None removes the entry
+        let drop_even = |d: Option<u64>| -> StdResult<Option<u64>> { Ok(d.filter(|v| v % 2 == 1)) };
+        assert_eq!(
+            None,
+            COUNTERS.update_or_remove(&mut store, 1, drop_even).unwrap()
+        );
+        assert_eq!(1, COUNTERS.count(&store).unwrap());
+        assert!(!COUNTERS.has(&store, 1));
+
+        // Some -> Some keeps it
+        assert_eq!(
+            Some(1),
+            COUNTERS.update_or_remove(&mut store, 2, drop_even).unwrap()
+        );
+        assert_eq!(1, COUNTERS.count(&store).unwrap());
+
+        // None -> None is a no-op
+        assert_eq!(
+            None,
+            COUNTERS.update_or_remove(&mut store, 5, drop_even).unwrap()
+        );
+        assert_eq!(1, COUNTERS.count(&store).unwrap());
+
+        // None -> Some inserts
+        let insert = |_: Option<u64>| -> StdResult<Option<u64>> { Ok(Some(7)) };
+        COUNTERS.update_or_remove(&mut store, 5, insert).unwrap();
+        assert_eq!(2, COUNTERS.count(&store).unwrap());
+        assert_eq!(7, COUNTERS.load(&store, 5).unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn range_and_clear() {
+        let mut store = MockStorage::new();
+
+        PEOPLE.save(&mut store, "john", &john()).unwrap();
+        PEOPLE.save(&mut store, "jack", &john()).unwrap();
+        PEOPLE.save(&mut store, "jim", &john()).unwrap();
+
+        let keys: Vec<_> = PEOPLE
+            .keys(&store, None, None, Order::Ascending)
+            .collect::<StdResult<_>>()
+            .unwrap();
+        assert_eq!(vec!["jack", "jim", "john"], keys);
+        assert_eq!(keys.len() as u32, PEOPLE.count(&store).unwrap());
+
+        PEOPLE.clear(&mut store);
+        assert_eq!(0, PEOPLE.count(&store).unwrap());
+        assert_eq!(
+            0,
+            PEOPLE
+                .range_raw(&store, None, None, Order::Ascending)
+                .count()
+        );
+    }
+}
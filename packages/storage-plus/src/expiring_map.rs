@@ -0,0 +1,471 @@
+// this module requires iterator to be useful at all
+#![cfg(feature = "iterator")]
+
+use std::any::type_name;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{BlockInfo, Order, StdError, StdResult, Storage, Timestamp};
+
+use crate::bound::{Bound, RawBound};
+use crate::codec::JsonCodec;
+use crate::de::KeyDeserialize;
+use crate::helpers::{may_deserialize, namespaces_with_key};
+use crate::keys::PrimaryKey;
+use crate::map::Map;
+use crate::prefix::range_with_prefix;
+
+/// Deadline is the point from which an entry of an `ExpiringMap` is expired
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Deadline {
+    /// expires once the block height reaches this value
+    Height(u64),
+    /// expires once the block time reaches this value
+    Time(Timestamp),
+}
+
+impl Deadline {
+    pub fn is_reached(&self, block: &BlockInfo) -> bool {
+        match self {
+            Deadline::Height(height) => block.height >= *height,
+            Deadline::Time(time) => block.time >= *time,
+        }
+    }
+
+    fn kind_and_value(&self) -> (u8, u64) {
+        match self {
+            Deadline::Height(height) => (HEIGHT_KIND, *height),
+            Deadline::Time(time) => (TIME_KIND, time.nanos()),
+        }
+    }
+
+    // prefix of the entries in the expiry index
+    fn index_key(&self) -> Vec<u8> {
+        let (kind, value) = self.kind_and_value();
+        [&[kind][..], &value.to_be_bytes()].concat()
+    }
+}
+
+const HEIGHT_KIND: u8 = 1;
+const TIME_KIND: u8 = 2;
+// kind and big endian u64 value
+const DEADLINE_LEN: usize = 9;
+
+/// Expires is implemented by the types that can be stored as expiration of an `ExpiringMap`
+/// entry, like `cw_utils::Expiration`.
+pub trait Expires {
+    /// Returns `None` if it never expires
+    fn deadline(&self) -> Option<Deadline>;
+
+    fn is_expired(&self, block: &BlockInfo) -> bool {
+        self.deadline()
+            .map(|deadline| deadline.is_reached(block))
+            .unwrap_or(false)
+    }
+}
+
+impl Expires for Deadline {
+    fn deadline(&self) -> Option<Deadline> {
+        Some(*self)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry<T, E> {
+    value: T,
+    expires: E,
+}
+
+/// `ExpiringMap` works like a `Map`, but stores an expiration along with every value.
+///
+/// Expired entries are hidden from all reads: they are not returned by `load` or `range`, and
+/// `update` sees them as missing. They still take up space though, until they are overwritten
+/// or removed by `purge_expired`.
+///
+/// To allow that, a second namespace keeps an index of the entries by deadline, so
+/// `purge_expired` only visits expired entries, and can be limited to a given number of them
+/// per call.
+pub struct ExpiringMap<'a, K, T, E> {
+    primary: Map<'a, K, Entry<T, E>>,
+    expiry_namespace: &'a [u8],
+}
+
+impl<'a, K, T, E> ExpiringMap<'a, K, T, E> {
+    /// Example:
+    ///
+    /// ```rust
+    /// use cosmwasm_std::Uint128;
+    /// use cw_storage_plus::{Deadline, ExpiringMap};
+    ///
+    /// ExpiringMap::<&str, Uint128, Deadline>::new("allowance", "allowance__expiry");
+    /// ```
+    pub const fn new(pk_namespace: &'a str, expiry_namespace: &'a str) -> Self {
+        ExpiringMap {
+            primary: Map::new(pk_namespace),
+            expiry_namespace: expiry_namespace.as_bytes(),
+        }
+    }
+
+    pub fn namespace(&self) -> &'a [u8] {
+        self.primary.namespace()
+    }
+}
+
+impl<'a, K, T, E> ExpiringMap<'a, K, T, E>
+where
+    K: PrimaryKey<'a>,
+    T: Serialize + DeserializeOwned,
+    E: Expires + Serialize + DeserializeOwned,
+{
+    /// save will store the value along with its expiration, replacing any previous entry
+    pub fn save(&self, store: &mut dyn Storage, k: K, data: &T, expires: E) -> StdResult<()> {
+        let pk = k.joined_key();
+        self.remove_from_index(store, &pk)?;
+        if let Some(deadline) = expires.deadline() {
+            // only the key matters, but empty values are not allowed
+            store.set(&self.index_key(&deadline, &pk), &[1]);
+        }
+        let entry = Entry {
+            value: data,
+            expires,
+        };
+        store.set(&self.primary_key(&pk), &cosmwasm_std::to_vec(&entry)?);
+        Ok(())
+    }
+
+    /// remove deletes the entry at the key, expired or not. Removing a missing key is a no-op.
+    pub fn remove(&self, store: &mut dyn Storage, k: K) -> StdResult<()> {
+        let pk = k.joined_key();
+        self.remove_from_index(store, &pk)?;
+        store.remove(&self.primary_key(&pk));
+        Ok(())
+    }
+
+    /// load will return an error if no data is set at the given key, if it is expired,
+    /// or on parse error
+    pub fn load(&self, store: &dyn Storage, k: K, block: &BlockInfo) -> StdResult<T> {
+        self.may_load(store, k, block)?
+            .ok_or_else(|| StdError::not_found(type_name::<T>()))
+    }
+
+    /// may_load returns Ok(None) if there is no data at the key, or if it is expired.
+    /// returns an error on issues parsing
+    pub fn may_load(&self, store: &dyn Storage, k: K, block: &BlockInfo) -> StdResult<Option<T>> {
+        Ok(self
+            .may_load_with_expiration(store, k, block)?
+            .map(|(value, _)| value))
+    }
+
+    /// Like `may_load`, but also returns the expiration of the entry
+    pub fn may_load_with_expiration(
+        &self,
+        store: &dyn Storage,
+        k: K,
+        block: &BlockInfo,
+    ) -> StdResult<Option<(T, E)>> {
+        let entry = self.primary.may_load(store, k)?;
+        Ok(entry
+            .filter(|entry| !entry.expires.is_expired(block))
+            .map(|entry| (entry.value, entry.expires)))
+    }
+
+    /// Loads the entry, perform the specified action, and store the result (with its new
+    /// expiration) in the database.
+    ///
+    /// If a live entry exists, `action(Some((value, expires)))` is called. Otherwise (also when
+    /// it is expired) `action(None)` is called.
+    pub fn update<A, Err>(
+        &self,
+        store: &mut dyn Storage,
+        k: K,
+        block: &BlockInfo,
+        action: A,
+    ) -> Result<T, Err>
+    where
+        A: FnOnce(Option<(T, E)>) -> Result<(T, E), Err>,
+        Err: From<StdError>,
+    {
+        let input = self.may_load_with_expiration(store, k.clone(), block)?;
+        let (value, expires) = action(input)?;
+        self.save(store, k, &value, expires)?;
+        Ok(value)
+    }
+
+    /// Removes up to `limit` expired entries, and returns how many were removed. Entries
+    /// expiring by height go first, then those expiring by time, each by increasing deadline.
+    /// Call it again while it returns `limit` to remove them all.
+    pub fn purge_expired(
+        &self,
+        store: &mut dyn Storage,
+        block: &BlockInfo,
+        limit: usize,
+    ) -> StdResult<usize> {
+        let now = [Deadline::Height(block.height), Deadline::Time(block.time)];
+        let mut expired = vec![];
+        for deadline in now.iter() {
+            if expired.len() >= limit {
+                break;
+            }
+            // deadlines of the same kind, up to (and including) the current one
+            let (kind, value) = deadline.kind_and_value();
+            let max = match value.checked_add(1) {
+                Some(next) => [&[kind][..], &next.to_be_bytes()].concat(),
+                None => vec![kind + 1],
+            };
+            let keys = range_with_prefix(
+                store,
+                &namespaces_with_key(&[self.expiry_namespace], b""),
+                Some(RawBound::Inclusive(vec![kind])),
+                Some(RawBound::Exclusive(max)),
+                Order::Ascending,
+            )
+            .map(|(k, _)| k)
+            .take(limit - expired.len());
+            expired.extend(keys);
+        }
+
+        for key in expired.iter() {
+            store.remove(&namespaces_with_key(&[self.expiry_namespace], key));
+            store.remove(&self.primary_key(&key[DEADLINE_LEN..]));
+        }
+        Ok(expired.len())
+    }
+
+    fn primary_key(&self, pk: &[u8]) -> Vec<u8> {
+        namespaces_with_key(&[self.primary.namespace()], pk)
+    }
+
+    fn index_key(&self, deadline: &Deadline, pk: &[u8]) -> Vec<u8> {
+        namespaces_with_key(
+            &[self.expiry_namespace],
+            &[deadline.index_key(), pk.to_vec()].concat(),
+        )
+    }
+
+    fn remove_from_index(&self, store: &mut dyn Storage, pk: &[u8]) -> StdResult<()> {
+        let old = may_deserialize::<Entry<T, E>, JsonCodec>(&store.get(&self.primary_key(pk)))?;
+        if let Some(deadline) = old.and_then(|entry| entry.expires.deadline()) {
+            store.remove(&self.index_key(&deadline, pk));
+        }
+        Ok(())
+    }
+}
+
+impl<'a, K, T, E> ExpiringMap<'a, K, T, E>
+where
+    K: PrimaryKey<'a> + KeyDeserialize,
+    T: Serialize + DeserializeOwned,
+    E: Expires + Serialize + DeserializeOwned,
+{
+    /// Iterates over the entries which are not expired at `block`
+    pub fn range<'c>(
+        &self,
+        store: &'c dyn Storage,
+        block: &BlockInfo,
+        min: Option<Bound<'a, K>>,
+        max: Option<Bound<'a, K>>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = StdResult<(K::Output, T)>> + 'c>
+    where
+        T: 'c,
+        E: 'c,
+        K::Output: 'static,
+    {
+        let block = block.clone();
+        let mapped =
+            self.primary
+                .range(store, min, max, order)
+                .filter_map(move |item| match item {
+                    Ok((_, entry)) if entry.expires.is_expired(&block) => None,
+                    Ok((k, entry)) => Some(Ok((k, entry.value))),
+                    Err(err) => Some(Err(err)),
+                });
+        Box::new(mapped)
+    }
+
+    /// Iterates over the keys of the entries which are not expired at `block`
+    pub fn keys<'c>(
+        &self,
+        store: &'c dyn Storage,
+        block: &BlockInfo,
+        min: Option<Bound<'a, K>>,
+        max: Option<Bound<'a, K>>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = StdResult<K::Output>> + 'c>
+    where
+        T: 'c,
+        E: 'c,
+        K::Output: 'static,
+    {
+        let mapped = self
+            .range(store, block, min, max, order)
+            .map(|item| item.map(|(k, _)| k));
+        Box::new(mapped)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use cosmwasm_std::testing::{mock_env, MockStorage};
+
+    const ALLOWANCES: ExpiringMap<&str, u64, Deadline> =
+        ExpiringMap::new("allowances", "allowances__expiry");
+
+    #[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+    enum Expiration {
+        AtHeight(u64),
+        Never,
+    }
+
+    impl Expires for Expiration {
+        fn deadline(&self) -> Option<Deadline> {
+            match self {
+                Expiration::AtHeight(height) => Some(Deadline::Height(*height)),
+                Expiration::Never => None,
+            }
+        }
+    }
+
+    fn index_len(store: &dyn Storage) -> usize {
+        range_with_prefix(
+            store,
+            &namespaces_with_key(&[b"allowances__expiry"], b""),
+            None,
+            None,
+            Order::Ascending,
+        )
+        .count()
+    }
+
+    #[test]
+    fn expired_entries_are_hidden() {
+        let mut store = MockStorage::new();
+        let mut block = mock_env().block;
+        let height = block.height;
+
+        ALLOWANCES
+            .save(&mut store, "john", &100, Deadline::Height(height + 10))
+            .unwrap();
+        ALLOWANCES
+            .save(
+                &mut store,
+                "jack",
+                &200,
+                Deadline::Time(block.time.plus_seconds(5)),
+            )
+            .unwrap();
+        assert_eq!(100, ALLOWANCES.load(&store, "john", &block).unwrap());
+        assert_eq!(
+            Some((200, Deadline::Time(block.time.plus_seconds(5)))),
+            ALLOWANCES
+                .may_load_with_expiration(&store, "jack", &block)
+                .unwrap()
+        );
+
+        // the time deadline is reached first
+        block.time = block.time.plus_seconds(5);
+        assert_eq!(None, ALLOWANCES.may_load(&store, "jack", &block).unwrap());
+        let all: Vec<_> = ALLOWANCES
+            .range(&store, &block, None, None, Order::Ascending)
+            .collect::<StdResult<_>>()
+            .unwrap();
+        assert_eq!(all, vec![("john".to_string(), 100)]);
+
+        // the height deadline is exclusive as well
+        block.height = height + 10;
+        let err = ALLOWANCES.load(&store, "john", &block).unwrap_err();
+        assert!(matches!(err, StdError::NotFound { .. }));
+        let keys: Vec<_> = ALLOWANCES
+            .keys(&store, &block, None, None, Order::Ascending)
+            .collect::<StdResult<_>>()
+            .unwrap();
+        assert!(keys.is_empty());
+
+        // update sees expired entries as missing
+        let value = ALLOWANCES
+            .update(&mut store, "john", &block, |old| -> StdResult<_> {
+                assert_eq!(old, None);
+                Ok((5, Deadline::Height(height + 20)))
+            })
+            .unwrap();
+        assert_eq!(5, value);
+        assert_eq!(5, ALLOWANCES.load(&store, "john", &block).unwrap());
+        // and the index follows the new deadline
+        assert_eq!(2, index_len(&store));
+    }
+
+    #[test]
+    fn purge_expired_in_batches() {
+        let mut store = MockStorage::new();
+        let mut block = mock_env().block;
+        let height = block.height;
+
+        for (i, name) in ["a", "b", "c", "d", "e"].iter().enumerate() {
+            ALLOWANCES
+                .save(&mut store, name, &1, Deadline::Height(height + i as u64))
+                .unwrap();
+        }
+        ALLOWANCES
+            .save(
+                &mut store,
+                "f",
+                &1,
+                Deadline::Time(block.time.plus_seconds(1)),
+            )
+            .unwrap();
+        // saving again replaces the index entry
+        ALLOWANCES
+            .save(&mut store, "e", &1, Deadline::Height(height + 100))
+            .unwrap();
+        assert_eq!(6, index_len(&store));
+
+        // "a" expires right away
+        assert_eq!(1, ALLOWANCES.purge_expired(&mut store, &block, 10).unwrap());
+        assert!(ALLOWANCES.primary.may_load(&store, "a").unwrap().is_none());
+
+        block.height += 50;
+        block.time = block.time.plus_seconds(1);
+        assert_eq!(2, ALLOWANCES.purge_expired(&mut store, &block, 2).unwrap());
+        assert_eq!(2, ALLOWANCES.purge_expired(&mut store, &block, 2).unwrap());
+        assert_eq!(0, ALLOWANCES.purge_expired(&mut store, &block, 2).unwrap());
+
+        // only "e" is left, in the map and in the index
+        let all: Vec<_> = ALLOWANCES
+            .primary
+            .keys(&store, None, None, Order::Ascending)
+            .collect::<StdResult<_>>()
+            .unwrap();
+        assert_eq!(all, vec!["e".to_string()]);
+        assert_eq!(1, index_len(&store));
+
+        ALLOWANCES.remove(&mut store, "e").unwrap();
+        assert_eq!(0, index_len(&store));
+    }
+
+    #[test]
+    fn never_expiring_entries() {
+        let mut store = MockStorage::new();
+        let mut block = mock_env().block;
+        let map: ExpiringMap<u32, String, Expiration> = ExpiringMap::new("names", "names__expiry");
+
+        map.save(&mut store, 1, &"one".to_string(), Expiration::Never)
+            .unwrap();
+        map.save(
+            &mut store,
+            2,
+            &"two".to_string(),
+            Expiration::AtHeight(block.height + 1),
+        )
+        .unwrap();
+
+        block.height += 1;
+        assert_eq!(1, map.purge_expired(&mut store, &block, 10).unwrap());
+        block.height = u64::MAX;
+        assert_eq!(0, map.purge_expired(&mut store, &block, 10).unwrap());
+        assert_eq!("one", map.load(&store, 1, &block).unwrap());
+    }
+}
@@ -4,33 +4,33 @@
 //! Everything in this file is only responsible for building such keys
 //! and is in no way specific to any kind of storage.
 
-use serde::de::DeserializeOwned;
 use std::any::type_name;
 
+use crate::codec::Codec;
 use crate::keys::Key;
 
 use cosmwasm_std::{
-    from_slice, to_vec, Addr, Binary, ContractResult, CustomQuery, QuerierWrapper, QueryRequest,
-    StdError, StdResult, SystemResult, WasmQuery,
+    to_vec, Addr, Binary, ContractResult, CustomQuery, QuerierWrapper, QueryRequest, StdError,
+    StdResult, SystemResult, WasmQuery,
 };
 
-/// may_deserialize parses json bytes from storage (Option), returning Ok(None) if no data present
+/// may_deserialize decodes bytes from storage (Option) with the given codec, returning Ok(None)
+/// if no data present
 ///
 /// value is an odd type, but this is meant to be easy to use with output from storage.get (Option<Vec<u8>>)
 /// and value.map(|s| s.as_slice()) seems trickier than &value
-pub(crate) fn may_deserialize<T: DeserializeOwned>(
-    value: &Option<Vec<u8>>,
-) -> StdResult<Option<T>> {
+pub(crate) fn may_deserialize<T, C: Codec<T>>(value: &Option<Vec<u8>>) -> StdResult<Option<T>> {
     match value {
-        Some(vec) => Ok(Some(from_slice(vec)?)),
+        Some(vec) => Ok(Some(C::decode(vec)?)),
         None => Ok(None),
     }
 }
 
-/// must_deserialize parses json bytes from storage (Option), returning NotFound error if no data present
-pub(crate) fn must_deserialize<T: DeserializeOwned>(value: &Option<Vec<u8>>) -> StdResult<T> {
+/// must_deserialize decodes bytes from storage (Option) with the given codec, returning NotFound
+/// error if no data present
+pub(crate) fn must_deserialize<T, C: Codec<T>>(value: &Option<Vec<u8>>) -> StdResult<T> {
     match value {
-        Some(vec) => from_slice(vec),
+        Some(vec) => C::decode(vec),
         None => Err(StdError::not_found(type_name::<T>())),
     }
 }
@@ -123,6 +123,7 @@ pub(crate) fn query_raw<Q: CustomQuery>(
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::codec::JsonCodec;
     use cosmwasm_std::{to_vec, StdError};
     use serde::{Deserialize, Serialize};
 
@@ -158,13 +159,13 @@ mod test {
         };
         let value = to_vec(&person).unwrap();
 
-        let may_parse: Option<Person> = may_deserialize(&Some(value)).unwrap();
+        let may_parse: Option<Person> = may_deserialize::<_, JsonCodec>(&Some(value)).unwrap();
         assert_eq!(may_parse, Some(person));
     }
 
     #[test]
     fn may_deserialize_handles_none() {
-        let may_parse = may_deserialize::<Person>(&None).unwrap();
+        let may_parse = may_deserialize::<Person, JsonCodec>(&None).unwrap();
         assert_eq!(may_parse, None);
     }
 
@@ -177,13 +178,13 @@ mod test {
         let value = to_vec(&person).unwrap();
         let loaded = Some(value);
 
-        let parsed: Person = must_deserialize(&loaded).unwrap();
+        let parsed: Person = must_deserialize::<_, JsonCodec>(&loaded).unwrap();
         assert_eq!(parsed, person);
     }
 
     #[test]
     fn must_deserialize_handles_none() {
-        let parsed = must_deserialize::<Person>(&None);
+        let parsed = must_deserialize::<Person, JsonCodec>(&None);
         match parsed.unwrap_err() {
             StdError::NotFound { kind, .. } => {
                 assert_eq!(kind, "cw_storage_plus::helpers::test::Person")
@@ -0,0 +1,389 @@
+// this module requires iterator to be useful at all
+#![cfg(feature = "iterator")]
+
+use cosmwasm_std::{StdError, StdResult, Storage};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::de::KeyDeserialize;
+use crate::indexed_map::IndexList;
+use crate::item::Item;
+use crate::iter_helpers::deserialize_kv;
+use crate::keys::{Prefixer, PrimaryKey};
+use crate::map::Map;
+use crate::prefix::{namespaced_prefix_range, Prefix};
+use crate::{Bound, Path, PrefixBound};
+
+/// `IndexedCountedMap` works like an `IndexedMap`, but also keeps track of the number of
+/// entries it holds, the same way `CountedMap` does for a plain `Map`.
+pub struct IndexedCountedMap<'a, K, T, I>
+where
+    K: PrimaryKey<'a>,
+    T: Serialize + DeserializeOwned + Clone,
+    I: IndexList<T>,
+{
+    pk_namespace: &'a [u8],
+    primary: Map<'a, K, T>,
+    counter: Item<'a, u32>,
+    /// This is meant to be read directly to get the proper types, like:
+    /// map.idx.owner.items(...)
+    pub idx: I,
+}
+
+impl<'a, K, T, I> IndexedCountedMap<'a, K, T, I>
+where
+    K: PrimaryKey<'a>,
+    T: Serialize + DeserializeOwned + Clone,
+    I: IndexList<T>,
+{
+    /// Examples:
+    ///
+    /// ```rust
+    /// use cw_storage_plus::{Index, IndexList, IndexedCountedMap, UniqueIndex};
+    /// use serde::{Deserialize, Serialize};
+    ///
+    /// #[derive(Serialize, Deserialize, Clone)]
+    /// struct Data {
+    ///     pub name: String,
+    ///     pub age: u32,
+    /// }
+    ///
+    /// struct DataIndexes<'a> {
+    ///     pub age: UniqueIndex<'a, u32, Data>,
+    /// }
+    ///
+    /// impl<'a> IndexList<Data> for DataIndexes<'a> {
+    ///     fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Data>> + '_> {
+    ///         let v: Vec<&dyn Index<Data>> = vec![&self.age];
+    ///         Box::new(v.into_iter())
+    ///     }
+    /// }
+    ///
+    /// let indexes = DataIndexes {
+    ///     age: UniqueIndex::new(|d: &Data| d.age, "data__age"),
+    /// };
+    ///
+    /// IndexedCountedMap::<&[u8], Data, DataIndexes>::new("data", "data__count", indexes);
+    /// ```
+    pub fn new(pk_namespace: &'a str, counter_key: &'a str, indexes: I) -> Self {
+        IndexedCountedMap {
+            pk_namespace: pk_namespace.as_bytes(),
+            primary: Map::new(pk_namespace),
+            counter: Item::new(counter_key),
+            idx: indexes,
+        }
+    }
+
+    pub fn key(&self, k: K) -> Path<T> {
+        self.primary.key(k)
+    }
+
+    /// Returns the number of entries currently stored in the map
+    pub fn count(&self, store: &dyn Storage) -> StdResult<u32> {
+        Ok(self.counter.may_load(store)?.unwrap_or_default())
+    }
+
+    /// save will serialize the model and store, returns an error on serialization issues.
+    /// this must load the old value to update the indexes and the counter properly
+    /// if you loaded the old value earlier in the same function, use replace to avoid needless db reads
+    pub fn save(&self, store: &mut dyn Storage, key: K, data: &T) -> StdResult<()> {
+        let old_data = self.may_load(store, key.clone())?;
+        self.replace(store, key, Some(data), old_data.as_ref())
+    }
+
+    pub fn remove(&self, store: &mut dyn Storage, key: K) -> StdResult<()> {
+        let old_data = self.may_load(store, key.clone())?;
+        self.replace(store, key, None, old_data.as_ref())
+    }
+
+    /// replace writes data to key. old_data must be the current stored value (from a previous load)
+    /// and is used to properly update the index and the counter.
+    /// Passing `None` as data removes the entry.
+    pub fn replace(
+        &self,
+        store: &mut dyn Storage,
+        key: K,
+        data: Option<&T>,
+        old_data: Option<&T>,
+    ) -> StdResult<()> {
+        let count = self.count(store)?;
+        let count = match (old_data.is_some(), data.is_some()) {
+            (false, true) => Some(
+                count
+                    .checked_add(1)
+                    .ok_or_else(|| StdError::generic_err("CountedMap counter overflow"))?,
+            ),
+            (true, false) => Some(
+                count
+                    .checked_sub(1)
+                    .ok_or_else(|| StdError::generic_err("CountedMap counter underflow"))?,
+            ),
+            _ => None,
+        };
+        // this is the key *relative* to the primary map namespace
+        let pk = key.joined_key();
+        if let Some(old) = old_data {
+            for index in self.idx.get_indexes() {
+                index.remove(store, &pk, old)?;
+            }
+        }
+        if let Some(updated) = data {
+            for index in self.idx.get_indexes() {
+                index.save(store, &pk, updated)?;
+            }
+            self.primary.save(store, key, updated)?;
+        } else {
+            self.primary.remove(store, key);
+        }
+        if let Some(count) = count {
+            self.counter.save(store, &count)?;
+        }
+        Ok(())
+    }
+
+    /// Loads the data, perform the specified action, and store the result
+    /// in the database. This is shorthand for some common sequences, which may be useful.
+    ///
+    /// If the data exists, `action(Some(value))` is called. Otherwise `action(None)` is called.
+    pub fn update<A, E>(&self, store: &mut dyn Storage, key: K, action: A) -> Result<T, E>
+    where
+        A: FnOnce(Option<T>) -> Result<T, E>,
+        E: From<StdError>,
+    {
+        let input = self.may_load(store, key.clone())?;
+        let old_val = input.clone();
+        let output = action(input)?;
+        self.replace(store, key, Some(&output), old_val.as_ref())?;
+        Ok(output)
+    }
+
+    /// Like `update`, but the action may return `None` to remove the entry
+    /// (along with its index entries).
+    pub fn update_or_remove<A, E>(
+        &self,
+        store: &mut dyn Storage,
+        key: K,
+        action: A,
+    ) -> Result<Option<T>, E>
+    where
+        A: FnOnce(Option<T>) -> Result<Option<T>, E>,
+        E: From<StdError>,
+    {
+        let input = self.may_load(store, key.clone())?;
+        let old_val = input.clone();
+        let output = action(input)?;
+        self.replace(store, key, output.as_ref(), old_val.as_ref())?;
+        Ok(output)
+    }
+
+    /// load will return an error if no data is set at the given key, or on parse error
+    pub fn load(&self, store: &dyn Storage, key: K) -> StdResult<T> {
+        self.primary.load(store, key)
+    }
+
+    /// may_load will parse the data stored at the key if present, returns Ok(None) if no data there.
+    /// returns an error on issues parsing
+    pub fn may_load(&self, store: &dyn Storage, key: K) -> StdResult<Option<T>> {
+        self.primary.may_load(store, key)
+    }
+
+    /// Returns true if storage contains this key, without parsing or interpreting the contents.
+    pub fn has(&self, store: &dyn Storage, k: K) -> bool {
+        self.primary.has(store, k)
+    }
+
+    pub fn sub_prefix(&self, p: K::SubPrefix) -> Prefix<K::SuperSuffix, T, K::SuperSuffix> {
+        Prefix::new(self.pk_namespace, &p.prefix())
+    }
+
+    pub fn prefix(&self, p: K::Prefix) -> Prefix<K::Suffix, T, K::Suffix> {
+        Prefix::new(self.pk_namespace, &p.prefix())
+    }
+
+    // use no_prefix to scan -> range
+    fn no_prefix_raw(&self) -> Prefix<Vec<u8>, T, K> {
+        Prefix::new(self.pk_namespace, &[])
+    }
+}
+
+impl<'a, K, T, I> IndexedCountedMap<'a, K, T, I>
+where
+    T: Serialize + DeserializeOwned + Clone,
+    K: PrimaryKey<'a> + KeyDeserialize,
+    I: IndexList<T>,
+{
+    /// While `range` over a `prefix` fixes the prefix to one element and iterates over the
+    /// remaining, `prefix_range` accepts bounds for the lowest and highest elements of the
+    /// `Prefix` itself, and iterates over those (inclusively or exclusively, depending on
+    /// `PrefixBound`).
+    pub fn prefix_range<'c>(
+        &self,
+        store: &'c dyn Storage,
+        min: Option<PrefixBound<'a, K::Prefix>>,
+        max: Option<PrefixBound<'a, K::Prefix>>,
+        order: cosmwasm_std::Order,
+    ) -> Box<dyn Iterator<Item = StdResult<(K::Output, T)>> + 'c>
+    where
+        T: 'c,
+        'a: 'c,
+        K: 'c,
+        K::Output: 'static,
+    {
+        let mapped = namespaced_prefix_range(store, self.pk_namespace, min, max, order)
+            .map(deserialize_kv::<K, T>);
+        Box::new(mapped)
+    }
+
+    pub fn range_raw<'c>(
+        &self,
+        store: &'c dyn Storage,
+        min: Option<Bound<'a, K>>,
+        max: Option<Bound<'a, K>>,
+        order: cosmwasm_std::Order,
+    ) -> Box<dyn Iterator<Item = StdResult<cosmwasm_std::Record<T>>> + 'c>
+    where
+        T: 'c,
+    {
+        self.no_prefix_raw().range_raw(store, min, max, order)
+    }
+
+    pub fn keys_raw<'c>(
+        &self,
+        store: &'c dyn Storage,
+        min: Option<Bound<'a, K>>,
+        max: Option<Bound<'a, K>>,
+        order: cosmwasm_std::Order,
+    ) -> Box<dyn Iterator<Item = Vec<u8>> + 'c> {
+        self.no_prefix_raw().keys_raw(store, min, max, order)
+    }
+
+    pub fn range<'c>(
+        &self,
+        store: &'c dyn Storage,
+        min: Option<Bound<'a, K>>,
+        max: Option<Bound<'a, K>>,
+        order: cosmwasm_std::Order,
+    ) -> Box<dyn Iterator<Item = StdResult<(K::Output, T)>> + 'c>
+    where
+        T: 'c,
+        K::Output: 'static,
+    {
+        self.no_prefix().range(store, min, max, order)
+    }
+
+    pub fn keys<'c>(
+        &self,
+        store: &'c dyn Storage,
+        min: Option<Bound<'a, K>>,
+        max: Option<Bound<'a, K>>,
+        order: cosmwasm_std::Order,
+    ) -> Box<dyn Iterator<Item = StdResult<K::Output>> + 'c>
+    where
+        T: 'c,
+        K::Output: 'static,
+    {
+        self.no_prefix().keys(store, min, max, order)
+    }
+
+    fn no_prefix(&self) -> Prefix<K, T, K> {
+        Prefix::new(self.pk_namespace, &[])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::{Index, MultiIndex, UniqueIndex};
+    use cosmwasm_std::testing::MockStorage;
+    use cosmwasm_std::Order;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+    struct Data {
+        pub name: String,
+        pub age: u32,
+    }
+
+    struct DataIndexes<'a> {
+        pub name: MultiIndex<'a, String, Data, String>,
+        pub age: UniqueIndex<'a, u32, Data, String>,
+    }
+
+    impl<'a> IndexList<Data> for DataIndexes<'a> {
+        fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Data>> + '_> {
+            let v: Vec<&dyn Index<Data>> = vec![&self.name, &self.age];
+            Box::new(v.into_iter())
+        }
+    }
+
+    fn build_map<'a>() -> IndexedCountedMap<'a, &'a str, Data, DataIndexes<'a>> {
+        let indexes = DataIndexes {
+            name: MultiIndex::new(|d| d.name.clone(), "data", "data__name"),
+            age: UniqueIndex::new(|d| d.age, "data__age"),
+        };
+        IndexedCountedMap::new("data", "data__count", indexes)
+    }
+
+    fn data(name: &str, age: u32) -> Data {
+        Data {
+            name: name.to_string(),
+            age,
+        }
+    }
+
+    #[test]
+    fn save_remove_count() {
+        let mut store = MockStorage::new();
+        let map = build_map();
+        assert_eq!(0, map.count(&store).unwrap());
+
+        map.save(&mut store, "1", &data("Maria", 42)).unwrap();
+        map.save(&mut store, "2", &data("Maria", 23)).unwrap();
+        assert_eq!(2, map.count(&store).unwrap());
+
+        // overwrite keeps count, but updates indexes
+        map.save(&mut store, "2", &data("John", 23)).unwrap();
+        assert_eq!(2, map.count(&store).unwrap());
+        let marias = map
+            .idx
+            .name
+            .prefix("Maria".to_string())
+            .keys(&store, None, None, Order::Ascending)
+            .count();
+        assert_eq!(1, marias);
+
+        // unique constraint violation leaves the count alone
+        map.save(&mut store, "3", &data("Pedro", 42)).unwrap_err();
+        assert_eq!(2, map.count(&store).unwrap());
+
+        map.remove(&mut store, "1").unwrap();
+        assert_eq!(1, map.count(&store).unwrap());
+        map.remove(&mut store, "1").unwrap();
+        assert_eq!(1, map.count(&store).unwrap());
+        assert_eq!(None, map.idx.age.item(&store, 42).unwrap());
+    }
+
+    #[test]
+    fn update_or_remove_count() {
+        let mut store = MockStorage::new();
+        let map = build_map();
+
+        map.update(&mut store, "1", |_| -> StdResult<_> {
+            Ok(data("Maria", 42))
+        })
+        .unwrap();
+        assert_eq!(1, map.count(&store).unwrap());
+
+        let out = map
+            .update_or_remove(&mut store, "1", |_| -> StdResult<_> { Ok(None) })
+            .unwrap();
+        assert_eq!(None, out);
+        assert_eq!(0, map.count(&store).unwrap());
+        assert_eq!(None, map.idx.age.item(&store, 42).unwrap());
+        assert_eq!(
+            0,
+            map.range_raw(&store, None, None, Order::Ascending).count()
+        );
+    }
+}
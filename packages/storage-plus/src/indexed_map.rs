@@ -6,12 +6,14 @@ use cosmwasm_std::{StdError, StdResult, Storage};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 
+use crate::codec::{Codec, JsonCodec};
 use crate::de::KeyDeserialize;
-use crate::indexes::Index;
-use crate::iter_helpers::{deserialize_kv, deserialize_v};
+use crate::indexes::{index_batch, Index};
+use crate::iter_helpers::{decode_kv, decode_v};
 use crate::keys::{Prefixer, PrimaryKey};
 use crate::map::Map;
 use crate::prefix::{namespaced_prefix_range, Prefix};
+use crate::sequence::Sequence;
 use crate::{Bound, Path};
 
 pub trait IndexList<T> {
@@ -20,14 +22,15 @@ pub trait IndexList<T> {
 
 // TODO: remove traits here and make this const fn new
 /// `IndexedMap` works like a `Map` but has a secondary index
-pub struct IndexedMap<'a, K, T, I>
+/// and, like `Map`, takes the codec for its values as last type parameter.
+pub struct IndexedMap<'a, K, T, I, C = JsonCodec>
 where
     K: PrimaryKey<'a>,
     T: Serialize + DeserializeOwned + Clone,
     I: IndexList<T>,
 {
     pk_namespace: &'a [u8],
-    primary: Map<'a, K, T>,
+    primary: Map<'a, K, T, C>,
     /// This is meant to be read directly to get the proper types, like:
     /// map.idx.owner.items(...)
     pub idx: I,
@@ -47,17 +50,43 @@ where
             idx: indexes,
         }
     }
+}
+
+impl<'a, K, T, I, C> IndexedMap<'a, K, T, I, C>
+where
+    K: PrimaryKey<'a>,
+    T: Serialize + DeserializeOwned + Clone,
+    I: IndexList<T>,
+{
+    /// Switches to another codec for the stored values.
+    /// Note that `MultiIndex` reads the values from the primary map, so it must use the same codec.
+    pub fn with_codec<D>(self) -> IndexedMap<'a, K, T, I, D> {
+        IndexedMap {
+            pk_namespace: self.pk_namespace,
+            primary: self.primary.with_codec(),
+            idx: self.idx,
+        }
+    }
+}
 
-    pub fn key(&self, k: K) -> Path<T> {
+impl<'a, K, T, I, C> IndexedMap<'a, K, T, I, C>
+where
+    K: PrimaryKey<'a>,
+    T: Serialize + DeserializeOwned + Clone,
+    I: IndexList<T>,
+    C: Codec<T>,
+{
+    pub fn key(&self, k: K) -> Path<T, C> {
         self.primary.key(k)
     }
 }
 
-impl<'a, K, T, I> IndexedMap<'a, K, T, I>
+impl<'a, K, T, I, C> IndexedMap<'a, K, T, I, C>
 where
     K: PrimaryKey<'a>,
     T: Serialize + DeserializeOwned + Clone,
     I: IndexList<T>,
+    C: Codec<T>,
 {
     /// save will serialize the model and store, returns an error on serialization issues.
     /// this must load the old value to update the indexes properly
@@ -135,18 +164,59 @@ where
         self.primary.key(k).has(store)
     }
 
+    /// Adds the entries of the map to all its indexes, in batches of at most `limit` entries.
+    /// Pass `None` as `start_after` for the first batch, and the returned key for the following
+    /// ones, until it returns `None`. `limit` must not be zero.
+    ///
+    /// This is for adopting indexes on existing data: when a `Map` is replaced by an
+    /// `IndexedMap` with the same namespace, or an index is added to an `IndexedMap`.
+    /// Entries already in an index are left as they are, but entries that do not match the
+    /// data anymore are not removed: an index whose data is stale should be moved to a new
+    /// namespace and rebuilt from there.
+    pub fn reindex(
+        &self,
+        store: &mut dyn Storage,
+        start_after: Option<Vec<u8>>,
+        limit: usize,
+    ) -> StdResult<Option<Vec<u8>>> {
+        let indexes: Vec<_> = self.idx.get_indexes().collect();
+        index_batch::<T, C>(store, self.pk_namespace, &indexes, start_after, limit)
+    }
+
     // use no_prefix to scan -> range
     fn no_prefix_raw(&self) -> Prefix<Vec<u8>, T, K> {
-        Prefix::new(self.pk_namespace, &[])
+        Prefix::new_with_codec::<C>(self.pk_namespace, &[])
+    }
+}
+
+impl<'a, T, I, C> IndexedMap<'a, u64, T, I, C>
+where
+    T: Serialize + DeserializeOwned + Clone,
+    I: IndexList<T>,
+    C: Codec<T>,
+{
+    /// Saves `data` under the next id of `sequence`, and returns that id.
+    /// Fails if there is already an entry with that id, e.g. when the sequence was set back.
+    pub fn push(&self, store: &mut dyn Storage, sequence: &Sequence, data: &T) -> StdResult<u64> {
+        let id = sequence.next(store)?;
+        if self.has(store, id) {
+            return Err(StdError::generic_err(format!(
+                "IndexedMap already has an entry with id {}",
+                id
+            )));
+        }
+        self.replace(store, id, Some(data), None)?;
+        Ok(id)
     }
 }
 
 #[cfg(feature = "iterator")]
-impl<'a, K, T, I> IndexedMap<'a, K, T, I>
+impl<'a, K, T, I, C> IndexedMap<'a, K, T, I, C>
 where
     K: PrimaryKey<'a>,
     T: Serialize + DeserializeOwned + Clone,
     I: IndexList<T>,
+    C: Codec<T>,
 {
     /// While `range_raw` over a `prefix` fixes the prefix to one element and iterates over the
     /// remaining, `prefix_range_raw` accepts bounds for the lowest and highest elements of the `Prefix`
@@ -163,35 +233,38 @@ where
     where
         T: 'c,
         'a: 'c,
+        C: 'c,
     {
-        let mapped =
-            namespaced_prefix_range(store, self.pk_namespace, min, max, order).map(deserialize_v);
+        let mapped = namespaced_prefix_range(store, self.pk_namespace, min, max, order)
+            .map(decode_v::<T, C>);
         Box::new(mapped)
     }
 }
 
 #[cfg(feature = "iterator")]
-impl<'a, K, T, I> IndexedMap<'a, K, T, I>
+impl<'a, K, T, I, C> IndexedMap<'a, K, T, I, C>
 where
     T: Serialize + DeserializeOwned + Clone,
     K: PrimaryKey<'a>,
     I: IndexList<T>,
+    C: Codec<T>,
 {
     pub fn sub_prefix(&self, p: K::SubPrefix) -> Prefix<K::SuperSuffix, T, K::SuperSuffix> {
-        Prefix::new(self.pk_namespace, &p.prefix())
+        Prefix::new_with_codec::<C>(self.pk_namespace, &p.prefix())
     }
 
     pub fn prefix(&self, p: K::Prefix) -> Prefix<K::Suffix, T, K::Suffix> {
-        Prefix::new(self.pk_namespace, &p.prefix())
+        Prefix::new_with_codec::<C>(self.pk_namespace, &p.prefix())
     }
 }
 
 #[cfg(feature = "iterator")]
-impl<'a, K, T, I> IndexedMap<'a, K, T, I>
+impl<'a, K, T, I, C> IndexedMap<'a, K, T, I, C>
 where
     T: Serialize + DeserializeOwned + Clone,
     K: PrimaryKey<'a> + KeyDeserialize,
     I: IndexList<T>,
+    C: Codec<T>,
 {
     /// While `range` over a `prefix` fixes the prefix to one element and iterates over the
     /// remaining, `prefix_range` accepts bounds for the lowest and highest elements of the
@@ -211,9 +284,10 @@ where
         'a: 'c,
         K: 'c,
         K::Output: 'static,
+        C: 'c,
     {
         let mapped = namespaced_prefix_range(store, self.pk_namespace, min, max, order)
-            .map(deserialize_kv::<K, T>);
+            .map(decode_kv::<K, T, C>);
         Box::new(mapped)
     }
 
@@ -269,7 +343,7 @@ where
     }
 
     fn no_prefix(&self) -> Prefix<K, T, K> {
-        Prefix::new(self.pk_namespace, &[])
+        Prefix::new_with_codec::<C>(self.pk_namespace, &[])
     }
 }
 
@@ -812,6 +886,91 @@ mod test {
         assert_eq!(v.age, data5.age);
     }
 
+    #[test]
+    fn reindex_existing_data() {
+        let mut store = MockStorage::new();
+        let map = build_map();
+        let (pks, datas) = save_data(&mut store, &map);
+
+        // the same data, saved in a plain map with the same namespace
+        let mut store = MockStorage::new();
+        let plain: Map<&str, Data> = Map::new("data");
+        for (pk, data) in pks.iter().zip(&datas) {
+            plain.save(&mut store, pk, data).unwrap();
+        }
+        assert_eq!(map.load(&store, pks[2]).unwrap(), datas[2]);
+        assert_eq!(map.idx.age.item(&store, 42).unwrap(), None);
+
+        let mut cursor = map.reindex(&mut store, None, 2).unwrap();
+        assert_eq!(cursor, Some(b"2".to_vec()));
+        // entries written in between are indexed right away, and can be indexed again
+        let data6 = Data {
+            name: "Maria".to_string(),
+            last_name: "Newman".to_string(),
+            age: 55,
+        };
+        map.save(&mut store, "6", &data6).unwrap();
+        let mut batches = 1;
+        while cursor.is_some() {
+            cursor = map.reindex(&mut store, cursor, 2).unwrap();
+            batches += 1;
+        }
+        assert_eq!(batches, 3);
+
+        let marias: Vec<_> = map
+            .idx
+            .name
+            .prefix("Maria".to_string())
+            .keys(&store, None, None, Order::Ascending)
+            .collect::<StdResult<_>>()
+            .unwrap();
+        assert_eq!(marias, vec!["1", "2", "6"]);
+        let (pk, _) = map.idx.age.item(&store, 42).unwrap().unwrap();
+        assert_eq!(pk, b"1".to_vec());
+        let (pk, _) = map.idx.age.item(&store, 55).unwrap().unwrap();
+        assert_eq!(pk, b"6".to_vec());
+        // outside of reindexing, saving an indexed entry again still violates the unique index
+        map.idx.age.save(&mut store, b"1", &datas[0]).unwrap_err();
+
+        // data violating a unique index fails to be indexed
+        let mut dup = datas[0].clone();
+        dup.name = "Other".to_string();
+        plain.save(&mut store, "7", &dup).unwrap();
+        map.reindex(&mut store, None, 10).unwrap_err();
+    }
+
+    #[test]
+    fn push_allocates_ids() {
+        let mut store = MockStorage::new();
+        let indexes = DataIndexes {
+            name: MultiIndex::new(|d| d.name.clone(), "people", "people__name"),
+            age: UniqueIndex::new(|d| d.age, "people__age"),
+            name_lastname: UniqueIndex::new(
+                |d| index_string_tuple(&d.name, &d.last_name),
+                "people__name_lastname",
+            ),
+        };
+        let map: IndexedMap<u64, Data, DataIndexes> = IndexedMap::new("people", indexes);
+        let ids = Sequence::new("people_count");
+
+        let (_, datas) = save_data(&mut MockStorage::new(), &build_map());
+        assert_eq!(map.push(&mut store, &ids, &datas[0]).unwrap(), 1);
+        assert_eq!(map.push(&mut store, &ids, &datas[1]).unwrap(), 2);
+        assert_eq!(map.load(&store, 2).unwrap(), datas[1]);
+        let (pk, _) = map.idx.age.item(&store, datas[1].age).unwrap().unwrap();
+        assert_eq!(pk, 2u64.to_be_bytes().to_vec());
+
+        // index violations do not save anything
+        map.push(&mut store, &ids, &datas[0]).unwrap_err();
+        assert!(!map.has(&store, 3));
+
+        // ids are never reused, unless the sequence is set back
+        map.remove(&mut store, 2).unwrap();
+        assert_eq!(map.push(&mut store, &ids, &datas[2]).unwrap(), 4);
+        ids.set_last(&mut store, 0).unwrap();
+        map.push(&mut store, &ids, &datas[3]).unwrap_err();
+    }
+
     #[test]
     fn unique_index_enforced_composite_key() {
         let mut store = MockStorage::new();
@@ -1529,4 +1688,84 @@ mod test {
             );
         }
     }
+
+    #[cfg(feature = "bin-codec")]
+    struct BinDataIndexes<'a> {
+        pub name: MultiIndex<'a, String, Data, String, crate::BinCodec>,
+        pub age: UniqueIndex<'a, u32, Data, String>,
+    }
+
+    #[cfg(feature = "bin-codec")]
+    impl<'a> IndexList<Data> for BinDataIndexes<'a> {
+        fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Data>> + '_> {
+            let v: Vec<&dyn Index<Data>> = vec![&self.name, &self.age];
+            Box::new(v.into_iter())
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "bin-codec")]
+    fn bin_codec_with_indexes() {
+        let mut store = MockStorage::new();
+        let indexes = BinDataIndexes {
+            name: MultiIndex::new(|d: &Data| d.name.clone(), "data", "data__name").with_codec(),
+            age: UniqueIndex::new(|d| d.age, "data__age"),
+        };
+        let map: IndexedMap<&str, Data, _, crate::BinCodec> =
+            IndexedMap::new("data", indexes).with_codec();
+
+        let maria = Data {
+            name: "Maria".to_string(),
+            last_name: "Doe".to_string(),
+            age: 42,
+        };
+        let john = Data {
+            name: "John".to_string(),
+            last_name: "Wayne".to_string(),
+            age: 32,
+        };
+        map.save(&mut store, "1", &maria).unwrap();
+        map.save(&mut store, "2", &john).unwrap();
+        map.save(
+            &mut store,
+            "3",
+            &Data {
+                age: 12,
+                ..maria.clone()
+            },
+        )
+        .unwrap();
+        assert_eq!(map.load(&store, "2").unwrap(), john);
+
+        // multi index reads values from the primary map, with its codec
+        let marias: Vec<_> = map
+            .idx
+            .name
+            .prefix("Maria".to_string())
+            .range(&store, None, None, Order::Ascending)
+            .collect::<StdResult<_>>()
+            .unwrap();
+        assert_eq!(marias.len(), 2);
+        assert_eq!(marias[0], ("1".to_string(), maria.clone()));
+
+        let (_, by_age) = map.idx.age.item(&store, 32).unwrap().unwrap();
+        assert_eq!(by_age, john);
+
+        let all: Vec<_> = map
+            .range(&store, None, None, Order::Descending)
+            .collect::<StdResult<_>>()
+            .unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[1], ("2".to_string(), john));
+
+        map.remove(&mut store, "1").unwrap();
+        assert_eq!(
+            map.idx
+                .name
+                .prefix("Maria".to_string())
+                .keys_raw(&store, None, None, Order::Ascending)
+                .count(),
+            1
+        );
+    }
 }
@@ -1,28 +1,36 @@
 // this module requires iterator to be useful at all
 #![cfg(feature = "iterator")]
 
-use cosmwasm_std::{StdError, StdResult, Storage};
+use cosmwasm_std::{StdError, StdResult, Storage, Timestamp};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 
+use crate::codec::{Codec, JsonCodec};
 use crate::de::KeyDeserialize;
-use crate::iter_helpers::deserialize_kv;
+use crate::iter_helpers::decode_kv;
 use crate::keys::{Prefixer, PrimaryKey};
 use crate::prefix::{namespaced_prefix_range, Prefix};
-use crate::snapshot::{ChangeSet, SnapshotMap};
+use crate::snapshot::{
+    ByHeight, ByTime, ChangeSet, CheckpointKind, PruneProgress, Retention, SnapshotMap,
+};
 use crate::PrefixBound;
 use crate::{Bound, IndexList, Map, Path, Strategy};
 
 /// `IndexedSnapshotMap` works like a `SnapshotMap` but has a secondary index
-pub struct IndexedSnapshotMap<'a, K, T, I> {
+///
+/// As for `SnapshotMap`, the codec `V` only applies to the current values: the changelog of the
+/// map, and the one of snapshot indexes, are always stored as JSON.
+pub struct IndexedSnapshotMap<'a, K, T, I, C = ByHeight, V = JsonCodec> {
     pk_namespace: &'a [u8],
-    primary: SnapshotMap<'a, K, T>,
+    primary: SnapshotMap<'a, K, T, C, V>,
     /// This is meant to be read directly to get the proper types, like:
     /// map.idx.owner.items(...)
     pub idx: I,
 }
 
-impl<'a, K, T, I> IndexedSnapshotMap<'a, K, T, I> {
+impl<'a, K, T, I> IndexedSnapshotMap<'a, K, T, I, ByHeight> {
+    /// Creates a map with checkpoints indexed by block height.
+    ///
     /// Examples:
     ///
     /// ```rust
@@ -57,26 +65,117 @@ impl<'a, K, T, I> IndexedSnapshotMap<'a, K, T, I> {
             idx: indexes,
         }
     }
+}
+
+impl<'a, K, T, I> IndexedSnapshotMap<'a, K, T, I, ByTime> {
+    /// Creates a map with checkpoints indexed by block time.
+    pub fn new_timed(
+        pk_namespace: &'a str,
+        checkpoints: &'a str,
+        changelog: &'a str,
+        strategy: Strategy,
+        indexes: I,
+    ) -> Self {
+        IndexedSnapshotMap {
+            pk_namespace: pk_namespace.as_bytes(),
+            primary: SnapshotMap::new_timed(pk_namespace, checkpoints, changelog, strategy),
+            idx: indexes,
+        }
+    }
+}
+
+impl<'a, K, T, I, C, V> IndexedSnapshotMap<'a, K, T, I, C, V> {
+    /// Switches to another codec for the current values, see `SnapshotMap::with_codec`.
+    /// Any `MultiIndex` must use the same codec.
+    pub fn with_codec<D>(self) -> IndexedSnapshotMap<'a, K, T, I, C, D> {
+        IndexedSnapshotMap {
+            pk_namespace: self.pk_namespace,
+            primary: self.primary.with_codec(),
+            idx: self.idx,
+        }
+    }
+
+    /// Enables pruning, and sets how much history is kept. See `SnapshotMap::with_pruning`.
+    pub fn with_pruning(self, namespace: &'a str, retention: Retention) -> Self {
+        IndexedSnapshotMap {
+            pk_namespace: self.pk_namespace,
+            primary: self.primary.with_pruning(namespace, retention),
+            idx: self.idx,
+        }
+    }
 
     pub fn changelog(&self) -> &Map<'a, (K, u64), ChangeSet<T>> {
         self.primary.changelog()
     }
 }
 
-impl<'a, K, T, I> IndexedSnapshotMap<'a, K, T, I>
+impl<'a, K, T, I, C, V> IndexedSnapshotMap<'a, K, T, I, C, V>
 where
     T: Serialize + DeserializeOwned + Clone,
     K: PrimaryKey<'a> + Prefixer<'a> + KeyDeserialize,
     I: IndexList<T>,
+    C: CheckpointKind,
+    V: Codec<T>,
 {
-    pub fn add_checkpoint(&self, store: &mut dyn Storage, height: u64) -> StdResult<()> {
-        self.primary.add_checkpoint(store, height)
+    pub fn add_checkpoint(&self, store: &mut dyn Storage, at: C::Point) -> StdResult<()> {
+        self.primary.add_checkpoint(store, at)
+    }
+
+    pub fn remove_checkpoint(&self, store: &mut dyn Storage, at: C::Point) -> StdResult<()> {
+        self.primary.remove_checkpoint(store, at)
+    }
+
+    /// Returns the value at the beginning of the given checkpoint (height or time)
+    pub fn may_load_at(&self, store: &dyn Storage, k: K, at: C::Point) -> StdResult<Option<T>> {
+        self.primary.may_load_at(store, k, at)
+    }
+
+    pub fn assert_checkpointed(&self, store: &dyn Storage, at: C::Point) -> StdResult<()> {
+        self.primary.assert_checkpointed(store, at)
+    }
+
+    /// Returns the lowest height that can still be queried, or `None` if nothing was pruned
+    pub fn pruned_height(&self, store: &dyn Storage) -> StdResult<Option<u64>> {
+        self.primary.pruned_height(store)
+    }
+
+    /// Removes the checkpoints and changelog entries older than `below`, going over at most
+    /// `limit` entries. Call it again until the result is `done`.
+    ///
+    /// The history of the map is pruned first, then the one of each index, each going over at
+    /// most `limit` entries.
+    pub fn prune(
+        &self,
+        store: &mut dyn Storage,
+        below: C::Point,
+        limit: usize,
+    ) -> StdResult<PruneProgress> {
+        let mut progress = self.primary.prune(store, below, limit)?;
+        for index in self.idx.get_indexes() {
+            if !progress.done {
+                break;
+            }
+            let index_progress = index.prune(store, C::checkpoint_key(below), limit)?;
+            progress = PruneProgress {
+                removed: progress.removed + index_progress.removed,
+                done: index_progress.done,
+            };
+        }
+        Ok(progress)
     }
 
-    pub fn remove_checkpoint(&self, store: &mut dyn Storage, height: u64) -> StdResult<()> {
-        self.primary.remove_checkpoint(store, height)
+    pub fn key(&self, k: K) -> Path<T, V> {
+        self.primary.key(k)
     }
+}
 
+impl<'a, K, T, I, V> IndexedSnapshotMap<'a, K, T, I, ByHeight, V>
+where
+    T: Serialize + DeserializeOwned + Clone,
+    K: PrimaryKey<'a> + Prefixer<'a> + KeyDeserialize,
+    I: IndexList<T>,
+    V: Codec<T>,
+{
     pub fn may_load_at_height(
         &self,
         store: &dyn Storage,
@@ -85,33 +184,44 @@ where
     ) -> StdResult<Option<T>> {
         self.primary.may_load_at_height(store, k, height)
     }
+}
 
-    pub fn assert_checkpointed(&self, store: &dyn Storage, height: u64) -> StdResult<()> {
-        self.primary.assert_checkpointed(store, height)
-    }
-
-    pub fn key(&self, k: K) -> Path<T> {
-        self.primary.key(k)
+impl<'a, K, T, I, V> IndexedSnapshotMap<'a, K, T, I, ByTime, V>
+where
+    T: Serialize + DeserializeOwned + Clone,
+    K: PrimaryKey<'a> + Prefixer<'a> + KeyDeserialize,
+    I: IndexList<T>,
+    V: Codec<T>,
+{
+    pub fn may_load_at_time(
+        &self,
+        store: &dyn Storage,
+        k: K,
+        time: Timestamp,
+    ) -> StdResult<Option<T>> {
+        self.primary.may_load_at_time(store, k, time)
     }
 }
 
-impl<'a, K, T, I> IndexedSnapshotMap<'a, K, T, I>
+impl<'a, K, T, I, C, V> IndexedSnapshotMap<'a, K, T, I, C, V>
 where
     K: PrimaryKey<'a> + Prefixer<'a> + KeyDeserialize,
     T: Serialize + DeserializeOwned + Clone,
     I: IndexList<T>,
+    C: CheckpointKind,
+    V: Codec<T>,
 {
     /// save will serialize the model and store, returns an error on serialization issues.
     /// this must load the old value to update the indexes properly
     /// if you loaded the old value earlier in the same function, use replace to avoid needless db reads
-    pub fn save(&self, store: &mut dyn Storage, key: K, data: &T, height: u64) -> StdResult<()> {
+    pub fn save(&self, store: &mut dyn Storage, key: K, data: &T, at: C::Point) -> StdResult<()> {
         let old_data = self.may_load(store, key.clone())?;
-        self.replace(store, key, Some(data), old_data.as_ref(), height)
+        self.replace(store, key, Some(data), old_data.as_ref(), at)
     }
 
-    pub fn remove(&self, store: &mut dyn Storage, key: K, height: u64) -> StdResult<()> {
+    pub fn remove(&self, store: &mut dyn Storage, key: K, at: C::Point) -> StdResult<()> {
         let old_data = self.may_load(store, key.clone())?;
-        self.replace(store, key, None, old_data.as_ref(), height)
+        self.replace(store, key, None, old_data.as_ref(), at)
     }
 
     /// replace writes data to key. old_data must be the current stored value (from a previous load)
@@ -123,22 +233,23 @@ where
         key: K,
         data: Option<&T>,
         old_data: Option<&T>,
-        height: u64,
+        at: C::Point,
     ) -> StdResult<()> {
         // this is the key *relative* to the primary map namespace
         let pk = key.joined_key();
+        let checkpoint = C::checkpoint_key(at);
         if let Some(old) = old_data {
             for index in self.idx.get_indexes() {
-                index.remove(store, &pk, old)?;
+                index.remove_at(store, &pk, old, checkpoint)?;
             }
         }
         if let Some(updated) = data {
             for index in self.idx.get_indexes() {
-                index.save(store, &pk, updated)?;
+                index.save_at(store, &pk, updated, checkpoint)?;
             }
-            self.primary.save(store, key, updated, height)?;
+            self.primary.save(store, key, updated, at)?;
         } else {
-            self.primary.remove(store, key, height)?;
+            self.primary.remove(store, key, at)?;
         }
         Ok(())
     }
@@ -151,7 +262,7 @@ where
         &self,
         store: &mut dyn Storage,
         key: K,
-        height: u64,
+        at: C::Point,
         action: A,
     ) -> Result<T, E>
     where
@@ -161,7 +272,7 @@ where
         let input = self.may_load(store, key.clone())?;
         let old_val = input.clone();
         let output = action(input)?;
-        self.replace(store, key, Some(&output), old_val.as_ref(), height)?;
+        self.replace(store, key, Some(&output), old_val.as_ref(), at)?;
         Ok(output)
     }
 
@@ -181,16 +292,18 @@ where
 
     // use no_prefix to scan -> range
     pub fn no_prefix_raw(&self) -> Prefix<Vec<u8>, T, K> {
-        Prefix::new(self.pk_namespace, &[])
+        Prefix::new_with_codec::<V>(self.pk_namespace, &[])
     }
 }
 
 // short-cut for simple keys, rather than .prefix(()).range_raw(...)
-impl<'a, K, T, I> IndexedSnapshotMap<'a, K, T, I>
+impl<'a, K, T, I, C, V> IndexedSnapshotMap<'a, K, T, I, C, V>
 where
     K: PrimaryKey<'a> + Prefixer<'a> + KeyDeserialize,
     T: Serialize + DeserializeOwned + Clone,
     I: IndexList<T>,
+    C: CheckpointKind,
+    V: Codec<T>,
 {
     // I would prefer not to copy code from Prefix, but no other way
     // with lifetimes (create Prefix inside function and return ref = no no)
@@ -219,27 +332,29 @@ where
 }
 
 #[cfg(feature = "iterator")]
-impl<'a, K, T, I> IndexedSnapshotMap<'a, K, T, I>
+impl<'a, K, T, I, C, V> IndexedSnapshotMap<'a, K, T, I, C, V>
 where
     T: Serialize + DeserializeOwned + Clone,
     K: PrimaryKey<'a>,
     I: IndexList<T>,
+    V: Codec<T>,
 {
     pub fn sub_prefix(&self, p: K::SubPrefix) -> Prefix<K::SuperSuffix, T, K::SuperSuffix> {
-        Prefix::new(self.pk_namespace, &p.prefix())
+        Prefix::new_with_codec::<V>(self.pk_namespace, &p.prefix())
     }
 
     pub fn prefix(&self, p: K::Prefix) -> Prefix<K::Suffix, T, K::Suffix> {
-        Prefix::new(self.pk_namespace, &p.prefix())
+        Prefix::new_with_codec::<V>(self.pk_namespace, &p.prefix())
     }
 }
 
 #[cfg(feature = "iterator")]
-impl<'a, K, T, I> IndexedSnapshotMap<'a, K, T, I>
+impl<'a, K, T, I, C, V> IndexedSnapshotMap<'a, K, T, I, C, V>
 where
     T: Serialize + DeserializeOwned + Clone,
     K: PrimaryKey<'a> + KeyDeserialize,
     I: IndexList<T>,
+    V: Codec<T>,
 {
     /// While `range` over a `prefix` fixes the prefix to one element and iterates over the
     /// remaining, `prefix_range` accepts bounds for the lowest and highest elements of the
@@ -259,9 +374,10 @@ where
         'a: 'c,
         K: 'c,
         K::Output: 'static,
+        V: 'c,
     {
         let mapped = namespaced_prefix_range(store, self.pk_namespace, min, max, order)
-            .map(deserialize_kv::<K, T>);
+            .map(decode_kv::<K, T, V>);
         Box::new(mapped)
     }
 
@@ -294,7 +410,7 @@ where
     }
 
     fn no_prefix(&self) -> Prefix<K, T, K> {
-        Prefix::new(self.pk_namespace, &[])
+        Prefix::new_with_codec::<V>(self.pk_namespace, &[])
     }
 }
 
@@ -303,7 +419,7 @@ mod test {
     use super::*;
 
     use crate::indexes::test::{index_string_tuple, index_tuple};
-    use crate::{Index, MultiIndex, UniqueIndex};
+    use crate::{Index, MultiIndex, SnapshotMultiIndex, SnapshotUniqueIndex, UniqueIndex};
     use cosmwasm_std::testing::MockStorage;
     use cosmwasm_std::{MemoryStorage, Order};
     use serde::{Deserialize, Serialize};
@@ -1209,4 +1325,235 @@ mod test {
             ]
         );
     }
+
+    struct DataSnapshotIndexes<'a> {
+        pub age: SnapshotMultiIndex<'a, u32, Data, String>,
+        pub name: SnapshotUniqueIndex<'a, String, Data, String>,
+    }
+
+    impl<'a> IndexList<Data> for DataSnapshotIndexes<'a> {
+        fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Data>> + '_> {
+            let v: Vec<&dyn Index<Data>> = vec![&self.age, &self.name];
+            Box::new(v.into_iter())
+        }
+    }
+
+    #[test]
+    fn historical_index_queries() {
+        let mut store = MockStorage::new();
+
+        let indexes = DataSnapshotIndexes {
+            age: SnapshotMultiIndex::new(
+                |d| d.age,
+                "data",
+                "data__age",
+                "checks",
+                "data__age__changes",
+                Strategy::EveryBlock,
+            ),
+            name: SnapshotUniqueIndex::new(
+                |d| d.name.clone(),
+                "data__name",
+                "checks",
+                "data__name__changes",
+                Strategy::EveryBlock,
+            ),
+        };
+        let map =
+            IndexedSnapshotMap::new("data", "checks", "changes", Strategy::EveryBlock, indexes);
+
+        let maria = Data {
+            name: "Maria".to_string(),
+            last_name: "Doe".to_string(),
+            age: 42,
+        };
+        let juan = Data {
+            name: "Juan".to_string(),
+            last_name: "Perez".to_string(),
+            age: 13,
+        };
+        map.save(&mut store, "1", &maria, 1).unwrap();
+        map.save(&mut store, "2", &juan, 1).unwrap();
+
+        // maria gets older, only the last change within a height counts
+        let older = Data {
+            age: 43,
+            ..maria.clone()
+        };
+        map.save(
+            &mut store,
+            "1",
+            &Data {
+                age: 50,
+                ..maria.clone()
+            },
+            3,
+        )
+        .unwrap();
+        map.save(&mut store, "1", &older, 3).unwrap();
+        // juan is gone, and his name taken by someone else
+        map.remove(&mut store, "2", 5).unwrap();
+        let other_juan = Data {
+            name: "Juan".to_string(),
+            last_name: "Gomez".to_string(),
+            age: 42,
+        };
+        map.save(&mut store, "3", &other_juan, 5).unwrap();
+
+        let by_age = |age: u32, height: u64| -> Vec<_> {
+            map.idx
+                .age
+                .prefix_at_height(&store, age, None, None, Order::Ascending, height)
+                .collect::<StdResult<_>>()
+                .unwrap()
+        };
+        assert_eq!(by_age(42, 1), vec![]);
+        assert_eq!(by_age(42, 2), vec![("1".to_string(), maria.clone())]);
+        assert_eq!(by_age(42, 3), vec![("1".to_string(), maria.clone())]);
+        assert_eq!(by_age(42, 4), vec![]);
+        assert_eq!(by_age(50, 4), vec![]);
+        assert_eq!(by_age(43, 4), vec![("1".to_string(), older.clone())]);
+        assert_eq!(by_age(42, 6), vec![("3".to_string(), other_juan.clone())]);
+
+        // the regular index only sees the current state
+        let current: Vec<_> = map
+            .idx
+            .age
+            .prefix(42)
+            .range(&store, None, None, Order::Ascending)
+            .collect::<StdResult<_>>()
+            .unwrap();
+        assert_eq!(current, vec![("3".to_string(), other_juan.clone())]);
+
+        // everyone younger than 43 at height 4, and at height 6
+        let younger = |height: u64| -> Vec<_> {
+            map.idx
+                .age
+                .prefix_range_at_height(
+                    &store,
+                    None,
+                    Some(PrefixBound::exclusive(43u32)),
+                    Order::Ascending,
+                    height,
+                )
+                .collect::<StdResult<_>>()
+                .unwrap()
+        };
+        assert_eq!(younger(4), vec![("2".to_string(), juan.clone())]);
+        assert_eq!(younger(6), vec![("3".to_string(), other_juan.clone())]);
+
+        let (pk, value) = map
+            .idx
+            .name
+            .item_at_height(&store, "Juan".to_string(), 5)
+            .unwrap()
+            .unwrap();
+        assert_eq!((pk, value), (b"2".to_vec(), juan.clone()));
+        let (pk, value) = map
+            .idx
+            .name
+            .item_at_height(&store, "Juan".to_string(), 6)
+            .unwrap()
+            .unwrap();
+        assert_eq!((pk, value), (b"3".to_vec(), other_juan));
+        assert_eq!(
+            map.idx
+                .name
+                .item_at_height(&store, "Juan".to_string(), 1)
+                .unwrap(),
+            None
+        );
+
+        let names: Vec<_> = map
+            .idx
+            .name
+            .range_at_height(&store, None, None, Order::Descending, 2)
+            .collect::<StdResult<_>>()
+            .unwrap();
+        assert_eq!(
+            names,
+            vec![("1".to_string(), maria), ("2".to_string(), juan)]
+        );
+    }
+
+    #[test]
+    fn historical_index_queries_follow_strategy_and_pruning() {
+        let mut store = MockStorage::new();
+
+        let indexes = DataSnapshotIndexes {
+            age: SnapshotMultiIndex::new(
+                |d: &Data| d.age,
+                "data",
+                "data__age",
+                "checks",
+                "data__age__changes",
+                Strategy::Selected,
+            )
+            .with_pruning("data__age__pruning", Retention::Forever),
+            name: SnapshotUniqueIndex::new(
+                |d: &Data| d.name.clone(),
+                "data__name",
+                "checks",
+                "data__name__changes",
+                Strategy::Selected,
+            )
+            .with_pruning("data__name__pruning", Retention::Forever),
+        };
+        let map = IndexedSnapshotMap::new("data", "checks", "changes", Strategy::Selected, indexes)
+            .with_pruning("pruning", Retention::Forever);
+
+        let maria = Data {
+            name: "Maria".to_string(),
+            last_name: "Doe".to_string(),
+            age: 42,
+        };
+        let older = Data {
+            age: 43,
+            ..maria.clone()
+        };
+        // no checkpoint, so no history
+        map.save(&mut store, "1", &maria, 1).unwrap();
+        map.add_checkpoint(&mut store, 3).unwrap();
+        map.save(&mut store, "1", &older, 3).unwrap();
+        map.add_checkpoint(&mut store, 5).unwrap();
+        map.remove(&mut store, "1", 5).unwrap();
+
+        let by_age = |store: &MockStorage, age: u32, height: u64| -> StdResult<Vec<_>> {
+            map.idx
+                .age
+                .prefix_at_height(store, age, None, None, Order::Ascending, height)
+                .collect()
+        };
+        assert_eq!(
+            by_age(&store, 42, 3).unwrap(),
+            vec![("1".to_string(), maria)]
+        );
+        assert_eq!(
+            by_age(&store, 43, 5).unwrap(),
+            vec![("1".to_string(), older)]
+        );
+        assert_eq!(by_age(&store, 43, 3).unwrap(), vec![]);
+        // only checkpointed heights can be queried
+        by_age(&store, 42, 4).unwrap_err();
+        map.idx
+            .name
+            .item_at_height(&store, "Maria".to_string(), 2)
+            .unwrap_err();
+
+        // the map and the index histories are pruned together
+        let progress = map.prune(&mut store, 4, 10).unwrap();
+        assert!(progress.done);
+        assert_eq!(progress.removed, 5);
+        by_age(&store, 42, 3).unwrap_err();
+        map.idx
+            .name
+            .item_at_height(&store, "Maria".to_string(), 3)
+            .unwrap_err();
+        assert!(map
+            .idx
+            .name
+            .item_at_height(&store, "Maria".to_string(), 5)
+            .unwrap()
+            .is_some());
+    }
 }
@@ -0,0 +1,305 @@
+//! Shared by the snapshot indexes, which keep the history of their entries in a `Snapshot`
+//! changelog: (entry, height) -> the value the index entry had before the change at that height,
+//! or `None` if it did not exist.
+//!
+//! Index keys have no fixed length, so they are escaped into changelog entries that keep their
+//! order and are never a prefix of one another. The height can then follow the entry, as it
+//! does for snapshot maps.
+
+use std::cmp::Ordering;
+use std::iter::Peekable;
+use std::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use cosmwasm_std::{from_slice, Order, Record, StdResult, Storage};
+
+use crate::bound::RawBound;
+use crate::iter_helpers::concat;
+use crate::prefix::{extend_one_byte, increment_last_byte, range_with_prefix};
+use crate::snapshot::{ChangeSet, Snapshot};
+
+/// Encodes an index key as a changelog entry: zero bytes are followed by a 1, and the entry
+/// ends with two zero bytes.
+fn escape(key: &[u8]) -> Vec<u8> {
+    let mut entry = escape_prefix(key);
+    entry.extend_from_slice(&[0, 0]);
+    entry
+}
+
+/// Like `escape`, without the terminator. The entries of keys that are greater than or equal
+/// to `key` are greater than or equal to this, the others are lower.
+fn escape_prefix(key: &[u8]) -> Vec<u8> {
+    let mut escaped = Vec::with_capacity(key.len() + 2);
+    for byte in key {
+        escaped.push(*byte);
+        if *byte == 0 {
+            escaped.push(1);
+        }
+    }
+    escaped
+}
+
+/// Decodes an entry (with its terminator) back into the index key
+fn unescape(entry: &[u8]) -> Vec<u8> {
+    let mut key = Vec::with_capacity(entry.len());
+    let mut bytes = entry.iter();
+    while let Some(byte) = bytes.next() {
+        if *byte == 0 && bytes.next() == Some(&0) {
+            break;
+        }
+        key.push(*byte);
+    }
+    key
+}
+
+/// Records the value the index entry `key` had before a change at `height`, unless the
+/// strategy says otherwise or it was already recorded at that height.
+pub(crate) fn write_change<V>(
+    store: &mut dyn Storage,
+    snapshot: &Snapshot<Vec<u8>, V>,
+    key: &[u8],
+    height: u64,
+    old: Option<V>,
+) -> StdResult<()>
+where
+    V: Serialize + DeserializeOwned + Clone,
+{
+    let entry = escape(key);
+    if snapshot.should_checkpoint_raw(store, &entry)?
+        && !snapshot.has_changelog_raw(store, &entry, height)
+    {
+        snapshot.write_changelog_raw(store, &entry, height, old)?;
+    }
+    Ok(())
+}
+
+/// Index keys (relative to the index namespace) from `start`, included, to `end`, excluded
+pub(crate) struct KeyRange {
+    start: Vec<u8>,
+    end: Option<Vec<u8>>,
+}
+
+impl KeyRange {
+    /// The keys starting with `prefix`, with bounds relative to it (as for `Prefix::range`)
+    pub fn new(prefix: &[u8], min: Option<RawBound>, max: Option<RawBound>) -> Self {
+        let start = match min {
+            None => prefix.to_vec(),
+            Some(RawBound::Inclusive(limit)) => concat(prefix, &limit),
+            Some(RawBound::Exclusive(limit)) => concat(prefix, &extend_one_byte(&limit)),
+        };
+        let end = match max {
+            None if prefix.is_empty() => None,
+            None => Some(increment_last_byte(prefix)),
+            Some(RawBound::Exclusive(limit)) => Some(concat(prefix, &limit)),
+            Some(RawBound::Inclusive(limit)) => Some(concat(prefix, &extend_one_byte(&limit))),
+        };
+        KeyRange { start, end }
+    }
+
+    /// The keys within prefix bounds (as for `prefix_range`)
+    pub fn with_prefix_bounds(min: Option<RawBound>, max: Option<RawBound>) -> Self {
+        let start = match min {
+            None => vec![],
+            Some(RawBound::Inclusive(limit)) => limit,
+            Some(RawBound::Exclusive(limit)) => increment_last_byte(&limit),
+        };
+        let end = match max {
+            None => None,
+            Some(RawBound::Exclusive(limit)) => Some(limit),
+            Some(RawBound::Inclusive(limit)) => Some(increment_last_byte(&limit)),
+        };
+        KeyRange { start, end }
+    }
+}
+
+/// Iterates over the index entries within `range` as they were at the beginning of `height`.
+///
+/// This merges the current entries of the index (under `namespace`) with the changelog:
+/// an entry changed at `height` or later had the value recorded by its first change since then,
+/// the others are still as they are now. `current` converts the values stored in the index.
+pub(crate) fn range_at_height<'c, V, F>(
+    store: &'c dyn Storage,
+    snapshot: &Snapshot<Vec<u8>, V>,
+    namespace: &[u8],
+    range: KeyRange,
+    order: Order,
+    height: u64,
+    current: F,
+) -> Box<dyn Iterator<Item = StdResult<(Vec<u8>, V)>> + 'c>
+where
+    V: Serialize + DeserializeOwned + Clone + 'c,
+    F: Fn(&[u8], &[u8]) -> StdResult<V> + 'c,
+{
+    if let Err(err) = snapshot
+        .assert_not_pruned(store, height)
+        .and_then(|_| snapshot.assert_checkpointed(store, height))
+    {
+        return Box::new(std::iter::once(Err(err)));
+    }
+
+    let entries = range_with_prefix(
+        store,
+        namespace,
+        Some(RawBound::Inclusive(range.start.clone())),
+        range.end.clone().map(RawBound::Exclusive),
+        order,
+    )
+    .peekable();
+    Box::new(HistoryIter {
+        store,
+        entries,
+        current,
+        changelog: snapshot.changelog_prefix(),
+        start: escape_prefix(&range.start),
+        end: range.end.map(|end| escape_prefix(&end)),
+        order,
+        height,
+        changed: None,
+        exhausted: false,
+        value_type: PhantomData,
+    })
+}
+
+struct HistoryIter<'c, V, F> {
+    store: &'c dyn Storage,
+    entries: Peekable<Box<dyn Iterator<Item = Record> + 'c>>,
+    current: F,
+    changelog: Vec<u8>,
+    // remaining changelog entries, the start is included and the end excluded
+    start: Vec<u8>,
+    end: Option<Vec<u8>>,
+    order: Order,
+    height: u64,
+    // next changelog entry, if already read
+    changed: Option<Vec<u8>>,
+    exhausted: bool,
+    value_type: PhantomData<V>,
+}
+
+impl<'c, V, F> HistoryIter<'c, V, F>
+where
+    V: DeserializeOwned,
+{
+    /// Reads the next entry of the changelog, and moves the range past all of its heights
+    fn next_changed(&mut self) -> Option<Vec<u8>> {
+        let (key, _) = range_with_prefix(
+            self.store,
+            &self.changelog,
+            Some(RawBound::Inclusive(self.start.clone())),
+            self.end.clone().map(RawBound::Exclusive),
+            self.order,
+        )
+        .next()?;
+        let entry = key[..key.len() - 8].to_vec();
+        match self.order {
+            Order::Ascending => {
+                self.start = extend_one_byte(&concat(&entry, &u64::MAX.to_be_bytes()))
+            }
+            Order::Descending => self.end = Some(entry.clone()),
+        }
+        Some(entry)
+    }
+
+    /// Returns the first change of the entry at or after the height we look at, if any
+    fn change_since(&self, entry: &[u8]) -> StdResult<Option<Option<V>>> {
+        range_with_prefix(
+            self.store,
+            &concat(&self.changelog, entry),
+            Some(RawBound::Inclusive(self.height.to_be_bytes().to_vec())),
+            None,
+            Order::Ascending,
+        )
+        .next()
+        .map(|(_, v)| from_slice::<ChangeSet<V>>(&v).map(|change| change.old))
+        .transpose()
+    }
+}
+
+impl<'c, V, F> Iterator for HistoryIter<'c, V, F>
+where
+    V: DeserializeOwned,
+    F: Fn(&[u8], &[u8]) -> StdResult<V>,
+{
+    type Item = StdResult<(Vec<u8>, V)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.changed.is_none() && !self.exhausted {
+                self.changed = self.next_changed();
+                self.exhausted = self.changed.is_none();
+            }
+            let changed_key = self.changed.as_deref().map(unescape);
+            let (take_entry, take_changed) = match (self.entries.peek(), &changed_key) {
+                (None, None) => return None,
+                (Some(_), None) => (true, false),
+                (None, Some(_)) => (false, true),
+                (Some((key, _)), Some(changed)) => match (key.cmp(changed), self.order) {
+                    (Ordering::Equal, _) => (true, true),
+                    (Ordering::Less, Order::Ascending) => (true, false),
+                    (Ordering::Greater, Order::Descending) => (true, false),
+                    _ => (false, true),
+                },
+            };
+            let entry = if take_entry {
+                self.entries.next()
+            } else {
+                None
+            };
+
+            if take_changed {
+                let changed = self.changed.take().unwrap();
+                match self.change_since(&changed) {
+                    Err(err) => return Some(Err(err)),
+                    // removed or replaced since then
+                    Ok(Some(Some(old))) => return Some(Ok((changed_key.unwrap(), old))),
+                    // created since then
+                    Ok(Some(None)) => continue,
+                    // not changed since then, so the current entry (if any) is the right one
+                    Ok(None) => {}
+                }
+            }
+            if let Some((key, value)) = entry {
+                return Some((self.current)(&key, &value).map(|value| (key, value)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn escaped_entries_keep_key_order() {
+        let mut keys: Vec<&[u8]> = vec![
+            b"",
+            b"\x00",
+            b"\x00\x00",
+            b"\x00\x01",
+            b"\x01",
+            b"a",
+            b"a\x00",
+            b"a\x00b",
+            b"ab",
+            b"\xff",
+        ];
+        keys.sort_unstable();
+        let entries: Vec<_> = keys.iter().map(|k| escape(k)).collect();
+        let mut sorted = entries.clone();
+        sorted.sort_unstable();
+        assert_eq!(entries, sorted);
+
+        for (key, entry) in keys.iter().zip(&entries) {
+            assert_eq!(&unescape(entry), key);
+            // no entry is a prefix of another one
+            assert_eq!(entries.iter().filter(|e| e.starts_with(entry)).count(), 1);
+            // the escaped prefix of a key splits the entries where the key does
+            let prefix = escape_prefix(key);
+            for (other, other_entry) in keys.iter().zip(&entries) {
+                assert_eq!(other >= key, other_entry >= &prefix);
+            }
+        }
+    }
+}
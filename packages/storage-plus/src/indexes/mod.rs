@@ -1,15 +1,26 @@
 // this module requires iterator to be useful at all
 #![cfg(feature = "iterator")]
+mod history;
 mod multi;
+mod snapshot_multi;
+mod snapshot_unique;
 mod unique;
 
 pub use multi::MultiIndex;
+pub use snapshot_multi::SnapshotMultiIndex;
+pub use snapshot_unique::SnapshotUniqueIndex;
 pub use unique::UniqueIndex;
 
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 
-use cosmwasm_std::{StdResult, Storage};
+use cosmwasm_std::{Order, StdError, StdResult, Storage};
+
+use crate::codec::Codec;
+use crate::helpers::namespaces_with_key;
+use crate::prefix::range_with_prefix;
+use crate::snapshot::PruneProgress;
+use crate::RawBound;
 
 // Note: we cannot store traits with generic functions inside `Box<dyn Index>`,
 // so I pull S: Storage to a top-level
@@ -19,6 +30,89 @@ where
 {
     fn save(&self, store: &mut dyn Storage, pk: &[u8], data: &T) -> StdResult<()>;
     fn remove(&self, store: &mut dyn Storage, pk: &[u8], old_data: &T) -> StdResult<()>;
+
+    /// Called when building the index from existing data, instead of `save`. Unlike `save`, it
+    /// must succeed if `pk` is already indexed with `data`, so an interrupted build can be run
+    /// again. Indexes where saving twice is harmless just `save`.
+    fn reindex(&self, store: &mut dyn Storage, pk: &[u8], data: &T) -> StdResult<()> {
+        self.save(store, pk, data)
+    }
+
+    /// Called by snapshot maps instead of `save`, with the checkpoint (height, or seconds for
+    /// time-based maps) the change happens at. Indexes that keep no history just `save`.
+    fn save_at(&self, store: &mut dyn Storage, pk: &[u8], data: &T, _at: u64) -> StdResult<()> {
+        self.save(store, pk, data)
+    }
+
+    /// Counterpart of `save_at` for `remove`.
+    fn remove_at(
+        &self,
+        store: &mut dyn Storage,
+        pk: &[u8],
+        old_data: &T,
+        _at: u64,
+    ) -> StdResult<()> {
+        self.remove(store, pk, old_data)
+    }
+
+    /// Called by `IndexedSnapshotMap::prune`, to remove the history of the index below the
+    /// given checkpoint. Indexes that keep no history have nothing to do.
+    fn prune(
+        &self,
+        _store: &mut dyn Storage,
+        _below: u64,
+        _limit: usize,
+    ) -> StdResult<PruneProgress> {
+        Ok(PruneProgress {
+            removed: 0,
+            done: true,
+        })
+    }
+}
+
+/// Adds the entries of the primary map (under `pk_namespace`) that come after `start_after`
+/// to `indexes`, at most `limit` of them. Returns the primary key of the last entry processed,
+/// to pass as `start_after` for the next batch, or `None` once all entries are indexed.
+/// Fails if `limit` is zero, as no batch would ever make progress.
+pub(crate) fn index_batch<T, C>(
+    store: &mut dyn Storage,
+    pk_namespace: &[u8],
+    indexes: &[&dyn Index<T>],
+    start_after: Option<Vec<u8>>,
+    limit: usize,
+) -> StdResult<Option<Vec<u8>>>
+where
+    T: Serialize + DeserializeOwned + Clone,
+    C: Codec<T>,
+{
+    if limit == 0 {
+        return Err(StdError::generic_err(
+            "Index batch limit must be greater than zero",
+        ));
+    }
+    let prefix = namespaces_with_key(&[pk_namespace], b"");
+    // read one more entry, to know whether there is anything left after this batch
+    let mut batch: Vec<_> = range_with_prefix(
+        store,
+        &prefix,
+        start_after.map(RawBound::Exclusive),
+        None,
+        Order::Ascending,
+    )
+    .take(limit.saturating_add(1))
+    .collect();
+    let more = batch.len() > limit;
+    batch.truncate(limit);
+
+    let mut last = None;
+    for (pk, value) in batch {
+        let data = C::decode(&value)?;
+        for index in indexes {
+            index.reindex(store, &pk, &data)?;
+        }
+        last = Some(pk);
+    }
+    Ok(if more { last } else { None })
 }
 
 #[cfg(test)]
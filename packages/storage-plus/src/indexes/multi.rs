@@ -7,6 +7,7 @@ use serde::Serialize;
 use cosmwasm_std::{from_slice, Order, Record, StdError, StdResult, Storage};
 
 use crate::bound::PrefixBound;
+use crate::codec::{Codec, JsonCodec};
 use crate::de::KeyDeserialize;
 use crate::helpers::namespaces_with_key;
 use crate::iter_helpers::deserialize_kv;
@@ -27,13 +28,16 @@ use std::marker::PhantomData;
 /// more important, type-safe bound key type.
 /// This type must match the encompassing `IndexedMap` primary key type,
 /// or its owned variant.
-pub struct MultiIndex<'a, IK, T, PK> {
+///
+/// The C type is the codec the values are stored with in the main map, so it must match the one
+/// of the encompassing `IndexedMap`, too.
+pub struct MultiIndex<'a, IK, T, PK, C = JsonCodec> {
     index: fn(&T) -> IK,
     idx_namespace: &'a [u8],
     // note, we collapse the ik - combining everything under the namespace - and concatenating the pk
     idx_map: Map<'a, Vec<u8>, u32>,
     pk_namespace: &'a [u8],
-    phantom: PhantomData<PK>,
+    phantom: PhantomData<(PK, C)>,
 }
 
 impl<'a, IK, T, PK> MultiIndex<'a, IK, T, PK>
@@ -76,7 +80,20 @@ where
     }
 }
 
-fn deserialize_multi_v<T: DeserializeOwned>(
+impl<'a, IK, T, PK, C> MultiIndex<'a, IK, T, PK, C> {
+    /// Sets the codec the values are stored with in the main map
+    pub fn with_codec<D>(self) -> MultiIndex<'a, IK, T, PK, D> {
+        MultiIndex {
+            index: self.index,
+            idx_namespace: self.idx_namespace,
+            idx_map: self.idx_map,
+            pk_namespace: self.pk_namespace,
+            phantom: PhantomData,
+        }
+    }
+}
+
+fn deserialize_multi_v<T, C: Codec<T>>(
     store: &dyn Storage,
     pk_namespace: &[u8],
     kv: Record,
@@ -95,12 +112,12 @@ fn deserialize_multi_v<T: DeserializeOwned>(
     let v = store
         .get(&full_key)
         .ok_or_else(|| StdError::generic_err("pk not found"))?;
-    let v = from_slice::<T>(&v)?;
+    let v = C::decode(&v)?;
 
     Ok((pk.to_vec(), v))
 }
 
-fn deserialize_multi_kv<K: KeyDeserialize, T: DeserializeOwned>(
+fn deserialize_multi_kv<K: KeyDeserialize, T, C: Codec<T>>(
     store: &dyn Storage,
     pk_namespace: &[u8],
     kv: Record,
@@ -119,13 +136,13 @@ fn deserialize_multi_kv<K: KeyDeserialize, T: DeserializeOwned>(
     let v = store
         .get(&full_key)
         .ok_or_else(|| StdError::generic_err("pk not found"))?;
-    let v = from_slice::<T>(&v)?;
+    let v = C::decode(&v)?;
 
     // We return deserialized `pk` here for consistency
     Ok((K::from_slice(pk)?, v))
 }
 
-impl<'a, IK, T, PK> Index<T> for MultiIndex<'a, IK, T, PK>
+impl<'a, IK, T, PK, C> Index<T> for MultiIndex<'a, IK, T, PK, C>
 where
     T: Serialize + DeserializeOwned + Clone,
     IK: PrimaryKey<'a>,
@@ -142,27 +159,29 @@ where
     }
 }
 
-impl<'a, IK, T, PK> MultiIndex<'a, IK, T, PK>
+impl<'a, IK, T, PK, C> MultiIndex<'a, IK, T, PK, C>
 where
     T: Serialize + DeserializeOwned + Clone,
     IK: PrimaryKey<'a> + Prefixer<'a>,
+    C: Codec<T>,
 {
     fn no_prefix_raw(&self) -> Prefix<Vec<u8>, T, (IK, PK)> {
         Prefix::with_deserialization_functions(
             self.idx_namespace,
             &[],
             self.pk_namespace,
-            deserialize_multi_v,
-            deserialize_multi_v,
+            deserialize_multi_v::<T, C>,
+            deserialize_multi_v::<T, C>,
         )
     }
 }
 
-impl<'a, IK, T, PK> MultiIndex<'a, IK, T, PK>
+impl<'a, IK, T, PK, C> MultiIndex<'a, IK, T, PK, C>
 where
     PK: PrimaryKey<'a> + KeyDeserialize,
     T: Serialize + DeserializeOwned + Clone,
     IK: PrimaryKey<'a> + Prefixer<'a>,
+    C: Codec<T>,
 {
     pub fn index_key(&self, k: IK) -> Vec<u8> {
         k.joined_extra_key(b"")
@@ -192,11 +211,12 @@ where
 }
 
 // short-cut for simple keys, rather than .prefix(()).range_raw(...)
-impl<'a, IK, T, PK> MultiIndex<'a, IK, T, PK>
+impl<'a, IK, T, PK, C> MultiIndex<'a, IK, T, PK, C>
 where
     T: Serialize + DeserializeOwned + Clone,
     IK: PrimaryKey<'a> + Prefixer<'a> + KeyDeserialize,
     PK: PrimaryKey<'a> + KeyDeserialize,
+    C: Codec<T>,
 {
     // I would prefer not to copy code from Prefix, but no other way
     // with lifetimes (create Prefix inside function and return ref = no no)
@@ -241,25 +261,26 @@ where
         'a: 'c,
     {
         let mapped = namespaced_prefix_range(store, self.idx_namespace, min, max, order)
-            .map(move |kv| (deserialize_multi_v)(store, self.pk_namespace, kv));
+            .map(move |kv| deserialize_multi_v::<T, C>(store, self.pk_namespace, kv));
         Box::new(mapped)
     }
 }
 
 #[cfg(feature = "iterator")]
-impl<'a, IK, T, PK> MultiIndex<'a, IK, T, PK>
+impl<'a, IK, T, PK, C> MultiIndex<'a, IK, T, PK, C>
 where
     PK: PrimaryKey<'a> + KeyDeserialize,
     T: Serialize + DeserializeOwned + Clone,
     IK: PrimaryKey<'a> + Prefixer<'a>,
+    C: Codec<T>,
 {
     pub fn prefix(&self, p: IK) -> Prefix<PK, T, PK> {
         Prefix::with_deserialization_functions(
             self.idx_namespace,
             &p.prefix(),
             self.pk_namespace,
-            deserialize_multi_kv::<PK, T>,
-            deserialize_multi_v,
+            deserialize_multi_kv::<PK, T, C>,
+            deserialize_multi_v::<T, C>,
         )
     }
 
@@ -268,18 +289,19 @@ where
             self.idx_namespace,
             &p.prefix(),
             self.pk_namespace,
-            deserialize_multi_kv::<PK, T>,
-            deserialize_multi_v,
+            deserialize_multi_kv::<PK, T, C>,
+            deserialize_multi_v::<T, C>,
         )
     }
 }
 
 #[cfg(feature = "iterator")]
-impl<'a, IK, T, PK> MultiIndex<'a, IK, T, PK>
+impl<'a, IK, T, PK, C> MultiIndex<'a, IK, T, PK, C>
 where
     PK: PrimaryKey<'a> + KeyDeserialize,
     T: Serialize + DeserializeOwned + Clone,
     IK: PrimaryKey<'a> + KeyDeserialize + Prefixer<'a>,
+    C: Codec<T>,
 {
     /// While `range` over a `prefix` fixes the prefix to one element and iterates over the
     /// remaining, `prefix_range` accepts bounds for the lowest and highest elements of the
@@ -339,8 +361,8 @@ where
             self.idx_namespace,
             &[],
             self.pk_namespace,
-            deserialize_multi_kv::<PK, T>,
-            deserialize_multi_v,
+            deserialize_multi_kv::<PK, T, C>,
+            deserialize_multi_v::<T, C>,
         )
     }
 }
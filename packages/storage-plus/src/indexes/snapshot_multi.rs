@@ -0,0 +1,297 @@
+// this module requires iterator to be useful at all
+#![cfg(feature = "iterator")]
+
+use std::ops::Deref;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{from_slice, Order, StdError, StdResult, Storage};
+
+use crate::bound::PrefixBound;
+use crate::codec::{Codec, JsonCodec};
+use crate::de::KeyDeserialize;
+use crate::helpers::namespaces_with_key;
+use crate::indexes::history::{range_at_height, write_change, KeyRange};
+use crate::snapshot::{PruneProgress, Retention, Snapshot};
+use crate::{Bound, Index, MultiIndex, Prefixer, PrimaryKey, Strategy};
+
+/// What an index entry pointed to, as stored in the changelog
+#[derive(Deserialize, Serialize, Clone)]
+struct MultiRef<T> {
+    pk_len: u32,
+    value: T,
+}
+
+/// SnapshotMultiIndex is a `MultiIndex` that also remembers its past entries, so it can be
+/// queried at an earlier height when used within an `IndexedSnapshotMap`.
+///
+/// Besides the regular index, it keeps a changelog like the one of `SnapshotMap`, with one
+/// entry per (index key, height) holding what the index key pointed to before that height.
+/// Regular queries go to the wrapped `MultiIndex` (through `Deref`), the `*_at_height`
+/// variants also look at the changelog.
+///
+/// Pass it the checkpoints namespace and strategy of the map, so it records the same heights.
+/// Changes are only recorded from the moment the index is in use: entries existing before
+/// that have no history. The changelog is always stored as JSON, whatever the codec `C` of the
+/// main map.
+pub struct SnapshotMultiIndex<'a, IK, T, PK, C = JsonCodec> {
+    index: fn(&T) -> IK,
+    inner: MultiIndex<'a, IK, T, PK, C>,
+    idx_namespace: &'a [u8],
+    pk_namespace: &'a [u8],
+    snapshots: Snapshot<'a, Vec<u8>, MultiRef<T>>,
+}
+
+impl<'a, IK, T, PK> SnapshotMultiIndex<'a, IK, T, PK>
+where
+    T: Serialize + DeserializeOwned + Clone,
+{
+    /// Create a new SnapshotMultiIndex
+    ///
+    /// idx_fn - lambda creating index key from value
+    /// pk_namespace - prefix for the primary key
+    /// idx_namespace - prefix for the index value
+    /// checkpoints - checkpoints namespace of the map
+    /// changelog - prefix for the history of the index entries
+    /// strategy - snapshot strategy of the map
+    ///
+    /// ## Example:
+    ///
+    /// ```rust
+    /// use cw_storage_plus::{SnapshotMultiIndex, Strategy};
+    /// use serde::{Deserialize, Serialize};
+    ///
+    /// #[derive(Deserialize, Serialize, Clone)]
+    /// struct Data {
+    ///     pub name: String,
+    ///     pub age: u32,
+    /// }
+    ///
+    /// let index: SnapshotMultiIndex<_, _, String> = SnapshotMultiIndex::new(
+    ///     |d: &Data| d.age,
+    ///     "age",
+    ///     "age__owner",
+    ///     "age__checkpoints",
+    ///     "age__owner__changelog",
+    ///     Strategy::EveryBlock,
+    /// );
+    /// ```
+    pub fn new(
+        idx_fn: fn(&T) -> IK,
+        pk_namespace: &'a str,
+        idx_namespace: &'a str,
+        checkpoints: &'a str,
+        changelog: &'a str,
+        strategy: Strategy,
+    ) -> Self {
+        SnapshotMultiIndex {
+            index: idx_fn,
+            inner: MultiIndex::new(idx_fn, pk_namespace, idx_namespace),
+            idx_namespace: idx_namespace.as_bytes(),
+            pk_namespace: pk_namespace.as_bytes(),
+            snapshots: Snapshot::new(checkpoints, changelog, strategy),
+        }
+    }
+}
+
+impl<'a, IK, T, PK, C> SnapshotMultiIndex<'a, IK, T, PK, C> {
+    /// Sets the codec the values are stored with in the main map
+    pub fn with_codec<D>(self) -> SnapshotMultiIndex<'a, IK, T, PK, D> {
+        SnapshotMultiIndex {
+            index: self.index,
+            inner: self.inner.with_codec(),
+            idx_namespace: self.idx_namespace,
+            pk_namespace: self.pk_namespace,
+            snapshots: self.snapshots,
+        }
+    }
+
+    /// Enables pruning of the index history, see `SnapshotMap::with_pruning`.
+    /// Required if the map is pruned.
+    pub fn with_pruning(self, namespace: &'a str, retention: Retention) -> Self {
+        SnapshotMultiIndex {
+            snapshots: self.snapshots.with_pruning(namespace, retention),
+            ..self
+        }
+    }
+}
+
+impl<'a, IK, T, PK, C> Deref for SnapshotMultiIndex<'a, IK, T, PK, C> {
+    type Target = MultiIndex<'a, IK, T, PK, C>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<'a, IK, T, PK, C> Index<T> for SnapshotMultiIndex<'a, IK, T, PK, C>
+where
+    T: Serialize + DeserializeOwned + Clone,
+    IK: PrimaryKey<'a>,
+{
+    fn save(&self, store: &mut dyn Storage, pk: &[u8], data: &T) -> StdResult<()> {
+        self.inner.save(store, pk, data)
+    }
+
+    fn remove(&self, store: &mut dyn Storage, pk: &[u8], old_data: &T) -> StdResult<()> {
+        self.inner.remove(store, pk, old_data)
+    }
+
+    fn save_at(&self, store: &mut dyn Storage, pk: &[u8], data: &T, at: u64) -> StdResult<()> {
+        let idx = (self.index)(data).joined_extra_key(pk);
+        let key = namespaces_with_key(&[self.idx_namespace], &idx);
+        // an entry that is already there points to the same pk, and to the same value
+        let old = store.get(&key).map(|_| MultiRef {
+            pk_len: pk.len() as u32,
+            value: data.clone(),
+        });
+        write_change(store, &self.snapshots, &idx, at, old)?;
+        self.inner.save(store, pk, data)
+    }
+
+    fn remove_at(
+        &self,
+        store: &mut dyn Storage,
+        pk: &[u8],
+        old_data: &T,
+        at: u64,
+    ) -> StdResult<()> {
+        let idx = (self.index)(old_data).joined_extra_key(pk);
+        let old = MultiRef {
+            pk_len: pk.len() as u32,
+            value: old_data.clone(),
+        };
+        write_change(store, &self.snapshots, &idx, at, Some(old))?;
+        self.inner.remove(store, pk, old_data)
+    }
+
+    fn prune(&self, store: &mut dyn Storage, below: u64, limit: usize) -> StdResult<PruneProgress> {
+        self.snapshots.prune(store, below, limit)
+    }
+}
+
+impl<'a, IK, T, PK, C> SnapshotMultiIndex<'a, IK, T, PK, C>
+where
+    T: Serialize + DeserializeOwned + Clone,
+    C: Codec<T>,
+{
+    fn history<'c, K: KeyDeserialize>(
+        &self,
+        store: &'c dyn Storage,
+        range: KeyRange,
+        order: Order,
+        height: u64,
+    ) -> Box<dyn Iterator<Item = StdResult<(K::Output, T)>> + 'c>
+    where
+        T: 'c,
+        K::Output: 'static,
+    {
+        let pk_namespace = self.pk_namespace.to_vec();
+        let current = move |key: &[u8], pk_len: &[u8]| {
+            let pk_len = from_slice::<u32>(pk_len)?;
+            let pk = &key[key.len() - pk_len as usize..];
+            let value = store
+                .get(&namespaces_with_key(&[&pk_namespace], pk))
+                .ok_or_else(|| StdError::generic_err("pk not found"))?;
+            Ok(MultiRef {
+                pk_len,
+                value: C::decode(&value)?,
+            })
+        };
+        let namespace = namespaces_with_key(&[self.idx_namespace], b"");
+        let mapped = range_at_height(
+            store,
+            &self.snapshots,
+            &namespace,
+            range,
+            order,
+            height,
+            current,
+        )
+        .map(|kv| {
+            let (key, entry) = kv?;
+            // Recover pk from last part of k
+            let offset = key.len() - entry.pk_len as usize;
+            Ok((K::from_slice(&key[offset..])?, entry.value))
+        });
+        Box::new(mapped)
+    }
+}
+
+impl<'a, IK, T, PK, C> SnapshotMultiIndex<'a, IK, T, PK, C>
+where
+    PK: PrimaryKey<'a> + KeyDeserialize,
+    T: Serialize + DeserializeOwned + Clone,
+    IK: PrimaryKey<'a> + KeyDeserialize + Prefixer<'a>,
+    C: Codec<T>,
+{
+    /// Like `prefix(p).range(...)`, but returns the entries (and values) as they were at the
+    /// beginning of `height`.
+    pub fn prefix_at_height<'c>(
+        &self,
+        store: &'c dyn Storage,
+        p: IK,
+        min: Option<Bound<'a, PK>>,
+        max: Option<Bound<'a, PK>>,
+        order: Order,
+        height: u64,
+    ) -> Box<dyn Iterator<Item = StdResult<(PK::Output, T)>> + 'c>
+    where
+        T: 'c,
+        PK::Output: 'static,
+    {
+        let range = KeyRange::new(
+            &p.joined_prefix(),
+            min.map(|b| b.to_raw_bound()),
+            max.map(|b| b.to_raw_bound()),
+        );
+        self.history::<PK>(store, range, order, height)
+    }
+
+    /// Like `range`, but returns the entries (and values) as they were at the beginning
+    /// of `height`.
+    pub fn range_at_height<'c>(
+        &self,
+        store: &'c dyn Storage,
+        min: Option<Bound<'a, (IK, PK)>>,
+        max: Option<Bound<'a, (IK, PK)>>,
+        order: Order,
+        height: u64,
+    ) -> Box<dyn Iterator<Item = StdResult<(PK::Output, T)>> + 'c>
+    where
+        T: 'c,
+        PK::Output: 'static,
+    {
+        let range = KeyRange::new(
+            &[],
+            min.map(|b| b.to_raw_bound()),
+            max.map(|b| b.to_raw_bound()),
+        );
+        self.history::<PK>(store, range, order, height)
+    }
+
+    /// Like `prefix_range`, but returns the entries (and values) as they were at the beginning
+    /// of `height`.
+    pub fn prefix_range_at_height<'c>(
+        &self,
+        store: &'c dyn Storage,
+        min: Option<PrefixBound<'a, IK>>,
+        max: Option<PrefixBound<'a, IK>>,
+        order: Order,
+        height: u64,
+    ) -> Box<dyn Iterator<Item = StdResult<(PK::Output, T)>> + 'c>
+    where
+        T: 'c,
+        'a: 'c,
+        IK: 'c,
+        PK: 'c,
+        PK::Output: 'static,
+    {
+        let range = KeyRange::with_prefix_bounds(
+            min.map(|b| b.to_raw_bound()),
+            max.map(|b| b.to_raw_bound()),
+        );
+        self.history::<PK>(store, range, order, height)
+    }
+}
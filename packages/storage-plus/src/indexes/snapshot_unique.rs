@@ -0,0 +1,249 @@
+// this module requires iterator to be useful at all
+#![cfg(feature = "iterator")]
+
+use std::ops::Deref;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use cosmwasm_std::{from_slice, Order, Record, StdResult, Storage};
+
+use crate::bound::RawBound;
+use crate::de::KeyDeserialize;
+use crate::helpers::namespaces_with_key;
+use crate::indexes::history::{range_at_height, write_change, KeyRange};
+use crate::indexes::unique::UniqueRef;
+use crate::snapshot::{PruneProgress, Retention, Snapshot};
+use crate::{Bound, Index, Prefixer, PrimaryKey, Strategy, UniqueIndex};
+
+/// SnapshotUniqueIndex is a `UniqueIndex` that also remembers its past entries, so it can be
+/// queried at an earlier height when used within an `IndexedSnapshotMap`.
+///
+/// Besides the regular index, it keeps a changelog like the one of `SnapshotMap`, with one
+/// entry per (index value, height) holding the pk and value it pointed to before that height.
+/// Regular queries go to the wrapped `UniqueIndex` (through `Deref`), the `*_at_height`
+/// variants also look at the changelog.
+///
+/// As for `SnapshotMultiIndex`, pass it the checkpoints namespace and strategy of the map.
+/// Changes are recorded starting from the moment the index is in use.
+pub struct SnapshotUniqueIndex<'a, IK, T, PK = ()> {
+    index: fn(&T) -> IK,
+    inner: UniqueIndex<'a, IK, T, PK>,
+    idx_namespace: &'a [u8],
+    snapshots: Snapshot<'a, Vec<u8>, UniqueRef<T>>,
+}
+
+impl<'a, IK, T, PK> SnapshotUniqueIndex<'a, IK, T, PK> {
+    /// Create a new SnapshotUniqueIndex
+    ///
+    /// idx_fn - lambda creating index key from index value
+    /// idx_namespace - prefix for the index value
+    /// checkpoints - checkpoints namespace of the map
+    /// changelog - prefix for the history of the index entries
+    /// strategy - snapshot strategy of the map
+    ///
+    /// ## Example:
+    ///
+    /// ```rust
+    /// use cw_storage_plus::{SnapshotUniqueIndex, Strategy};
+    ///
+    /// struct Data {
+    ///     pub name: String,
+    ///     pub age: u32,
+    /// }
+    ///
+    /// SnapshotUniqueIndex::<_, _, ()>::new(
+    ///     |d: &Data| d.age,
+    ///     "data__age",
+    ///     "data__checkpoints",
+    ///     "data__age__changelog",
+    ///     Strategy::EveryBlock,
+    /// );
+    /// ```
+    pub fn new(
+        idx_fn: fn(&T) -> IK,
+        idx_namespace: &'a str,
+        checkpoints: &'a str,
+        changelog: &'a str,
+        strategy: Strategy,
+    ) -> Self {
+        SnapshotUniqueIndex {
+            index: idx_fn,
+            inner: UniqueIndex::new(idx_fn, idx_namespace),
+            idx_namespace: idx_namespace.as_bytes(),
+            snapshots: Snapshot::new(checkpoints, changelog, strategy),
+        }
+    }
+
+    /// Enables pruning of the index history, see `SnapshotMap::with_pruning`.
+    /// Required if the map is pruned.
+    pub fn with_pruning(self, namespace: &'a str, retention: Retention) -> Self {
+        SnapshotUniqueIndex {
+            snapshots: self.snapshots.with_pruning(namespace, retention),
+            ..self
+        }
+    }
+}
+
+impl<'a, IK, T, PK> Deref for SnapshotUniqueIndex<'a, IK, T, PK> {
+    type Target = UniqueIndex<'a, IK, T, PK>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<'a, IK, T, PK> Index<T> for SnapshotUniqueIndex<'a, IK, T, PK>
+where
+    T: Serialize + DeserializeOwned + Clone,
+    IK: PrimaryKey<'a>,
+{
+    fn save(&self, store: &mut dyn Storage, pk: &[u8], data: &T) -> StdResult<()> {
+        self.inner.save(store, pk, data)
+    }
+
+    fn remove(&self, store: &mut dyn Storage, pk: &[u8], old_data: &T) -> StdResult<()> {
+        self.inner.remove(store, pk, old_data)
+    }
+
+    fn reindex(&self, store: &mut dyn Storage, pk: &[u8], data: &T) -> StdResult<()> {
+        self.inner.reindex(store, pk, data)
+    }
+
+    fn save_at(&self, store: &mut dyn Storage, pk: &[u8], data: &T, at: u64) -> StdResult<()> {
+        let idx = (self.index)(data).joined_key();
+        let old = store
+            .get(&namespaces_with_key(&[self.idx_namespace], &idx))
+            .map(|v| from_slice::<UniqueRef<T>>(&v))
+            .transpose()?;
+        // checks the unique constraint before touching the history
+        self.inner.save(store, pk, data)?;
+        write_change(store, &self.snapshots, &idx, at, old)
+    }
+
+    fn remove_at(
+        &self,
+        store: &mut dyn Storage,
+        pk: &[u8],
+        old_data: &T,
+        at: u64,
+    ) -> StdResult<()> {
+        let idx = (self.index)(old_data).joined_key();
+        let old = UniqueRef {
+            pk: pk.into(),
+            value: old_data.clone(),
+        };
+        write_change(store, &self.snapshots, &idx, at, Some(old))?;
+        self.inner.remove(store, pk, old_data)
+    }
+
+    fn prune(&self, store: &mut dyn Storage, below: u64, limit: usize) -> StdResult<PruneProgress> {
+        self.snapshots.prune(store, below, limit)
+    }
+}
+
+impl<'a, IK, T, PK> SnapshotUniqueIndex<'a, IK, T, PK>
+where
+    T: Serialize + DeserializeOwned + Clone,
+    IK: PrimaryKey<'a>,
+{
+    /// Like `item`, but returns the entry as it was at the beginning of `height`.
+    pub fn item_at_height(
+        &self,
+        store: &dyn Storage,
+        idx: IK,
+        height: u64,
+    ) -> StdResult<Option<Record<T>>> {
+        let key = idx.joined_key();
+        let range = KeyRange::new(&key, None, Some(RawBound::Exclusive(vec![0])));
+        let entry = range_at_height(
+            store,
+            &self.snapshots,
+            &namespaces_with_key(&[self.idx_namespace], b""),
+            range,
+            Order::Ascending,
+            height,
+            |_, v| from_slice(v),
+        )
+        .next()
+        .transpose()?;
+        Ok(entry.map(|(_, entry)| (entry.pk.into(), entry.value)))
+    }
+}
+
+impl<'a, IK, T, PK> SnapshotUniqueIndex<'a, IK, T, PK>
+where
+    PK: PrimaryKey<'a> + KeyDeserialize,
+    T: Serialize + DeserializeOwned + Clone,
+    IK: PrimaryKey<'a>,
+{
+    /// Like `range`, but returns the entries as they were at the beginning of `height`.
+    pub fn range_at_height<'c>(
+        &self,
+        store: &'c dyn Storage,
+        min: Option<Bound<'a, IK>>,
+        max: Option<Bound<'a, IK>>,
+        order: Order,
+        height: u64,
+    ) -> Box<dyn Iterator<Item = StdResult<(PK::Output, T)>> + 'c>
+    where
+        T: 'c,
+        PK::Output: 'static,
+    {
+        self.range_history(store, vec![], min, max, order, height)
+    }
+
+    /// Like `prefix(p).range(...)`, but returns the entries as they were at the beginning
+    /// of `height`.
+    pub fn prefix_at_height<'c>(
+        &self,
+        store: &'c dyn Storage,
+        p: IK::Prefix,
+        min: Option<Bound<'a, IK::Suffix>>,
+        max: Option<Bound<'a, IK::Suffix>>,
+        order: Order,
+        height: u64,
+    ) -> Box<dyn Iterator<Item = StdResult<(PK::Output, T)>> + 'c>
+    where
+        T: 'c,
+        PK::Output: 'static,
+        IK::Suffix: PrimaryKey<'a>,
+    {
+        self.range_history(store, p.joined_prefix(), min, max, order, height)
+    }
+
+    fn range_history<'c, B: PrimaryKey<'a>>(
+        &self,
+        store: &'c dyn Storage,
+        prefix: Vec<u8>,
+        min: Option<Bound<'a, B>>,
+        max: Option<Bound<'a, B>>,
+        order: Order,
+        height: u64,
+    ) -> Box<dyn Iterator<Item = StdResult<(PK::Output, T)>> + 'c>
+    where
+        T: 'c,
+        PK::Output: 'static,
+    {
+        let range = KeyRange::new(
+            &prefix,
+            min.map(|b| b.to_raw_bound()),
+            max.map(|b| b.to_raw_bound()),
+        );
+        let namespace = namespaces_with_key(&[self.idx_namespace], b"");
+        let mapped = range_at_height(
+            store,
+            &self.snapshots,
+            &namespace,
+            range,
+            order,
+            height,
+            |_, v| from_slice(v),
+        )
+        .map(|kv| {
+            let (_, UniqueRef { pk, value }) = kv?;
+            Ok((PK::from_vec(pk.0)?, value))
+        });
+        Box::new(mapped)
+    }
+}
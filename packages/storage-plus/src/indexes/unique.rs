@@ -16,11 +16,11 @@ use crate::prefix::namespaced_prefix_range;
 use crate::{Bound, Index, Prefix, Prefixer, PrimaryKey};
 
 /// UniqueRef stores Binary(Vec[u8]) representation of private key and index value
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, Clone)]
 pub(crate) struct UniqueRef<T> {
     // note, we collapse the pk - combining everything under the namespace - even if it is composite
-    pk: Binary,
-    value: T,
+    pub(crate) pk: Binary,
+    pub(crate) value: T,
 }
 
 /// UniqueIndex stores (namespace, index_name, idx_value) -> {key, value}
@@ -83,6 +83,24 @@ where
         Ok(())
     }
 
+    fn reindex(&self, store: &mut dyn Storage, pk: &[u8], data: &T) -> StdResult<()> {
+        let idx = (self.index)(data);
+        // error if this is already set for another primary key
+        self.idx_map
+            .update(store, idx, |existing| -> StdResult<_> {
+                match existing {
+                    Some(existing) if existing.pk != pk => {
+                        Err(StdError::generic_err("Violates unique constraint on index"))
+                    }
+                    _ => Ok(UniqueRef::<T> {
+                        pk: pk.into(),
+                        value: data.clone(),
+                    }),
+                }
+            })?;
+        Ok(())
+    }
+
     fn remove(&self, store: &mut dyn Storage, _pk: &[u8], old_data: &T) -> StdResult<()> {
         let idx = (self.index)(old_data);
         self.idx_map.remove(store, idx);
@@ -0,0 +1,330 @@
+// this module requires iterator to be useful at all
+#![cfg(feature = "iterator")]
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use cosmwasm_std::{to_vec, Record, StdError, StdResult};
+
+use crate::codec::Codec;
+use crate::de::KeyDeserialize;
+use crate::helpers::namespaces_with_key;
+use crate::item::Item;
+use crate::keys::PrimaryKey;
+use crate::map::Map;
+
+/// One storage record, decoded by a `StorageInspector`.
+/// Keys and values are rendered as JSON, whatever the codec they are stored with.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodedRecord {
+    Item {
+        namespace: String,
+        value: String,
+    },
+    Map {
+        namespace: String,
+        key: String,
+        value: String,
+    },
+    /// A record no registered `Item` or `Map` matches, or which could not be decoded.
+    /// `error` is set in the latter case.
+    Unknown {
+        key: Vec<u8>,
+        value: Vec<u8>,
+        error: Option<String>,
+    },
+}
+
+impl fmt::Display for DecodedRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodedRecord::Item { namespace, value } => write!(f, "{} = {}", namespace, value),
+            DecodedRecord::Map {
+                namespace,
+                key,
+                value,
+            } => write!(f, "{}[{}] = {}", namespace, key, value),
+            DecodedRecord::Unknown { key, value, error } => {
+                write!(f, "? {} = {}", escape(key), escape(value))?;
+                match error {
+                    Some(error) => write!(f, " ({})", error),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+// bytes as an ascii string, with everything non printable escaped
+fn escape(data: &[u8]) -> String {
+    data.iter()
+        .flat_map(|b| std::ascii::escape_default(*b))
+        .map(char::from)
+        .collect()
+}
+
+// renders any serializable value as JSON
+fn to_json<T: Serialize>(value: &T) -> StdResult<String> {
+    String::from_utf8(to_vec(value)?).map_err(StdError::invalid_utf8)
+}
+
+/// Describes how to decode the records of one `Item` or `Map`
+trait Descriptor {
+    /// Returns `None` if the record does not belong to this descriptor
+    fn decode(&self, key: &[u8], value: &[u8]) -> Option<StdResult<DecodedRecord>>;
+}
+
+struct ItemDescriptor<T, C> {
+    storage_key: Vec<u8>,
+    data_type: PhantomData<(T, C)>,
+}
+
+impl<T, C> Descriptor for ItemDescriptor<T, C>
+where
+    T: Serialize + DeserializeOwned,
+    C: Codec<T>,
+{
+    fn decode(&self, key: &[u8], value: &[u8]) -> Option<StdResult<DecodedRecord>> {
+        if key != self.storage_key.as_slice() {
+            return None;
+        }
+        Some(C::decode(value).and_then(|value| {
+            Ok(DecodedRecord::Item {
+                namespace: String::from_utf8_lossy(&self.storage_key).into_owned(),
+                value: to_json(&value)?,
+            })
+        }))
+    }
+}
+
+struct MapDescriptor<K, T, C> {
+    namespace: Vec<u8>,
+    prefix: Vec<u8>,
+    data_type: PhantomData<(K, T, C)>,
+}
+
+impl<K, T, C> Descriptor for MapDescriptor<K, T, C>
+where
+    K: KeyDeserialize,
+    K::Output: Serialize,
+    T: Serialize + DeserializeOwned,
+    C: Codec<T>,
+{
+    fn decode(&self, key: &[u8], value: &[u8]) -> Option<StdResult<DecodedRecord>> {
+        let key = key.strip_prefix(self.prefix.as_slice())?;
+        let decoded = K::from_slice(key).and_then(|key| {
+            Ok(DecodedRecord::Map {
+                namespace: String::from_utf8_lossy(&self.namespace).into_owned(),
+                key: to_json(&key)?,
+                value: to_json(&C::decode(value)?)?,
+            })
+        });
+        Some(decoded)
+    }
+}
+
+/// StorageInspector turns raw storage records (e.g. the state dump of a contract in tests) back
+/// into readable keys and values, given the `Item`s and `Map`s the contract stores them with.
+///
+/// ```rust
+/// use cosmwasm_std::testing::MockStorage;
+/// use cosmwasm_std::{Order, Storage};
+/// use cw_storage_plus::{Item, Map, StorageInspector};
+///
+/// const OWNER: Item<String> = Item::new("owner");
+/// const BALANCES: Map<&str, u64> = Map::new("balances");
+///
+/// let mut store = MockStorage::new();
+/// OWNER.save(&mut store, &"admin".to_string()).unwrap();
+/// BALANCES.save(&mut store, "alice", &100).unwrap();
+///
+/// let inspector = StorageInspector::new().item(&OWNER).map(&BALANCES);
+/// let records: Vec<_> = store.range(None, None, Order::Ascending).collect();
+/// assert_eq!(
+///     inspector.dump(&records),
+///     "balances[\"alice\"] = 100\nowner = \"admin\""
+/// );
+/// ```
+#[derive(Default)]
+pub struct StorageInspector<'a> {
+    descriptors: Vec<Box<dyn Descriptor + 'a>>,
+}
+
+impl<'a> StorageInspector<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers an `Item`, so its record gets decoded
+    pub fn item<T, C>(mut self, item: &Item<T, C>) -> Self
+    where
+        T: Serialize + DeserializeOwned + 'a,
+        C: Codec<T> + 'a,
+    {
+        self.descriptors.push(Box::new(ItemDescriptor::<T, C> {
+            storage_key: item.as_slice().to_vec(),
+            data_type: PhantomData,
+        }));
+        self
+    }
+
+    /// Registers a `Map`, so its records get decoded.
+    ///
+    /// Maps with a composite key are decoded as a whole, with keys rendered as JSON arrays.
+    /// Indexes of an `IndexedMap` can be registered as a `Map` over their namespace, with
+    /// `(index key, primary key)` keys.
+    pub fn map<'k, K, T, C>(mut self, map: &Map<'k, K, T, C>) -> Self
+    where
+        K: PrimaryKey<'k> + KeyDeserialize + 'a,
+        K::Output: Serialize,
+        T: Serialize + DeserializeOwned + 'a,
+        C: Codec<T> + 'a,
+    {
+        self.descriptors.push(Box::new(MapDescriptor::<K, T, C> {
+            namespace: map.namespace().to_vec(),
+            prefix: namespaces_with_key(&[map.namespace()], b""),
+            data_type: PhantomData,
+        }));
+        self
+    }
+
+    /// Decodes one record with the first registered `Item` or `Map` it belongs to
+    pub fn decode(&self, key: &[u8], value: &[u8]) -> DecodedRecord {
+        let decoded = self
+            .descriptors
+            .iter()
+            .find_map(|descriptor| descriptor.decode(key, value));
+        match decoded {
+            Some(Ok(record)) => record,
+            Some(Err(err)) => DecodedRecord::Unknown {
+                key: key.to_vec(),
+                value: value.to_vec(),
+                error: Some(err.to_string()),
+            },
+            None => DecodedRecord::Unknown {
+                key: key.to_vec(),
+                value: value.to_vec(),
+                error: None,
+            },
+        }
+    }
+
+    /// Decodes all the records, keeping their order
+    pub fn decode_all(&self, records: &[Record]) -> Vec<DecodedRecord> {
+        records.iter().map(|(k, v)| self.decode(k, v)).collect()
+    }
+
+    /// Renders the records, one per line
+    pub fn dump(&self, records: &[Record]) -> String {
+        self.decode_all(records)
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use cosmwasm_std::testing::MockStorage;
+    use cosmwasm_std::{Addr, Order, Storage, Uint128};
+    use serde::Deserialize;
+
+    // the inspector decodes each value with its own codec
+    #[cfg(feature = "bin-codec")]
+    type TotalCodec = crate::codec::BinCodec;
+    #[cfg(not(feature = "bin-codec"))]
+    type TotalCodec = crate::codec::JsonCodec;
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Config {
+        pub owner: Addr,
+        pub limit: u32,
+    }
+
+    const CONFIG: Item<Config> = Item::new("config");
+    const TOTAL: Item<Uint128, TotalCodec> = Item::new("total").with_codec();
+    const BALANCES: Map<&Addr, Uint128> = Map::new("balance");
+    const ALLOWANCES: Map<(&str, &str), u64> = Map::new("allowance");
+
+    fn records(store: &MockStorage) -> Vec<Record> {
+        store.range(None, None, Order::Ascending).collect()
+    }
+
+    #[test]
+    fn decodes_registered_records() {
+        let mut store = MockStorage::new();
+        let config = Config {
+            owner: Addr::unchecked("admin"),
+            limit: 5,
+        };
+        CONFIG.save(&mut store, &config).unwrap();
+        TOTAL.save(&mut store, &Uint128::new(300)).unwrap();
+        BALANCES
+            .save(&mut store, &Addr::unchecked("alice"), &Uint128::new(300))
+            .unwrap();
+        ALLOWANCES.save(&mut store, ("alice", "bob"), &7).unwrap();
+
+        let inspector = StorageInspector::new()
+            .item(&CONFIG)
+            .item(&TOTAL)
+            .map(&BALANCES)
+            .map(&ALLOWANCES);
+        let decoded = inspector.decode_all(&records(&store));
+        // map keys are ordered by the length of the namespace first
+        assert_eq!(
+            decoded,
+            vec![
+                DecodedRecord::Map {
+                    namespace: "balance".to_string(),
+                    key: r#""alice""#.to_string(),
+                    value: r#""300""#.to_string(),
+                },
+                DecodedRecord::Map {
+                    namespace: "allowance".to_string(),
+                    key: r#"["alice","bob"]"#.to_string(),
+                    value: "7".to_string(),
+                },
+                DecodedRecord::Item {
+                    namespace: "config".to_string(),
+                    value: r#"{"owner":"admin","limit":5}"#.to_string(),
+                },
+                DecodedRecord::Item {
+                    namespace: "total".to_string(),
+                    value: r#""300""#.to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn renders_unknown_records() {
+        let mut store = MockStorage::new();
+        CONFIG
+            .save(
+                &mut store,
+                &Config {
+                    owner: Addr::unchecked("admin"),
+                    limit: 5,
+                },
+            )
+            .unwrap();
+        BALANCES
+            .save(&mut store, &Addr::unchecked("bob"), &Uint128::new(1))
+            .unwrap();
+        // a value which is not what the map expects
+        store.set(&namespaces_with_key(&[b"balance"], b"eve"), b"[1]");
+
+        let inspector = StorageInspector::new().map(&BALANCES);
+        let dump = inspector.dump(&records(&store));
+        let lines: Vec<_> = dump.lines().collect();
+        assert_eq!(lines[0], r#"balance["bob"] = "1""#);
+        assert!(lines[1].starts_with(r#"? \x00\x07balanceeve = [1] ("#));
+        assert_eq!(lines[2], r#"? config = {\"owner\":\"admin\",\"limit\":5}"#);
+    }
+}
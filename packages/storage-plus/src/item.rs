@@ -2,20 +2,22 @@ use serde::de::DeserializeOwned;
 use serde::Serialize;
 use std::marker::PhantomData;
 
-use cosmwasm_std::{
-    to_vec, Addr, CustomQuery, QuerierWrapper, StdError, StdResult, Storage, WasmQuery,
-};
+use cosmwasm_std::{Addr, CustomQuery, QuerierWrapper, StdError, StdResult, Storage};
 
-use crate::helpers::{may_deserialize, must_deserialize};
+use crate::codec::{Codec, JsonCodec};
+use crate::helpers::{may_deserialize, must_deserialize, query_raw};
 
 /// Item stores one typed item at the given key.
 /// This is an analog of Singleton.
 /// It functions the same way as Path does but doesn't use a Vec and thus has a const fn constructor.
-pub struct Item<'a, T> {
+/// The value is encoded with the `C` codec, JSON by default.
+#[derive(Debug, Clone)]
+pub struct Item<'a, T, C = JsonCodec> {
     // this is full key - no need to length-prefix it, we only store one item
     storage_key: &'a [u8],
     // see https://doc.rust-lang.org/std/marker/struct.PhantomData.html#unused-type-parameters for why this is needed
     data_type: PhantomData<T>,
+    codec: PhantomData<C>,
 }
 
 impl<'a, T> Item<'a, T> {
@@ -23,13 +25,27 @@ impl<'a, T> Item<'a, T> {
         Item {
             storage_key: storage_key.as_bytes(),
             data_type: PhantomData,
+            codec: PhantomData,
         }
     }
 }
 
-impl<'a, T> Item<'a, T>
+impl<'a, T, C> Item<'a, T, C> {
+    /// Switches to another codec for the stored value, e.g.
+    /// `Item::new("total").with_codec::<BinCodec>()` (with the `bin-codec` feature)
+    pub const fn with_codec<D>(self) -> Item<'a, T, D> {
+        Item {
+            storage_key: self.storage_key,
+            data_type: PhantomData,
+            codec: PhantomData,
+        }
+    }
+}
+
+impl<'a, T, C> Item<'a, T, C>
 where
     T: Serialize + DeserializeOwned,
+    C: Codec<T>,
 {
     // this gets the path of the data to use elsewhere
     pub fn as_slice(&self) -> &[u8] {
@@ -38,7 +54,7 @@ where
 
     /// save will serialize the model and store, returns an error on serialization issues
     pub fn save(&self, store: &mut dyn Storage, data: &T) -> StdResult<()> {
-        store.set(self.storage_key, &to_vec(data)?);
+        store.set(self.storage_key, &C::encode(data)?);
         Ok(())
     }
 
@@ -49,14 +65,14 @@ where
     /// load will return an error if no data is set at the given key, or on parse error
     pub fn load(&self, store: &dyn Storage) -> StdResult<T> {
         let value = store.get(self.storage_key);
-        must_deserialize(&value)
+        must_deserialize::<T, C>(&value)
     }
 
     /// may_load will parse the data stored at the key if present, returns `Ok(None)` if no data there.
     /// returns an error on issues parsing
     pub fn may_load(&self, store: &dyn Storage) -> StdResult<Option<T>> {
         let value = store.get(self.storage_key);
-        may_deserialize(&value)
+        may_deserialize::<T, C>(&value)
     }
 
     /// Loads the data, perform the specified action, and store the result
@@ -84,11 +100,8 @@ where
         querier: &QuerierWrapper<Q>,
         remote_contract: Addr,
     ) -> StdResult<T> {
-        let request = WasmQuery::Raw {
-            contract_addr: remote_contract.into(),
-            key: self.storage_key.into(),
-        };
-        querier.query(&request.into())
+        let result = query_raw(querier, remote_contract, self.storage_key.into())?;
+        C::decode(&result)
     }
 }
 
@@ -98,7 +111,7 @@ mod test {
     use cosmwasm_std::testing::MockStorage;
     use serde::{Deserialize, Serialize};
 
-    use cosmwasm_std::{OverflowError, OverflowOperation, StdError};
+    use cosmwasm_std::{to_vec, OverflowError, OverflowOperation, StdError};
 
     #[derive(Serialize, Deserialize, PartialEq, Debug)]
     struct Config {
@@ -316,4 +329,28 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn other_codecs() {
+        let mut store = MockStorage::new();
+
+        #[cfg(feature = "bin-codec")]
+        {
+            const BIN_CONFIG: Item<Config, crate::BinCodec> = Item::new("config").with_codec();
+            let cfg = Config {
+                owner: "admin".to_string(),
+                max_tokens: 1234,
+            };
+            BIN_CONFIG.save(&mut store, &cfg).unwrap();
+            assert_eq!(cfg, BIN_CONFIG.load(&store).unwrap());
+            // the JSON codec does not understand it
+            assert!(CONFIG.load(&store).is_err());
+        }
+
+        const RAW: Item<Vec<u8>, crate::RawCodec> = Item::new("raw").with_codec();
+        assert_eq!(RAW.may_load(&store).unwrap(), None);
+        RAW.save(&mut store, &b"foo".to_vec()).unwrap();
+        assert_eq!(store.get(b"raw").unwrap(), b"foo".to_vec());
+        assert_eq!(RAW.load(&store).unwrap(), b"foo".to_vec());
+    }
 }
@@ -1,10 +1,14 @@
 #![cfg(feature = "iterator")]
 
+use std::cmp::Ordering;
+use std::iter::Peekable;
+
 use serde::de::DeserializeOwned;
 
 use cosmwasm_std::Record;
-use cosmwasm_std::{from_slice, StdResult};
+use cosmwasm_std::{from_slice, Order, StdError, StdResult};
 
+use crate::codec::Codec;
 use crate::de::KeyDeserialize;
 use crate::helpers::encode_length;
 
@@ -24,6 +28,23 @@ pub(crate) fn deserialize_kv<K: KeyDeserialize, T: DeserializeOwned>(
     Ok((kt, vt))
 }
 
+/// Same as `deserialize_v`, with the value stored using the given codec
+pub(crate) fn decode_v<T, C: Codec<T>>(kv: Record) -> StdResult<Record<T>> {
+    let (k, v) = kv;
+    let t = C::decode(&v)?;
+    Ok((k, t))
+}
+
+/// Same as `deserialize_kv`, with the value stored using the given codec
+pub(crate) fn decode_kv<K: KeyDeserialize, T, C: Codec<T>>(
+    kv: Record,
+) -> StdResult<(K::Output, T)> {
+    let (k, v) = kv;
+    let kt = K::from_vec(k)?;
+    let vt = C::decode(&v)?;
+    Ok((kt, vt))
+}
+
 /// Calculates the raw key prefix for a given namespace as documented
 /// in https://github.com/webmaster128/key-namespacing#length-prefixed-keys
 #[allow(dead_code)]
@@ -47,10 +68,456 @@ pub(crate) fn concat(namespace: &[u8], key: &[u8]) -> Vec<u8> {
     k
 }
 
+// Combinators over ranges.
+//
+// They all expect their inputs to be sorted by key in the given `Order`, as returned by
+// `range` / `keys` (with any bounds). Keys are compared with `Ord`, which matches the storage
+// order for raw keys (`range_raw`, `keys_raw`) and simple keys (strings, addresses, integers),
+// but not for composite keys, whose first elements are length-prefixed in storage: use the raw
+// variants for those.
+// On error, the error is returned and the iteration stops.
+
+fn compare<K: Ord>(order: Order, a: &K, b: &K) -> Ordering {
+    match order {
+        Order::Ascending => a.cmp(b),
+        Order::Descending => b.cmp(a),
+    }
+}
+
+// takes the next item out of the iterator if it is an error
+fn take_err<T, I>(iter: &mut Peekable<I>) -> Option<StdError>
+where
+    I: Iterator<Item = StdResult<T>>,
+{
+    match iter.peek() {
+        Some(Err(_)) => iter.next().and_then(Result::err),
+        _ => None,
+    }
+}
+
+fn peek_key<'a, K, T: 'a, I>(iter: &'a mut Peekable<I>) -> Option<&'a K>
+where
+    I: Iterator<Item = StdResult<(K, T)>>,
+{
+    match iter.peek() {
+        Some(Ok((k, _))) => Some(k),
+        _ => None,
+    }
+}
+
+struct MergeJoin<L: Iterator, R: Iterator> {
+    left: Peekable<L>,
+    right: Peekable<R>,
+    order: Order,
+    done: bool,
+}
+
+impl<K, A, B, L, R> Iterator for MergeJoin<L, R>
+where
+    K: Ord,
+    L: Iterator<Item = StdResult<(K, A)>>,
+    R: Iterator<Item = StdResult<(K, B)>>,
+{
+    type Item = StdResult<(K, A, B)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            if let Some(err) = take_err(&mut self.left).or_else(|| take_err(&mut self.right)) {
+                self.done = true;
+                return Some(Err(err));
+            }
+            let ordering = match (peek_key(&mut self.left), peek_key(&mut self.right)) {
+                (Some(l), Some(r)) => compare(self.order, l, r),
+                _ => break,
+            };
+            match ordering {
+                Ordering::Less => {
+                    self.left.next();
+                }
+                Ordering::Greater => {
+                    self.right.next();
+                }
+                Ordering::Equal => {
+                    let (k, a) = self.left.next()?.ok()?;
+                    let (_, b) = self.right.next()?.ok()?;
+                    return Some(Ok((k, a, b)));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Joins two ranges on their keys, returning `(key, left value, right value)` for the keys
+/// found in both. Both ranges must be sorted in `order`. This reads each range only once,
+/// which is cheaper than loading the right value for every left key when the ranges overlap
+/// a lot.
+pub fn merge_join<'a, K, A, B, L, R>(
+    left: L,
+    right: R,
+    order: Order,
+) -> Box<dyn Iterator<Item = StdResult<(K, A, B)>> + 'a>
+where
+    K: Ord + 'a,
+    A: 'a,
+    B: 'a,
+    L: Iterator<Item = StdResult<(K, A)>> + 'a,
+    R: Iterator<Item = StdResult<(K, B)>> + 'a,
+{
+    Box::new(MergeJoin {
+        left: left.peekable(),
+        right: right.peekable(),
+        order,
+        done: false,
+    })
+}
+
+struct MergeKeys<I: Iterator> {
+    sources: Vec<Peekable<I>>,
+    order: Order,
+    intersect: bool,
+    done: bool,
+}
+
+impl<K, I> Iterator for MergeKeys<I>
+where
+    K: Ord + Clone,
+    I: Iterator<Item = StdResult<K>>,
+{
+    type Item = StdResult<K>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let order = self.order;
+        while !self.done {
+            if let Some(err) = self.sources.iter_mut().find_map(take_err) {
+                self.done = true;
+                return Some(Err(err));
+            }
+
+            // the first of the next keys for a union, the last one for an intersection
+            let pick = if self.intersect {
+                Ordering::Greater
+            } else {
+                Ordering::Less
+            };
+            let mut target: Option<K> = None;
+            let mut exhausted = false;
+            for source in self.sources.iter_mut() {
+                match source.peek() {
+                    Some(Ok(k)) => {
+                        let better = match &target {
+                            Some(t) => compare(order, k, t) == pick,
+                            None => true,
+                        };
+                        if better {
+                            target = Some(k.clone());
+                        }
+                    }
+                    _ => exhausted = true,
+                }
+            }
+            let target = match target {
+                Some(target) if !(self.intersect && exhausted) => target,
+                _ => break,
+            };
+
+            if !self.intersect {
+                // the target is the first key: take it from all the sources having it
+                for source in self.sources.iter_mut() {
+                    if matches!(source.peek(), Some(Ok(k)) if *k == target) {
+                        source.next();
+                    }
+                }
+                return Some(Ok(target));
+            }
+
+            // the target is the last key: skip everything before it, and take it if all the
+            // sources have it
+            let mut all = true;
+            for source in self.sources.iter_mut() {
+                if !matches!(source.peek(), Some(Ok(k)) if *k == target) {
+                    source.next();
+                    all = false;
+                }
+            }
+            if all {
+                for source in self.sources.iter_mut() {
+                    source.next();
+                }
+                return Some(Ok(target));
+            }
+        }
+        self.done = true;
+        None
+    }
+}
+
+/// Returns the keys found in any of the given key ranges (e.g. from several `MultiIndex`
+/// prefixes), each key once. All the ranges must be sorted in `order`.
+pub fn union_keys<'a, K, I>(
+    sources: Vec<I>,
+    order: Order,
+) -> Box<dyn Iterator<Item = StdResult<K>> + 'a>
+where
+    K: Ord + Clone + 'a,
+    I: Iterator<Item = StdResult<K>> + 'a,
+{
+    Box::new(MergeKeys {
+        sources: sources.into_iter().map(Iterator::peekable).collect(),
+        order,
+        intersect: false,
+        done: false,
+    })
+}
+
+/// Returns the keys found in all the given key ranges (e.g. from several `MultiIndex`
+/// prefixes). All the ranges must be sorted in `order`.
+pub fn intersect_keys<'a, K, I>(
+    sources: Vec<I>,
+    order: Order,
+) -> Box<dyn Iterator<Item = StdResult<K>> + 'a>
+where
+    K: Ord + Clone + 'a,
+    I: Iterator<Item = StdResult<K>> + 'a,
+{
+    Box::new(MergeKeys {
+        sources: sources.into_iter().map(Iterator::peekable).collect(),
+        order,
+        intersect: true,
+        done: false,
+    })
+}
+
+/// Extends every item of a range with a related value, returned by `lookup` (typically a
+/// `load` or `may_load` on another `Map`). Errors from the range or the lookup are returned,
+/// and stop the iteration.
+pub fn lookup_join<'a, K, V, J, I, F>(
+    iter: I,
+    mut lookup: F,
+) -> Box<dyn Iterator<Item = StdResult<(K, V, J)>> + 'a>
+where
+    I: Iterator<Item = StdResult<(K, V)>> + 'a,
+    F: FnMut(&K, &V) -> StdResult<J> + 'a,
+{
+    let joined = iter.scan(false, move |done, item| {
+        if *done {
+            return None;
+        }
+        let joined = item.and_then(|(k, v)| {
+            let j = lookup(&k, &v)?;
+            Ok((k, v, j))
+        });
+        *done = joined.is_err();
+        Some(joined)
+    });
+    Box::new(joined)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
 
+    use cosmwasm_std::testing::MockStorage;
+    use serde::{Deserialize, Serialize};
+
+    use crate::indexed_map::{IndexList, IndexedMap};
+    use crate::indexes::{Index, MultiIndex};
+    use crate::map::Map;
+    use crate::Bound;
+
+    const BALANCES: Map<&str, u64> = Map::new("balances");
+    const ALLOWANCES: Map<&str, u64> = Map::new("allowances");
+
+    fn setup_balances(store: &mut MockStorage) {
+        for (addr, balance) in [("alice", 10), ("bob", 20), ("carl", 30), ("dave", 40)] {
+            BALANCES.save(store, addr, &balance).unwrap();
+        }
+        for (spender, allowance) in [("abe", 1), ("bob", 2), ("dave", 4), ("zed", 5)] {
+            ALLOWANCES.save(store, spender, &allowance).unwrap();
+        }
+    }
+
+    #[test]
+    fn merge_join_works() {
+        let mut store = MockStorage::new();
+        setup_balances(&mut store);
+
+        let joined: Vec<_> = merge_join(
+            ALLOWANCES.range(&store, None, None, Order::Ascending),
+            BALANCES.range(&store, None, None, Order::Ascending),
+            Order::Ascending,
+        )
+        .collect::<StdResult<_>>()
+        .unwrap();
+        assert_eq!(
+            joined,
+            vec![("bob".to_string(), 2, 20), ("dave".to_string(), 4, 40)]
+        );
+
+        let joined: Vec<_> = merge_join(
+            ALLOWANCES.range(
+                &store,
+                None,
+                Some(Bound::exclusive("dave")),
+                Order::Descending,
+            ),
+            BALANCES.range(&store, None, None, Order::Descending),
+            Order::Descending,
+        )
+        .collect::<StdResult<_>>()
+        .unwrap();
+        assert_eq!(joined, vec![("bob".to_string(), 2, 20)]);
+
+        // errors stop the iteration
+        let left = vec![
+            Ok(("bob".to_string(), 2)),
+            Err(StdError::generic_err("broken")),
+            Ok(("dave".to_string(), 4)),
+        ];
+        let joined: Vec<_> = merge_join(
+            left.into_iter(),
+            BALANCES.range(&store, None, None, Order::Ascending),
+            Order::Ascending,
+        )
+        .collect();
+        assert_eq!(joined.len(), 2);
+        assert!(joined[1].is_err());
+    }
+
+    #[test]
+    fn lookup_join_works() {
+        let mut store = MockStorage::new();
+        setup_balances(&mut store);
+
+        let store = &store;
+        let joined: Vec<_> = lookup_join(
+            ALLOWANCES.range(store, None, None, Order::Ascending),
+            |spender, _| BALANCES.may_load(store, spender),
+        )
+        .collect::<StdResult<_>>()
+        .unwrap();
+        assert_eq!(
+            joined,
+            vec![
+                ("abe".to_string(), 1, None),
+                ("bob".to_string(), 2, Some(20)),
+                ("dave".to_string(), 4, Some(40)),
+                ("zed".to_string(), 5, None),
+            ]
+        );
+
+        let joined: Vec<_> = lookup_join(
+            ALLOWANCES.range(store, None, None, Order::Ascending),
+            |spender, _| BALANCES.load(store, spender),
+        )
+        .collect();
+        assert_eq!(joined.len(), 1);
+        assert!(joined[0].is_err());
+    }
+
+    #[derive(Serialize, Deserialize, Clone)]
+    struct Person {
+        name: String,
+        city: String,
+    }
+
+    struct PersonIndexes<'a> {
+        name: MultiIndex<'a, String, Person, String>,
+        city: MultiIndex<'a, String, Person, String>,
+    }
+
+    impl<'a> IndexList<Person> for PersonIndexes<'a> {
+        fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Person>> + '_> {
+            let v: Vec<&dyn Index<Person>> = vec![&self.name, &self.city];
+            Box::new(v.into_iter())
+        }
+    }
+
+    #[test]
+    fn union_and_intersection_of_indexes() {
+        let mut store = MockStorage::new();
+        let people = IndexedMap::new(
+            "people",
+            PersonIndexes {
+                name: MultiIndex::new(|p: &Person| p.name.clone(), "people", "people__name"),
+                city: MultiIndex::new(|p: &Person| p.city.clone(), "people", "people__city"),
+            },
+        );
+        let data = [
+            ("1", "John", "Paris"),
+            ("2", "Jane", "Paris"),
+            ("3", "John", "Rome"),
+            ("4", "John", "Paris"),
+            ("5", "Mary", "Paris"),
+        ];
+        for (pk, name, city) in data {
+            let person = Person {
+                name: name.to_string(),
+                city: city.to_string(),
+            };
+            people.save(&mut store, pk, &person).unwrap();
+        }
+        let by_name = |name: &str, order| {
+            people
+                .idx
+                .name
+                .prefix(name.to_string())
+                .keys(&store, None, None, order)
+        };
+        let by_city = |city: &str, order| {
+            people
+                .idx
+                .city
+                .prefix(city.to_string())
+                .keys(&store, None, None, order)
+        };
+
+        let all: Vec<_> = union_keys(
+            vec![
+                by_name("Jane", Order::Ascending),
+                by_name("John", Order::Ascending),
+            ],
+            Order::Ascending,
+        )
+        .collect::<StdResult<_>>()
+        .unwrap();
+        assert_eq!(all, vec!["1", "2", "3", "4"]);
+
+        let all: Vec<_> = union_keys(
+            vec![
+                by_city("Rome", Order::Descending),
+                by_name("John", Order::Descending),
+                by_name("Nobody", Order::Descending),
+            ],
+            Order::Descending,
+        )
+        .collect::<StdResult<_>>()
+        .unwrap();
+        assert_eq!(all, vec!["4", "3", "1"]);
+
+        let johns_in_paris: Vec<_> = intersect_keys(
+            vec![
+                by_name("John", Order::Ascending),
+                by_city("Paris", Order::Ascending),
+            ],
+            Order::Ascending,
+        )
+        .collect::<StdResult<_>>()
+        .unwrap();
+        assert_eq!(johns_in_paris, vec!["1", "4"]);
+
+        let none: Vec<_> = intersect_keys(
+            vec![
+                by_name("Mary", Order::Descending),
+                by_city("Rome", Order::Descending),
+            ],
+            Order::Descending,
+        )
+        .collect::<StdResult<_>>()
+        .unwrap();
+        assert!(none.is_empty());
+    }
+
     #[test]
     fn to_length_prefixed_works() {
         assert_eq!(to_length_prefixed(b""), b"\x00\x00");
@@ -1,24 +1,44 @@
 mod bound;
+mod cached_storage;
+mod chunked_item;
+mod codec;
+mod counted_map;
 mod de;
 mod endian;
+mod expiring_map;
 mod helpers;
+mod indexed_counted_map;
 mod indexed_map;
 mod indexed_snapshot;
 mod indexes;
+mod inspect;
 mod int_key;
 mod item;
 mod iter_helpers;
 mod keys;
 mod map;
+mod merkle_map;
 mod path;
 mod prefix;
+mod sequence;
 mod snapshot;
+mod sorted_map;
 
 #[cfg(feature = "iterator")]
 pub use bound::{Bound, Bounder, PrefixBound, RawBound};
+pub use cached_storage::CachedStorage;
+pub use chunked_item::{BlobManifest, ChunkedItem};
+#[cfg(feature = "bin-codec")]
+pub use codec::BinCodec;
+pub use codec::{Codec, JsonCodec, RawCodec};
+pub use counted_map::CountedMap;
 pub use de::KeyDeserialize;
 pub use endian::Endian;
 #[cfg(feature = "iterator")]
+pub use expiring_map::{Deadline, Expires, ExpiringMap};
+#[cfg(feature = "iterator")]
+pub use indexed_counted_map::IndexedCountedMap;
+#[cfg(feature = "iterator")]
 pub use indexed_map::{IndexList, IndexedMap};
 #[cfg(feature = "iterator")]
 pub use indexed_snapshot::IndexedSnapshotMap;
@@ -28,15 +48,27 @@ pub use indexes::Index;
 pub use indexes::MultiIndex;
 #[cfg(feature = "iterator")]
 pub use indexes::UniqueIndex;
+#[cfg(feature = "iterator")]
+pub use indexes::{SnapshotMultiIndex, SnapshotUniqueIndex};
+#[cfg(feature = "iterator")]
+pub use inspect::{DecodedRecord, StorageInspector};
 pub use int_key::IntKey;
 pub use item::Item;
+#[cfg(feature = "iterator")]
+pub use iter_helpers::{intersect_keys, lookup_join, merge_join, union_keys};
 pub use keys::{Key, Prefixer, PrimaryKey};
 pub use map::Map;
+pub use merkle_map::{verify_merkle_proof, MerkleMap, MerkleProof};
 pub use path::Path;
 #[cfg(feature = "iterator")]
 pub use prefix::{range_with_prefix, Prefix};
+pub use sequence::Sequence;
+#[cfg(feature = "iterator")]
+pub use snapshot::{
+    ByHeight, ByTime, CheckpointKind, PruneProgress, Retention, SnapshotItem, SnapshotMap, Strategy,
+};
 #[cfg(feature = "iterator")]
-pub use snapshot::{SnapshotItem, SnapshotMap, Strategy};
+pub use sorted_map::{Score, SortedMap};
 
 #[cfg(all(feature = "iterator", feature = "macro"))]
 #[macro_use]
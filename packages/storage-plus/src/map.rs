@@ -4,25 +4,31 @@ use std::marker::PhantomData;
 
 #[cfg(feature = "iterator")]
 use crate::bound::{Bound, PrefixBound};
+use crate::codec::{Codec, JsonCodec};
 #[cfg(feature = "iterator")]
 use crate::de::KeyDeserialize;
 use crate::helpers::query_raw;
 #[cfg(feature = "iterator")]
-use crate::iter_helpers::{deserialize_kv, deserialize_v};
+use crate::indexes::{index_batch, Index};
+#[cfg(feature = "iterator")]
+use crate::iter_helpers::{decode_kv, decode_v};
 #[cfg(feature = "iterator")]
 use crate::keys::Prefixer;
 use crate::keys::{Key, PrimaryKey};
 use crate::path::Path;
 #[cfg(feature = "iterator")]
 use crate::prefix::{namespaced_prefix_range, Prefix};
-use cosmwasm_std::{from_slice, Addr, CustomQuery, QuerierWrapper, StdError, StdResult, Storage};
+use cosmwasm_std::{Addr, CustomQuery, QuerierWrapper, StdError, StdResult, Storage};
 
+/// Map stores typed values under typed keys, all below the given namespace.
+/// Values are encoded with the `C` codec, JSON by default.
 #[derive(Debug, Clone)]
-pub struct Map<'a, K, T> {
+pub struct Map<'a, K, T, C = JsonCodec> {
     namespace: &'a [u8],
     // see https://doc.rust-lang.org/std/marker/struct.PhantomData.html#unused-type-parameters for why this is needed
     key_type: PhantomData<K>,
     data_type: PhantomData<T>,
+    codec: PhantomData<C>,
 }
 
 impl<'a, K, T> Map<'a, K, T> {
@@ -31,6 +37,20 @@ impl<'a, K, T> Map<'a, K, T> {
             namespace: namespace.as_bytes(),
             data_type: PhantomData,
             key_type: PhantomData,
+            codec: PhantomData,
+        }
+    }
+}
+
+impl<'a, K, T, C> Map<'a, K, T, C> {
+    /// Switches to another codec for the stored values, e.g.
+    /// `Map::new("balances").with_codec::<BinCodec>()` (with the `bin-codec` feature)
+    pub const fn with_codec<D>(self) -> Map<'a, K, T, D> {
+        Map {
+            namespace: self.namespace,
+            data_type: PhantomData,
+            key_type: PhantomData,
+            codec: PhantomData,
         }
     }
 
@@ -39,21 +59,23 @@ impl<'a, K, T> Map<'a, K, T> {
     }
 }
 
-impl<'a, K, T> Map<'a, K, T>
+impl<'a, K, T, C> Map<'a, K, T, C>
 where
     T: Serialize + DeserializeOwned,
+    C: Codec<T>,
     K: PrimaryKey<'a>,
 {
-    pub fn key(&self, k: K) -> Path<T> {
+    pub fn key(&self, k: K) -> Path<T, C> {
         Path::new(
             self.namespace,
             &k.key().iter().map(Key::as_ref).collect::<Vec<_>>(),
         )
+        .with_codec()
     }
 
     #[cfg(feature = "iterator")]
     pub(crate) fn no_prefix_raw(&self) -> Prefix<Vec<u8>, T, K> {
-        Prefix::new(self.namespace, &[])
+        Prefix::new_with_codec::<C>(self.namespace, &[])
     }
 
     pub fn save(&self, store: &mut dyn Storage, k: K, data: &T) -> StdResult<()> {
@@ -106,31 +128,33 @@ where
         if result.is_empty() {
             Ok(None)
         } else {
-            from_slice(&result).map(Some)
+            C::decode(&result).map(Some)
         }
     }
 }
 
 #[cfg(feature = "iterator")]
-impl<'a, K, T> Map<'a, K, T>
+impl<'a, K, T, C> Map<'a, K, T, C>
 where
     T: Serialize + DeserializeOwned,
+    C: Codec<T>,
     K: PrimaryKey<'a>,
 {
     pub fn sub_prefix(&self, p: K::SubPrefix) -> Prefix<K::SuperSuffix, T, K::SuperSuffix> {
-        Prefix::new(self.namespace, &p.prefix())
+        Prefix::new_with_codec::<C>(self.namespace, &p.prefix())
     }
 
     pub fn prefix(&self, p: K::Prefix) -> Prefix<K::Suffix, T, K::Suffix> {
-        Prefix::new(self.namespace, &p.prefix())
+        Prefix::new_with_codec::<C>(self.namespace, &p.prefix())
     }
 }
 
 // short-cut for simple keys, rather than .prefix(()).range_raw(...)
 #[cfg(feature = "iterator")]
-impl<'a, K, T> Map<'a, K, T>
+impl<'a, K, T, C> Map<'a, K, T, C>
 where
     T: Serialize + DeserializeOwned,
+    C: Codec<T>,
     // TODO: this should only be when K::Prefix == ()
     // Other cases need to call prefix() first
     K: PrimaryKey<'a>,
@@ -150,17 +174,19 @@ where
     where
         T: 'c,
         'a: 'c,
+        C: 'c,
     {
         let mapped =
-            namespaced_prefix_range(store, self.namespace, min, max, order).map(deserialize_v);
+            namespaced_prefix_range(store, self.namespace, min, max, order).map(decode_v::<T, C>);
         Box::new(mapped)
     }
 }
 
 #[cfg(feature = "iterator")]
-impl<'a, K, T> Map<'a, K, T>
+impl<'a, K, T, C> Map<'a, K, T, C>
 where
     T: Serialize + DeserializeOwned,
+    C: Codec<T>,
     K: PrimaryKey<'a> + KeyDeserialize,
 {
     /// While `range` over a `prefix` fixes the prefix to one element and iterates over the
@@ -181,21 +207,23 @@ where
         'a: 'c,
         K: 'c,
         K::Output: 'static,
+        C: 'c,
     {
         let mapped = namespaced_prefix_range(store, self.namespace, min, max, order)
-            .map(deserialize_kv::<K, T>);
+            .map(decode_kv::<K, T, C>);
         Box::new(mapped)
     }
 
     fn no_prefix(&self) -> Prefix<K, T, K> {
-        Prefix::new(self.namespace, &[])
+        Prefix::new_with_codec::<C>(self.namespace, &[])
     }
 }
 
 #[cfg(feature = "iterator")]
-impl<'a, K, T> Map<'a, K, T>
+impl<'a, K, T, C> Map<'a, K, T, C>
 where
     T: Serialize + DeserializeOwned,
+    C: Codec<T>,
     K: PrimaryKey<'a>,
 {
     pub fn range_raw<'c>(
@@ -226,9 +254,34 @@ where
 }
 
 #[cfg(feature = "iterator")]
-impl<'a, K, T> Map<'a, K, T>
+impl<'a, K, T, C> Map<'a, K, T, C>
+where
+    T: Serialize + DeserializeOwned + Clone,
+    C: Codec<T>,
+{
+    /// Adds the entries of this map to `index`, in batches of at most `limit` entries, so an
+    /// index can be attached to a map already holding data. Pass `None` as `start_after` for
+    /// the first batch, and the returned key for the following ones, until it returns `None`.
+    ///
+    /// This is typically done in a migration, before replacing the `Map` with an `IndexedMap`
+    /// using the same namespace (which keeps the entries where they are) and the index.
+    /// Entries already in the index are left as they are. `limit` must not be zero.
+    pub fn build_index(
+        &self,
+        store: &mut dyn Storage,
+        index: &dyn Index<T>,
+        start_after: Option<Vec<u8>>,
+        limit: usize,
+    ) -> StdResult<Option<Vec<u8>>> {
+        index_batch::<T, C>(store, self.namespace, &[index], start_after, limit)
+    }
+}
+
+#[cfg(feature = "iterator")]
+impl<'a, K, T, C> Map<'a, K, T, C>
 where
     T: Serialize + DeserializeOwned,
+    C: Codec<T>,
     K: PrimaryKey<'a> + KeyDeserialize,
 {
     pub fn range<'c>(
@@ -297,6 +350,47 @@ mod test {
 
     const TRIPLE: Map<(&[u8], u8, &str), u64> = Map::new("triple");
 
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn build_index_in_batches() {
+        use crate::MultiIndex;
+
+        let mut store = MockStorage::new();
+        for (key, name, age) in [("a", "John", 30), ("b", "Jane", 20), ("c", "John", 40)] {
+            let data = Data {
+                name: name.to_string(),
+                age,
+            };
+            PEOPLE_STR.save(&mut store, key, &data).unwrap();
+        }
+
+        let by_name: MultiIndex<String, Data, String> =
+            MultiIndex::new(|d| d.name.clone(), PEOPLE_STR_KEY, "people2__name");
+        // an empty batch would never get anywhere
+        let err = PEOPLE_STR
+            .build_index(&mut store, &by_name, None, 0)
+            .unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err("Index batch limit must be greater than zero")
+        );
+        let cursor = PEOPLE_STR
+            .build_index(&mut store, &by_name, None, 2)
+            .unwrap();
+        assert_eq!(cursor, Some(b"b".to_vec()));
+        let cursor = PEOPLE_STR
+            .build_index(&mut store, &by_name, cursor, 2)
+            .unwrap();
+        assert_eq!(cursor, None);
+
+        let johns: Vec<_> = by_name
+            .prefix("John".to_string())
+            .keys(&store, None, None, Order::Ascending)
+            .collect::<StdResult<_>>()
+            .unwrap();
+        assert_eq!(johns, vec!["a", "c"]);
+    }
+
     #[test]
     fn create_path() {
         let path = PEOPLE.key(b"john");
@@ -1511,4 +1605,62 @@ mod test {
         assert_eq!(include.len(), 1);
         assert_eq!(include, vec![456]);
     }
+
+    #[test]
+    #[cfg(all(feature = "iterator", feature = "bin-codec"))]
+    fn bin_codec_works() {
+        const BIN_PEOPLE: Map<(&str, u32), Data, crate::BinCodec> =
+            Map::new("bin_people").with_codec();
+        let mut store = MockStorage::new();
+
+        let john = Data {
+            name: "John".to_string(),
+            age: 32,
+        };
+        let jim = Data {
+            name: "Jim".to_string(),
+            age: 44,
+        };
+        BIN_PEOPLE.save(&mut store, ("a", 1), &john).unwrap();
+        BIN_PEOPLE.save(&mut store, ("a", 2), &jim).unwrap();
+        BIN_PEOPLE.save(&mut store, ("b", 1), &john).unwrap();
+
+        assert_eq!(BIN_PEOPLE.load(&store, ("a", 2)).unwrap(), jim);
+        // one length byte and 4 chars for the name, one byte for the age
+        let raw = store.get(&BIN_PEOPLE.key(("a", 1))).unwrap();
+        assert_eq!(raw.len(), 6);
+
+        // the json encoded value can not be read back
+        PEOPLE.save(&mut store, b"john", &john).unwrap();
+        let json_raw = store.get(&PEOPLE.key(b"john")).unwrap();
+        store.set(&BIN_PEOPLE.key(("c", 1)), &json_raw);
+        assert!(BIN_PEOPLE.load(&store, ("c", 1)).is_err());
+        BIN_PEOPLE.remove(&mut store, ("c", 1));
+
+        let all: StdResult<Vec<_>> = BIN_PEOPLE
+            .prefix("a")
+            .range(&store, None, None, Order::Ascending)
+            .collect();
+        assert_eq!(all.unwrap(), vec![(1, john.clone()), (2, jim.clone())]);
+
+        let all: StdResult<Vec<_>> = BIN_PEOPLE
+            .prefix_range(
+                &store,
+                Some(PrefixBound::exclusive("a")),
+                None,
+                Order::Ascending,
+            )
+            .collect();
+        assert_eq!(all.unwrap(), vec![(("b".to_string(), 1), john)]);
+
+        let updated = BIN_PEOPLE
+            .update(&mut store, ("a", 2), |d| -> StdResult<_> {
+                let mut d = d.unwrap();
+                d.age += 1;
+                Ok(d)
+            })
+            .unwrap();
+        assert_eq!(updated.age, 45);
+        assert_eq!(BIN_PEOPLE.load(&store, ("a", 2)).unwrap(), updated);
+    }
 }
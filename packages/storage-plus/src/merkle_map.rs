@@ -0,0 +1,507 @@
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use cosmwasm_std::{to_vec, Binary, StdError, StdResult, Storage};
+
+use crate::helpers::namespaces_with_key;
+use crate::keys::PrimaryKey;
+use crate::map::Map;
+
+type Hash = [u8; 32];
+
+// hash of an empty subtree
+const EMPTY: Hash = [0; 32];
+const LEAF_PREFIX: u8 = 0;
+const INTERNAL_PREFIX: u8 = 1;
+// the key hashes have 256 bits, so no path can be deeper
+const MAX_DEPTH: usize = 256;
+
+/// MerkleProof proves that a key is set to a given value in a `MerkleMap` with a known root.
+///
+/// It contains the hashes of the siblings of all the nodes on the path from the root to the
+/// leaf of the key, starting from the root.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MerkleProof {
+    pub siblings: Vec<Binary>,
+}
+
+/// Checks that `proof` proves that `key` is set to `value` in the `MerkleMap` with the given
+/// `root`. It does not need access to the map, so it can run in any contract the root and the
+/// proof were sent to.
+///
+/// `key` is the raw key, as produced by `PrimaryKey::joined_key` (for simple keys like `&str`
+/// or `&Addr`, that is just their bytes), and `value` the value as stored (JSON encoded).
+pub fn verify_merkle_proof(root: &[u8], key: &[u8], value: &[u8], proof: &MerkleProof) -> bool {
+    if proof.siblings.len() > MAX_DEPTH {
+        return false;
+    }
+    let key_hash = sha256(&[key]);
+    let mut hash = Node::Leaf {
+        key: key_hash,
+        value: sha256(&[value]),
+    }
+    .hash();
+    for (depth, sibling) in proof.siblings.iter().enumerate().rev() {
+        if sibling.len() != 32 {
+            return false;
+        }
+        let mut sibling_hash = EMPTY;
+        sibling_hash.copy_from_slice(sibling.as_slice());
+        let sibling = sibling_hash;
+        hash = if bit(&key_hash, depth) {
+            internal_hash(&sibling, &hash)
+        } else {
+            internal_hash(&hash, &sibling)
+        };
+    }
+    root == hash
+}
+
+fn sha256(parts: &[&[u8]]) -> Hash {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize().into()
+}
+
+fn internal_hash(left: &Hash, right: &Hash) -> Hash {
+    sha256(&[&[INTERNAL_PREFIX], left, right])
+}
+
+// the bit of the key hash choosing the child at the given depth (0 = left)
+fn bit(key_hash: &Hash, depth: usize) -> bool {
+    key_hash[depth / 8] >> (7 - depth % 8) & 1 == 1
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Node {
+    Leaf { key: Hash, value: Hash },
+    Internal { left: Hash, right: Hash },
+}
+
+impl Node {
+    fn hash(&self) -> Hash {
+        match self {
+            Node::Leaf { key, value } => sha256(&[&[LEAF_PREFIX], key, value]),
+            Node::Internal { left, right } => internal_hash(left, right),
+        }
+    }
+
+    // hash of the child on the side of the key hash (first), and of the other one
+    fn children(&self, key_hash: &Hash, depth: usize) -> (Hash, Hash) {
+        match self {
+            Node::Internal { left, right } if bit(key_hash, depth) => (*right, *left),
+            Node::Internal { left, right } => (*left, *right),
+            Node::Leaf { .. } => (EMPTY, EMPTY),
+        }
+    }
+
+    fn with_children(key_hash: &Hash, depth: usize, ours: Hash, other: Hash) -> Node {
+        if bit(key_hash, depth) {
+            Node::Internal {
+                left: other,
+                right: ours,
+            }
+        } else {
+            Node::Internal {
+                left: ours,
+                right: other,
+            }
+        }
+    }
+
+    fn to_bytes(self) -> Vec<u8> {
+        match self {
+            Node::Leaf { key, value } => [&[LEAF_PREFIX][..], &key, &value].concat(),
+            Node::Internal { left, right } => [&[INTERNAL_PREFIX][..], &left, &right].concat(),
+        }
+    }
+
+    fn from_bytes(data: &[u8]) -> StdResult<Node> {
+        if data.len() != 65 {
+            return Err(StdError::parse_err("MerkleMap node", "invalid length"));
+        }
+        let mut first = [0u8; 32];
+        let mut second = [0u8; 32];
+        first.copy_from_slice(&data[1..33]);
+        second.copy_from_slice(&data[33..]);
+        match data[0] {
+            LEAF_PREFIX => Ok(Node::Leaf {
+                key: first,
+                value: second,
+            }),
+            INTERNAL_PREFIX => Ok(Node::Internal {
+                left: first,
+                right: second,
+            }),
+            _ => Err(StdError::parse_err("MerkleMap node", "unknown node type")),
+        }
+    }
+}
+
+/// `MerkleMap` works like a `Map`, but also maintains a Merkle root committing to all its
+/// entries, which is updated on every `save` and `remove`. `prove` returns a proof that a key
+/// is set to a value, which anyone knowing the root can check with `verify_merkle_proof`.
+///
+/// The tree is a sparse Merkle tree over the sha256 hash of the keys, where subtrees holding a
+/// single entry are replaced by that entry's leaf. It is stored under its own namespace, one
+/// node per storage entry, so an update reads and writes about log2(n) nodes.
+///
+/// Leaves are `sha256(0x00 || sha256(key) || sha256(value))`, internal nodes are
+/// `sha256(0x01 || left || right)` and empty subtrees are 32 zero bytes.
+pub struct MerkleMap<'a, K, T> {
+    primary: Map<'a, K, T>,
+    nodes_namespace: &'a [u8],
+}
+
+impl<'a, K, T> MerkleMap<'a, K, T> {
+    /// Example:
+    ///
+    /// ```rust
+    /// use cosmwasm_std::Uint128;
+    /// use cw_storage_plus::MerkleMap;
+    ///
+    /// MerkleMap::<&str, Uint128>::new("claims", "claims__tree");
+    /// ```
+    pub const fn new(pk_namespace: &'a str, nodes_namespace: &'a str) -> Self {
+        MerkleMap {
+            primary: Map::new(pk_namespace),
+            nodes_namespace: nodes_namespace.as_bytes(),
+        }
+    }
+
+    pub fn namespace(&self) -> &'a [u8] {
+        self.primary.namespace()
+    }
+}
+
+impl<'a, K, T> MerkleMap<'a, K, T>
+where
+    T: Serialize + DeserializeOwned,
+    K: PrimaryKey<'a>,
+{
+    /// save will serialize the value, store it and update the root
+    pub fn save(&self, store: &mut dyn Storage, k: K, data: &T) -> StdResult<()> {
+        let value = to_vec(data)?;
+        let pk = k.joined_key();
+        self.insert(store, &sha256(&[&pk]), sha256(&[&value]))?;
+        store.set(&namespaces_with_key(&[self.namespace()], &pk), &value);
+        Ok(())
+    }
+
+    /// remove deletes the value at the key and updates the root.
+    /// Removing a missing key is a no-op.
+    pub fn remove(&self, store: &mut dyn Storage, k: K) -> StdResult<()> {
+        let key_hash = sha256(&[&k.joined_key()]);
+        self.delete(store, &key_hash)?;
+        self.primary.remove(store, k);
+        Ok(())
+    }
+
+    /// load will return an error if no data is set at the given key, or on parse error
+    pub fn load(&self, store: &dyn Storage, k: K) -> StdResult<T> {
+        self.primary.load(store, k)
+    }
+
+    /// may_load will parse the data stored at the key if present, returns Ok(None) if no data there.
+    /// returns an error on issues parsing
+    pub fn may_load(&self, store: &dyn Storage, k: K) -> StdResult<Option<T>> {
+        self.primary.may_load(store, k)
+    }
+
+    /// has returns true or false if any data is at this key, without parsing or interpreting the
+    /// contents.
+    pub fn has(&self, store: &dyn Storage, k: K) -> bool {
+        self.primary.has(store, k)
+    }
+
+    /// Returns the current root of the tree (32 zero bytes when the map is empty)
+    pub fn root(&self, store: &dyn Storage) -> StdResult<Binary> {
+        let root = self.load_node(store, &EMPTY, 0)?;
+        Ok(Binary::from(root.map(|node| node.hash()).unwrap_or(EMPTY)))
+    }
+
+    /// Returns the proof that the key is set to its current value, or `None` if it is not set
+    pub fn prove(&self, store: &dyn Storage, k: K) -> StdResult<Option<MerkleProof>> {
+        let key_hash = sha256(&[&k.joined_key()]);
+        let mut siblings = vec![];
+        for depth in 0..=MAX_DEPTH {
+            match self.load_node(store, &key_hash, depth)? {
+                Some(Node::Leaf { key, .. }) if key == key_hash => {
+                    return Ok(Some(MerkleProof { siblings }))
+                }
+                Some(node @ Node::Internal { .. }) => {
+                    let (_, other) = node.children(&key_hash, depth);
+                    siblings.push(Binary::from(other));
+                }
+                _ => return Ok(None),
+            }
+        }
+        Ok(None)
+    }
+
+    fn insert(&self, store: &mut dyn Storage, key_hash: &Hash, value_hash: Hash) -> StdResult<()> {
+        // internal nodes on the path, from the root
+        let mut path = vec![];
+        // another leaf that has to move down, below the new one's branching point
+        let mut moved = None;
+        let mut depth = 0;
+        loop {
+            match self.load_node(store, key_hash, depth)? {
+                Some(node @ Node::Internal { .. }) => {
+                    path.push(node);
+                    depth += 1;
+                }
+                Some(Node::Leaf { key, value }) if key != *key_hash => {
+                    let split = (depth..MAX_DEPTH)
+                        .find(|d| bit(&key, *d) != bit(key_hash, *d))
+                        .ok_or_else(|| StdError::generic_err("MerkleMap key hash collision"))?;
+                    let leaf = Node::Leaf { key, value };
+                    self.save_node(store, &key, split + 1, leaf);
+                    moved = Some((split, leaf.hash()));
+                    break;
+                }
+                _ => break,
+            }
+        }
+
+        let leaf = Node::Leaf {
+            key: *key_hash,
+            value: value_hash,
+        };
+        let leaf_depth = moved.map(|(split, _)| split + 1).unwrap_or(depth);
+        self.save_node(store, key_hash, leaf_depth, leaf);
+
+        let mut hash = leaf.hash();
+        for d in (0..leaf_depth).rev() {
+            let other = match (path.get(d), moved) {
+                (Some(node), _) => node.children(key_hash, d).1,
+                (None, Some((split, moved_hash))) if split == d => moved_hash,
+                _ => EMPTY,
+            };
+            let node = Node::with_children(key_hash, d, hash, other);
+            self.save_node(store, key_hash, d, node);
+            hash = node.hash();
+        }
+        Ok(())
+    }
+
+    fn delete(&self, store: &mut dyn Storage, key_hash: &Hash) -> StdResult<()> {
+        let mut path = vec![];
+        let mut depth = 0;
+        loop {
+            match self.load_node(store, key_hash, depth)? {
+                Some(node @ Node::Internal { .. }) => {
+                    path.push(node);
+                    depth += 1;
+                }
+                Some(Node::Leaf { key, .. }) if key == *key_hash => break,
+                // not in the tree
+                _ => return Ok(()),
+            }
+        }
+        self.remove_node(store, key_hash, depth);
+
+        // what is left on our side of the path: nothing, a leaf moving up (as long as it has
+        // no sibling), or a subtree that stays in place
+        let mut moving: Option<Node> = None;
+        let mut hash = EMPTY;
+        for d in (0..depth).rev() {
+            let other = path[d].children(key_hash, d).1;
+            if hash == EMPTY && moving.is_none() {
+                let sibling = self.load_node(store, &flip(key_hash, d), d + 1)?;
+                if let Some(leaf @ Node::Leaf { .. }) = sibling {
+                    // alone below this node, the sibling leaf takes its place
+                    self.remove_node(store, &flip(key_hash, d), d + 1);
+                    self.remove_node(store, key_hash, d);
+                    moving = Some(leaf);
+                    continue;
+                }
+            }
+            if let Some(leaf) = moving {
+                if other == EMPTY {
+                    self.remove_node(store, key_hash, d);
+                    continue;
+                }
+                self.save_node(store, key_hash, d + 1, leaf);
+                hash = leaf.hash();
+                moving = None;
+            }
+            let node = Node::with_children(key_hash, d, hash, other);
+            self.save_node(store, key_hash, d, node);
+            hash = node.hash();
+        }
+        if let Some(leaf) = moving {
+            self.save_node(store, key_hash, 0, leaf);
+        }
+        Ok(())
+    }
+
+    fn node_key(&self, key_hash: &Hash, depth: usize) -> Vec<u8> {
+        // the first `depth` bits of the key hash, with the rest of the last byte zeroed
+        let (bytes, bits) = (depth / 8, depth % 8);
+        let mut prefix = key_hash[..bytes].to_vec();
+        if bits > 0 {
+            prefix.push(key_hash[bytes] & (0xff << (8 - bits)));
+        }
+        let key = [&(depth as u16).to_be_bytes()[..], &prefix].concat();
+        namespaces_with_key(&[self.nodes_namespace], &key)
+    }
+
+    fn load_node(
+        &self,
+        store: &dyn Storage,
+        key_hash: &Hash,
+        depth: usize,
+    ) -> StdResult<Option<Node>> {
+        store
+            .get(&self.node_key(key_hash, depth))
+            .map(|data| Node::from_bytes(&data))
+            .transpose()
+    }
+
+    fn save_node(&self, store: &mut dyn Storage, key_hash: &Hash, depth: usize, node: Node) {
+        store.set(&self.node_key(key_hash, depth), &node.to_bytes());
+    }
+
+    fn remove_node(&self, store: &mut dyn Storage, key_hash: &Hash, depth: usize) {
+        store.remove(&self.node_key(key_hash, depth));
+    }
+}
+
+// the key hash with the bit at the given depth inverted, to address the sibling subtree
+fn flip(key_hash: &Hash, depth: usize) -> Hash {
+    let mut flipped = *key_hash;
+    flipped[depth / 8] ^= 1 << (7 - depth % 8);
+    flipped
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use cosmwasm_std::testing::MockStorage;
+    #[cfg(feature = "iterator")]
+    use cosmwasm_std::Order;
+
+    const BALANCES: MerkleMap<&str, u64> = MerkleMap::new("balances", "balances__tree");
+
+    fn from_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[cfg(feature = "iterator")]
+    fn node_count(store: &dyn Storage) -> usize {
+        store
+            .range(None, None, Order::Ascending)
+            .filter(|(k, _)| k.starts_with(&namespaces_with_key(&[b"balances__tree"], b"")))
+            .count()
+    }
+
+    #[test]
+    fn root_test_vectors() {
+        let mut store = MockStorage::new();
+        assert_eq!(BALANCES.root(&store).unwrap(), Binary::from(EMPTY));
+
+        BALANCES.save(&mut store, "alice", &100).unwrap();
+        assert_eq!(
+            BALANCES.root(&store).unwrap().to_vec(),
+            from_hex("e88595db6e2373140c77336108e543dd79e1c6420dff08568b120417c8af88b5")
+        );
+
+        BALANCES.save(&mut store, "bob", &250).unwrap();
+        BALANCES.save(&mut store, "carl", &7).unwrap();
+        assert_eq!(
+            BALANCES.root(&store).unwrap().to_vec(),
+            from_hex("7c0d17ef2edf56d436b01e4ba36ad994cee0780089476cad4836421dacbe04ce")
+        );
+
+        BALANCES.remove(&mut store, "bob").unwrap();
+        assert_eq!(
+            BALANCES.root(&store).unwrap().to_vec(),
+            from_hex("a67d05b3de5130a3e8159d8362d887681bf81a99944cb9a8a4be622970bae9f2")
+        );
+        assert_eq!(BALANCES.may_load(&store, "bob").unwrap(), None);
+        assert_eq!(BALANCES.load(&store, "carl").unwrap(), 7);
+
+        BALANCES.remove(&mut store, "alice").unwrap();
+        BALANCES.remove(&mut store, "carl").unwrap();
+        assert_eq!(BALANCES.root(&store).unwrap(), Binary::from(EMPTY));
+        #[cfg(feature = "iterator")]
+        assert_eq!(node_count(&store), 0);
+    }
+
+    #[test]
+    fn proofs_verify() {
+        let mut store = MockStorage::new();
+        for i in 0..50u64 {
+            BALANCES
+                .save(&mut store, &format!("addr{}", i), &(i * 10))
+                .unwrap();
+        }
+        let root = BALANCES.root(&store).unwrap();
+
+        let proof = BALANCES.prove(&store, "addr7").unwrap().unwrap();
+        assert!(verify_merkle_proof(&root, b"addr7", b"70", &proof));
+        // wrong value, key or root
+        assert!(!verify_merkle_proof(&root, b"addr7", b"71", &proof));
+        assert!(!verify_merkle_proof(&root, b"addr8", b"70", &proof));
+        assert!(!verify_merkle_proof(&EMPTY, b"addr7", b"70", &proof));
+        // tampered proof
+        let mut bad = proof.clone();
+        bad.siblings.pop();
+        assert!(!verify_merkle_proof(&root, b"addr7", b"70", &bad));
+
+        assert_eq!(BALANCES.prove(&store, "addr50").unwrap(), None);
+
+        // proofs are invalidated by updates
+        BALANCES.save(&mut store, "addr7", &71).unwrap();
+        let new_root = BALANCES.root(&store).unwrap();
+        assert!(!verify_merkle_proof(&new_root, b"addr7", b"70", &proof));
+        let proof = BALANCES.prove(&store, "addr7").unwrap().unwrap();
+        assert!(verify_merkle_proof(&new_root, b"addr7", b"71", &proof));
+    }
+
+    #[test]
+    fn root_does_not_depend_on_history() {
+        let mut store = MockStorage::new();
+        for i in 0..40u64 {
+            BALANCES
+                .save(&mut store, &format!("addr{}", i), &i)
+                .unwrap();
+        }
+        for i in (0..40u64).filter(|i| i % 3 != 0) {
+            BALANCES.remove(&mut store, &format!("addr{}", i)).unwrap();
+        }
+        // removing a missing key is a no-op
+        BALANCES.remove(&mut store, "addr1").unwrap();
+
+        let mut fresh = MockStorage::new();
+        for i in (0..40u64).rev().filter(|i| i % 3 == 0) {
+            BALANCES
+                .save(&mut fresh, &format!("addr{}", i), &i)
+                .unwrap();
+        }
+        assert_eq!(
+            BALANCES.root(&store).unwrap(),
+            BALANCES.root(&fresh).unwrap()
+        );
+        // no leftover nodes either
+        #[cfg(feature = "iterator")]
+        assert_eq!(node_count(&store), node_count(&fresh));
+
+        let root = BALANCES.root(&store).unwrap();
+        for i in (0..40u64).filter(|i| i % 3 == 0) {
+            let key = format!("addr{}", i);
+            let proof = BALANCES.prove(&store, &key).unwrap().unwrap();
+            let value = to_vec(&i).unwrap();
+            assert!(verify_merkle_proof(&root, key.as_bytes(), &value, &proof));
+        }
+    }
+}
@@ -2,13 +2,14 @@ use serde::de::DeserializeOwned;
 use serde::Serialize;
 use std::marker::PhantomData;
 
+use crate::codec::{Codec, JsonCodec};
 use crate::helpers::{may_deserialize, must_deserialize, nested_namespaces_with_key};
 use crate::keys::Key;
-use cosmwasm_std::{to_vec, StdError, StdResult, Storage};
+use cosmwasm_std::{StdError, StdResult, Storage};
 use std::ops::Deref;
 
 #[derive(Debug, Clone)]
-pub struct Path<T>
+pub struct Path<T, C = JsonCodec>
 where
     T: Serialize + DeserializeOwned,
 {
@@ -16,9 +17,10 @@ where
     pub(crate) storage_key: Vec<u8>,
     // see https://doc.rust-lang.org/std/marker/struct.PhantomData.html#unused-type-parameters for why this is needed
     data: PhantomData<T>,
+    codec: PhantomData<C>,
 }
 
-impl<T> Deref for Path<T>
+impl<T, C> Deref for Path<T, C>
 where
     T: Serialize + DeserializeOwned,
 {
@@ -47,12 +49,33 @@ where
         Path {
             storage_key,
             data: PhantomData,
+            codec: PhantomData,
         }
     }
+}
 
+impl<T, C> Path<T, C>
+where
+    T: Serialize + DeserializeOwned,
+{
+    /// Switches to another codec for the stored value
+    pub fn with_codec<D>(self) -> Path<T, D> {
+        Path {
+            storage_key: self.storage_key,
+            data: PhantomData,
+            codec: PhantomData,
+        }
+    }
+}
+
+impl<T, C> Path<T, C>
+where
+    T: Serialize + DeserializeOwned,
+    C: Codec<T>,
+{
     /// save will serialize the model and store, returns an error on serialization issues
     pub fn save(&self, store: &mut dyn Storage, data: &T) -> StdResult<()> {
-        store.set(&self.storage_key, &to_vec(data)?);
+        store.set(&self.storage_key, &C::encode(data)?);
         Ok(())
     }
 
@@ -63,14 +86,14 @@ where
     /// load will return an error if no data is set at the given key, or on parse error
     pub fn load(&self, store: &dyn Storage) -> StdResult<T> {
         let value = store.get(&self.storage_key);
-        must_deserialize(&value)
+        must_deserialize::<T, C>(&value)
     }
 
     /// may_load will parse the data stored at the key if present, returns Ok(None) if no data there.
     /// returns an error on issues parsing
     pub fn may_load(&self, store: &dyn Storage) -> StdResult<Option<T>> {
         let value = store.get(&self.storage_key);
-        may_deserialize(&value)
+        may_deserialize::<T, C>(&value)
     }
 
     /// has returns true or false if any data is at this key, without parsing or interpreting the
@@ -7,9 +7,10 @@ use cosmwasm_std::{Order, Record, StdResult, Storage};
 use std::ops::Deref;
 
 use crate::bound::{PrefixBound, RawBound};
+use crate::codec::Codec;
 use crate::de::KeyDeserialize;
 use crate::helpers::{namespaces_with_key, nested_namespaces_with_key};
-use crate::iter_helpers::{concat, deserialize_kv, deserialize_v, trim};
+use crate::iter_helpers::{concat, decode_kv, decode_v, deserialize_kv, deserialize_v, trim};
 use crate::keys::Key;
 use crate::{Bound, Prefixer, PrimaryKey};
 
@@ -76,6 +77,17 @@ where
         )
     }
 
+    /// Like `new`, for values stored with the given codec
+    pub(crate) fn new_with_codec<C: Codec<T>>(top_name: &[u8], sub_names: &[Key]) -> Self {
+        Prefix::with_deserialization_functions(
+            top_name,
+            sub_names,
+            &[],
+            |_, _, kv| decode_kv::<K, T, C>(kv),
+            |_, _, kv| decode_v::<T, C>(kv),
+        )
+    }
+
     pub fn with_deserialization_functions(
         top_name: &[u8],
         sub_names: &[Key],
@@ -270,7 +282,7 @@ fn calc_prefix_end_bound<'a, K: Prefixer<'a>>(
     }
 }
 
-fn extend_one_byte(limit: &[u8]) -> Vec<u8> {
+pub(crate) fn extend_one_byte(limit: &[u8]) -> Vec<u8> {
     let mut v = limit.to_vec();
     v.push(0);
     v
@@ -279,7 +291,7 @@ fn extend_one_byte(limit: &[u8]) -> Vec<u8> {
 /// Returns a new vec of same length and last byte incremented by one
 /// If last bytes are 255, we handle overflow up the chain.
 /// If all bytes are 255, this returns wrong data - but that is never possible as a namespace
-fn increment_last_byte(input: &[u8]) -> Vec<u8> {
+pub(crate) fn increment_last_byte(input: &[u8]) -> Vec<u8> {
     let mut copy = input.to_vec();
     // zero out all trailing 255, increment first that is not such
     for i in (0..input.len()).rev() {
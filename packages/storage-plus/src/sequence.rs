@@ -0,0 +1,107 @@
+use cosmwasm_std::{OverflowError, OverflowOperation, StdError, StdResult, Storage};
+
+use crate::item::Item;
+
+/// Sequence allocates increasing `u64` ids, e.g. for the keys of a `Map` or `IndexedMap`.
+///
+/// It stores the last id allocated, as an `Item<u64>` would: a contract keeping its own counter
+/// as "last id used" in an `Item<u64>` can switch to a `Sequence` on the same key.
+pub struct Sequence<'a> {
+    last: Item<'a, u64>,
+    start: u64,
+}
+
+impl<'a> Sequence<'a> {
+    /// Creates a sequence starting at 1
+    pub const fn new(storage_key: &'a str) -> Self {
+        Sequence {
+            last: Item::new(storage_key),
+            start: 1,
+        }
+    }
+
+    /// Sets the first id allocated by the sequence. It has no effect once an id was allocated.
+    ///
+    /// ```rust
+    /// use cw_storage_plus::Sequence;
+    ///
+    /// const TOKEN_IDS: Sequence = Sequence::new("token_ids").with_start(1000);
+    /// ```
+    pub const fn with_start(self, start: u64) -> Self {
+        Sequence {
+            last: self.last,
+            start,
+        }
+    }
+
+    /// Allocates the next id
+    pub fn next(&self, store: &mut dyn Storage) -> StdResult<u64> {
+        let id = self.peek(store)?;
+        self.last.save(store, &id)?;
+        Ok(id)
+    }
+
+    /// Returns the id the next call to `next` will allocate, without allocating it
+    pub fn peek(&self, store: &dyn Storage) -> StdResult<u64> {
+        match self.last.may_load(store)? {
+            Some(last) => last.checked_add(1).ok_or_else(|| {
+                StdError::overflow(OverflowError::new(OverflowOperation::Add, last, 1))
+            }),
+            None => Ok(self.start),
+        }
+    }
+
+    /// Returns the last id allocated, or `None` if there was none
+    pub fn last(&self, store: &dyn Storage) -> StdResult<Option<u64>> {
+        self.last.may_load(store)
+    }
+
+    /// Sets the last id allocated, so the sequence continues from `last + 1`.
+    /// Lowering it makes the sequence allocate the same ids again.
+    pub fn set_last(&self, store: &mut dyn Storage, last: u64) -> StdResult<()> {
+        self.last.save(store, &last)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use cosmwasm_std::testing::MockStorage;
+
+    const IDS: Sequence = Sequence::new("ids");
+
+    #[test]
+    fn allocates_increasing_ids() {
+        let mut store = MockStorage::new();
+        assert_eq!(IDS.last(&store).unwrap(), None);
+        assert_eq!(IDS.peek(&store).unwrap(), 1);
+        assert_eq!(IDS.next(&mut store).unwrap(), 1);
+        assert_eq!(IDS.next(&mut store).unwrap(), 2);
+        assert_eq!(IDS.last(&store).unwrap(), Some(2));
+        assert_eq!(IDS.peek(&store).unwrap(), 3);
+
+        // compatible with a plain counter
+        let counter: Item<u64> = Item::new("ids");
+        assert_eq!(counter.load(&store).unwrap(), 2);
+        counter.save(&mut store, &41).unwrap();
+        assert_eq!(IDS.next(&mut store).unwrap(), 42);
+
+        IDS.set_last(&mut store, u64::MAX).unwrap();
+        let err = IDS.next(&mut store).unwrap_err();
+        assert!(matches!(err, StdError::Overflow { .. }));
+    }
+
+    #[test]
+    fn custom_start() {
+        let mut store = MockStorage::new();
+        let seq = Sequence::new("seq").with_start(0);
+        assert_eq!(seq.next(&mut store).unwrap(), 0);
+        assert_eq!(seq.next(&mut store).unwrap(), 1);
+
+        let seq = Sequence::new("other").with_start(1000);
+        assert_eq!(seq.next(&mut store).unwrap(), 1000);
+        // independent
+        assert_eq!(IDS.next(&mut store).unwrap(), 1);
+    }
+}
@@ -1,21 +1,34 @@
 use serde::de::DeserializeOwned;
 use serde::Serialize;
+use std::marker::PhantomData;
 
-use cosmwasm_std::{StdError, StdResult, Storage};
+use cosmwasm_std::{StdError, StdResult, Storage, Timestamp};
 
-use crate::snapshot::{ChangeSet, Snapshot};
+use crate::codec::{Codec, JsonCodec};
+use crate::snapshot::{
+    ByHeight, ByTime, ChangeSet, CheckpointKind, PruneProgress, Retention, Snapshot,
+};
 use crate::{Item, Map, Strategy};
 
 /// Item that maintains a snapshot of one or more checkpoints.
 /// We can query historical data as well as current state.
 /// What data is snapshotted depends on the Strategy.
-pub struct SnapshotItem<'a, T> {
-    primary: Item<'a, T>,
+///
+/// Checkpoints are indexed by block height by default. Use `ByTime` as the last type
+/// parameter to index them by block time instead.
+///
+/// `V` is the codec of the current value (see `Item`). The changelog is always stored as JSON,
+/// whatever the codec.
+pub struct SnapshotItem<'a, T, C = ByHeight, V = JsonCodec> {
+    primary: Item<'a, T, V>,
     changelog_namespace: &'a str,
     snapshots: Snapshot<'a, (), T>,
+    checkpoint_kind: PhantomData<C>,
 }
 
-impl<'a, T> SnapshotItem<'a, T> {
+impl<'a, T> SnapshotItem<'a, T, ByHeight> {
+    /// Creates an item with checkpoints indexed by block height.
+    ///
     /// Example:
     ///
     /// ```rust
@@ -32,20 +45,59 @@ impl<'a, T> SnapshotItem<'a, T> {
         checkpoints: &'a str,
         changelog: &'a str,
         strategy: Strategy,
+    ) -> Self {
+        Self::new_with_kind(storage_key, checkpoints, changelog, strategy)
+    }
+}
+
+impl<'a, T> SnapshotItem<'a, T, ByTime> {
+    /// Creates an item with checkpoints indexed by block time.
+    pub const fn new_timed(
+        storage_key: &'a str,
+        checkpoints: &'a str,
+        changelog: &'a str,
+        strategy: Strategy,
+    ) -> Self {
+        Self::new_with_kind(storage_key, checkpoints, changelog, strategy)
+    }
+}
+
+impl<'a, T, C> SnapshotItem<'a, T, C> {
+    const fn new_with_kind(
+        storage_key: &'a str,
+        checkpoints: &'a str,
+        changelog: &'a str,
+        strategy: Strategy,
     ) -> Self {
         SnapshotItem {
             primary: Item::new(storage_key),
             changelog_namespace: changelog,
             snapshots: Snapshot::new(checkpoints, changelog, strategy),
+            checkpoint_kind: PhantomData,
         }
     }
+}
 
-    pub fn add_checkpoint(&self, store: &mut dyn Storage, height: u64) -> StdResult<()> {
-        self.snapshots.add_checkpoint(store, height)
+impl<'a, T, C, V> SnapshotItem<'a, T, C, V> {
+    /// Switches to another codec for the current value, see `Item::with_codec`.
+    /// The changelog is always stored as JSON.
+    pub const fn with_codec<D>(self) -> SnapshotItem<'a, T, C, D> {
+        SnapshotItem {
+            primary: self.primary.with_codec(),
+            changelog_namespace: self.changelog_namespace,
+            snapshots: self.snapshots,
+            checkpoint_kind: PhantomData,
+        }
     }
 
-    pub fn remove_checkpoint(&self, store: &mut dyn Storage, height: u64) -> StdResult<()> {
-        self.snapshots.remove_checkpoint(store, height)
+    /// Enables pruning, and sets how much history is kept. See `SnapshotMap::with_pruning`.
+    pub const fn with_pruning(self, namespace: &'a str, retention: Retention) -> Self {
+        SnapshotItem {
+            primary: self.primary,
+            changelog_namespace: self.changelog_namespace,
+            snapshots: self.snapshots.with_pruning(namespace, retention),
+            checkpoint_kind: PhantomData,
+        }
     }
 
     pub fn changelog(&self) -> Map<u64, ChangeSet<T>> {
@@ -54,9 +106,25 @@ impl<'a, T> SnapshotItem<'a, T> {
     }
 }
 
-impl<'a, T> SnapshotItem<'a, T>
+impl<'a, T, C, V> SnapshotItem<'a, T, C, V>
+where
+    C: CheckpointKind,
+{
+    pub fn add_checkpoint(&self, store: &mut dyn Storage, at: C::Point) -> StdResult<()> {
+        self.snapshots.add_checkpoint(store, C::checkpoint_key(at))
+    }
+
+    pub fn remove_checkpoint(&self, store: &mut dyn Storage, at: C::Point) -> StdResult<()> {
+        self.snapshots
+            .remove_checkpoint(store, C::checkpoint_key(at))
+    }
+}
+
+impl<'a, T, C, V> SnapshotItem<'a, T, C, V>
 where
     T: Serialize + DeserializeOwned + Clone,
+    C: CheckpointKind,
+    V: Codec<T>,
 {
     /// load old value and store changelog
     fn write_change(&self, store: &mut dyn Storage, height: u64) -> StdResult<()> {
@@ -69,16 +137,16 @@ where
         self.snapshots.write_changelog(store, (), height, old)
     }
 
-    pub fn save(&self, store: &mut dyn Storage, data: &T, height: u64) -> StdResult<()> {
+    pub fn save(&self, store: &mut dyn Storage, data: &T, at: C::Point) -> StdResult<()> {
         if self.snapshots.should_checkpoint(store, &())? {
-            self.write_change(store, height)?;
+            self.write_change(store, C::checkpoint_key(at))?;
         }
         self.primary.save(store, data)
     }
 
-    pub fn remove(&self, store: &mut dyn Storage, height: u64) -> StdResult<()> {
+    pub fn remove(&self, store: &mut dyn Storage, at: C::Point) -> StdResult<()> {
         if self.snapshots.should_checkpoint(store, &())? {
-            self.write_change(store, height)?;
+            self.write_change(store, C::checkpoint_key(at))?;
         }
         self.primary.remove(store);
         Ok(())
@@ -95,8 +163,11 @@ where
         self.primary.may_load(store)
     }
 
-    pub fn may_load_at_height(&self, store: &dyn Storage, height: u64) -> StdResult<Option<T>> {
-        let snapshot = self.snapshots.may_load_at_height(store, (), height)?;
+    /// Returns the value at the beginning of the given checkpoint (height or time)
+    pub fn may_load_at(&self, store: &dyn Storage, at: C::Point) -> StdResult<Option<T>> {
+        let snapshot = self
+            .snapshots
+            .may_load_at_height(store, (), C::checkpoint_key(at))?;
 
         if let Some(r) = snapshot {
             Ok(r)
@@ -107,8 +178,28 @@ where
     }
 
     // If there is no checkpoint for that height, then we return StdError::NotFound
-    pub fn assert_checkpointed(&self, store: &dyn Storage, height: u64) -> StdResult<()> {
-        self.snapshots.assert_checkpointed(store, height)
+    pub fn assert_checkpointed(&self, store: &dyn Storage, at: C::Point) -> StdResult<()> {
+        self.snapshots
+            .assert_checkpointed(store, C::checkpoint_key(at))
+    }
+
+    /// Returns the lowest checkpoint position (height or seconds) that can still be queried,
+    /// or `None` if nothing was pruned
+    pub fn pruned_height(&self, store: &dyn Storage) -> StdResult<Option<u64>> {
+        self.snapshots.pruned_height(store)
+    }
+
+    /// Removes the checkpoints and changelog entries older than `below`, going over at most
+    /// `limit` entries. Call it again until the result is `done`.
+    /// Historical queries below `below` return an error afterwards.
+    /// Requires pruning to be enabled with `with_pruning`.
+    pub fn prune(
+        &self,
+        store: &mut dyn Storage,
+        below: C::Point,
+        limit: usize,
+    ) -> StdResult<PruneProgress> {
+        self.snapshots.prune(store, C::checkpoint_key(below), limit)
     }
 
     /// Loads the data, perform the specified action, and store the result in the database.
@@ -117,18 +208,38 @@ where
     /// If the data exists, `action(Some(value))` is called. Otherwise `action(None)` is called.
     ///
     /// This is a bit more customized than needed to only read "old" value 1 time, not 2 per naive approach
-    pub fn update<A, E>(&self, store: &mut dyn Storage, height: u64, action: A) -> Result<T, E>
+    pub fn update<A, E>(&self, store: &mut dyn Storage, at: C::Point, action: A) -> Result<T, E>
     where
         A: FnOnce(Option<T>) -> Result<T, E>,
         E: From<StdError>,
     {
         let input = self.may_load(store)?;
         let output = action(input)?;
-        self.save(store, &output, height)?;
+        self.save(store, &output, at)?;
         Ok(output)
     }
 }
 
+impl<'a, T, V> SnapshotItem<'a, T, ByHeight, V>
+where
+    T: Serialize + DeserializeOwned + Clone,
+    V: Codec<T>,
+{
+    pub fn may_load_at_height(&self, store: &dyn Storage, height: u64) -> StdResult<Option<T>> {
+        self.may_load_at(store, height)
+    }
+}
+
+impl<'a, T, V> SnapshotItem<'a, T, ByTime, V>
+where
+    T: Serialize + DeserializeOwned + Clone,
+    V: Codec<T>,
+{
+    pub fn may_load_at_time(&self, store: &dyn Storage, time: Timestamp) -> StdResult<Option<T>> {
+        self.may_load_at(store, time)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -322,4 +433,80 @@ mod tests {
         assert_eq!(1, all.len());
         assert_eq!(all, vec![(4, ChangeSet { old: Some(8) }),]);
     }
+
+    #[test]
+    fn prune_and_retention() {
+        const PRUNED: TestItem = SnapshotItem::new(
+            "every",
+            "every__check",
+            "every__change",
+            Strategy::EveryBlock,
+        )
+        .with_pruning("every__pruning", Retention::Forever);
+
+        let mut store = MockStorage::new();
+        init_data(&PRUNED, &mut store);
+
+        // changes at 1, 2, 3, 4 and 5
+        let progress = PRUNED.prune(&mut store, 3, 2).unwrap();
+        assert_eq!(
+            PruneProgress {
+                removed: 2,
+                done: false
+            },
+            progress
+        );
+        let progress = PRUNED.prune(&mut store, 3, 2).unwrap();
+        assert_eq!(
+            PruneProgress {
+                removed: 0,
+                done: true
+            },
+            progress
+        );
+        assert_eq!(Some(7), PRUNED.may_load_at_height(&store, 3).unwrap());
+        assert!(PRUNED.may_load_at_height(&store, 2).is_err());
+
+        const RECENT: TestItem = SnapshotItem::new(
+            "recent",
+            "recent__check",
+            "recent__change",
+            Strategy::EveryBlock,
+        )
+        .with_pruning("recent__pruning", Retention::LastHeights(1));
+        for height in 1..=5 {
+            RECENT.save(&mut store, &height, height).unwrap();
+        }
+        let changes = RECENT
+            .changelog()
+            .keys_raw(&store, None, None, cosmwasm_std::Order::Ascending)
+            .count();
+        assert_eq!(2, changes);
+        assert_eq!(Some(4), RECENT.may_load_at_height(&store, 5).unwrap());
+        assert!(RECENT.may_load_at_height(&store, 3).is_err());
+    }
+
+    #[test]
+    fn time_based_snapshots() {
+        const TIMED: SnapshotItem<u64, ByTime> = SnapshotItem::new_timed(
+            "timed",
+            "timed__check",
+            "timed__change",
+            Strategy::EveryBlock,
+        );
+
+        let mut store = MockStorage::new();
+        TIMED
+            .save(&mut store, &5, Timestamp::from_seconds(100))
+            .unwrap();
+        TIMED
+            .save(&mut store, &7, Timestamp::from_seconds(200))
+            .unwrap();
+
+        let at = |secs| TIMED.may_load_at_time(&store, Timestamp::from_seconds(secs));
+        assert_eq!(None, at(100).unwrap());
+        assert_eq!(Some(5), at(150).unwrap());
+        assert_eq!(Some(5), at(200).unwrap());
+        assert_eq!(Some(7), at(201).unwrap());
+    }
 }
@@ -1,27 +1,40 @@
 use serde::de::DeserializeOwned;
 use serde::Serialize;
+use std::marker::PhantomData;
 
-use cosmwasm_std::{StdError, StdResult, Storage};
+use cosmwasm_std::{StdError, StdResult, Storage, Timestamp};
 
 use crate::bound::PrefixBound;
+use crate::codec::{Codec, JsonCodec};
 use crate::de::KeyDeserialize;
-use crate::iter_helpers::deserialize_kv;
+use crate::iter_helpers::decode_kv;
 use crate::keys::PrimaryKey;
 use crate::map::Map;
 use crate::path::Path;
 use crate::prefix::{namespaced_prefix_range, Prefix};
-use crate::snapshot::{ChangeSet, Snapshot};
+use crate::snapshot::{
+    ByHeight, ByTime, ChangeSet, CheckpointKind, PruneProgress, Retention, Snapshot,
+};
 use crate::{Bound, Prefixer, Strategy};
 
 /// Map that maintains a snapshots of one or more checkpoints.
 /// We can query historical data as well as current state.
 /// What data is snapshotted depends on the Strategy.
-pub struct SnapshotMap<'a, K, T> {
-    primary: Map<'a, K, T>,
+///
+/// Checkpoints are indexed by block height by default. Use `ByTime` as the `C` type
+/// parameter to index them by block time instead.
+///
+/// `V` is the codec of the current values (see `Map`). The changelog is always stored as JSON,
+/// whatever the codec.
+pub struct SnapshotMap<'a, K, T, C = ByHeight, V = JsonCodec> {
+    primary: Map<'a, K, T, V>,
     snapshots: Snapshot<'a, K, T>,
+    checkpoint_kind: PhantomData<C>,
 }
 
-impl<'a, K, T> SnapshotMap<'a, K, T> {
+impl<'a, K, T> SnapshotMap<'a, K, T, ByHeight> {
+    /// Creates a map with checkpoints indexed by block height.
+    ///
     /// Example:
     ///
     /// ```rust
@@ -39,10 +52,80 @@ impl<'a, K, T> SnapshotMap<'a, K, T> {
         checkpoints: &'a str,
         changelog: &'a str,
         strategy: Strategy,
+    ) -> Self {
+        Self::new_with_kind(pk, checkpoints, changelog, strategy)
+    }
+}
+
+impl<'a, K, T> SnapshotMap<'a, K, T, ByTime> {
+    /// Creates a map with checkpoints indexed by block time.
+    ///
+    /// Example:
+    ///
+    /// ```rust
+    /// use cw_storage_plus::{ByTime, SnapshotMap, Strategy};
+    ///
+    /// SnapshotMap::<&[u8], &str, ByTime>::new_timed(
+    ///     "timed",
+    ///     "timed__check",
+    ///     "timed__change",
+    ///     Strategy::EveryBlock
+    /// );
+    /// ```
+    pub const fn new_timed(
+        pk: &'a str,
+        checkpoints: &'a str,
+        changelog: &'a str,
+        strategy: Strategy,
+    ) -> Self {
+        Self::new_with_kind(pk, checkpoints, changelog, strategy)
+    }
+}
+
+impl<'a, K, T, C> SnapshotMap<'a, K, T, C> {
+    const fn new_with_kind(
+        pk: &'a str,
+        checkpoints: &'a str,
+        changelog: &'a str,
+        strategy: Strategy,
     ) -> Self {
         SnapshotMap {
             primary: Map::new(pk),
             snapshots: Snapshot::new(checkpoints, changelog, strategy),
+            checkpoint_kind: PhantomData,
+        }
+    }
+}
+
+impl<'a, K, T, C, V> SnapshotMap<'a, K, T, C, V> {
+    /// Switches to another codec for the current values, see `Map::with_codec`
+    pub const fn with_codec<D>(self) -> SnapshotMap<'a, K, T, C, D> {
+        SnapshotMap {
+            primary: self.primary.with_codec(),
+            snapshots: self.snapshots,
+            checkpoint_kind: PhantomData,
+        }
+    }
+
+    /// Enables pruning, and sets how much history is kept. See `Retention` for details.
+    /// The lowest height that can be queried, and the progress of `prune`, are stored under
+    /// `namespace`.
+    ///
+    /// ```rust
+    /// use cw_storage_plus::{Retention, SnapshotMap, Strategy};
+    ///
+    /// SnapshotMap::<&[u8], u64>::new(
+    ///     "recent",
+    ///     "recent__check",
+    ///     "recent__change",
+    ///     Strategy::EveryBlock
+    /// ).with_pruning("recent__pruning", Retention::LastHeights(1000));
+    /// ```
+    pub const fn with_pruning(self, namespace: &'a str, retention: Retention) -> Self {
+        SnapshotMap {
+            primary: self.primary,
+            snapshots: self.snapshots.with_pruning(namespace, retention),
+            checkpoint_kind: PhantomData,
         }
     }
 
@@ -51,33 +134,40 @@ impl<'a, K, T> SnapshotMap<'a, K, T> {
     }
 }
 
-impl<'a, K, T> SnapshotMap<'a, K, T>
+impl<'a, K, T, C, V> SnapshotMap<'a, K, T, C, V>
 where
     T: Serialize + DeserializeOwned + Clone,
     K: PrimaryKey<'a> + Prefixer<'a>,
+    C: CheckpointKind,
+    V: Codec<T>,
 {
-    pub fn add_checkpoint(&self, store: &mut dyn Storage, height: u64) -> StdResult<()> {
-        self.snapshots.add_checkpoint(store, height)
+    pub fn add_checkpoint(&self, store: &mut dyn Storage, at: C::Point) -> StdResult<()> {
+        self.snapshots.add_checkpoint(store, C::checkpoint_key(at))
+    }
+
+    pub fn remove_checkpoint(&self, store: &mut dyn Storage, at: C::Point) -> StdResult<()> {
+        self.snapshots
+            .remove_checkpoint(store, C::checkpoint_key(at))
     }
 
-    pub fn remove_checkpoint(&self, store: &mut dyn Storage, height: u64) -> StdResult<()> {
-        self.snapshots.remove_checkpoint(store, height)
+    /// Returns the lowest checkpoint position (height or seconds) that can still be queried,
+    /// or `None` if nothing was pruned
+    pub fn pruned_height(&self, store: &dyn Storage) -> StdResult<Option<u64>> {
+        self.snapshots.pruned_height(store)
     }
 }
 
-impl<'a, K, T> SnapshotMap<'a, K, T>
+impl<'a, K, T, C, V> SnapshotMap<'a, K, T, C, V>
 where
     T: Serialize + DeserializeOwned + Clone,
     K: PrimaryKey<'a> + Prefixer<'a> + KeyDeserialize,
+    C: CheckpointKind,
+    V: Codec<T>,
 {
-    pub fn key(&self, k: K) -> Path<T> {
+    pub fn key(&self, k: K) -> Path<T, V> {
         self.primary.key(k)
     }
 
-    fn no_prefix_raw(&self) -> Prefix<Vec<u8>, T, K> {
-        self.primary.no_prefix_raw()
-    }
-
     /// load old value and store changelog
     fn write_change(&self, store: &mut dyn Storage, k: K, height: u64) -> StdResult<()> {
         // if there is already data in the changelog for this key and block, do not write more
@@ -89,16 +179,16 @@ where
         self.snapshots.write_changelog(store, k, height, old)
     }
 
-    pub fn save(&self, store: &mut dyn Storage, k: K, data: &T, height: u64) -> StdResult<()> {
+    pub fn save(&self, store: &mut dyn Storage, k: K, data: &T, at: C::Point) -> StdResult<()> {
         if self.snapshots.should_checkpoint(store, &k)? {
-            self.write_change(store, k.clone(), height)?;
+            self.write_change(store, k.clone(), C::checkpoint_key(at))?;
         }
         self.primary.save(store, k, data)
     }
 
-    pub fn remove(&self, store: &mut dyn Storage, k: K, height: u64) -> StdResult<()> {
+    pub fn remove(&self, store: &mut dyn Storage, k: K, at: C::Point) -> StdResult<()> {
         if self.snapshots.should_checkpoint(store, &k)? {
-            self.write_change(store, k.clone(), height)?;
+            self.write_change(store, k.clone(), C::checkpoint_key(at))?;
         }
         self.primary.remove(store, k);
         Ok(())
@@ -115,15 +205,11 @@ where
         self.primary.may_load(store, k)
     }
 
-    pub fn may_load_at_height(
-        &self,
-        store: &dyn Storage,
-        k: K,
-        height: u64,
-    ) -> StdResult<Option<T>> {
-        let snapshot = self
-            .snapshots
-            .may_load_at_height(store, k.clone(), height)?;
+    /// Returns the value at the beginning of the given checkpoint (height or time)
+    pub fn may_load_at(&self, store: &dyn Storage, k: K, at: C::Point) -> StdResult<Option<T>> {
+        let snapshot =
+            self.snapshots
+                .may_load_at_height(store, k.clone(), C::checkpoint_key(at))?;
 
         if let Some(r) = snapshot {
             Ok(r)
@@ -133,8 +219,22 @@ where
         }
     }
 
-    pub fn assert_checkpointed(&self, store: &dyn Storage, height: u64) -> StdResult<()> {
-        self.snapshots.assert_checkpointed(store, height)
+    pub fn assert_checkpointed(&self, store: &dyn Storage, at: C::Point) -> StdResult<()> {
+        self.snapshots
+            .assert_checkpointed(store, C::checkpoint_key(at))
+    }
+
+    /// Removes the checkpoints and changelog entries older than `below`, going over at most
+    /// `limit` entries. Call it again until the result is `done`.
+    /// Historical queries below `below` return an error afterwards.
+    /// Requires pruning to be enabled with `with_pruning`.
+    pub fn prune(
+        &self,
+        store: &mut dyn Storage,
+        below: C::Point,
+        limit: usize,
+    ) -> StdResult<PruneProgress> {
+        self.snapshots.prune(store, C::checkpoint_key(below), limit)
     }
 
     /// Loads the data, perform the specified action, and store the result
@@ -147,7 +247,7 @@ where
         &self,
         store: &mut dyn Storage,
         k: K,
-        height: u64,
+        at: C::Point,
         action: A,
     ) -> Result<T, E>
     where
@@ -156,16 +256,49 @@ where
     {
         let input = self.may_load(store, k.clone())?;
         let output = action(input)?;
-        self.save(store, k, &output, height)?;
+        self.save(store, k, &output, at)?;
         Ok(output)
     }
 }
 
+impl<'a, K, T, V> SnapshotMap<'a, K, T, ByHeight, V>
+where
+    T: Serialize + DeserializeOwned + Clone,
+    K: PrimaryKey<'a> + Prefixer<'a> + KeyDeserialize,
+    V: Codec<T>,
+{
+    pub fn may_load_at_height(
+        &self,
+        store: &dyn Storage,
+        k: K,
+        height: u64,
+    ) -> StdResult<Option<T>> {
+        self.may_load_at(store, k, height)
+    }
+}
+
+impl<'a, K, T, V> SnapshotMap<'a, K, T, ByTime, V>
+where
+    T: Serialize + DeserializeOwned + Clone,
+    K: PrimaryKey<'a> + Prefixer<'a> + KeyDeserialize,
+    V: Codec<T>,
+{
+    pub fn may_load_at_time(
+        &self,
+        store: &dyn Storage,
+        k: K,
+        time: Timestamp,
+    ) -> StdResult<Option<T>> {
+        self.may_load_at(store, k, time)
+    }
+}
+
 // short-cut for simple keys, rather than .prefix(()).range_raw(...)
-impl<'a, K, T> SnapshotMap<'a, K, T>
+impl<'a, K, T, C, V> SnapshotMap<'a, K, T, C, V>
 where
     T: Serialize + DeserializeOwned + Clone,
     K: PrimaryKey<'a> + Prefixer<'a> + KeyDeserialize,
+    V: Codec<T>,
 {
     // I would prefer not to copy code from Prefix, but no other way
     // with lifetimes (create Prefix inside function and return ref = no no)
@@ -179,7 +312,9 @@ where
     where
         T: 'c,
     {
-        self.no_prefix_raw().range_raw(store, min, max, order)
+        self.primary
+            .no_prefix_raw()
+            .range_raw(store, min, max, order)
     }
 
     pub fn keys_raw<'c>(
@@ -192,15 +327,18 @@ where
     where
         T: 'c,
     {
-        self.no_prefix_raw().keys_raw(store, min, max, order)
+        self.primary
+            .no_prefix_raw()
+            .keys_raw(store, min, max, order)
     }
 }
 
 #[cfg(feature = "iterator")]
-impl<'a, K, T> SnapshotMap<'a, K, T>
+impl<'a, K, T, C, V> SnapshotMap<'a, K, T, C, V>
 where
     T: Serialize + DeserializeOwned,
     K: PrimaryKey<'a> + KeyDeserialize,
+    V: Codec<T>,
 {
     /// While `range` over a `prefix` fixes the prefix to one element and iterates over the
     /// remaining, `prefix_range` accepts bounds for the lowest and highest elements of the
@@ -220,9 +358,10 @@ where
         'a: 'c,
         K: 'c,
         K::Output: 'static,
+        V: 'c,
     {
         let mapped = namespaced_prefix_range(store, self.primary.namespace(), min, max, order)
-            .map(deserialize_kv::<K, T>);
+            .map(decode_kv::<K, T, V>);
         Box::new(mapped)
     }
 
@@ -255,15 +394,15 @@ where
     }
 
     pub fn prefix(&self, p: K::Prefix) -> Prefix<K::Suffix, T, K::Suffix> {
-        Prefix::new(self.primary.namespace(), &p.prefix())
+        Prefix::new_with_codec::<V>(self.primary.namespace(), &p.prefix())
     }
 
     pub fn sub_prefix(&self, p: K::SubPrefix) -> Prefix<K::SuperSuffix, T, K::SuperSuffix> {
-        Prefix::new(self.primary.namespace(), &p.prefix())
+        Prefix::new_with_codec::<V>(self.primary.namespace(), &p.prefix())
     }
 
     fn no_prefix(&self) -> Prefix<K, T, K> {
-        Prefix::new(self.primary.namespace(), &[])
+        Prefix::new_with_codec::<V>(self.primary.namespace(), &[])
     }
 }
 
@@ -641,4 +780,300 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn prune_removes_old_history() {
+        const PRUNED: TestMap = SnapshotMap::new(
+            "every",
+            "every__check",
+            "every__change",
+            Strategy::EveryBlock,
+        )
+        .with_pruning("every__pruning", Retention::Forever);
+
+        let mut storage = MockStorage::new();
+        init_data(&PRUNED, &mut storage);
+        assert_eq!(None, PRUNED.pruned_height(&storage).unwrap());
+
+        // removing everything below 3, going over one entry at a time
+        let mut removed = 0;
+        let mut calls = 0;
+        loop {
+            let progress = PRUNED.prune(&mut storage, 3, 1).unwrap();
+            assert_eq!(Some(3), PRUNED.pruned_height(&storage).unwrap());
+            removed += progress.removed;
+            calls += 1;
+            if progress.done {
+                break;
+            }
+        }
+        assert_eq!(2, removed);
+        // each call resumes after the last entry visited: A@1, A@3, B@2, B@4, C@3 and D@5,
+        // then the end of the changelog
+        assert_eq!(7, calls);
+
+        // history from 3 on is intact
+        assert_final_values(&PRUNED, &storage);
+        assert_values_at_height(&PRUNED, &storage, 3, VALUES_START_3);
+        assert_values_at_height(&PRUNED, &storage, 5, VALUES_START_5);
+
+        // while older history reports the pruning
+        let err = PRUNED.may_load_at_height(&storage, "A", 2).unwrap_err();
+        assert_eq!(
+            StdError::generic_err(
+                "Snapshot at height 2 has been pruned, history is available from height 3"
+            ),
+            err
+        );
+
+        // pruning again below an older height does not lower the limit
+        let progress = PRUNED.prune(&mut storage, 1, 10).unwrap();
+        assert_eq!(
+            PruneProgress {
+                removed: 0,
+                done: true
+            },
+            progress
+        );
+        assert_eq!(Some(3), PRUNED.pruned_height(&storage).unwrap());
+
+        // pruning up to 5 removes the checkpoint at 3 and the changes at 3 and 4
+        let progress = PRUNED.prune(&mut storage, 5, 10).unwrap();
+        assert_eq!(
+            PruneProgress {
+                removed: 5,
+                done: true
+            },
+            progress
+        );
+        assert_values_at_height(&PRUNED, &storage, 5, VALUES_START_5);
+        let remaining = PRUNED
+            .changelog()
+            .keys(&storage, None, None, cosmwasm_std::Order::Ascending)
+            .count();
+        assert_eq!(2, remaining);
+
+        // pruning must be enabled
+        EVERY.prune(&mut storage, 5, 10).unwrap_err();
+    }
+
+    #[test]
+    fn prune_removes_checkpoints() {
+        const PRUNED: TestMap = SnapshotMap::new(
+            "select",
+            "select__check",
+            "select__change",
+            Strategy::Selected,
+        )
+        .with_pruning("select__pruning", Retention::Forever);
+
+        let mut storage = MockStorage::new();
+        init_data(&PRUNED, &mut storage);
+        assert_values_at_height(&PRUNED, &storage, 3, VALUES_START_3);
+
+        // checkpoint at 3 and the 2 changes written at that height
+        let progress = PRUNED.prune(&mut storage, 4, 10).unwrap();
+        assert_eq!(3, progress.removed);
+        assert!(progress.done);
+        assert!(PRUNED.may_load_at_height(&storage, "A", 3).is_err());
+        assert_final_values(&PRUNED, &storage);
+    }
+
+    #[test]
+    fn prune_resumes_changelog_after_checkpoints() {
+        const PRUNED: TestMap =
+            SnapshotMap::new("many", "many__check", "many__change", Strategy::Selected)
+                .with_pruning("many__pruning", Retention::Forever);
+
+        let mut storage = MockStorage::new();
+        for height in 1..=4 {
+            PRUNED.add_checkpoint(&mut storage, height).unwrap();
+        }
+        PRUNED.save(&mut storage, "A", &1, 2).unwrap();
+        PRUNED.save(&mut storage, "B", &1, 3).unwrap();
+        PRUNED.add_checkpoint(&mut storage, 5).unwrap();
+        PRUNED.save(&mut storage, "A", &2, 5).unwrap();
+
+        // the first call only removes checkpoints, the changelog is visited by the next ones
+        let progress = PRUNED.prune(&mut storage, 5, 2).unwrap();
+        assert_eq!(
+            PruneProgress {
+                removed: 2,
+                done: false
+            },
+            progress
+        );
+        let mut removed = progress.removed;
+        loop {
+            let progress = PRUNED.prune(&mut storage, 5, 2).unwrap();
+            removed += progress.removed;
+            if progress.done {
+                break;
+            }
+        }
+        assert_eq!(6, removed);
+        let remaining = PRUNED
+            .changelog()
+            .keys(&storage, None, None, cosmwasm_std::Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(remaining, vec![("A".to_string(), 5)]);
+        assert_eq!(
+            Some(1),
+            PRUNED.may_load_at_height(&storage, "A", 5).unwrap()
+        );
+    }
+
+    #[test]
+    fn retention_drops_old_changes_on_write() {
+        const RECENT: TestMap = SnapshotMap::new(
+            "recent",
+            "recent__check",
+            "recent__change",
+            Strategy::EveryBlock,
+        )
+        .with_pruning("recent__pruning", Retention::LastHeights(2));
+
+        let mut storage = MockStorage::new();
+        for height in 1..=10 {
+            RECENT.save(&mut storage, "A", &height, height).unwrap();
+        }
+        RECENT.save(&mut storage, "B", &1, 10).unwrap();
+
+        // only the last two heights of A are left, plus B
+        let changes = RECENT
+            .changelog()
+            .keys(&storage, None, None, cosmwasm_std::Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            changes,
+            vec![
+                ("A".to_string(), 8),
+                ("A".to_string(), 9),
+                ("A".to_string(), 10),
+                ("B".to_string(), 10)
+            ]
+        );
+
+        assert_eq!(Some(8), RECENT.pruned_height(&storage).unwrap());
+        assert_eq!(
+            Some(8),
+            RECENT.may_load_at_height(&storage, "A", 9).unwrap()
+        );
+        assert_eq!(None, RECENT.may_load_at_height(&storage, "B", 8).unwrap());
+        assert!(RECENT.may_load_at_height(&storage, "A", 7).is_err());
+    }
+
+    #[test]
+    fn retention_above_height() {
+        const ABOVE: TestMap = SnapshotMap::new(
+            "above",
+            "above__check",
+            "above__change",
+            Strategy::EveryBlock,
+        )
+        .with_pruning("above__pruning", Retention::AboveHeight(3));
+
+        let mut storage = MockStorage::new();
+        ABOVE.save(&mut storage, "A", &1, 1).unwrap();
+        ABOVE.save(&mut storage, "A", &2, 5).unwrap();
+
+        assert_eq!(Some(4), ABOVE.pruned_height(&storage).unwrap());
+        assert!(ABOVE.may_load_at_height(&storage, "A", 3).is_err());
+        assert_eq!(Some(1), ABOVE.may_load_at_height(&storage, "A", 4).unwrap());
+        assert_eq!(Some(2), ABOVE.may_load_at_height(&storage, "A", 6).unwrap());
+    }
+
+    #[test]
+    fn time_based_snapshots() {
+        const TIMED: SnapshotMap<&str, u64, ByTime> = SnapshotMap::new_timed(
+            "timed",
+            "timed__check",
+            "timed__change",
+            Strategy::EveryBlock,
+        )
+        .with_pruning("timed__pruning", Retention::Forever);
+
+        let mut storage = MockStorage::new();
+        let t = |secs| Timestamp::from_seconds(secs);
+
+        TIMED.save(&mut storage, "A", &5, t(1000)).unwrap();
+        TIMED.save(&mut storage, "A", &8, t(1500)).unwrap();
+        TIMED
+            .update(&mut storage, "B", t(1500), |_| -> StdResult<u64> { Ok(3) })
+            .unwrap();
+        TIMED.remove(&mut storage, "A", t(2000)).unwrap();
+
+        // values are those at the beginning of the given second
+        let at = |k, secs| TIMED.may_load_at_time(&storage, k, t(secs)).unwrap();
+        assert_eq!(None, at("A", 1000));
+        assert_eq!(Some(5), at("A", 1001));
+        assert_eq!(Some(5), at("A", 1500));
+        assert_eq!(Some(8), at("A", 2000));
+        assert_eq!(None, at("A", 2001));
+        assert_eq!(None, at("B", 1500));
+        assert_eq!(Some(3), at("B", 1501));
+
+        // sub-second precision is dropped
+        assert_eq!(
+            Some(8),
+            TIMED
+                .may_load_at_time(&storage, "A", Timestamp::from_nanos(1_999_999_999_999))
+                .unwrap()
+        );
+
+        // the changelog is keyed by seconds
+        assert!(TIMED.changelog().has(&storage, ("A", 1500)));
+
+        TIMED.prune(&mut storage, t(1500), 10).unwrap();
+        assert!(TIMED.may_load_at_time(&storage, "A", t(1200)).is_err());
+    }
+
+    #[test]
+    fn time_based_selected_checkpoints() {
+        const TIMED: SnapshotMap<&str, u64, ByTime> =
+            SnapshotMap::new_timed("timed", "timed__check", "timed__change", Strategy::Selected);
+
+        let mut storage = MockStorage::new();
+        let t = |secs| Timestamp::from_seconds(secs);
+
+        TIMED.save(&mut storage, "A", &5, t(1000)).unwrap();
+        TIMED.add_checkpoint(&mut storage, t(1200)).unwrap();
+        TIMED.save(&mut storage, "A", &8, t(1200)).unwrap();
+
+        TIMED.assert_checkpointed(&storage, t(1200)).unwrap();
+        TIMED.assert_checkpointed(&storage, t(1000)).unwrap_err();
+        assert_eq!(
+            Some(5),
+            TIMED.may_load_at_time(&storage, "A", t(1200)).unwrap()
+        );
+        TIMED.may_load_at_time(&storage, "A", t(1100)).unwrap_err();
+    }
+
+    #[test]
+    #[cfg(feature = "bin-codec")]
+    fn bin_codec_snapshots() {
+        let mut store = MockStorage::new();
+        let map: SnapshotMap<&str, u64, ByHeight, crate::BinCodec> =
+            SnapshotMap::new("bin", "bin__check", "bin__change", Strategy::EveryBlock).with_codec();
+
+        map.save(&mut store, "A", &5, 1).unwrap();
+        map.save(&mut store, "A", &300, 3).unwrap();
+        map.remove(&mut store, "A", 5).unwrap();
+        map.save(&mut store, "B", &8, 5).unwrap();
+
+        assert_eq!(map.may_load_at_height(&store, "A", 2).unwrap(), Some(5));
+        assert_eq!(map.may_load_at_height(&store, "A", 4).unwrap(), Some(300));
+        assert_eq!(map.may_load_at_height(&store, "A", 6).unwrap(), None);
+        assert_eq!(map.may_load(&store, "B").unwrap(), Some(8));
+        // a varint rather than the json "8"
+        assert_eq!(store.get(&map.key("B")).unwrap(), vec![8]);
+
+        let all: Vec<_> = map
+            .range(&store, None, None, cosmwasm_std::Order::Ascending)
+            .collect::<StdResult<_>>()
+            .unwrap();
+        assert_eq!(all, vec![("B".to_string(), 8)]);
+    }
 }
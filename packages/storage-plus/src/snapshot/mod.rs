@@ -5,12 +5,20 @@ mod map;
 pub use item::SnapshotItem;
 pub use map::SnapshotMap;
 
-use crate::bound::Bound;
+use crate::bound::{Bound, RawBound};
 use crate::de::KeyDeserialize;
-use crate::{Map, Prefixer, PrimaryKey};
-use cosmwasm_std::{Order, StdError, StdResult, Storage};
+use crate::helpers::namespaces_with_key;
+use crate::iter_helpers::concat;
+use crate::prefix::range_with_prefix;
+use crate::{Item, Map, Prefixer, PrimaryKey};
+use cosmwasm_std::{from_slice, to_vec, Binary, Order, StdError, StdResult, Storage, Timestamp};
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::convert::TryInto;
+
+/// Maximum number of stale changelog entries removed for a single key on each write,
+/// when a `Retention` window is set. Anything left over is removed on later writes, or by `prune`.
+const AUTO_PRUNE_LIMIT: usize = 10;
 
 /// Structure holding a map of checkpoints composited from
 /// height (as u64) and counter of how many times it has
@@ -24,8 +32,15 @@ pub(crate) struct Snapshot<'a, K, T> {
     // and explicit None (just inserted)
     pub changelog: Map<'a, (K, u64), ChangeSet<T>>,
 
+    // lowest height that can still be queried, and where `prune` stopped.
+    // Only set if pruning is enabled
+    pruning: Option<Item<'a, PruneState>>,
+
     // How aggressive we are about checkpointing all data
     strategy: Strategy,
+
+    // How much history we keep around
+    retention: Retention,
 }
 
 impl<'a, K, T> Snapshot<'a, K, T> {
@@ -37,7 +52,66 @@ impl<'a, K, T> Snapshot<'a, K, T> {
         Snapshot {
             checkpoints: Map::new(checkpoints),
             changelog: Map::new(changelog),
+            pruning: None,
             strategy,
+            retention: Retention::Forever,
+        }
+    }
+
+    pub const fn with_pruning(
+        self,
+        namespace: &'a str,
+        retention: Retention,
+    ) -> Snapshot<'a, K, T> {
+        Snapshot {
+            pruning: Some(Item::new(namespace)),
+            retention,
+            ..self
+        }
+    }
+
+    fn pruning(&self) -> StdResult<&Item<'a, PruneState>> {
+        self.pruning
+            .as_ref()
+            .ok_or_else(|| StdError::generic_err("Pruning is not enabled, see `with_pruning`"))
+    }
+
+    fn prune_state(&self, store: &dyn Storage) -> StdResult<PruneState> {
+        match &self.pruning {
+            Some(pruning) => Ok(pruning.may_load(store)?.unwrap_or_default()),
+            None => Ok(PruneState::default()),
+        }
+    }
+
+    /// Returns the lowest height that can still be queried, or `None` if nothing was pruned
+    pub fn pruned_height(&self, store: &dyn Storage) -> StdResult<Option<u64>> {
+        let stored = self.prune_state(store)?.height;
+        let fixed = match self.retention {
+            Retention::AboveHeight(height) => Some(height.saturating_add(1)),
+            _ => None,
+        };
+        Ok(stored.max(fixed))
+    }
+
+    /// Returns an error if the history at the given height has been pruned
+    pub fn assert_not_pruned(&self, store: &dyn Storage, height: u64) -> StdResult<()> {
+        match self.pruned_height(store)? {
+            Some(lowest) if height < lowest => Err(StdError::generic_err(format!(
+                "Snapshot at height {} has been pruned, history is available from height {}",
+                height, lowest
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    fn raise_pruned_height(&self, store: &mut dyn Storage, height: u64) -> StdResult<()> {
+        let mut state = self.prune_state(store)?;
+        match state.height {
+            Some(current) if current >= height => Ok(()),
+            _ => {
+                state.height = Some(height);
+                self.pruning()?.save(store, &state)
+            }
         }
     }
 
@@ -68,15 +142,69 @@ where
 {
     /// should_checkpoint looks at the strategy and determines if we want to checkpoint
     pub fn should_checkpoint(&self, store: &dyn Storage, k: &K) -> StdResult<bool> {
+        self.should_checkpoint_raw(store, &k.joined_prefix())
+    }
+
+    pub fn has_changelog(&self, store: &mut dyn Storage, key: K, height: u64) -> StdResult<bool> {
+        Ok(self.has_changelog_raw(store, &key.joined_prefix(), height))
+    }
+
+    pub fn write_changelog(
+        &self,
+        store: &mut dyn Storage,
+        key: K,
+        height: u64,
+        old: Option<T>,
+    ) -> StdResult<()> {
+        self.write_changelog_raw(store, &key.joined_prefix(), height, old)
+    }
+
+    // may_load_at_height reads historical data from given checkpoints.
+    // Returns StdError::NotFound if we have no checkpoint, and can give no data.
+    // Returns Ok(None) if there is a checkpoint, but no cached data (no changes since the
+    // checkpoint. Caller should query current state).
+    // Return Ok(Some(x)) if there is a checkpoint and data written to changelog, returning the state at that time
+    pub fn may_load_at_height(
+        &self,
+        store: &dyn Storage,
+        key: K,
+        height: u64,
+    ) -> StdResult<Option<Option<T>>> {
+        self.may_load_at_height_raw(store, &key.joined_prefix(), height)
+    }
+}
+
+/// The changelog is stored as (entry, height) -> ChangeSet, where the entry is the encoded key
+/// (the joined prefix of the key for maps, so the same layout as `changelog`), and the height
+/// takes the last 8 bytes. Entries must be prefix-free, so the ones of different keys cannot be
+/// confused.
+impl<'a, K, T> Snapshot<'a, K, T>
+where
+    T: Serialize + DeserializeOwned + Clone,
+{
+    /// Raw prefix of all changelog entries
+    pub(crate) fn changelog_prefix(&self) -> Vec<u8> {
+        namespaces_with_key(&[self.changelog.namespace()], b"")
+    }
+
+    fn entry_prefix(&self, entry: &[u8]) -> Vec<u8> {
+        concat(&self.changelog_prefix(), entry)
+    }
+
+    pub(crate) fn should_checkpoint_raw(
+        &self,
+        store: &dyn Storage,
+        entry: &[u8],
+    ) -> StdResult<bool> {
         match self.strategy {
             Strategy::EveryBlock => Ok(true),
             Strategy::Never => Ok(false),
-            Strategy::Selected => self.should_checkpoint_selected(store, k),
+            Strategy::Selected => self.should_checkpoint_selected(store, entry),
         }
     }
 
     /// this is just pulled out from above for the selected block
-    fn should_checkpoint_selected(&self, store: &dyn Storage, k: &K) -> StdResult<bool> {
+    fn should_checkpoint_selected(&self, store: &dyn Storage, entry: &[u8]) -> StdResult<bool> {
         // most recent checkpoint
         let checkpoint = self
             .checkpoints
@@ -85,13 +213,15 @@ where
             .transpose()?;
         if let Some((height, _)) = checkpoint {
             // any changelog for the given key since then?
-            let start = Bound::inclusive(height);
-            let first = self
-                .changelog
-                .prefix(k.clone())
-                .range_raw(store, Some(start), None, Order::Ascending)
-                .next()
-                .transpose()?;
+            let start = RawBound::Inclusive(height.to_be_bytes().to_vec());
+            let first = range_with_prefix(
+                store,
+                &self.entry_prefix(entry),
+                Some(start),
+                None,
+                Order::Ascending,
+            )
+            .next();
             if first.is_none() {
                 // there must be at least one open checkpoint and no changelog for the given height since then
                 return Ok(true);
@@ -101,63 +231,174 @@ where
         Ok(false)
     }
 
-    // If there is no checkpoint for that height, then we return StdError::NotFound
-    pub fn assert_checkpointed(&self, store: &dyn Storage, height: u64) -> StdResult<()> {
-        let has = match self.strategy {
-            Strategy::EveryBlock => true,
-            Strategy::Never => false,
-            Strategy::Selected => self.checkpoints.may_load(store, height)?.is_some(),
-        };
-        match has {
-            true => Ok(()),
-            false => Err(StdError::not_found("checkpoint")),
-        }
+    pub(crate) fn has_changelog_raw(&self, store: &dyn Storage, entry: &[u8], height: u64) -> bool {
+        store
+            .get(&concat(&self.entry_prefix(entry), &height.to_be_bytes()))
+            .is_some()
     }
 
-    pub fn has_changelog(&self, store: &mut dyn Storage, key: K, height: u64) -> StdResult<bool> {
-        Ok(self.changelog.may_load(store, (key, height))?.is_some())
-    }
-
-    pub fn write_changelog(
+    pub(crate) fn write_changelog_raw(
         &self,
         store: &mut dyn Storage,
-        key: K,
+        entry: &[u8],
         height: u64,
         old: Option<T>,
     ) -> StdResult<()> {
-        self.changelog
-            .save(store, (key, height), &ChangeSet { old })
+        store.set(
+            &concat(&self.entry_prefix(entry), &height.to_be_bytes()),
+            &to_vec(&ChangeSet { old })?,
+        );
+        if let Some(lowest) = self.retention.lowest_retained(height) {
+            self.raise_pruned_height(store, lowest)?;
+            self.prune_entry(store, entry, lowest);
+        }
+        Ok(())
     }
 
-    // may_load_at_height reads historical data from given checkpoints.
-    // Returns StdError::NotFound if we have no checkpoint, and can give no data.
-    // Returns Ok(None) if there is a checkpoint, but no cached data (no changes since the
-    // checkpoint. Caller should query current state).
-    // Return Ok(Some(x)) if there is a checkpoint and data written to changelog, returning the state at that time
-    pub fn may_load_at_height(
+    /// Removes (a bounded number of) the changelog entries of one key below the given height
+    fn prune_entry(&self, store: &mut dyn Storage, entry: &[u8], below_height: u64) {
+        let prefix = self.entry_prefix(entry);
+        let stale = range_with_prefix(
+            store,
+            &prefix,
+            None,
+            Some(RawBound::Exclusive(below_height.to_be_bytes().to_vec())),
+            Order::Ascending,
+        )
+        .take(AUTO_PRUNE_LIMIT)
+        .map(|(height, _)| height)
+        .collect::<Vec<_>>();
+        for height in stale {
+            store.remove(&concat(&prefix, &height));
+        }
+    }
+
+    /// Raw version of `may_load_at_height`
+    pub(crate) fn may_load_at_height_raw(
         &self,
         store: &dyn Storage,
-        key: K,
+        entry: &[u8],
         height: u64,
     ) -> StdResult<Option<Option<T>>> {
+        self.assert_not_pruned(store, height)?;
         self.assert_checkpointed(store, height)?;
 
         // this will look for the first snapshot of height >= given height
         // If None, there is no snapshot since that time.
-        let start = Bound::inclusive(height);
-        let first = self
-            .changelog
-            .prefix(key)
-            .range_raw(store, Some(start), None, Order::Ascending)
-            .next();
-
-        if let Some(r) = first {
+        let start = RawBound::Inclusive(height.to_be_bytes().to_vec());
+        let first = range_with_prefix(
+            store,
+            &self.entry_prefix(entry),
+            Some(start),
+            None,
+            Order::Ascending,
+        )
+        .next();
+
+        match first {
             // if we found a match, return this last one
-            r.map(|(_, v)| Some(v.old))
-        } else {
-            Ok(None)
+            Some((_, v)) => Ok(Some(from_slice::<ChangeSet<T>>(&v)?.old)),
+            None => Ok(None),
+        }
+    }
+
+    // If there is no checkpoint for that height, then we return StdError::NotFound
+    pub fn assert_checkpointed(&self, store: &dyn Storage, height: u64) -> StdResult<()> {
+        let has = match self.strategy {
+            Strategy::EveryBlock => true,
+            Strategy::Never => false,
+            Strategy::Selected => self.checkpoints.may_load(store, height)?.is_some(),
+        };
+        match has {
+            true => Ok(()),
+            false => Err(StdError::not_found("checkpoint")),
         }
     }
+
+    /// Removes the checkpoints and changelog entries below the given height, going over at most
+    /// `limit` of them. If `done` is false in the result, call it again to continue: it resumes
+    /// where it stopped (as long as `below_height` is the same), and does nothing once done.
+    ///
+    /// Queries for heights below `below_height` fail from now on, even while pruning is
+    /// still in progress, so this can safely be spread over several calls.
+    pub fn prune(
+        &self,
+        store: &mut dyn Storage,
+        below_height: u64,
+        limit: usize,
+    ) -> StdResult<PruneProgress> {
+        self.raise_pruned_height(store, below_height)?;
+        let mut state = self.prune_state(store)?;
+        let mut start = match state.cursor.take() {
+            Some(cursor) if cursor.below_height == below_height => {
+                if cursor.done {
+                    return Ok(PruneProgress {
+                        removed: 0,
+                        done: true,
+                    });
+                }
+                cursor.key.map(|key| RawBound::Exclusive(key.into()))
+            }
+            _ => None,
+        };
+
+        let checkpoints = self
+            .checkpoints
+            .keys(
+                store,
+                None,
+                Some(Bound::exclusive(below_height)),
+                Order::Ascending,
+            )
+            .take(limit)
+            .collect::<StdResult<Vec<u64>>>()?;
+        for height in &checkpoints {
+            self.checkpoints.remove(store, *height);
+        }
+        let mut removed = checkpoints.len();
+        let mut visited = checkpoints.len();
+
+        // Changelog keys are (key, height), with the height in the last 8 bytes.
+        // We go over them in order, and skip the rest of a key once we reach a height to keep.
+        let namespace = self.changelog_prefix();
+        let mut done = false;
+        while visited < limit {
+            let next =
+                range_with_prefix(store, &namespace, start.clone(), None, Order::Ascending).next();
+            let key = match next {
+                Some((key, _)) => key,
+                None => {
+                    done = true;
+                    break;
+                }
+            };
+            visited += 1;
+            let (key_prefix, height) = key.split_at(key.len() - 8);
+            let height = u64::from_be_bytes(height.try_into().unwrap());
+            if height < below_height {
+                store.remove(&concat(&namespace, &key));
+                removed += 1;
+                start = Some(RawBound::Exclusive(key));
+            } else {
+                start = Some(RawBound::Exclusive(concat(
+                    key_prefix,
+                    &u64::MAX.to_be_bytes(),
+                )));
+            }
+        }
+
+        let key = match start {
+            Some(RawBound::Exclusive(key)) if !done => Some(key.into()),
+            _ => None,
+        };
+        state.cursor = Some(PruneCursor {
+            below_height,
+            key,
+            done,
+        });
+        self.pruning()?.save(store, &state)?;
+        Ok(PruneProgress { removed, done })
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
@@ -172,6 +413,95 @@ pub enum Strategy {
     Selected,
 }
 
+/// Defines what the checkpoints of a snapshot are indexed by.
+/// Whatever the kind, the position of a checkpoint is stored as a `u64`.
+pub trait CheckpointKind {
+    /// The type used to refer to a checkpoint, like a block height or time
+    type Point: Copy;
+
+    fn checkpoint_key(point: Self::Point) -> u64;
+}
+
+/// Snapshots indexed by block height. This is the default.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ByHeight;
+
+impl CheckpointKind for ByHeight {
+    type Point = u64;
+
+    fn checkpoint_key(height: u64) -> u64 {
+        height
+    }
+}
+
+/// Snapshots indexed by block time, with a resolution of seconds.
+/// With this kind, `Retention` windows are also expressed in seconds.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ByTime;
+
+impl CheckpointKind for ByTime {
+    type Point = Timestamp;
+
+    fn checkpoint_key(time: Timestamp) -> u64 {
+        time.seconds()
+    }
+}
+
+/// How much history a snapshot keeps around.
+///
+/// With anything but `Forever`, old changelog entries of a key are removed whenever that
+/// key is written again, and queries for heights outside of the window return an error.
+/// Use `prune` to clean up keys that are no longer written to.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Retention {
+    /// Keeps the complete history. This is the default.
+    Forever,
+    /// Keeps the history of the given number of heights before the latest write
+    LastHeights(u64),
+    /// Keeps the history for all heights strictly above the given one
+    AboveHeight(u64),
+}
+
+impl Retention {
+    /// Returns the lowest height to keep after a write at the given height
+    fn lowest_retained(&self, height: u64) -> Option<u64> {
+        match self {
+            Retention::Forever => None,
+            Retention::LastHeights(heights) => Some(height.saturating_sub(*heights)),
+            Retention::AboveHeight(above) => Some(above.saturating_add(1)),
+        }
+    }
+}
+
+/// Returned by `prune`
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct PruneProgress {
+    /// Number of checkpoints and changelog entries removed
+    pub removed: usize,
+    /// False if there may be more to remove, in which case `prune` must be called again
+    pub done: bool,
+}
+
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+struct PruneState {
+    /// Lowest height that can still be queried
+    height: Option<u64>,
+    /// Where the last call to `prune` stopped
+    cursor: Option<PruneCursor>,
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+struct PruneCursor {
+    below_height: u64,
+    /// Last changelog key visited, relative to the changelog namespace.
+    /// `None` if the changelog was not visited yet
+    key: Option<Binary>,
+    /// Set once the changelog was visited to its end, so everything below `below_height`
+    /// was removed
+    #[serde(default)]
+    done: bool,
+}
+
 #[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
 pub struct ChangeSet<T> {
     pub old: Option<T>,
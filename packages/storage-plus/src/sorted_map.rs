@@ -0,0 +1,324 @@
+// this module requires iterator to be useful at all
+#![cfg(feature = "iterator")]
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use cosmwasm_std::{Order, StdError, StdResult, Storage};
+
+use crate::bound::{Bound, PrefixBound};
+use crate::de::KeyDeserialize;
+use crate::indexed_map::{IndexList, IndexedMap};
+use crate::indexes::{Index, MultiIndex};
+use crate::int_key::IntKey;
+use crate::keys::{Prefixer, PrimaryKey};
+
+/// Score is implemented for the integer types that can be used as scores of a `SortedMap`
+pub trait Score: IntKey {
+    /// The score of a key that has none yet
+    fn zero() -> Self;
+    fn checked_add(self, other: Self) -> Option<Self>;
+}
+
+macro_rules! score_impls {
+    (for $($t:ty),+) => {
+        $(impl Score for $t {
+            fn zero() -> Self {
+                0
+            }
+
+            fn checked_add(self, other: Self) -> Option<Self> {
+                <$t>::checked_add(self, other)
+            }
+        })*
+    }
+}
+
+score_impls!(for u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+struct ScoreIndex<'a, K, S> {
+    score: MultiIndex<'a, S, S, K>,
+}
+
+impl<'a, K, S> IndexList<S> for ScoreIndex<'a, K, S>
+where
+    S: Score + PrimaryKey<'a> + Serialize + DeserializeOwned,
+{
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<S>> + '_> {
+        let v: Vec<&dyn Index<S>> = vec![&self.score];
+        Box::new(v.into_iter())
+    }
+}
+
+/// `SortedMap` stores a score for every key, and keeps the keys ordered by score as well,
+/// so it can answer leaderboard queries (`top`, `rank`, `range_by_score`) without scanning the
+/// whole map.
+///
+/// It is an `IndexedMap` from key to score, with a `MultiIndex` on the score. Entries with the
+/// same score are ordered by key.
+pub struct SortedMap<'a, K, S>
+where
+    K: PrimaryKey<'a>,
+    S: Score + PrimaryKey<'a> + Serialize + DeserializeOwned,
+{
+    scores: IndexedMap<'a, K, S, ScoreIndex<'a, K, S>>,
+}
+
+impl<'a, K, S> SortedMap<'a, K, S>
+where
+    K: PrimaryKey<'a>,
+    S: Score + PrimaryKey<'a> + Serialize + DeserializeOwned,
+{
+    /// Example:
+    ///
+    /// ```rust
+    /// use cw_storage_plus::SortedMap;
+    ///
+    /// SortedMap::<&str, u64>::new("points", "points__score");
+    /// ```
+    pub fn new(pk_namespace: &'a str, score_namespace: &'a str) -> Self {
+        let indexes = ScoreIndex {
+            score: MultiIndex::new(|s: &S| *s, pk_namespace, score_namespace),
+        };
+        SortedMap {
+            scores: IndexedMap::new(pk_namespace, indexes),
+        }
+    }
+
+    /// Sets the score of the key, moving it to its new position
+    pub fn save(&self, store: &mut dyn Storage, k: K, score: S) -> StdResult<()> {
+        self.scores.save(store, k, &score)
+    }
+
+    pub fn remove(&self, store: &mut dyn Storage, k: K) -> StdResult<()> {
+        self.scores.remove(store, k)
+    }
+
+    /// Returns the score of the key, if any
+    pub fn score(&self, store: &dyn Storage, k: K) -> StdResult<Option<S>> {
+        self.scores.may_load(store, k)
+    }
+
+    /// Loads the score, perform the specified action, and store the result.
+    ///
+    /// If the key has a score, `action(Some(score))` is called. Otherwise `action(None)` is called.
+    pub fn update_score<A, E>(&self, store: &mut dyn Storage, k: K, action: A) -> Result<S, E>
+    where
+        A: FnOnce(Option<S>) -> Result<S, E>,
+        E: From<StdError>,
+    {
+        self.scores.update(store, k, action)
+    }
+
+    /// Adds `delta` to the score of the key (starting from zero if it has none),
+    /// and returns the new score. Fails on overflow.
+    pub fn increment_score(&self, store: &mut dyn Storage, k: K, delta: S) -> StdResult<S> {
+        self.update_score(store, k, |score| {
+            score
+                .unwrap_or_else(S::zero)
+                .checked_add(delta)
+                .ok_or_else(|| StdError::generic_err("SortedMap score overflow"))
+        })
+    }
+}
+
+impl<'a, K, S> SortedMap<'a, K, S>
+where
+    K: PrimaryKey<'a> + KeyDeserialize,
+    S: Score + PrimaryKey<'a> + Prefixer<'a> + KeyDeserialize + Serialize + DeserializeOwned,
+{
+    /// Returns the position of the key when sorted by decreasing score: 0 for the highest
+    /// score, or `None` if the key has no score.
+    ///
+    /// This counts the entries ranked above, so its cost grows with the rank.
+    pub fn rank(&self, store: &dyn Storage, k: K) -> StdResult<Option<u32>> {
+        let score = match self.scores.may_load(store, k.clone())? {
+            Some(score) => score,
+            None => return Ok(None),
+        };
+        let position = score.joined_extra_key(&k.joined_key());
+        let above = self
+            .scores
+            .idx
+            .score
+            .keys_raw(
+                store,
+                Some(Bound::ExclusiveRaw(position)),
+                None,
+                Order::Ascending,
+            )
+            .count();
+        Ok(Some(above as u32))
+    }
+
+    /// Returns the `n` entries with the highest scores, highest first
+    pub fn top(&self, store: &dyn Storage, n: usize) -> StdResult<Vec<(K::Output, S)>> {
+        self.scores
+            .idx
+            .score
+            .range_raw(store, None, None, Order::Descending)
+            .take(n)
+            .map(|item| item.and_then(|(pk, score)| Ok((K::from_vec(pk)?, score))))
+            .collect()
+    }
+
+    /// Iterates over the entries with a score within the given bounds, ordered by score
+    pub fn range_by_score<'c>(
+        &'c self,
+        store: &'c dyn Storage,
+        min: Option<PrefixBound<'a, S>>,
+        max: Option<PrefixBound<'a, S>>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = StdResult<(K::Output, S)>> + 'c>
+    where
+        S: 'c,
+        'a: 'c,
+    {
+        let mapped = self
+            .scores
+            .idx
+            .score
+            .prefix_range_raw(store, min, max, order)
+            .map(|item| item.and_then(|(pk, score)| Ok((K::from_vec(pk)?, score))));
+        Box::new(mapped)
+    }
+
+    /// Iterates over the entries ordered by key
+    pub fn range<'c>(
+        &self,
+        store: &'c dyn Storage,
+        min: Option<Bound<'a, K>>,
+        max: Option<Bound<'a, K>>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = StdResult<(K::Output, S)>> + 'c>
+    where
+        S: 'c,
+        K::Output: 'static,
+    {
+        self.scores.range(store, min, max, order)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use cosmwasm_std::testing::MockStorage;
+
+    fn points<'a>() -> SortedMap<'a, &'a str, u64> {
+        SortedMap::new("points", "points__score")
+    }
+
+    fn setup(store: &mut dyn Storage) {
+        let map = points();
+        map.save(store, "alice", 50).unwrap();
+        map.save(store, "bob", 20).unwrap();
+        map.save(store, "carl", 70).unwrap();
+        map.save(store, "dave", 20).unwrap();
+    }
+
+    #[test]
+    fn top_and_rank() {
+        let mut store = MockStorage::new();
+        setup(&mut store);
+        let map = points();
+
+        let top = map.top(&store, 3).unwrap();
+        assert_eq!(
+            top,
+            vec![
+                ("carl".to_string(), 70),
+                ("alice".to_string(), 50),
+                ("dave".to_string(), 20),
+            ]
+        );
+        assert_eq!(4, map.top(&store, 10).unwrap().len());
+
+        assert_eq!(Some(0), map.rank(&store, "carl").unwrap());
+        assert_eq!(Some(1), map.rank(&store, "alice").unwrap());
+        // ties are ranked by key, from the end
+        assert_eq!(Some(2), map.rank(&store, "dave").unwrap());
+        assert_eq!(Some(3), map.rank(&store, "bob").unwrap());
+        assert_eq!(None, map.rank(&store, "eve").unwrap());
+    }
+
+    #[test]
+    fn updates_move_entries() {
+        let mut store = MockStorage::new();
+        setup(&mut store);
+        let map = points();
+
+        assert_eq!(80, map.increment_score(&mut store, "bob", 60).unwrap());
+        assert_eq!(Some(0), map.rank(&store, "bob").unwrap());
+        assert_eq!(Some(1), map.rank(&store, "carl").unwrap());
+
+        // new entries start at zero
+        assert_eq!(5, map.increment_score(&mut store, "eve", 5).unwrap());
+        assert_eq!(Some(4), map.rank(&store, "eve").unwrap());
+
+        let err = map
+            .increment_score(&mut store, "eve", u64::MAX)
+            .unwrap_err();
+        assert!(matches!(err, StdError::GenericErr { .. }));
+        assert_eq!(Some(5), map.score(&store, "eve").unwrap());
+
+        map.remove(&mut store, "bob").unwrap();
+        assert_eq!(Some(0), map.rank(&store, "carl").unwrap());
+        assert_eq!(4, map.top(&store, 10).unwrap().len());
+
+        map.save(&mut store, "carl", 1).unwrap();
+        let top = map.top(&store, 1).unwrap();
+        assert_eq!(top, vec![("alice".to_string(), 50)]);
+    }
+
+    #[test]
+    fn range_by_score_works() {
+        let mut store = MockStorage::new();
+        setup(&mut store);
+        let map = points();
+
+        let all: Vec<_> = map
+            .range_by_score(
+                &store,
+                Some(PrefixBound::inclusive(20u64)),
+                Some(PrefixBound::exclusive(70u64)),
+                Order::Ascending,
+            )
+            .collect::<StdResult<_>>()
+            .unwrap();
+        assert_eq!(
+            all,
+            vec![
+                ("bob".to_string(), 20),
+                ("dave".to_string(), 20),
+                ("alice".to_string(), 50),
+            ]
+        );
+
+        let by_key: Vec<_> = map
+            .range(&store, None, None, Order::Ascending)
+            .collect::<StdResult<_>>()
+            .unwrap();
+        assert_eq!(by_key[0], ("alice".to_string(), 50));
+    }
+
+    #[test]
+    fn signed_scores() {
+        let mut store = MockStorage::new();
+        let map: SortedMap<u32, i32> = SortedMap::new("balance", "balance__score");
+        map.save(&mut store, 1, -5).unwrap();
+        map.save(&mut store, 2, 3).unwrap();
+        map.save(&mut store, 3, -10).unwrap();
+
+        let top = map.top(&store, 3).unwrap();
+        assert_eq!(top, vec![(2, 3), (1, -5), (3, -10)]);
+        assert_eq!(-3, map.increment_score(&mut store, 2, -6).unwrap());
+        assert_eq!(Some(0), map.rank(&store, 2).unwrap());
+
+        // a missing score starts from zero
+        assert_eq!(-4, map.increment_score(&mut store, 4, -4).unwrap());
+        assert_eq!(7, map.increment_score(&mut store, 5, 7).unwrap());
+        let top = map.top(&store, 5).unwrap();
+        assert_eq!(top, vec![(5, 7), (2, -3), (4, -4), (1, -5), (3, -10)]);
+    }
+}
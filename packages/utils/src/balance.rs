@@ -1,8 +1,9 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
 use std::{fmt, ops};
 
-use cosmwasm_std::{Coin, OverflowError, OverflowOperation, StdError, StdResult, Uint128};
+use cosmwasm_std::{Coin, Decimal, OverflowError, OverflowOperation, StdError, StdResult, Uint128};
 
 // Balance wraps Vec<Coin> and provides some nice helpers. It mutates the Vec and can be
 // unwrapped when done.
@@ -89,17 +90,203 @@ impl NativeBalance {
         };
         Ok(self)
     }
+
+    /// returns the amount of the given denom (zero if there is none)
+    pub fn amount_of(&self, denom: &str) -> Uint128 {
+        self.0
+            .iter()
+            .filter(|c| c.denom == denom)
+            .map(|c| c.amount)
+            .sum()
+    }
+
+    /// returns true if this has at least all the coins of other
+    pub fn has_all(&self, other: &NativeBalance) -> bool {
+        let mut other = other.clone();
+        other.normalize();
+        other.0.iter().all(|c| self.amount_of(&c.denom) >= c.amount)
+    }
+
+    /// adds all the coins of other, erroring on overflow. The result is normalized.
+    pub fn checked_add(mut self, other: &NativeBalance) -> StdResult<Self> {
+        self.normalize();
+        for coin in &other.0 {
+            match self.find(&coin.denom) {
+                Some((i, c)) => self.0[i].amount = c.amount.checked_add(coin.amount)?,
+                None => self.insert(coin.clone()),
+            }
+        }
+        self.normalize();
+        Ok(self)
+    }
+
+    /// subtracts all the coins of other, erroring if any denom is not covered.
+    /// The result is normalized.
+    pub fn checked_sub(mut self, other: &NativeBalance) -> StdResult<Self> {
+        self.normalize();
+        for coin in &other.0 {
+            let have = self.amount_of(&coin.denom);
+            let remainder = have.checked_sub(coin.amount)?;
+            if let Some((i, _)) = self.find(&coin.denom) {
+                self.0[i].amount = remainder;
+            }
+        }
+        self.normalize();
+        Ok(self)
+    }
+
+    /// subtracts all the coins of other, stopping at zero for each denom.
+    /// The result is normalized.
+    pub fn saturating_sub(mut self, other: &NativeBalance) -> Self {
+        self.normalize();
+        for coin in &other.0 {
+            if let Some((i, c)) = self.find(&coin.denom) {
+                self.0[i].amount = c.amount.saturating_sub(coin.amount);
+            }
+        }
+        self.normalize();
+        self
+    }
+
+    /// multiplies every amount by ratio, rounding down, and erroring on overflow.
+    /// The result is normalized.
+    pub fn checked_mul_decimal(mut self, ratio: Decimal) -> StdResult<Self> {
+        for coin in self.0.iter_mut() {
+            coin.amount = coin
+                .amount
+                .checked_multiply_ratio(ratio.atomics(), Decimal::one().atomics())
+                .map_err(|_| {
+                    StdError::overflow(OverflowError::new(
+                        OverflowOperation::Mul,
+                        coin.amount,
+                        ratio,
+                    ))
+                })?;
+        }
+        self.normalize();
+        Ok(self)
+    }
+
+    /// returns the smallest amount of each denom. Denoms missing from one side count as zero,
+    /// so only the denoms in both remain. The result is normalized.
+    pub fn min(&self, other: &NativeBalance) -> Self {
+        self.combine(other, |a, b| a.min(b))
+    }
+
+    /// returns the largest amount of each denom, over the denoms in any of both.
+    /// The result is normalized.
+    pub fn max(&self, other: &NativeBalance) -> Self {
+        self.combine(other, |a, b| a.max(b))
+    }
+
+    /// returns the coins of this balance whose denom is also in other (with the amounts of
+    /// this balance). The result is normalized.
+    pub fn intersection(&self, other: &NativeBalance) -> Self {
+        self.combine(other, |a, b| if b.is_zero() { b } else { a })
+    }
+
+    // applies op to the amounts of each denom of either side
+    fn combine<F>(&self, other: &NativeBalance, op: F) -> Self
+    where
+        F: Fn(Uint128, Uint128) -> Uint128,
+    {
+        let mut denoms: Vec<&str> = self
+            .0
+            .iter()
+            .chain(other.0.iter())
+            .map(|c| c.denom.as_str())
+            .collect();
+        denoms.sort_unstable();
+        denoms.dedup();
+        let mut res = NativeBalance(
+            denoms
+                .into_iter()
+                .map(|denom| Coin {
+                    denom: denom.to_string(),
+                    amount: op(self.amount_of(denom), other.amount_of(denom)),
+                })
+                .collect(),
+        );
+        res.normalize();
+        res
+    }
+
+    // inserts a coin of a denom which is not in the Wallet yet, in sorted order
+    fn insert(&mut self, coin: Coin) {
+        match self.insert_pos(&coin.denom) {
+            Some(idx) => self.0.insert(idx, coin),
+            None => self.0.push(coin),
+        }
+    }
 }
 
+/// Formats the balance like the SDK does, e.g. `100ujuno,5uatom`
 impl fmt::Display for NativeBalance {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        for c in &self.0 {
-            write!(f, "{}{}", c.denom, c.amount)?
+        for (i, c) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{}{}", c.amount, c.denom)?
         }
         Ok(())
     }
 }
 
+/// Parses a balance formatted like `100ujuno,5uatom`. The result is normalized.
+impl FromStr for NativeBalance {
+    type Err = StdError;
+
+    fn from_str(s: &str) -> StdResult<Self> {
+        let mut balance = NativeBalance::default();
+        if s.trim().is_empty() {
+            return Ok(balance);
+        }
+        for part in s.split(',') {
+            let coin = parse_coin(part.trim())?;
+            balance = balance.checked_add(&NativeBalance(vec![coin]))?;
+        }
+        Ok(balance)
+    }
+}
+
+fn parse_coin(s: &str) -> StdResult<Coin> {
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (amount, denom) = s.split_at(split);
+    if amount.is_empty() {
+        return Err(StdError::parse_err(
+            "Coin",
+            format!("missing amount in {:?}", s),
+        ));
+    }
+    let valid_denom = denom.starts_with(|c: char| c.is_ascii_alphabetic())
+        && denom
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "/:._-".contains(c));
+    if !valid_denom {
+        return Err(StdError::parse_err(
+            "Coin",
+            format!("invalid denom in {:?}", s),
+        ));
+    }
+    Ok(Coin {
+        denom: denom.to_string(),
+        amount: Uint128::from_str(amount)?,
+    })
+}
+
+impl From<Vec<Coin>> for NativeBalance {
+    fn from(coins: Vec<Coin>) -> Self {
+        NativeBalance(coins)
+    }
+}
+
+impl From<Coin> for NativeBalance {
+    fn from(coin: Coin) -> Self {
+        NativeBalance(vec![coin])
+    }
+}
+
 impl ops::AddAssign<Coin> for NativeBalance {
     fn add_assign(&mut self, other: Coin) {
         match self.find(&other.denom) {
@@ -107,10 +294,7 @@ impl ops::AddAssign<Coin> for NativeBalance {
                 self.0[i].amount = c.amount + other.amount;
             }
             // place this in proper sorted order
-            None => match self.insert_pos(&other.denom) {
-                Some(idx) => self.0.insert(idx, other),
-                None => self.0.push(other),
-            },
+            None => self.insert(other),
         };
     }
 }
@@ -316,4 +500,123 @@ mod test {
             NativeBalance(vec![coin(800, "BTC"), coin(444, "ETH")])
         );
     }
+
+    #[test]
+    fn checked_balance_arithmetic() {
+        let a = NativeBalance(vec![coin(100, "ujuno"), coin(5, "uatom")]);
+        let b = NativeBalance(vec![coin(20, "uatom"), coin(7, "ujuno"), coin(0, "uosmo")]);
+
+        let sum = a.clone().checked_add(&b).unwrap();
+        assert_eq!(
+            sum,
+            NativeBalance(vec![coin(25, "uatom"), coin(107, "ujuno")])
+        );
+        let overflow = NativeBalance(vec![coin(u128::MAX, "uatom")]);
+        overflow.checked_add(&a).unwrap_err();
+
+        assert_eq!(
+            sum.clone().checked_sub(&b).unwrap(),
+            NativeBalance(vec![coin(5, "uatom"), coin(100, "ujuno")])
+        );
+        // all or nothing
+        a.clone().checked_sub(&b).unwrap_err();
+        a.clone()
+            .checked_sub(&NativeBalance(vec![coin(1, "uosmo")]))
+            .unwrap_err();
+        assert_eq!(
+            a.clone().saturating_sub(&b),
+            NativeBalance(vec![coin(93, "ujuno")])
+        );
+
+        assert!(sum.has_all(&a));
+        assert!(sum.has_all(&b));
+        assert!(!a.has_all(&b));
+        assert!(a.has_all(&NativeBalance::default()));
+        assert_eq!(sum.amount_of("ujuno"), Uint128::new(107));
+        assert_eq!(sum.amount_of("uosmo"), Uint128::zero());
+    }
+
+    #[test]
+    fn multiply_by_decimal() {
+        let fees = NativeBalance(vec![coin(1000, "ujuno"), coin(15, "uatom")]);
+        assert_eq!(
+            fees.clone()
+                .checked_mul_decimal(Decimal::percent(10))
+                .unwrap(),
+            // rounded down, zero amounts dropped
+            NativeBalance(vec![coin(1, "uatom"), coin(100, "ujuno")])
+        );
+        assert_eq!(
+            fees.clone()
+                .checked_mul_decimal(Decimal::permille(1))
+                .unwrap(),
+            NativeBalance(vec![coin(1, "ujuno")])
+        );
+        assert_eq!(
+            fees.checked_mul_decimal(Decimal::percent(250)).unwrap(),
+            NativeBalance(vec![coin(37, "uatom"), coin(2500, "ujuno")])
+        );
+        let err = NativeBalance(vec![coin(u128::MAX, "uatom")])
+            .checked_mul_decimal(Decimal::percent(200))
+            .unwrap_err();
+        assert!(matches!(err, StdError::Overflow { .. }));
+    }
+
+    #[test]
+    fn balance_set_operations() {
+        let a = NativeBalance(vec![coin(100, "ujuno"), coin(5, "uatom")]);
+        let b = NativeBalance(vec![coin(20, "uatom"), coin(7, "ujuno"), coin(3, "uosmo")]);
+        assert_eq!(
+            a.min(&b),
+            NativeBalance(vec![coin(5, "uatom"), coin(7, "ujuno")])
+        );
+        assert_eq!(
+            a.max(&b),
+            NativeBalance(vec![
+                coin(20, "uatom"),
+                coin(100, "ujuno"),
+                coin(3, "uosmo")
+            ])
+        );
+        let only_juno = NativeBalance(vec![coin(1, "ujuno")]);
+        assert_eq!(
+            a.intersection(&only_juno),
+            NativeBalance(vec![coin(100, "ujuno")])
+        );
+        assert_eq!(
+            only_juno.intersection(&NativeBalance::default()),
+            NativeBalance::default()
+        );
+    }
+
+    #[test]
+    fn parse_and_format_balance() {
+        let balance: NativeBalance = "100ujuno,5uatom".parse().unwrap();
+        assert_eq!(
+            balance,
+            NativeBalance(vec![coin(5, "uatom"), coin(100, "ujuno")])
+        );
+        assert_eq!(balance.to_string(), "5uatom,100ujuno");
+        assert_eq!(
+            balance.to_string().parse::<NativeBalance>().unwrap(),
+            balance
+        );
+
+        // duplicates are merged, spaces and zeros ignored
+        let balance: NativeBalance = "1ujuno, 2ujuno,0uatom,3ibc/27394FB0".parse().unwrap();
+        assert_eq!(
+            balance,
+            NativeBalance(vec![coin(3, "ibc/27394FB0"), coin(3, "ujuno")])
+        );
+        assert_eq!(
+            "".parse::<NativeBalance>().unwrap(),
+            NativeBalance::default()
+        );
+
+        "ujuno".parse::<NativeBalance>().unwrap_err();
+        "100".parse::<NativeBalance>().unwrap_err();
+        "100ujuno,".parse::<NativeBalance>().unwrap_err();
+        "100 ujuno".parse::<NativeBalance>().unwrap_err();
+        "1u juno".parse::<NativeBalance>().unwrap_err();
+    }
 }
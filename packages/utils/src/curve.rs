@@ -0,0 +1,383 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use cosmwasm_std::Uint128;
+
+/// Curve maps an x value (block height or time, depending on the use) to an amount.
+/// All the arithmetic is done on `Uint128` and never overflows: intermediate products are
+/// computed on 256 bits, and combining curves returns an error instead of wrapping.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Curve {
+    /// The same value for all x
+    Constant { y: Uint128 },
+    /// `min_y` until `min_x`, `max_y` from `max_x` on, and linear in between
+    SaturatingLinear(SaturatingLinear),
+    /// Linear between each pair of consecutive steps, and constant before the first and after
+    /// the last one
+    PiecewiseLinear(PiecewiseLinear),
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, JsonSchema, Debug)]
+pub struct SaturatingLinear {
+    pub min_x: u64,
+    pub min_y: Uint128,
+    pub max_x: u64,
+    pub max_y: Uint128,
+}
+
+/// Steps are `(x, y)` points ordered by x. Two consecutive steps with the same x make a jump:
+/// the curve takes the first value right before x, and the second one from x on.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PiecewiseLinear {
+    pub steps: Vec<(u64, Uint128)>,
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum CurveError {
+    #[error("Curve must have at least one step")]
+    MissingSteps {},
+
+    #[error("Curve steps must be ordered by x, with at most two steps at the same x")]
+    UnorderedSteps {},
+
+    #[error("Curve is not monotonic")]
+    NotMonotonic {},
+
+    #[error("Curve has more than {max} steps")]
+    TooComplex { max: usize },
+
+    #[error("Durations must use the same unit as the start of the schedule")]
+    MismatchedUnits {},
+
+    #[error("Curve value overflow")]
+    Overflow {},
+
+    #[error("Curve value underflow")]
+    Underflow {},
+}
+
+impl Curve {
+    pub fn constant(y: u128) -> Self {
+        Curve::Constant { y: y.into() }
+    }
+
+    pub fn saturating_linear((min_x, min_y): (u64, u128), (max_x, max_y): (u64, u128)) -> Self {
+        Curve::SaturatingLinear(SaturatingLinear {
+            min_x,
+            min_y: min_y.into(),
+            max_x,
+            max_y: max_y.into(),
+        })
+    }
+
+    pub fn piecewise_linear(steps: impl IntoIterator<Item = (u64, u128)>) -> Self {
+        Curve::PiecewiseLinear(PiecewiseLinear {
+            steps: steps.into_iter().map(|(x, y)| (x, y.into())).collect(),
+        })
+    }
+
+    /// Returns the value of the curve at x.
+    /// Only valid curves (see `validate`) give meaningful results.
+    pub fn value(&self, x: u64) -> Uint128 {
+        match self {
+            Curve::Constant { y } => *y,
+            Curve::SaturatingLinear(s) => interpolate((s.min_x, s.min_y), (s.max_x, s.max_y), x),
+            Curve::PiecewiseLinear(p) => p.value(x),
+        }
+    }
+
+    /// Checks the curve is well formed: steps are present and ordered
+    pub fn validate(&self) -> Result<(), CurveError> {
+        self.steps().map(|_| ())
+    }
+
+    /// Checks the curve is well formed and never goes down
+    pub fn validate_monotonic_increasing(&self) -> Result<(), CurveError> {
+        let steps = self.steps()?;
+        if steps.windows(2).all(|w| w[0].1 <= w[1].1) {
+            Ok(())
+        } else {
+            Err(CurveError::NotMonotonic {})
+        }
+    }
+
+    /// Checks the curve is well formed and never goes up
+    pub fn validate_monotonic_decreasing(&self) -> Result<(), CurveError> {
+        let steps = self.steps()?;
+        if steps.windows(2).all(|w| w[0].1 >= w[1].1) {
+            Ok(())
+        } else {
+            Err(CurveError::NotMonotonic {})
+        }
+    }
+
+    /// Checks the curve does not have more than `max` steps, so evaluating or combining it
+    /// has a bounded cost
+    pub fn validate_complexity(&self, max: usize) -> Result<(), CurveError> {
+        if self.size() > max {
+            Err(CurveError::TooComplex { max })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Number of steps the curve is made of
+    pub fn size(&self) -> usize {
+        match self {
+            Curve::Constant { .. } => 1,
+            Curve::SaturatingLinear(_) => 2,
+            Curve::PiecewiseLinear(p) => p.steps.len(),
+        }
+    }
+
+    /// Returns the lowest and highest values of the curve
+    pub fn range(&self) -> Result<(Uint128, Uint128), CurveError> {
+        let steps = self.steps()?;
+        // steps is never empty
+        let low = steps.iter().map(|(_, y)| *y).min().unwrap_or_default();
+        let high = steps.iter().map(|(_, y)| *y).max().unwrap_or_default();
+        Ok((low, high))
+    }
+
+    /// Returns the curve whose value is the sum of both curves at all x
+    pub fn checked_add(&self, other: &Curve) -> Result<Curve, CurveError> {
+        self.combine(other, |a, b| {
+            a.checked_add(b).map_err(|_| CurveError::Overflow {})
+        })
+    }
+
+    /// Returns the curve whose value is the difference of both curves at all x.
+    /// Fails if `other` is above this curve anywhere.
+    pub fn checked_sub(&self, other: &Curve) -> Result<Curve, CurveError> {
+        self.combine(other, |a, b| {
+            a.checked_sub(b).map_err(|_| CurveError::Underflow {})
+        })
+    }
+
+    /// Combines the curves point by point. Both are linear between the union of their steps,
+    /// so applying `op` on those points (and on both sides of every jump) is enough.
+    fn combine<F>(&self, other: &Curve, op: F) -> Result<Curve, CurveError>
+    where
+        F: Fn(Uint128, Uint128) -> Result<Uint128, CurveError>,
+    {
+        let left = self.steps()?;
+        let right = other.steps()?;
+        let mut xs: Vec<u64> = left.iter().chain(right.iter()).map(|(x, _)| *x).collect();
+        xs.sort_unstable();
+        xs.dedup();
+
+        let mut steps = Vec::with_capacity(xs.len());
+        for x in xs {
+            let before = op(value_before(&left, x), value_before(&right, x))?;
+            let after = op(steps_value(&left, x), steps_value(&right, x))?;
+            steps.push((x, before));
+            if before != after {
+                steps.push((x, after));
+            }
+        }
+        Ok(simplify(steps))
+    }
+
+    /// Returns the curve as a list of ordered steps
+    fn steps(&self) -> Result<Vec<(u64, Uint128)>, CurveError> {
+        match self {
+            Curve::Constant { y } => Ok(vec![(0, *y)]),
+            Curve::SaturatingLinear(s) => {
+                if s.min_x > s.max_x {
+                    return Err(CurveError::UnorderedSteps {});
+                }
+                Ok(vec![(s.min_x, s.min_y), (s.max_x, s.max_y)])
+            }
+            Curve::PiecewiseLinear(p) => {
+                p.validate()?;
+                Ok(p.steps.clone())
+            }
+        }
+    }
+}
+
+impl PiecewiseLinear {
+    fn validate(&self) -> Result<(), CurveError> {
+        if self.steps.is_empty() {
+            return Err(CurveError::MissingSteps {});
+        }
+        let ordered = self.steps.windows(2).all(|w| w[0].0 <= w[1].0)
+            && self.steps.windows(3).all(|w| w[0].0 < w[2].0);
+        if ordered {
+            Ok(())
+        } else {
+            Err(CurveError::UnorderedSteps {})
+        }
+    }
+
+    fn value(&self, x: u64) -> Uint128 {
+        steps_value(&self.steps, x)
+    }
+}
+
+/// Value of the steps at x (after a jump at x, if any)
+fn steps_value(steps: &[(u64, Uint128)], x: u64) -> Uint128 {
+    // index of the first step after x
+    let next = steps.partition_point(|(sx, _)| *sx <= x);
+    match (
+        next.checked_sub(1).map(|i| steps[i]),
+        steps.get(next).copied(),
+    ) {
+        (Some(prev), Some(next)) => interpolate(prev, next, x),
+        (Some((_, y)), None) | (None, Some((_, y))) => y,
+        (None, None) => Uint128::zero(),
+    }
+}
+
+/// Value of the steps right before x (before a jump at x, if any)
+fn value_before(steps: &[(u64, Uint128)], x: u64) -> Uint128 {
+    match steps.iter().find(|(sx, _)| *sx >= x) {
+        Some((sx, y)) if *sx == x => *y,
+        _ => steps_value(steps, x),
+    }
+}
+
+/// Linear interpolation between two points, constant outside of them
+fn interpolate((x0, y0): (u64, Uint128), (x1, y1): (u64, Uint128), x: u64) -> Uint128 {
+    if x <= x0 {
+        y0
+    } else if x >= x1 {
+        y1
+    } else if y1 >= y0 {
+        // multiply_ratio never overflows, and the result is below y1 - y0
+        y0 + (y1 - y0).multiply_ratio(x - x0, x1 - x0)
+    } else {
+        y0 - (y0 - y1).multiply_ratio(x - x0, x1 - x0)
+    }
+}
+
+/// Drops the steps in the middle of flat parts, and turns a flat curve into a constant
+fn simplify(steps: Vec<(u64, Uint128)>) -> Curve {
+    let mut result: Vec<(u64, Uint128)> = Vec::with_capacity(steps.len());
+    for step in steps {
+        if let [.., a, b] = result.as_slice() {
+            if a.1 == b.1 && b.1 == step.1 && a.0 < b.0 && b.0 < step.0 {
+                result.pop();
+            }
+        }
+        result.push(step);
+    }
+    if result.iter().all(|(_, y)| *y == result[0].1) {
+        Curve::Constant { y: result[0].1 }
+    } else {
+        Curve::PiecewiseLinear(PiecewiseLinear { steps: result })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn curve_values() {
+        let curve = Curve::constant(10);
+        assert_eq!(curve.value(0), Uint128::new(10));
+        assert_eq!(curve.value(u64::MAX), Uint128::new(10));
+
+        let curve = Curve::saturating_linear((100, 0), (200, 1000));
+        assert_eq!(curve.value(0), Uint128::zero());
+        assert_eq!(curve.value(100), Uint128::zero());
+        assert_eq!(curve.value(150), Uint128::new(500));
+        assert_eq!(curve.value(199), Uint128::new(990));
+        assert_eq!(curve.value(300), Uint128::new(1000));
+
+        let curve = Curve::saturating_linear((0, 300), (3, 0));
+        assert_eq!(curve.value(1), Uint128::new(200));
+        assert_eq!(curve.value(5), Uint128::zero());
+
+        // a jump at 10, then linear up to 20
+        let curve = Curve::piecewise_linear(vec![(10, 0), (10, 50), (20, 100), (30, 100)]);
+        assert_eq!(curve.value(9), Uint128::zero());
+        assert_eq!(curve.value(10), Uint128::new(50));
+        assert_eq!(curve.value(15), Uint128::new(75));
+        assert_eq!(curve.value(25), Uint128::new(100));
+        assert_eq!(curve.value(31), Uint128::new(100));
+
+        // no overflow on huge values
+        let curve = Curve::saturating_linear((0, 0), (u64::MAX, u128::MAX));
+        // u128::MAX is u64::MAX * (u64::MAX + 2)
+        assert_eq!(
+            curve.value(1000),
+            Uint128::new(1000 * (u64::MAX as u128 + 2))
+        );
+    }
+
+    #[test]
+    fn validation() {
+        let increasing = Curve::piecewise_linear(vec![(1, 5), (5, 5), (5, 10), (8, 20)]);
+        increasing.validate_monotonic_increasing().unwrap();
+        assert_eq!(
+            increasing.validate_monotonic_decreasing(),
+            Err(CurveError::NotMonotonic {})
+        );
+        assert_eq!(
+            increasing.range().unwrap(),
+            (Uint128::new(5), Uint128::new(20))
+        );
+        increasing.validate_complexity(4).unwrap();
+        assert_eq!(
+            increasing.validate_complexity(3),
+            Err(CurveError::TooComplex { max: 3 })
+        );
+
+        let decreasing = Curve::saturating_linear((10, 100), (20, 0));
+        decreasing.validate_monotonic_decreasing().unwrap();
+        assert_eq!(
+            decreasing.validate_monotonic_increasing(),
+            Err(CurveError::NotMonotonic {})
+        );
+        Curve::constant(7).validate_monotonic_increasing().unwrap();
+        Curve::constant(7).validate_monotonic_decreasing().unwrap();
+
+        assert_eq!(
+            Curve::piecewise_linear(vec![]).validate(),
+            Err(CurveError::MissingSteps {})
+        );
+        assert_eq!(
+            Curve::piecewise_linear(vec![(5, 1), (4, 2)]).validate(),
+            Err(CurveError::UnorderedSteps {})
+        );
+        assert_eq!(
+            Curve::piecewise_linear(vec![(5, 1), (5, 2), (5, 3)]).validate(),
+            Err(CurveError::UnorderedSteps {})
+        );
+        assert_eq!(
+            Curve::saturating_linear((5, 1), (4, 2)).validate(),
+            Err(CurveError::UnorderedSteps {})
+        );
+    }
+
+    #[test]
+    fn combine_curves() {
+        let a = Curve::saturating_linear((0, 0), (100, 1000));
+        let b = Curve::piecewise_linear(vec![(50, 0), (50, 500), (150, 500)]);
+        let sum = a.checked_add(&b).unwrap();
+        for x in [0, 25, 49, 50, 75, 100, 125, 200] {
+            assert_eq!(sum.value(x), a.value(x) + b.value(x), "x = {}", x);
+        }
+        sum.validate_monotonic_increasing().unwrap();
+
+        let diff = sum.checked_sub(&b).unwrap();
+        for x in [0, 25, 50, 75, 100, 200] {
+            assert_eq!(diff.value(x), a.value(x), "x = {}", x);
+        }
+
+        // a is below b right before its jump
+        assert_eq!(b.checked_sub(&a), Err(CurveError::Underflow {}));
+        assert_eq!(
+            Curve::constant(u128::MAX).checked_add(&Curve::constant(1)),
+            Err(CurveError::Overflow {})
+        );
+        assert_eq!(
+            Curve::constant(5).checked_add(&Curve::constant(6)),
+            Ok(Curve::constant(11))
+        );
+    }
+}
@@ -2,6 +2,7 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use cosmwasm_std::{BlockInfo, StdError, StdResult, Timestamp};
+use cw_storage_plus::{Deadline, Expires};
 use std::cmp::Ordering;
 use std::fmt;
 use std::ops::{Add, Mul};
@@ -47,6 +48,17 @@ impl Expiration {
     }
 }
 
+/// Allows using `Expiration` in a `cw_storage_plus::ExpiringMap`
+impl Expires for Expiration {
+    fn deadline(&self) -> Option<Deadline> {
+        match self {
+            Expiration::AtHeight(height) => Some(Deadline::Height(*height)),
+            Expiration::AtTime(time) => Some(Deadline::Time(*time)),
+            Expiration::Never {} => None,
+        }
+    }
+}
+
 impl Add<Duration> for Expiration {
     type Output = StdResult<Expiration>;
 
@@ -236,4 +248,31 @@ mod test {
         let days = DAY * 3;
         assert_eq!(Duration::Time(3 * 24 * 60 * 60), days);
     }
+
+    #[test]
+    fn expiring_map_deadlines() {
+        let block = BlockInfo {
+            height: 1000,
+            time: Timestamp::from_seconds(7777),
+            chain_id: "foo".to_string(),
+        };
+        let expirations = [
+            Expiration::AtHeight(999),
+            Expiration::AtHeight(1000),
+            Expiration::AtHeight(1001),
+            Expiration::AtTime(Timestamp::from_seconds(7777)),
+            Expiration::AtTime(Timestamp::from_seconds(7778)),
+            Expiration::Never {},
+        ];
+        // the deadline is reached exactly when the expiration is expired
+        for expiration in expirations.iter() {
+            assert_eq!(
+                expiration.is_expired(&block),
+                Expires::is_expired(expiration, &block),
+                "{}",
+                expiration
+            );
+        }
+        assert_eq!(Expiration::Never {}.deadline(), None);
+    }
 }
@@ -1,4 +1,5 @@
 mod balance;
+mod curve;
 mod event;
 mod expiration;
 mod migrate;
@@ -7,20 +8,31 @@ mod parse_reply;
 mod payment;
 mod scheduled;
 mod threshold;
+mod vesting;
 
-pub use migrate::ensure_from_older_version;
+pub use migrate::{
+    ensure_from_older_version, migrate_item, migrate_map, MigrationError, MigrationFn,
+    MigrationProgress, Migrations, MIGRATION_CURSORS_NAMESPACE,
+};
 pub use pagination::{
     calc_range_end, calc_range_start, calc_range_start_string, maybe_addr, maybe_canonical,
 };
 pub use parse_reply::{
-    parse_execute_response_data, parse_instantiate_response_data, parse_reply_execute_data,
-    parse_reply_instantiate_data, MsgExecuteContractResponse, MsgInstantiateContractResponse,
-    ParseReplyError,
+    find_attribute, find_attributes, parse_delegate_response_data, parse_execute_response_data,
+    parse_instantiate_response_data, parse_migrate_response_data, parse_reply_delegate_data,
+    parse_reply_execute_data, parse_reply_instantiate_data, parse_reply_migrate_data,
+    parse_reply_send_data, parse_reply_transfer_data, parse_reply_undelegate_data,
+    parse_send_response_data, parse_transfer_response_data, parse_undelegate_response_data,
+    MsgDelegateResponse, MsgExecuteContractResponse, MsgInstantiateContractResponse,
+    MsgMigrateContractResponse, MsgSendResponse, MsgTransferResponse, MsgUndelegateResponse,
+    ParseReplyError, ProtobufReader, WireType,
 };
 pub use payment::{may_pay, must_pay, nonpayable, one_coin, PaymentError};
 pub use threshold::{Threshold, ThresholdError, ThresholdResponse};
 
 pub use crate::balance::NativeBalance;
+pub use crate::curve::{Curve, CurveError, PiecewiseLinear, SaturatingLinear};
 pub use crate::event::Event;
 pub use crate::expiration::{Duration, Expiration, DAY, HOUR, WEEK};
 pub use crate::scheduled::Scheduled;
+pub use crate::vesting::VestingSchedule;
@@ -1,5 +1,11 @@
-use cosmwasm_std::{StdError, StdResult, Storage};
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use cosmwasm_std::{Binary, DepsMut, Env, Order, StdError, StdResult, Storage};
 use cw2::{get_contract_version, set_contract_version};
+use cw_storage_plus::{Bound, Codec, Item, Map, Path, PrimaryKey};
 use semver::Version;
 
 /// This function not only validates that the right contract and version can be migrated, but also
@@ -38,10 +44,306 @@ fn from_semver(err: semver::Error) -> StdError {
     StdError::generic_err(format!("Semver: {}", err))
 }
 
+#[derive(Error, Debug, PartialEq)]
+pub enum MigrationError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Cannot migrate from {previous_contract} to {contract}")]
+    CannotMigrate {
+        previous_contract: String,
+        contract: String,
+    },
+
+    #[error("Cannot migrate from newer version ({previous_version}) to older ({version})")]
+    CannotMigrateVersion {
+        previous_version: String,
+        version: String,
+    },
+
+    #[error("No migration step from version {version}")]
+    MissingStep { version: String },
+
+    #[error("Invalid migration step from {from} to {to}")]
+    InvalidStep { from: String, to: String },
+}
+
+impl From<MigrationError> for StdError {
+    fn from(err: MigrationError) -> Self {
+        match err {
+            MigrationError::Std(err) => err,
+            err => StdError::generic_err(err.to_string()),
+        }
+    }
+}
+
+/// Returned by a migration step (and by `Migrations::run`) to tell whether all the data
+/// was converted, or whether another call is needed to finish the job.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum MigrationProgress {
+    Done,
+    Pending,
+}
+
+impl MigrationProgress {
+    pub fn is_done(&self) -> bool {
+        *self == MigrationProgress::Done
+    }
+}
+
+pub type MigrationFn<E> = fn(DepsMut, &Env) -> Result<MigrationProgress, E>;
+
+struct MigrationStep<E> {
+    from: &'static str,
+    to: &'static str,
+    migrate: MigrationFn<E>,
+}
+
+/// Migrations is a registry of the steps converting the state of a contract from one version
+/// to the next. A step `from -> to` converts the data written by any version in `[from, to)`
+/// into the layout used from `to` on, so the steps must be declared in order and follow each
+/// other (the `to` of a step is the `from` of the next one).
+///
+/// `run` reads the version stored with `cw2`, executes all the steps needed to bring it to the
+/// new version and updates the stored version.
+///
+/// A step can return `MigrationProgress::Pending` when it could only convert part of the data
+/// (see `migrate_map`). The stored version is then left at the `from` of that step, and the
+/// migration continues from there the next time `run` is called (e.g. from a dedicated execute
+/// message, or from `migrate` again). Steps that completed before are not run again.
+///
+/// ## Example:
+///
+/// ```rust
+/// # use cosmwasm_std::{DepsMut, Env, StdError};
+/// use cw_utils::{Migrations, MigrationProgress};
+///
+/// fn v1_to_v2(deps: DepsMut, _env: &Env) -> Result<MigrationProgress, StdError> {
+///     // load the old data and store it in the new format
+///     Ok(MigrationProgress::Done)
+/// }
+///
+/// fn v2_to_v3(deps: DepsMut, _env: &Env) -> Result<MigrationProgress, StdError> {
+///     Ok(MigrationProgress::Done)
+/// }
+///
+/// let migrations = Migrations::new()
+///     .add_step("0.1.0", "0.2.0", v1_to_v2)
+///     .add_step("0.2.0", "0.3.0", v2_to_v3);
+/// ```
+pub struct Migrations<E = StdError> {
+    steps: Vec<MigrationStep<E>>,
+}
+
+impl<E> Default for Migrations<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E> Migrations<E> {
+    pub fn new() -> Self {
+        Migrations { steps: vec![] }
+    }
+
+    /// Registers the step converting the data of versions `[from, to)` to the layout of `to`.
+    /// Steps must be added in order.
+    pub fn add_step(
+        mut self,
+        from: &'static str,
+        to: &'static str,
+        migrate: MigrationFn<E>,
+    ) -> Self {
+        self.steps.push(MigrationStep { from, to, migrate });
+        self
+    }
+}
+
+impl<E> Migrations<E>
+where
+    E: From<MigrationError>,
+{
+    /// Runs all the steps needed to bring the state of contract `name` from the version stored
+    /// with `cw2` to `new_version`, and stores the new version.
+    ///
+    /// Fails if the stored state belongs to another contract, to a newer version, or if there
+    /// is no step starting from the stored version while some data still needs to be migrated.
+    pub fn run(
+        &self,
+        mut deps: DepsMut,
+        env: &Env,
+        name: &str,
+        new_version: &str,
+    ) -> Result<MigrationProgress, E> {
+        let version = parse_version(new_version)?;
+        let stored = get_contract_version(deps.storage).map_err(MigrationError::Std)?;
+        if name != stored.contract {
+            return Err(MigrationError::CannotMigrate {
+                previous_contract: stored.contract,
+                contract: name.to_string(),
+            }
+            .into());
+        }
+        let mut current = parse_version(&stored.version)?;
+        if current > version {
+            return Err(MigrationError::CannotMigrateVersion {
+                previous_version: stored.version,
+                version: new_version.to_string(),
+            }
+            .into());
+        }
+
+        let versions = self.step_versions()?;
+        for ((from, to), step) in versions.into_iter().zip(self.steps.iter()) {
+            // already migrated, or a step for a future version
+            if to <= current || to > version {
+                continue;
+            }
+            if current < from {
+                return Err(MigrationError::MissingStep {
+                    version: current.to_string(),
+                }
+                .into());
+            }
+            if (step.migrate)(deps.branch(), env)? == MigrationProgress::Pending {
+                return Ok(MigrationProgress::Pending);
+            }
+            set_contract_version(deps.storage, name, step.to).map_err(MigrationError::Std)?;
+            current = to;
+        }
+
+        if current < version {
+            set_contract_version(deps.storage, name, new_version).map_err(MigrationError::Std)?;
+        }
+        Ok(MigrationProgress::Done)
+    }
+
+    /// Parses the versions of the steps, ensuring they follow each other
+    fn step_versions(&self) -> Result<Vec<(Version, Version)>, MigrationError> {
+        let mut versions: Vec<(Version, Version)> = vec![];
+        for step in self.steps.iter() {
+            let from = parse_version(step.from)?;
+            let to = parse_version(step.to)?;
+            let follows = match versions.last() {
+                Some((_, prev_to)) => *prev_to == from,
+                None => true,
+            };
+            if from >= to || !follows {
+                return Err(MigrationError::InvalidStep {
+                    from: step.from.to_string(),
+                    to: step.to.to_string(),
+                });
+            }
+            versions.push((from, to));
+        }
+        Ok(versions)
+    }
+}
+
+fn parse_version(version: &str) -> Result<Version, MigrationError> {
+    version
+        .parse()
+        .map_err(|err| MigrationError::Std(from_semver(err)))
+}
+
+/// Converts the value of `from` with `transform` and stores it in `to`. Both items can use the
+/// same key (to change the schema in place) or not, in which case the old value is removed.
+/// Does nothing if `from` is empty.
+pub fn migrate_item<O, N, CO, CN, F, E>(
+    store: &mut dyn Storage,
+    from: &Item<O, CO>,
+    to: &Item<N, CN>,
+    transform: F,
+) -> Result<(), E>
+where
+    O: Serialize + DeserializeOwned,
+    N: Serialize + DeserializeOwned,
+    CO: Codec<O>,
+    CN: Codec<N>,
+    F: FnOnce(O) -> Result<N, E>,
+    E: From<StdError>,
+{
+    let old = match from.may_load(store)? {
+        Some(old) => old,
+        None => return Ok(()),
+    };
+    let new = transform(old)?;
+    if from.as_slice() != to.as_slice() {
+        from.remove(store);
+    }
+    to.save(store, &new)?;
+    Ok(())
+}
+
+/// Storage namespace where `migrate_map` keeps the last key it migrated, by namespace of the old
+/// map. Contracts using `migrate_map` must not use it for their own data.
+pub const MIGRATION_CURSORS_NAMESPACE: &str = "__cw_utils_migration_cursors";
+
+const MIGRATION_CURSORS: Map<&[u8], Binary> = Map::new(MIGRATION_CURSORS_NAMESPACE);
+
+/// Converts up to `limit` entries of `from` with `transform` and stores them in `to`, under the
+/// same keys. As for `migrate_item`, both maps can share their namespace or not, in which case
+/// the old entries are removed.
+///
+/// The position reached is stored (under `MIGRATION_CURSORS_NAMESPACE`), so that calling this
+/// again (in the same migration step, in a later call) continues with the next entries. Returns
+/// `MigrationProgress::Done` once all the entries were converted. `limit` must not be zero.
+pub fn migrate_map<'a, K, O, N, CO, CN, F, E>(
+    store: &mut dyn Storage,
+    from: &Map<'a, K, O, CO>,
+    to: &Map<'a, K, N, CN>,
+    limit: usize,
+    mut transform: F,
+) -> Result<MigrationProgress, E>
+where
+    K: PrimaryKey<'a>,
+    O: Serialize + DeserializeOwned,
+    N: Serialize + DeserializeOwned,
+    CO: Codec<O>,
+    CN: Codec<N>,
+    F: FnMut(O) -> Result<N, E>,
+    E: From<StdError>,
+{
+    if limit == 0 {
+        return Err(StdError::generic_err("Migration limit must be greater than zero").into());
+    }
+    let cursor = MIGRATION_CURSORS.may_load(store, from.namespace())?;
+    let min = cursor.map(|c| Bound::ExclusiveRaw(c.into()));
+    let batch = from
+        .range_raw(store, min, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let done = batch.len() < limit;
+    let last_key = batch.last().map(|(key, _)| key.clone());
+    let in_place = from.namespace() == to.namespace();
+    for (key, old) in batch {
+        let new = transform(old)?;
+        if !in_place {
+            Path::<O>::new(from.namespace(), &[&key]).remove(store);
+        }
+        Path::<N>::new(to.namespace(), &[&key])
+            .with_codec::<CN>()
+            .save(store, &new)?;
+    }
+
+    match last_key {
+        Some(key) if !done => {
+            MIGRATION_CURSORS.save(store, from.namespace(), &key.into())?;
+            Ok(MigrationProgress::Pending)
+        }
+        _ => {
+            MIGRATION_CURSORS.remove(store, from.namespace());
+            Ok(MigrationProgress::Done)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use cosmwasm_std::testing::MockStorage;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, MockStorage};
 
     #[test]
     fn accepts_identical_version() {
@@ -97,4 +399,275 @@ mod tests {
             err
         );
     }
+
+    // the steps record that they ran here
+    const STEPS_RUN: Item<Vec<String>> = Item::new("steps_run");
+
+    fn record(storage: &mut dyn Storage, step: &str) -> StdResult<()> {
+        let mut run = STEPS_RUN.may_load(storage)?.unwrap_or_default();
+        run.push(step.to_string());
+        STEPS_RUN.save(storage, &run)
+    }
+
+    fn step_one(deps: DepsMut, _env: &Env) -> StdResult<MigrationProgress> {
+        record(deps.storage, "one")?;
+        Ok(MigrationProgress::Done)
+    }
+
+    fn step_two(deps: DepsMut, _env: &Env) -> StdResult<MigrationProgress> {
+        record(deps.storage, "two")?;
+        Ok(MigrationProgress::Done)
+    }
+
+    fn migrations() -> Migrations {
+        Migrations::new()
+            .add_step("0.1.0", "0.2.0", step_one)
+            .add_step("0.2.0", "0.3.0", step_two)
+    }
+
+    #[test]
+    fn runs_steps_in_order() {
+        let mut deps = mock_dependencies();
+        set_contract_version(&mut deps.storage, "demo", "0.1.3").unwrap();
+
+        let progress = migrations()
+            .run(deps.as_mut(), &mock_env(), "demo", "0.3.2")
+            .unwrap();
+        assert_eq!(progress, MigrationProgress::Done);
+        assert_eq!(STEPS_RUN.load(&deps.storage).unwrap(), vec!["one", "two"]);
+        let stored = get_contract_version(&deps.storage).unwrap();
+        assert_eq!(stored.version, "0.3.2".to_string());
+
+        // nothing left to do
+        migrations()
+            .run(deps.as_mut(), &mock_env(), "demo", "0.3.2")
+            .unwrap();
+        assert_eq!(STEPS_RUN.load(&deps.storage).unwrap(), vec!["one", "two"]);
+    }
+
+    #[test]
+    fn only_runs_needed_steps() {
+        let mut deps = mock_dependencies();
+        set_contract_version(&mut deps.storage, "demo", "0.2.0").unwrap();
+        migrations()
+            .run(deps.as_mut(), &mock_env(), "demo", "0.4.0")
+            .unwrap();
+        assert_eq!(STEPS_RUN.load(&deps.storage).unwrap(), vec!["two"]);
+
+        // steps for versions above the new one are left for later
+        let mut deps = mock_dependencies();
+        set_contract_version(&mut deps.storage, "demo", "0.1.0").unwrap();
+        migrations()
+            .run(deps.as_mut(), &mock_env(), "demo", "0.2.1")
+            .unwrap();
+        assert_eq!(STEPS_RUN.load(&deps.storage).unwrap(), vec!["one"]);
+        let stored = get_contract_version(&deps.storage).unwrap();
+        assert_eq!(stored.version, "0.2.1".to_string());
+    }
+
+    #[test]
+    fn rejects_unsupported_versions() {
+        let mut deps = mock_dependencies();
+        set_contract_version(&mut deps.storage, "demo", "0.0.9").unwrap();
+        let err = migrations()
+            .run(deps.as_mut(), &mock_env(), "demo", "0.3.0")
+            .unwrap_err();
+        assert_eq!(
+            err,
+            StdError::from(MigrationError::MissingStep {
+                version: "0.0.9".to_string()
+            })
+        );
+        assert!(STEPS_RUN.may_load(&deps.storage).unwrap().is_none());
+
+        set_contract_version(&mut deps.storage, "demo", "0.4.0").unwrap();
+        let err = migrations()
+            .run(deps.as_mut(), &mock_env(), "demo", "0.3.0")
+            .unwrap_err();
+        assert!(err.to_string().contains("newer version"), "{}", err);
+
+        let err = migrations()
+            .run(deps.as_mut(), &mock_env(), "cw20-base", "0.4.0")
+            .unwrap_err();
+        assert!(err.to_string().contains("demo"), "{}", err);
+    }
+
+    #[test]
+    fn rejects_gaps_between_steps() {
+        let mut deps = mock_dependencies();
+        set_contract_version(&mut deps.storage, "demo", "0.1.0").unwrap();
+        let err = Migrations::new()
+            .add_step("0.1.0", "0.2.0", step_one)
+            .add_step("0.2.1", "0.3.0", step_two)
+            .run(deps.as_mut(), &mock_env(), "demo", "0.3.0")
+            .unwrap_err();
+        assert_eq!(
+            err,
+            StdError::from(MigrationError::InvalidStep {
+                from: "0.2.1".to_string(),
+                to: "0.3.0".to_string()
+            })
+        );
+        assert!(STEPS_RUN.may_load(&deps.storage).unwrap().is_none());
+    }
+
+    #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+    struct OldConfig {
+        owner: String,
+    }
+
+    #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+    struct Config {
+        owner: String,
+        paused: bool,
+    }
+
+    #[test]
+    fn migrate_item_works() {
+        let mut storage = MockStorage::new();
+        let old: Item<OldConfig> = Item::new("config");
+        let new: Item<Config> = Item::new("config");
+        let renamed: Item<Config> = Item::new("config_v2");
+
+        // nothing to migrate
+        migrate_item::<_, _, _, _, _, StdError>(&mut storage, &old, &new, |_| unreachable!())
+            .unwrap();
+
+        old.save(
+            &mut storage,
+            &OldConfig {
+                owner: "admin".to_string(),
+            },
+        )
+        .unwrap();
+        migrate_item::<_, _, _, _, _, StdError>(&mut storage, &old, &new, |old| {
+            Ok(Config {
+                owner: old.owner,
+                paused: false,
+            })
+        })
+        .unwrap();
+        let expected = Config {
+            owner: "admin".to_string(),
+            paused: false,
+        };
+        assert_eq!(new.load(&storage).unwrap(), expected);
+
+        // moving to another key removes the original
+        migrate_item::<_, _, _, _, _, StdError>(&mut storage, &new, &renamed, |mut config| {
+            config.paused = true;
+            Ok(config)
+        })
+        .unwrap();
+        assert!(new.may_load(&storage).unwrap().is_none());
+        assert!(renamed.load(&storage).unwrap().paused);
+    }
+
+    const OLD_BALANCES: Map<&str, u64> = Map::new("balances");
+    const BALANCES: Map<&str, Config> = Map::new("balances");
+
+    fn migrate_balances(deps: DepsMut, _env: &Env) -> StdResult<MigrationProgress> {
+        record(deps.storage, "balances")?;
+        migrate_map(deps.storage, &OLD_BALANCES, &BALANCES, 2, |amount| {
+            Ok(Config {
+                owner: amount.to_string(),
+                paused: false,
+            })
+        })
+    }
+
+    #[test]
+    fn migrate_map_in_batches() {
+        let mut deps = mock_dependencies();
+        set_contract_version(&mut deps.storage, "demo", "0.1.0").unwrap();
+        for (i, addr) in ["a", "b", "c", "d", "e"].iter().enumerate() {
+            OLD_BALANCES
+                .save(&mut deps.storage, addr, &(i as u64 * 100))
+                .unwrap();
+        }
+
+        let migrations = Migrations::new()
+            .add_step("0.1.0", "0.2.0", step_one)
+            .add_step("0.2.0", "0.3.0", migrate_balances);
+        let mut calls = 0;
+        loop {
+            calls += 1;
+            let progress = migrations
+                .run(deps.as_mut(), &mock_env(), "demo", "0.3.0")
+                .unwrap();
+            if progress.is_done() {
+                break;
+            }
+            // the step is resumed, not restarted
+            let stored = get_contract_version(&deps.storage).unwrap();
+            assert_eq!(stored.version, "0.2.0".to_string());
+        }
+        assert_eq!(calls, 3);
+        assert_eq!(
+            STEPS_RUN.load(&deps.storage).unwrap(),
+            vec!["one", "balances", "balances", "balances"]
+        );
+        let stored = get_contract_version(&deps.storage).unwrap();
+        assert_eq!(stored.version, "0.3.0".to_string());
+
+        let all = BALANCES
+            .range(&deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(all.len(), 5);
+        assert_eq!(
+            all[3],
+            (
+                "d".to_string(),
+                Config {
+                    owner: "300".to_string(),
+                    paused: false
+                }
+            )
+        );
+        assert!(MIGRATION_CURSORS
+            .may_load(&deps.storage, OLD_BALANCES.namespace())
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn migrate_map_rejects_zero_limit() {
+        let mut storage = MockStorage::new();
+        let old: Map<&str, u64> = Map::new("old");
+        let new: Map<&str, String> = Map::new("new");
+        old.save(&mut storage, "a", &7).unwrap();
+
+        let res: StdResult<_> = migrate_map(&mut storage, &old, &new, 0, |v| Ok(v.to_string()));
+        assert_eq!(
+            res.unwrap_err(),
+            StdError::generic_err("Migration limit must be greater than zero")
+        );
+        // nothing was migrated, nor marked as done
+        assert_eq!(old.load(&storage, "a").unwrap(), 7);
+        assert!(!new.has(&storage, "a"));
+
+        let progress: StdResult<_> =
+            migrate_map(&mut storage, &old, &new, 1, |v| Ok(v.to_string()));
+        assert_eq!(progress.unwrap(), MigrationProgress::Pending);
+        assert_eq!(new.load(&storage, "a").unwrap(), "7");
+    }
+
+    #[test]
+    fn migrate_map_to_new_namespace() {
+        let mut storage = MockStorage::new();
+        let old: Map<(&str, u8), u64> = Map::new("old");
+        let new: Map<(&str, u8), String> = Map::new("new");
+        old.save(&mut storage, ("a", 1), &7).unwrap();
+        old.save(&mut storage, ("b", 2), &8).unwrap();
+
+        let progress: StdResult<_> =
+            migrate_map(&mut storage, &old, &new, 10, |v| Ok(v.to_string()));
+        assert_eq!(progress.unwrap(), MigrationProgress::Done);
+        assert_eq!(
+            old.keys_raw(&storage, None, None, Order::Ascending).count(),
+            0
+        );
+        assert_eq!(new.load(&storage, ("b", 2)).unwrap(), "8".to_string());
+    }
 }
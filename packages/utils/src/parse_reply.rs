@@ -1,11 +1,9 @@
 use thiserror::Error;
 
-use cosmwasm_std::{Binary, Reply};
+use cosmwasm_std::{Binary, Reply, SubMsgResult, Timestamp};
 
-// Protobuf wire types (https://developers.google.com/protocol-buffers/docs/encoding)
-const WIRE_TYPE_LENGTH_DELIMITED: u8 = 2;
-// Up to 9 bytes of varints as a practical limit (https://github.com/multiformats/unsigned-varint#practical-maximum-of-9-bytes-for-security)
-const VARINT_MAX_BYTES: usize = 9;
+// A full u64 (or a negative int64 / int32) needs 10 bytes
+const VARINT_U64_MAX_BYTES: usize = 10;
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct MsgInstantiateContractResponse {
@@ -18,89 +16,162 @@ pub struct MsgExecuteContractResponse {
     pub data: Option<Binary>,
 }
 
-/// Base128 varint decoding.
-/// The remaining of the data is kept in the data parameter.
-fn parse_protobuf_varint(data: &mut Vec<u8>, field_number: u8) -> Result<usize, ParseReplyError> {
-    let data_len = data.len();
-    let mut len: u64 = 0;
-    let mut i = 0;
-    while i < VARINT_MAX_BYTES {
-        if data_len == i {
+#[derive(Clone, Debug, PartialEq)]
+pub struct MsgMigrateContractResponse {
+    pub data: Option<Binary>,
+}
+
+/// Response to a `BankMsg::Send`, which has no content
+#[derive(Clone, Debug, PartialEq)]
+pub struct MsgSendResponse {}
+
+/// Response to a `StakingMsg::Delegate`, which has no content
+#[derive(Clone, Debug, PartialEq)]
+pub struct MsgDelegateResponse {}
+
+/// Response to a `StakingMsg::Undelegate`
+#[derive(Clone, Debug, PartialEq)]
+pub struct MsgUndelegateResponse {
+    /// When the unbonded tokens are released
+    pub completion_time: Timestamp,
+}
+
+/// Response to an `IbcMsg::Transfer`
+#[derive(Clone, Debug, PartialEq)]
+pub struct MsgTransferResponse {
+    /// Sequence number of the packet sent on the channel
+    pub sequence: u64,
+}
+
+/// Protobuf wire types (only the ones still in use)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WireType {
+    Varint,
+    Fixed64,
+    LengthDelimited,
+    Fixed32,
+}
+
+/// ProtobufReader decodes protobuf messages field by field, without any generated code.
+/// It only covers what is needed to read the responses of messages dispatched by a contract.
+///
+/// ```rust
+/// use cw_utils::{ProtobufReader, WireType};
+///
+/// // field #1: varint 150
+/// let mut reader = ProtobufReader::new(b"\x08\x96\x01");
+/// assert_eq!(reader.next_field().unwrap(), Some((1, WireType::Varint)));
+/// assert_eq!(reader.read_varint().unwrap(), 150);
+/// assert_eq!(reader.next_field().unwrap(), None);
+/// ```
+pub struct ProtobufReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> ProtobufReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        ProtobufReader { data }
+    }
+
+    /// Returns true if all the data was read
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Reads the key of the next field, returning its field number and wire type,
+    /// or `None` if all the data was read.
+    pub fn next_field(&mut self) -> Result<Option<(u32, WireType)>, ParseReplyError> {
+        if self.data.is_empty() {
+            return Ok(None);
+        }
+        let key = self.read_varint()?;
+        let wire_type = match key & 0b111 {
+            0 => WireType::Varint,
+            1 => WireType::Fixed64,
+            2 => WireType::LengthDelimited,
+            5 => WireType::Fixed32,
+            wire_type => {
+                return Err(ParseReplyError::ParseFailure(format!(
+                    "failed to decode Protobuf message: unsupported wire type {}",
+                    wire_type
+                )))
+            }
+        };
+        let field = key >> 3;
+        if field == 0 || field > u32::MAX as u64 {
             return Err(ParseReplyError::ParseFailure(format!(
-                "failed to decode Protobuf message: field #{}: varint data too short",
-                field_number
+                "failed to decode Protobuf message: invalid field #{}",
+                field
             )));
         }
-        len += ((data[i] & 0x7f) as u64) << (i * 7);
-        if data[i] & 0x80 == 0 {
-            break;
-        }
-        i += 1;
+        Ok(Some((field as u32, wire_type)))
     }
-    if i == VARINT_MAX_BYTES {
-        return Err(ParseReplyError::ParseFailure(format!(
-            "failed to decode Protobuf message: field #{}: varint data too long",
-            field_number
-        )));
-    }
-    *data = data[i + 1..].to_owned();
-
-    Ok(len as usize) // Gently fall back to the arch's max addressable size
-}
 
-/// Helper function to parse length-prefixed protobuf fields.
-/// The remaining of the data is kept in the data parameter.
-fn parse_protobuf_length_prefixed(
-    data: &mut Vec<u8>,
-    field_number: u8,
-) -> Result<Vec<u8>, ParseReplyError> {
-    if data.is_empty() {
-        return Ok(vec![]);
-    };
-    let mut rest_1 = data.split_off(1);
-    let wire_type = data[0] & 0b11;
-    let field = data[0] >> 3;
-
-    if field != field_number {
-        return Err(ParseReplyError::ParseFailure(format!(
-            "failed to decode Protobuf message: invalid field #{} for field #{}",
-            field, field_number
-        )));
+    /// Reads a base128 varint. Negative `int64` / `int32` values are returned as their
+    /// two's complement, as `u64`.
+    pub fn read_varint(&mut self) -> Result<u64, ParseReplyError> {
+        let mut value: u64 = 0;
+        for i in 0..VARINT_U64_MAX_BYTES {
+            let byte = match self.data.get(i) {
+                Some(byte) => *byte,
+                None => {
+                    return Err(ParseReplyError::ParseFailure(
+                        "failed to decode Protobuf message: varint data too short".to_owned(),
+                    ))
+                }
+            };
+            if i == VARINT_U64_MAX_BYTES - 1 && byte > 1 {
+                break;
+            }
+            value += ((byte & 0x7f) as u64) << (i * 7);
+            if byte & 0x80 == 0 {
+                self.data = &self.data[i + 1..];
+                return Ok(value);
+            }
+        }
+        Err(ParseReplyError::ParseFailure(
+            "failed to decode Protobuf message: varint data too long".to_owned(),
+        ))
     }
-    if wire_type != WIRE_TYPE_LENGTH_DELIMITED {
-        return Err(ParseReplyError::ParseFailure(format!(
-            "failed to decode Protobuf message: field #{}: invalid wire type {}",
-            field_number, wire_type
-        )));
+
+    /// Reads the content of a length-delimited field (bytes, string or embedded message)
+    pub fn read_bytes(&mut self) -> Result<&'a [u8], ParseReplyError> {
+        let len = self.read_varint()?;
+        self.take(len)
     }
 
-    let len = parse_protobuf_varint(&mut rest_1, field_number)?;
-    if rest_1.len() < len {
-        return Err(ParseReplyError::ParseFailure(format!(
-            "failed to decode Protobuf message: field #{}: message too short",
-            field_number
-        )));
+    /// Reads a string field
+    pub fn read_string(&mut self) -> Result<String, ParseReplyError> {
+        Ok(String::from_utf8(self.read_bytes()?.to_vec())?)
     }
-    *data = rest_1.split_off(len);
 
-    Ok(rest_1)
-}
+    /// Skips the content of a field of the given wire type, e.g. an unknown field
+    pub fn skip(&mut self, wire_type: WireType) -> Result<(), ParseReplyError> {
+        match wire_type {
+            WireType::Varint => self.read_varint().map(|_| ()),
+            WireType::Fixed64 => self.take(8).map(|_| ()),
+            WireType::LengthDelimited => self.read_bytes().map(|_| ()),
+            WireType::Fixed32 => self.take(4).map(|_| ()),
+        }
+    }
 
-fn parse_protobuf_string(data: &mut Vec<u8>, field_number: u8) -> Result<String, ParseReplyError> {
-    let str_field = parse_protobuf_length_prefixed(data, field_number)?;
-    Ok(String::from_utf8(str_field)?)
+    fn take(&mut self, len: u64) -> Result<&'a [u8], ParseReplyError> {
+        if (self.data.len() as u64) < len {
+            return Err(ParseReplyError::ParseFailure(
+                "failed to decode Protobuf message: message too short".to_owned(),
+            ));
+        }
+        let (field, rest) = self.data.split_at(len as usize);
+        self.data = rest;
+        Ok(field)
+    }
 }
 
-fn parse_protobuf_bytes(
-    data: &mut Vec<u8>,
-    field_number: u8,
-) -> Result<Option<Binary>, ParseReplyError> {
-    let bytes_field = parse_protobuf_length_prefixed(data, field_number)?;
-    if bytes_field.is_empty() {
-        Ok(None)
-    } else {
-        Ok(Some(Binary(bytes_field)))
-    }
+fn invalid_wire_type(field: u32, wire_type: WireType) -> ParseReplyError {
+    ParseReplyError::ParseFailure(format!(
+        "failed to decode Protobuf message: field #{}: invalid wire type {:?}",
+        field, wire_type
+    ))
 }
 
 pub fn parse_reply_instantiate_data(
@@ -128,28 +199,201 @@ pub fn parse_reply_execute_data(msg: Reply) -> Result<MsgExecuteContractResponse
 pub fn parse_instantiate_response_data(
     data: &[u8],
 ) -> Result<MsgInstantiateContractResponse, ParseReplyError> {
-    // Manual protobuf decoding
-    let mut data = data.to_vec();
-    // Parse contract addr
-    let contract_addr = parse_protobuf_string(&mut data, 1)?;
-
-    // Parse (optional) data
-    let data = parse_protobuf_bytes(&mut data, 2)?;
-
-    Ok(MsgInstantiateContractResponse {
-        contract_address: contract_addr,
-        data,
-    })
+    let mut reader = ProtobufReader::new(data);
+    let mut res = MsgInstantiateContractResponse {
+        contract_address: String::new(),
+        data: None,
+    };
+    while let Some((field, wire_type)) = reader.next_field()? {
+        match (field, wire_type) {
+            (1, WireType::LengthDelimited) => res.contract_address = reader.read_string()?,
+            (2, WireType::LengthDelimited) => res.data = non_empty(reader.read_bytes()?),
+            (1, _) | (2, _) => return Err(invalid_wire_type(field, wire_type)),
+            _ => reader.skip(wire_type)?,
+        }
+    }
+    Ok(res)
 }
 
 pub fn parse_execute_response_data(
     data: &[u8],
 ) -> Result<MsgExecuteContractResponse, ParseReplyError> {
-    // Manual protobuf decoding
-    let mut data = data.to_vec();
-    let inner_data = parse_protobuf_bytes(&mut data, 1)?;
+    let mut reader = ProtobufReader::new(data);
+    let mut res = MsgExecuteContractResponse { data: None };
+    while let Some((field, wire_type)) = reader.next_field()? {
+        match (field, wire_type) {
+            (1, WireType::LengthDelimited) => res.data = non_empty(reader.read_bytes()?),
+            (1, _) => return Err(invalid_wire_type(field, wire_type)),
+            _ => reader.skip(wire_type)?,
+        }
+    }
+    Ok(res)
+}
+
+pub fn parse_reply_migrate_data(msg: Reply) -> Result<MsgMigrateContractResponse, ParseReplyError> {
+    parse_migrate_response_data(&reply_data(msg)?)
+}
+
+pub fn parse_reply_send_data(msg: Reply) -> Result<MsgSendResponse, ParseReplyError> {
+    parse_send_response_data(&reply_data(msg)?)
+}
+
+pub fn parse_reply_delegate_data(msg: Reply) -> Result<MsgDelegateResponse, ParseReplyError> {
+    parse_delegate_response_data(&reply_data(msg)?)
+}
+
+pub fn parse_reply_undelegate_data(msg: Reply) -> Result<MsgUndelegateResponse, ParseReplyError> {
+    parse_undelegate_response_data(&reply_data(msg)?)
+}
+
+pub fn parse_reply_transfer_data(msg: Reply) -> Result<MsgTransferResponse, ParseReplyError> {
+    parse_transfer_response_data(&reply_data(msg)?)
+}
+
+pub fn parse_migrate_response_data(
+    data: &[u8],
+) -> Result<MsgMigrateContractResponse, ParseReplyError> {
+    let mut reader = ProtobufReader::new(data);
+    let mut res = MsgMigrateContractResponse { data: None };
+    while let Some((field, wire_type)) = reader.next_field()? {
+        match (field, wire_type) {
+            (1, WireType::LengthDelimited) => res.data = non_empty(reader.read_bytes()?),
+            (1, _) => return Err(invalid_wire_type(field, wire_type)),
+            _ => reader.skip(wire_type)?,
+        }
+    }
+    Ok(res)
+}
+
+/// Checks the response to a bank send is a valid protobuf message. Unknown fields are ignored.
+pub fn parse_send_response_data(data: &[u8]) -> Result<MsgSendResponse, ParseReplyError> {
+    skip_all(data)?;
+    Ok(MsgSendResponse {})
+}
+
+/// Checks the response to a delegation is a valid protobuf message. Unknown fields are ignored.
+pub fn parse_delegate_response_data(data: &[u8]) -> Result<MsgDelegateResponse, ParseReplyError> {
+    skip_all(data)?;
+    Ok(MsgDelegateResponse {})
+}
+
+pub fn parse_undelegate_response_data(
+    data: &[u8],
+) -> Result<MsgUndelegateResponse, ParseReplyError> {
+    let mut reader = ProtobufReader::new(data);
+    let mut completion_time = None;
+    while let Some((field, wire_type)) = reader.next_field()? {
+        match (field, wire_type) {
+            (1, WireType::LengthDelimited) => {
+                completion_time = Some(parse_timestamp(reader.read_bytes()?)?)
+            }
+            (1, _) => return Err(invalid_wire_type(field, wire_type)),
+            _ => reader.skip(wire_type)?,
+        }
+    }
+    let completion_time = completion_time
+        .ok_or_else(|| ParseReplyError::ParseFailure("Missing completion time".to_owned()))?;
+    Ok(MsgUndelegateResponse { completion_time })
+}
+
+pub fn parse_transfer_response_data(data: &[u8]) -> Result<MsgTransferResponse, ParseReplyError> {
+    let mut reader = ProtobufReader::new(data);
+    let mut sequence = 0;
+    while let Some((field, wire_type)) = reader.next_field()? {
+        match (field, wire_type) {
+            (1, WireType::Varint) => sequence = reader.read_varint()?,
+            (1, _) => return Err(invalid_wire_type(field, wire_type)),
+            _ => reader.skip(wire_type)?,
+        }
+    }
+    Ok(MsgTransferResponse { sequence })
+}
+
+/// Returns the value of the first attribute with the given key, in the first event of the
+/// given type emitted by the sub-message (e.g. `find_attribute(&reply, "wasm", "token_id")`).
+pub fn find_attribute<'a>(
+    msg: &'a Reply,
+    event_type: &str,
+    key: &str,
+) -> Result<&'a str, ParseReplyError> {
+    find_attributes(msg, event_type, key)?
+        .into_iter()
+        .next()
+        .ok_or_else(|| {
+            ParseReplyError::ParseFailure(format!(
+                "Missing attribute {} in event {}",
+                key, event_type
+            ))
+        })
+}
+
+/// Returns the values of all the attributes with the given key, in all the events of the
+/// given type emitted by the sub-message, in order
+pub fn find_attributes<'a>(
+    msg: &'a Reply,
+    event_type: &str,
+    key: &str,
+) -> Result<Vec<&'a str>, ParseReplyError> {
+    let response = match &msg.result {
+        SubMsgResult::Ok(response) => response,
+        SubMsgResult::Err(err) => return Err(ParseReplyError::SubMsgFailure(err.clone())),
+    };
+    Ok(response
+        .events
+        .iter()
+        .filter(|event| event.ty == event_type)
+        .flat_map(|event| event.attributes.iter())
+        .filter(|attr| attr.key == key)
+        .map(|attr| attr.value.as_str())
+        .collect())
+}
+
+// returns the data of a successful reply, empty if there is none
+fn reply_data(msg: Reply) -> Result<Binary, ParseReplyError> {
+    let data = msg
+        .result
+        .into_result()
+        .map_err(ParseReplyError::SubMsgFailure)?
+        .data;
+    Ok(data.unwrap_or_default())
+}
+
+// bytes fields are optional, with an empty value for none
+fn non_empty(data: &[u8]) -> Option<Binary> {
+    if data.is_empty() {
+        None
+    } else {
+        Some(Binary::from(data))
+    }
+}
 
-    Ok(MsgExecuteContractResponse { data: inner_data })
+fn skip_all(data: &[u8]) -> Result<(), ParseReplyError> {
+    let mut reader = ProtobufReader::new(data);
+    while let Some((_, wire_type)) = reader.next_field()? {
+        reader.skip(wire_type)?;
+    }
+    Ok(())
+}
+
+// decodes a google.protobuf.Timestamp
+fn parse_timestamp(data: &[u8]) -> Result<Timestamp, ParseReplyError> {
+    let mut reader = ProtobufReader::new(data);
+    let mut seconds = 0;
+    let mut nanos = 0;
+    while let Some((field, wire_type)) = reader.next_field()? {
+        match (field, wire_type) {
+            (1, WireType::Varint) => seconds = reader.read_varint()?,
+            (2, WireType::Varint) => nanos = reader.read_varint()?,
+            (1, _) | (2, _) => return Err(invalid_wire_type(field, wire_type)),
+            _ => reader.skip(wire_type)?,
+        }
+    }
+    if seconds > u64::MAX / 1_000_000_000 || nanos >= 1_000_000_000 {
+        return Err(ParseReplyError::ParseFailure(
+            "Invalid timestamp".to_owned(),
+        ));
+    }
+    Ok(Timestamp::from_seconds(seconds).plus_nanos(nanos))
 }
 
 #[derive(Error, Debug, PartialEq)]
@@ -168,9 +412,8 @@ pub enum ParseReplyError {
 mod test {
     use super::*;
     use crate::parse_reply::ParseReplyError::{BrokenUtf8, ParseFailure};
-    use cosmwasm_std::{SubMsgResponse, SubMsgResult};
+    use cosmwasm_std::{Event, SubMsgResponse};
     use prost::Message;
-    use std::str::from_utf8;
 
     fn encode_bytes(data: &[u8]) -> Vec<u8> {
         #[derive(Clone, PartialEq, Message)]
@@ -218,197 +461,358 @@ mod test {
         pub data: ::prost::alloc::vec::Vec<u8>,
     }
 
-    #[test]
-    fn parse_protobuf_varint_tests() {
-        let field_number = 1;
-        // Single-byte varint works
-        let mut data = b"\x0a".to_vec();
-        let len = parse_protobuf_varint(&mut data, field_number).unwrap();
-        assert_eq!(len, 10);
+    #[derive(Clone, PartialEq, Message)]
+    struct ProtobufTimestamp {
+        #[prost(int64, tag = "1")]
+        pub seconds: i64,
+        #[prost(int32, tag = "2")]
+        pub nanos: i32,
+    }
 
-        // Rest is returned
-        let mut data = b"\x0a\x0b".to_vec();
-        let len = parse_protobuf_varint(&mut data, field_number).unwrap();
-        assert_eq!(len, 10);
-        assert_eq!(data, b"\x0b".to_vec());
+    #[derive(Clone, PartialEq, Message)]
+    struct MsgUndelegateResponse {
+        #[prost(message, optional, tag = "1")]
+        pub completion_time: ::core::option::Option<ProtobufTimestamp>,
+    }
 
-        // Multi-byte varint works
-        // 300 % 128 = 44. 44 + 128 = 172 (0xac) (1st byte)
-        // 300 / 128 = 2 (x02) (2nd byte)
-        let mut data = b"\xac\x02".to_vec();
-        let len = parse_protobuf_varint(&mut data, field_number).unwrap();
-        assert_eq!(len, 300);
+    #[derive(Clone, PartialEq, Message)]
+    struct MsgTransferResponse {
+        #[prost(uint64, tag = "1")]
+        pub sequence: u64,
+    }
+
+    // same as MsgTransferResponse, with fields added by a newer version
+    #[derive(Clone, PartialEq, Message)]
+    struct MsgTransferResponseV2 {
+        #[prost(uint64, tag = "1")]
+        pub sequence: u64,
+        #[prost(string, tag = "2")]
+        pub memo: ::prost::alloc::string::String,
+        #[prost(fixed64, tag = "3")]
+        pub fixed: u64,
+        #[prost(fixed32, tag = "4")]
+        pub small: u32,
+    }
 
-        // Rest is returned
-        let mut data = b"\xac\x02\x0c".to_vec();
-        let len = parse_protobuf_varint(&mut data, field_number).unwrap();
-        assert_eq!(len, 300);
-        assert_eq!(data, b"\x0c".to_vec());
+    fn encode<M: Message>(msg: M) -> Vec<u8> {
+        let mut encoded = Vec::<u8>::with_capacity(msg.encoded_len());
+        msg.encode(&mut encoded).unwrap();
+        encoded
+    }
 
-        // varint data too short (Empty varint)
-        let mut data = vec![];
-        let err = parse_protobuf_varint(&mut data, field_number).unwrap_err();
+    fn ok_reply(data: Option<Vec<u8>>, events: Vec<Event>) -> Reply {
+        Reply {
+            id: 1,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events,
+                data: data.map(Binary),
+            }),
+        }
+    }
+
+    #[test]
+    fn protobuf_reader_works() {
+        let encoded = encode(MsgTransferResponseV2 {
+            sequence: 300,
+            memo: "hello".to_string(),
+            fixed: 7,
+            small: 3,
+        });
+        let mut reader = ProtobufReader::new(&encoded);
+        assert_eq!(reader.next_field().unwrap(), Some((1, WireType::Varint)));
+        assert_eq!(reader.read_varint().unwrap(), 300);
+        assert_eq!(
+            reader.next_field().unwrap(),
+            Some((2, WireType::LengthDelimited))
+        );
+        assert_eq!(reader.read_string().unwrap(), "hello");
+        assert_eq!(reader.next_field().unwrap(), Some((3, WireType::Fixed64)));
+        reader.skip(WireType::Fixed64).unwrap();
+        assert_eq!(reader.next_field().unwrap(), Some((4, WireType::Fixed32)));
+        reader.skip(WireType::Fixed32).unwrap();
+        assert!(reader.is_empty());
+        assert_eq!(reader.next_field().unwrap(), None);
+
+        // truncated data
+        let mut reader = ProtobufReader::new(&encoded[..encoded.len() - 1]);
+        for _ in 0..3 {
+            let (_, wire_type) = reader.next_field().unwrap().unwrap();
+            reader.skip(wire_type).unwrap();
+        }
+        let (_, wire_type) = reader.next_field().unwrap().unwrap();
+        let err = reader.skip(wire_type).unwrap_err();
+        assert!(matches!(err, ParseFailure(..)));
+        let mut reader = ProtobufReader::new(b"\x08\x80");
+        assert_eq!(reader.next_field().unwrap(), Some((1, WireType::Varint)));
+        let err = reader.read_varint().unwrap_err();
         assert!(matches!(err, ParseFailure(..)));
 
-        // varint data too short (Incomplete varint)
-        let mut data = b"\x80".to_vec();
-        let err = parse_protobuf_varint(&mut data, field_number).unwrap_err();
+        // varints up to u64::MAX
+        let max = b"\xff\xff\xff\xff\xff\xff\xff\xff\xff\x01";
+        assert_eq!(ProtobufReader::new(max).read_varint().unwrap(), u64::MAX);
+        let too_long = b"\xff\xff\xff\xff\xff\xff\xff\xff\xff\x02";
+        let err = ProtobufReader::new(too_long).read_varint().unwrap_err();
         assert!(matches!(err, ParseFailure(..)));
 
-        // varint data too long
-        let mut data = b"\x80\x81\x82\x83\x84\x83\x82\x81\x80".to_vec();
-        let err = parse_protobuf_varint(&mut data, field_number).unwrap_err();
+        // groups are not supported, nor is field #0
+        let err = ProtobufReader::new(b"\x0b").next_field().unwrap_err();
+        assert!(matches!(err, ParseFailure(..)));
+        let err = ProtobufReader::new(b"\x00").next_field().unwrap_err();
         assert!(matches!(err, ParseFailure(..)));
     }
 
     #[test]
-    fn parse_protobuf_length_prefixed_tests() {
-        let field_number = 1;
-        // Single-byte length-prefixed works
-        let mut data = b"\x0a\x03abc".to_vec();
-        let res = parse_protobuf_length_prefixed(&mut data, field_number).unwrap();
-        assert_eq!(res, b"abc".to_vec());
-        assert_eq!(data, vec![0u8; 0]);
-
-        // Rest is returned
-        let mut data = b"\x0a\x03abcd".to_vec();
-        let res = parse_protobuf_length_prefixed(&mut data, field_number).unwrap();
-        assert_eq!(res, b"abc".to_vec());
-        assert_eq!(data, b"d".to_vec());
-
-        // Multi-byte length-prefixed works
-        let mut data = [b"\x0a\xac\x02", vec![65u8; 300].as_slice()]
-            .concat()
-            .to_vec();
-        let res = parse_protobuf_length_prefixed(&mut data, field_number).unwrap();
-        assert_eq!(res, vec![65u8; 300]);
-        assert_eq!(data, vec![0u8; 0]);
-
-        // Rest is returned
-        let mut data = [b"\x0a\xac\x02", vec![65u8; 300].as_slice(), b"rest"]
-            .concat()
-            .to_vec();
-        let res = parse_protobuf_length_prefixed(&mut data, field_number).unwrap();
-        assert_eq!(res, vec![65u8; 300]);
-        assert_eq!(data, b"rest");
+    fn parse_reply_undelegate_data_works() {
+        let encoded = encode(MsgUndelegateResponse {
+            completion_time: Some(ProtobufTimestamp {
+                seconds: 1_650_000_000,
+                nanos: 123,
+            }),
+        });
+        let res = parse_reply_undelegate_data(ok_reply(Some(encoded), vec![])).unwrap();
+        assert_eq!(
+            res.completion_time,
+            Timestamp::from_seconds(1_650_000_000).plus_nanos(123)
+        );
+
+        // the epoch is encoded as an empty message
+        let encoded = encode(MsgUndelegateResponse {
+            completion_time: Some(ProtobufTimestamp::default()),
+        });
+        let res = parse_undelegate_response_data(&encoded).unwrap();
+        assert_eq!(res.completion_time, Timestamp::from_seconds(0));
+
+        let err = parse_reply_undelegate_data(ok_reply(None, vec![])).unwrap_err();
+        assert_eq!(err, ParseFailure("Missing completion time".to_owned()));
+        let encoded = encode(MsgUndelegateResponse {
+            completion_time: Some(ProtobufTimestamp {
+                seconds: 1,
+                nanos: -1,
+            }),
+        });
+        let err = parse_undelegate_response_data(&encoded).unwrap_err();
+        assert_eq!(err, ParseFailure("Invalid timestamp".to_owned()));
+    }
 
-        // message too short
-        let mut data = b"\x0a\x01".to_vec();
-        let field_number = 1;
-        let err = parse_protobuf_length_prefixed(&mut data, field_number).unwrap_err();
+    #[test]
+    fn parse_reply_transfer_data_works() {
+        let encoded = encode(MsgTransferResponse { sequence: 42 });
+        let res = parse_reply_transfer_data(ok_reply(Some(encoded), vec![])).unwrap();
+        assert_eq!(res, super::MsgTransferResponse { sequence: 42 });
+
+        // unknown fields are skipped
+        let encoded = encode(MsgTransferResponseV2 {
+            sequence: 1 << 40,
+            memo: "memo".to_string(),
+            fixed: 1,
+            small: 2,
+        });
+        let res = parse_transfer_response_data(&encoded).unwrap();
+        assert_eq!(res.sequence, 1 << 40);
+
+        // a known field with an unexpected wire type fails
+        let err = parse_transfer_response_data(&encode_string("1")).unwrap_err();
         assert!(matches!(err, ParseFailure(..)));
 
-        // invalid wire type
-        let mut data = b"\x0b\x01a".to_vec();
-        let err = parse_protobuf_length_prefixed(&mut data, field_number).unwrap_err();
-        assert!(matches!(err, ParseFailure(..)));
+        let err = parse_reply_transfer_data(Reply {
+            id: 1,
+            result: SubMsgResult::Err("channel closed".to_string()),
+        })
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ParseReplyError::SubMsgFailure("channel closed".to_string())
+        );
+    }
 
-        // invalid field number
-        let field_number = 2;
-        let mut data = b"\x0a\x01a".to_vec();
-        let err = parse_protobuf_length_prefixed(&mut data, field_number).unwrap_err();
+    #[test]
+    fn parse_other_responses_works() {
+        let encoded = encode(MsgExecuteContractResponse {
+            data: vec![1, 2, 3],
+        });
+        let res = parse_reply_migrate_data(ok_reply(Some(encoded), vec![])).unwrap();
+        assert_eq!(
+            res,
+            super::MsgMigrateContractResponse {
+                data: Some(Binary(vec![1, 2, 3]))
+            }
+        );
+        let res = parse_reply_migrate_data(ok_reply(None, vec![])).unwrap();
+        assert_eq!(res, super::MsgMigrateContractResponse { data: None });
+
+        parse_send_response_data(&[]).unwrap();
+        parse_reply_send_data(ok_reply(None, vec![])).unwrap();
+        parse_delegate_response_data(&[]).unwrap();
+        parse_reply_delegate_data(ok_reply(Some(vec![]), vec![])).unwrap();
+        parse_delegate_response_data(&encode_string("unknown")).unwrap();
+        let err = parse_send_response_data(b"\x0a\x05abc").unwrap_err();
         assert!(matches!(err, ParseFailure(..)));
     }
 
     #[test]
-    fn parse_protobuf_bytes_works() {
-        let field_number = 1;
+    fn find_attribute_works() {
+        let reply = ok_reply(
+            None,
+            vec![
+                Event::new("instantiate").add_attribute("_contract_address", "contract1"),
+                Event::new("wasm")
+                    .add_attribute("_contract_address", "contract1")
+                    .add_attribute("token_id", "7"),
+                Event::new("wasm")
+                    .add_attribute("_contract_address", "contract2")
+                    .add_attribute("token_id", "8"),
+            ],
+        );
+        assert_eq!(
+            find_attribute(&reply, "instantiate", "_contract_address").unwrap(),
+            "contract1"
+        );
+        assert_eq!(find_attribute(&reply, "wasm", "token_id").unwrap(), "7");
+        assert_eq!(
+            find_attributes(&reply, "wasm", "token_id").unwrap(),
+            vec!["7", "8"]
+        );
+        assert_eq!(
+            find_attributes(&reply, "transfer", "amount").unwrap(),
+            Vec::<&str>::new()
+        );
+        let err = find_attribute(&reply, "wasm", "amount").unwrap_err();
+        assert_eq!(
+            err,
+            ParseFailure("Missing attribute amount in event wasm".to_owned())
+        );
+
+        let failed = Reply {
+            id: 1,
+            result: SubMsgResult::Err("out of gas".to_string()),
+        };
+        let err = find_attribute(&failed, "wasm", "token_id").unwrap_err();
+        assert_eq!(
+            err,
+            ParseReplyError::SubMsgFailure("out of gas".to_string())
+        );
+    }
 
-        // Empty works
-        let data = vec![];
-        let mut encoded_data = encode_bytes(&data);
+    #[test]
+    fn read_varint_works() {
+        // Single-byte varint works
+        let mut reader = ProtobufReader::new(b"\x0a");
+        assert_eq!(reader.read_varint().unwrap(), 10);
+        assert!(reader.is_empty());
 
-        let res = parse_protobuf_bytes(&mut encoded_data, field_number).unwrap();
-        assert_eq!(res, None);
+        // Rest is kept
+        let mut reader = ProtobufReader::new(b"\x0a\x0b");
+        assert_eq!(reader.read_varint().unwrap(), 10);
+        assert_eq!(reader.read_varint().unwrap(), 11);
 
-        // Simple works
-        let data = b"test".to_vec();
-        let mut encoded_data = encode_bytes(&data);
+        // Multi-byte varint works
+        // 300 % 128 = 44. 44 + 128 = 172 (0xac) (1st byte)
+        // 300 / 128 = 2 (x02) (2nd byte)
+        let mut reader = ProtobufReader::new(b"\xac\x02\x0c");
+        assert_eq!(reader.read_varint().unwrap(), 300);
+        assert_eq!(reader.read_varint().unwrap(), 12);
 
-        let res = parse_protobuf_bytes(&mut encoded_data, field_number).unwrap();
-        assert_eq!(res, Some(Binary(data)));
+        // varint data too short (Empty varint)
+        let err = ProtobufReader::new(b"").read_varint().unwrap_err();
+        assert!(matches!(err, ParseFailure(..)));
 
-        // Large works
-        let data = vec![0x40; 300];
-        let mut encoded_data = encode_bytes(&data);
+        // varint data too short (Incomplete varint)
+        let err = ProtobufReader::new(b"\x80").read_varint().unwrap_err();
+        assert!(matches!(err, ParseFailure(..)));
+
+        // varint data too long
+        let err = ProtobufReader::new(b"\x80\x81\x82\x83\x84\x83\x82\x81\x80\x80")
+            .read_varint()
+            .unwrap_err();
+        assert!(matches!(err, ParseFailure(..)));
+    }
 
-        let res = parse_protobuf_bytes(&mut encoded_data, field_number).unwrap();
-        assert_eq!(res, Some(Binary(data)));
+    #[test]
+    fn read_bytes_works() {
+        // Single-byte length-prefixed works
+        let mut reader = ProtobufReader::new(b"\x03abcd");
+        assert_eq!(reader.read_bytes().unwrap(), b"abc");
+        assert_eq!(reader.data, b"d");
 
-        // Field number works
-        let field_number = 5;
-        let data = b"test field 5".to_vec();
-        let mut encoded_data = encode_bytes(&data);
-        encoded_data[0] = (field_number << 3) + WIRE_TYPE_LENGTH_DELIMITED;
+        // Multi-byte length-prefixed works
+        let data = [b"\xac\x02", vec![65u8; 300].as_slice(), b"rest"].concat();
+        let mut reader = ProtobufReader::new(&data);
+        assert_eq!(reader.read_bytes().unwrap(), vec![65u8; 300].as_slice());
+        assert_eq!(reader.data, b"rest");
 
-        let res = parse_protobuf_bytes(&mut encoded_data, field_number).unwrap();
-        assert_eq!(res, Some(Binary(data)));
+        // message too short
+        let err = ProtobufReader::new(b"\x01").read_bytes().unwrap_err();
+        assert!(matches!(err, ParseFailure(..)));
 
-        // Remainder is kept
-        let field_number = 1;
-        let test_len: usize = 4;
-        let data = b"test_remainder".to_vec();
-        let mut encoded_data = encode_bytes(&data);
-        encoded_data[1] = test_len as u8;
+        // Large strings work
+        let data = "@".repeat(300);
+        let encoded = encode_string(&data);
+        let mut reader = ProtobufReader::new(&encoded);
+        reader.next_field().unwrap();
+        assert_eq!(reader.read_string().unwrap(), data);
 
-        let res = parse_protobuf_bytes(&mut encoded_data, field_number).unwrap();
-        assert_eq!(res, Some(Binary(data[..test_len].to_owned())));
-        assert_eq!(encoded_data, data[test_len..].to_owned());
+        // Broken utf-8 errs
+        let mut encoded = encode_string("test_X");
+        let encoded_len = encoded.len();
+        encoded[encoded_len - 1] = 0xd3;
+        let mut reader = ProtobufReader::new(&encoded);
+        reader.next_field().unwrap();
+        let err = reader.read_string().unwrap_err();
+        assert!(matches!(err, BrokenUtf8(..)));
     }
 
     #[test]
-    fn parse_protobuf_string_tests() {
-        let field_number = 1;
-
+    fn parse_execute_response_data_works() {
         // Empty works
-        let data = "";
-        let mut encoded_data = encode_string(data);
-
-        let res = parse_protobuf_string(&mut encoded_data, field_number).unwrap();
-        assert_eq!(res, data);
-
-        // Simple works
-        let data = "test";
-        let mut encoded_data = encode_string(data);
-
-        let res = parse_protobuf_string(&mut encoded_data, field_number).unwrap();
-        assert_eq!(res, data);
+        let res = parse_execute_response_data(&encode_bytes(&[])).unwrap();
+        assert_eq!(res.data, None);
+        let res = parse_execute_response_data(&[]).unwrap();
+        assert_eq!(res.data, None);
 
         // Large works
         let data = vec![0x40; 300];
-        let str_data = from_utf8(data.as_slice()).unwrap();
-        let mut encoded_data = encode_string(str_data);
-
-        let res = parse_protobuf_string(&mut encoded_data, field_number).unwrap();
-        assert_eq!(res, str_data);
+        let res = parse_execute_response_data(&encode_bytes(&data)).unwrap();
+        assert_eq!(res.data, Some(Binary(data)));
 
-        // Field number works
-        let field_number = 5;
-        let data = "test field 5";
-        let mut encoded_data = encode_string(data);
-        encoded_data[0] = (field_number << 3) + WIRE_TYPE_LENGTH_DELIMITED;
+        // unknown fields are skipped
+        let mut encoded = encode_bytes(b"test");
+        encoded.extend(encode(MsgTransferResponse { sequence: 5 }));
+        let encoded_len = encoded.len();
+        encoded[encoded_len - 2] = 2 << 3;
+        let res = parse_execute_response_data(&encoded).unwrap();
+        assert_eq!(res.data, Some(Binary(b"test".to_vec())));
 
-        let res = parse_protobuf_string(&mut encoded_data, field_number).unwrap();
-        assert_eq!(res, data);
+        // invalid wire type
+        let err = parse_execute_response_data(b"\x0b\x01a").unwrap_err();
+        assert!(matches!(err, ParseFailure(..)));
+        let err = parse_execute_response_data(b"\x08\x01").unwrap_err();
+        assert!(matches!(err, ParseFailure(..)));
 
-        // Remainder is kept
-        let field_number = 1;
-        let test_len: usize = 4;
-        let data = "test_remainder";
-        let mut encoded_data = encode_string(data);
-        encoded_data[1] = test_len as u8;
+        // message too short
+        let err = parse_execute_response_data(b"\x0a\x05abc").unwrap_err();
+        assert!(matches!(err, ParseFailure(..)));
+    }
 
-        let res = parse_protobuf_string(&mut encoded_data, field_number).unwrap();
-        assert_eq!(res, data[..test_len]);
-        assert_eq!(encoded_data, data[test_len..].as_bytes());
+    #[test]
+    fn parse_instantiate_response_data_works() {
+        // fields can come in any order
+        let mut encoded = encode_bytes(b"data");
+        encoded[0] = (2 << 3) | 2;
+        encoded.extend(encode_string("contract"));
+        let res = parse_instantiate_response_data(&encoded).unwrap();
+        assert_eq!(
+            res,
+            super::MsgInstantiateContractResponse {
+                contract_address: "contract".to_string(),
+                data: Some(Binary(b"data".to_vec())),
+            }
+        );
 
         // Broken utf-8 errs
-        let field_number = 1;
-        let data = "test_X";
-        let mut encoded_data = encode_string(data);
-        let encoded_len = encoded_data.len();
-        encoded_data[encoded_len - 1] = 0xd3;
-        let err = parse_protobuf_string(&mut encoded_data, field_number).unwrap_err();
+        let mut encoded = encode_string("test_X");
+        let encoded_len = encoded.len();
+        encoded[encoded_len - 1] = 0xd3;
+        let err = parse_instantiate_response_data(&encoded).unwrap_err();
         assert!(matches!(err, BrokenUtf8(..)));
     }
 
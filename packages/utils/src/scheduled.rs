@@ -9,7 +9,7 @@ use std::ops::Add;
 
 #[derive(Serialize, Deserialize, Clone, Copy, PartialEq, JsonSchema, Debug)]
 #[serde(rename_all = "snake_case")]
-/// Scheduled represents a point in time when an event happens.
+/// Scheduled represents a point in time when an event happens, or a series of them.
 /// It can compare with a BlockInfo and will return is_triggered() == true
 /// once the condition is hit (and for every block in the future)
 pub enum Scheduled {
@@ -17,6 +17,16 @@ pub enum Scheduled {
     AtHeight(u64),
     /// AtTime will schedule when `env.block.time` >= time
     AtTime(Timestamp),
+    /// Recurring will schedule at `start`, then every `interval`.
+    /// `start` and `end` are block heights if `interval` is a `Duration::Height`, or seconds if
+    /// it is a `Duration::Time`. There are no occurrences after `end` (if set), nor more than
+    /// `max_occurrences` (if set).
+    Recurring {
+        start: u64,
+        interval: Duration,
+        end: Option<u64>,
+        max_occurrences: Option<u64>,
+    },
 }
 
 impl fmt::Display for Scheduled {
@@ -24,6 +34,9 @@ impl fmt::Display for Scheduled {
         match self {
             Scheduled::AtHeight(height) => write!(f, "scheduled height: {}", height),
             Scheduled::AtTime(time) => write!(f, "scheduled time: {}", time),
+            Scheduled::Recurring {
+                start, interval, ..
+            } => write!(f, "scheduled every {} from {}", interval, start),
         }
     }
 }
@@ -34,8 +47,164 @@ impl Scheduled {
         match self {
             Scheduled::AtHeight(height) => block.height >= *height,
             Scheduled::AtTime(time) => block.time >= *time,
+            Scheduled::Recurring { start, .. } => self.point(block) >= *start,
         }
     }
+
+    /// Checks a recurring schedule has a non zero interval and does not end before it starts
+    pub fn validate(&self) -> StdResult<()> {
+        if let Scheduled::Recurring {
+            start,
+            interval,
+            end,
+            ..
+        } = self
+        {
+            if interval_length(interval) == 0 {
+                return Err(StdError::generic_err("Recurring interval cannot be zero"));
+            }
+            if let Some(end) = end {
+                if end < start {
+                    return Err(StdError::generic_err("Recurring end is before start"));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the next trigger point strictly after the given block,
+    /// or `None` if the schedule will not trigger anymore
+    pub fn next_after(&self, block: &BlockInfo) -> Option<Scheduled> {
+        let passed = self.occurrences_until(self.point(block));
+        let point = self.occurrence(passed)?;
+        if self.is_height() {
+            Some(Scheduled::AtHeight(point))
+        } else {
+            Some(Scheduled::AtTime(Timestamp::from_seconds(point)))
+        }
+    }
+
+    /// Returns how many times the schedule triggers after `from` and up to `to` (included)
+    pub fn occurrences_between(&self, from: &BlockInfo, to: &BlockInfo) -> u64 {
+        self.occurrences_until(self.point(to))
+            .saturating_sub(self.occurrences_until(self.point(from)))
+    }
+
+    /// Returns how many times the schedule triggered after `last_run` and up to the current
+    /// block, or since the beginning if it never ran. Calling it on every run, passing the
+    /// block of the previous run, counts each occurrence exactly once.
+    pub fn missed_since(&self, last_run: Option<&BlockInfo>, block: &BlockInfo) -> u64 {
+        match last_run {
+            Some(last_run) => self.occurrences_between(last_run, block),
+            None => self.occurrences_until(self.point(block)),
+        }
+    }
+
+    /// Whether the schedule is measured in block heights (or else in seconds)
+    pub(crate) fn is_height(&self) -> bool {
+        matches!(
+            self,
+            Scheduled::AtHeight(_)
+                | Scheduled::Recurring {
+                    interval: Duration::Height(_),
+                    ..
+                }
+        )
+    }
+
+    /// Returns the block height or time in seconds, whichever the schedule is measured in
+    pub(crate) fn point(&self, block: &BlockInfo) -> u64 {
+        if self.is_height() {
+            block.height
+        } else {
+            block.time.seconds()
+        }
+    }
+
+    /// The first trigger point
+    pub(crate) fn start_point(&self) -> u64 {
+        match self {
+            Scheduled::AtHeight(height) => *height,
+            Scheduled::AtTime(time) => time.seconds(),
+            Scheduled::Recurring { start, .. } => *start,
+        }
+    }
+
+    // number of occurrences at or before point
+    fn occurrences_until(&self, point: u64) -> u64 {
+        match self {
+            Scheduled::AtHeight(_) | Scheduled::AtTime(_) => {
+                if point >= self.start_point() {
+                    1
+                } else {
+                    0
+                }
+            }
+            Scheduled::Recurring {
+                start,
+                interval,
+                end,
+                max_occurrences,
+            } => {
+                let point = match end {
+                    Some(end) => point.min(*end),
+                    None => point,
+                };
+                if point < *start {
+                    return 0;
+                }
+                let count = match interval_length(interval) {
+                    0 => 1,
+                    length => (point - start) / length + 1,
+                };
+                match max_occurrences {
+                    Some(max) => count.min(*max),
+                    None => count,
+                }
+            }
+        }
+    }
+
+    // point of the n-th occurrence (from 0), if there is one
+    fn occurrence(&self, n: u64) -> Option<u64> {
+        match self {
+            Scheduled::AtHeight(_) | Scheduled::AtTime(_) => {
+                if n == 0 {
+                    Some(self.start_point())
+                } else {
+                    None
+                }
+            }
+            Scheduled::Recurring {
+                start,
+                interval,
+                end,
+                max_occurrences,
+            } => {
+                if let Some(max) = max_occurrences {
+                    if n >= *max {
+                        return None;
+                    }
+                }
+                let length = interval_length(interval);
+                if length == 0 && n > 0 {
+                    return None;
+                }
+                let point = length.checked_mul(n)?.checked_add(*start)?;
+                match end {
+                    Some(end) if point > *end => None,
+                    _ => Some(point),
+                }
+            }
+        }
+    }
+}
+
+fn interval_length(interval: &Duration) -> u64 {
+    match interval {
+        Duration::Height(height) => *height,
+        Duration::Time(time) => *time,
+    }
 }
 
 impl Add<Duration> for Scheduled {
@@ -47,6 +216,23 @@ impl Add<Duration> for Scheduled {
                 Ok(Scheduled::AtTime(t.plus_seconds(delta)))
             }
             (Scheduled::AtHeight(h), Duration::Height(delta)) => Ok(Scheduled::AtHeight(h + delta)),
+            (
+                Scheduled::Recurring {
+                    start,
+                    interval,
+                    end,
+                    max_occurrences,
+                },
+                _,
+            ) if self.is_height() == matches!(duration, Duration::Height(_)) => {
+                let delta = interval_length(&duration);
+                Ok(Scheduled::Recurring {
+                    start: start + delta,
+                    interval,
+                    end: end.map(|end| end + delta),
+                    max_occurrences,
+                })
+            }
             _ => Err(StdError::generic_err("Cannot add height and time")),
         }
     }
@@ -114,4 +300,143 @@ mod test {
         let end = Scheduled::AtHeight(12345) + Duration::Time(1500);
         end.unwrap_err();
     }
+
+    fn at_height(height: u64) -> BlockInfo {
+        let mut block = cosmwasm_std::testing::mock_env().block;
+        block.height = height;
+        block
+    }
+
+    fn at_time(seconds: u64) -> BlockInfo {
+        let mut block = cosmwasm_std::testing::mock_env().block;
+        block.time = Timestamp::from_seconds(seconds);
+        block
+    }
+
+    #[test]
+    fn recurring_schedule() {
+        let every_ten = Scheduled::Recurring {
+            start: 100,
+            interval: Duration::Height(10),
+            end: Some(135),
+            max_occurrences: None,
+        };
+        every_ten.validate().unwrap();
+        assert!(!every_ten.is_triggered(&at_height(99)));
+        assert!(every_ten.is_triggered(&at_height(100)));
+
+        assert_eq!(
+            every_ten.next_after(&at_height(0)),
+            Some(Scheduled::AtHeight(100))
+        );
+        assert_eq!(
+            every_ten.next_after(&at_height(100)),
+            Some(Scheduled::AtHeight(110))
+        );
+        assert_eq!(
+            every_ten.next_after(&at_height(125)),
+            Some(Scheduled::AtHeight(130))
+        );
+        assert_eq!(every_ten.next_after(&at_height(130)), None);
+
+        // (from, to]
+        assert_eq!(
+            every_ten.occurrences_between(&at_height(0), &at_height(99)),
+            0
+        );
+        assert_eq!(
+            every_ten.occurrences_between(&at_height(0), &at_height(100)),
+            1
+        );
+        assert_eq!(
+            every_ten.occurrences_between(&at_height(100), &at_height(120)),
+            2
+        );
+        assert_eq!(
+            every_ten.occurrences_between(&at_height(101), &at_height(500)),
+            3
+        );
+        assert_eq!(
+            every_ten.occurrences_between(&at_height(120), &at_height(110)),
+            0
+        );
+
+        let limited = Scheduled::Recurring {
+            start: 1000,
+            interval: Duration::Time(60),
+            end: None,
+            max_occurrences: Some(3),
+        };
+        assert_eq!(limited.occurrences_between(&at_time(0), &at_time(5000)), 3);
+        assert_eq!(
+            limited.next_after(&at_time(1060)),
+            Some(Scheduled::AtTime(Timestamp::from_seconds(1120)))
+        );
+        assert_eq!(limited.next_after(&at_time(1120)), None);
+
+        // single trigger points are one occurrence
+        let once = Scheduled::AtHeight(50);
+        assert_eq!(once.next_after(&at_height(49)), Some(once));
+        assert_eq!(once.next_after(&at_height(50)), None);
+        assert_eq!(once.occurrences_between(&at_height(10), &at_height(60)), 1);
+        assert_eq!(once.occurrences_between(&at_height(50), &at_height(60)), 0);
+
+        let shifted = limited + Duration::Time(40);
+        assert_eq!(
+            shifted.unwrap(),
+            Scheduled::Recurring {
+                start: 1040,
+                interval: Duration::Time(60),
+                end: None,
+                max_occurrences: Some(3),
+            }
+        );
+        (every_ten + Duration::Time(40)).unwrap_err();
+    }
+
+    #[test]
+    fn catch_up_missed_occurrences() {
+        let payroll = Scheduled::Recurring {
+            start: 1000,
+            interval: Duration::Time(100),
+            end: None,
+            max_occurrences: None,
+        };
+        assert_eq!(payroll.missed_since(None, &at_time(999)), 0);
+        assert_eq!(payroll.missed_since(None, &at_time(1250)), 3);
+
+        // however the runs are spread, every occurrence is counted once
+        let runs = [1000, 1001, 1099, 1100, 1450, 1450, 1999, 2000];
+        let mut last_run = None;
+        let mut paid = 0;
+        for run in runs.iter().map(|t| at_time(*t)) {
+            paid += payroll.missed_since(last_run.as_ref(), &run);
+            last_run = Some(run);
+        }
+        assert_eq!(paid, payroll.missed_since(None, &at_time(2000)));
+        assert_eq!(paid, 11);
+    }
+
+    #[test]
+    fn invalid_recurring_schedule() {
+        let zero = Scheduled::Recurring {
+            start: 10,
+            interval: Duration::Height(0),
+            end: None,
+            max_occurrences: None,
+        };
+        zero.validate().unwrap_err();
+        // still does not trigger forever
+        assert_eq!(zero.occurrences_between(&at_height(0), &at_height(100)), 1);
+        assert_eq!(zero.next_after(&at_height(10)), None);
+
+        let ends_early = Scheduled::Recurring {
+            start: 10,
+            interval: Duration::Height(5),
+            end: Some(9),
+            max_occurrences: None,
+        };
+        ends_early.validate().unwrap_err();
+        Scheduled::AtHeight(5).validate().unwrap();
+    }
 }
@@ -27,6 +27,20 @@ pub enum Threshold {
     /// for the vote to be considered at all.
     /// See `ThresholdResponse.ThresholdQuorum` in the cw3 spec for details.
     ThresholdQuorum { threshold: Decimal, quorum: Decimal },
+
+    /// Like `ThresholdQuorum`, but the proposal is rejected if `veto_threshold` of the votes
+    /// cast are Veto, whatever the other votes.
+    /// See `ThresholdResponse.ThresholdQuorumVeto` in the cw3 spec for details.
+    ThresholdQuorumVeto {
+        threshold: Decimal,
+        quorum: Decimal,
+        veto_threshold: Decimal,
+    },
+
+    /// Declares a `quorum` of the total votes that must participate in the election, of which
+    /// two thirds (ignoring Abstain) must be Yes votes.
+    /// See `ThresholdResponse.Supermajority` in the cw3 spec for details.
+    Supermajority { quorum: Decimal },
 }
 
 impl Threshold {
@@ -55,9 +69,24 @@ impl Threshold {
                 valid_threshold(threshold)?;
                 valid_quorum(quroum)
             }
+            Threshold::ThresholdQuorumVeto {
+                threshold,
+                quorum,
+                veto_threshold,
+            } => {
+                valid_threshold(threshold)?;
+                valid_quorum(quorum)?;
+                valid_veto_threshold(veto_threshold)
+            }
+            Threshold::Supermajority { quorum } => valid_quorum(quorum),
         }
     }
 
+    /// The share of Yes votes (ignoring Abstain) needed by a `Supermajority`
+    pub fn supermajority() -> Decimal {
+        Decimal::from_ratio(2u128, 3u128)
+    }
+
     /// Creates a response from the saved data, just missing the total_weight info
     pub fn to_response(&self, total_weight: u64) -> ThresholdResponse {
         match self.clone() {
@@ -76,6 +105,20 @@ impl Threshold {
                     total_weight,
                 }
             }
+            Threshold::ThresholdQuorumVeto {
+                threshold,
+                quorum,
+                veto_threshold,
+            } => ThresholdResponse::ThresholdQuorumVeto {
+                threshold,
+                quorum,
+                veto_threshold,
+                total_weight,
+            },
+            Threshold::Supermajority { quorum } => ThresholdResponse::Supermajority {
+                quorum,
+                total_weight,
+            },
         }
     }
 }
@@ -100,6 +143,15 @@ fn valid_quorum(percent: &Decimal) -> Result<(), ThresholdError> {
     }
 }
 
+/// Asserts that 0 < percent <= 1.0
+fn valid_veto_threshold(percent: &Decimal) -> Result<(), ThresholdError> {
+    if percent.is_zero() || *percent > Decimal::one() {
+        Err(ThresholdError::InvalidVetoThreshold {})
+    } else {
+        Ok(())
+    }
+}
+
 /// This defines the different ways tallies can happen.
 /// Every contract should support a subset of these, ideally all.
 ///
@@ -167,6 +219,32 @@ pub enum ThresholdResponse {
         quorum: Decimal,
         total_weight: u64,
     },
+
+    /// Tallies like `ThresholdQuorum`, but gives a blocking minority the power to veto.
+    /// If Veto votes reach `veto_threshold` of all the votes cast (including Abstain), the
+    /// proposal is rejected, even if there are enough Yes votes.
+    ///
+    /// Such a proposal can only pass early if no remaining votes could veto it, that is, if
+    /// `veto_threshold` of the total weight cannot be reached even if all voters who have not
+    /// voted yet cast Veto. It is rejected early as soon as the Veto votes reach
+    /// `veto_threshold` of the total weight.
+    ///
+    /// With `veto_threshold` set to 33.4%, 60 Yes, 5 No, and 35 Veto out of 100 is rejected,
+    /// even if `threshold` is only 50%.
+    ThresholdQuorumVeto {
+        threshold: Decimal,
+        quorum: Decimal,
+        veto_threshold: Decimal,
+        total_weight: u64,
+    },
+
+    /// Tallies like `ThresholdQuorum` with a fixed threshold of two thirds: a proposal passes
+    /// if `quorum` of the total weight participates, and at least two thirds of
+    /// `Yes + No + Veto` are Yes votes.
+    ///
+    /// This is meant for changes that need broad support, like updating the configuration
+    /// or the members of a group.
+    Supermajority { quorum: Decimal, total_weight: u64 },
 }
 
 #[derive(Error, Debug, PartialEq)]
@@ -183,6 +261,9 @@ pub enum ThresholdError {
     #[error("Not possible to reach required quorum threshold")]
     UnreachableQuorumThreshold {},
 
+    #[error("Invalid veto threshold percentage, must be in the 0.0-1.0 range (and not zero)")]
+    InvalidVetoThreshold {},
+
     #[error("Required weight cannot be zero")]
     ZeroWeight {},
 
@@ -297,6 +378,59 @@ mod tests {
             err.to_string(),
             ThresholdError::ZeroQuorumThreshold {}.to_string()
         );
+
+        // Veto adds a valid veto threshold to ThresholdQuorum
+        Threshold::ThresholdQuorumVeto {
+            threshold: Decimal::percent(51),
+            quorum: Decimal::percent(40),
+            veto_threshold: Decimal::permille(334),
+        }
+        .validate(5)
+        .unwrap();
+        Threshold::ThresholdQuorumVeto {
+            threshold: Decimal::percent(51),
+            quorum: Decimal::percent(40),
+            veto_threshold: Decimal::one(),
+        }
+        .validate(5)
+        .unwrap();
+        let err = Threshold::ThresholdQuorumVeto {
+            threshold: Decimal::percent(51),
+            quorum: Decimal::percent(40),
+            veto_threshold: Decimal::zero(),
+        }
+        .validate(5)
+        .unwrap_err();
+        assert_eq!(err, ThresholdError::InvalidVetoThreshold {});
+        let err = Threshold::ThresholdQuorumVeto {
+            threshold: Decimal::percent(51),
+            quorum: Decimal::percent(40),
+            veto_threshold: Decimal::percent(101),
+        }
+        .validate(5)
+        .unwrap_err();
+        assert_eq!(err, ThresholdError::InvalidVetoThreshold {});
+        let err = Threshold::ThresholdQuorumVeto {
+            threshold: Decimal::percent(40),
+            quorum: Decimal::percent(40),
+            veto_threshold: Decimal::percent(50),
+        }
+        .validate(5)
+        .unwrap_err();
+        assert_eq!(err, ThresholdError::InvalidThreshold {});
+
+        // Supermajority only has a quorum
+        Threshold::Supermajority {
+            quorum: Decimal::percent(20),
+        }
+        .validate(5)
+        .unwrap();
+        let err = Threshold::Supermajority {
+            quorum: Decimal::percent(120),
+        }
+        .validate(5)
+        .unwrap_err();
+        assert_eq!(err, ThresholdError::UnreachableQuorumThreshold {});
     }
 
     #[test]
@@ -337,5 +471,33 @@ mod tests {
                 total_weight
             }
         );
+
+        let res = Threshold::ThresholdQuorumVeto {
+            threshold: Decimal::percent(51),
+            quorum: Decimal::percent(30),
+            veto_threshold: Decimal::percent(33),
+        }
+        .to_response(total_weight);
+        assert_eq!(
+            res,
+            ThresholdResponse::ThresholdQuorumVeto {
+                threshold: Decimal::percent(51),
+                quorum: Decimal::percent(30),
+                veto_threshold: Decimal::percent(33),
+                total_weight
+            }
+        );
+
+        let res = Threshold::Supermajority {
+            quorum: Decimal::percent(30),
+        }
+        .to_response(total_weight);
+        assert_eq!(
+            res,
+            ThresholdResponse::Supermajority {
+                quorum: Decimal::percent(30),
+                total_weight
+            }
+        );
     }
 }
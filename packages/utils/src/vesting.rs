@@ -0,0 +1,287 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{BlockInfo, Uint128};
+
+use crate::curve::{Curve, CurveError, PiecewiseLinear};
+use crate::{Duration, Scheduled};
+
+/// VestingSchedule describes how an amount gets unlocked over time.
+///
+/// The schedule is measured in block heights if `start` is `Scheduled::AtHeight`, or in seconds
+/// if it is `Scheduled::AtTime`. Its durations must use the same unit. A recurring `start` only
+/// counts with its first occurrence.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum VestingSchedule {
+    /// `amount` vests linearly from `start` to `start + duration`. Nothing is unlocked before
+    /// `start + cliff`, where everything vested so far is unlocked at once.
+    Linear {
+        amount: Uint128,
+        start: Scheduled,
+        duration: Duration,
+        cliff: Option<Duration>,
+    },
+    /// `amount` vests in `tranches` equal parts, one every `period` after `start`.
+    /// The last tranche gets the rounding remainder.
+    Periodic {
+        amount: Uint128,
+        start: Scheduled,
+        period: Duration,
+        tranches: u32,
+    },
+}
+
+impl VestingSchedule {
+    /// Returns the amount unlocked at the given block
+    pub fn value_at(&self, block: &BlockInfo) -> Result<Uint128, CurveError> {
+        Ok(self.to_curve()?.value(self.point(block)))
+    }
+
+    /// Returns the amount still locked at the given block
+    pub fn locked_at(&self, block: &BlockInfo) -> Result<Uint128, CurveError> {
+        Ok(self.amount() - self.value_at(block)?)
+    }
+
+    /// Total amount vested by this schedule
+    pub fn amount(&self) -> Uint128 {
+        match self {
+            VestingSchedule::Linear { amount, .. } | VestingSchedule::Periodic { amount, .. } => {
+                *amount
+            }
+        }
+    }
+
+    /// Returns the x value of the block on the axis of this schedule
+    pub fn point(&self, block: &BlockInfo) -> u64 {
+        self.start().point(block)
+    }
+
+    /// Returns whether the other schedule is measured in the same unit,
+    /// so their curves can be combined
+    pub fn same_unit(&self, other: &VestingSchedule) -> bool {
+        self.start().is_height() == other.start().is_height()
+    }
+
+    /// Checks the durations use the unit of `start`, and the schedule does not overflow
+    pub fn validate(&self) -> Result<(), CurveError> {
+        self.to_curve()?.validate()
+    }
+
+    /// Returns the (monotonic increasing) curve of the unlocked amount over the axis of this
+    /// schedule. Curves of schedules in the same unit can be added up, or subtracted from
+    /// each other.
+    pub fn to_curve(&self) -> Result<Curve, CurveError> {
+        let start = self.start().start_point();
+        match self {
+            VestingSchedule::Linear {
+                amount,
+                duration,
+                cliff,
+                ..
+            } => {
+                let duration = self.length(duration)?;
+                let end = start.checked_add(duration).ok_or(CurveError::Overflow {})?;
+                let cliff = match cliff {
+                    Some(cliff) => self.length(cliff)?,
+                    None => 0,
+                };
+                if cliff == 0 {
+                    return Ok(Curve::PiecewiseLinear(PiecewiseLinear {
+                        steps: vec![(start, Uint128::zero()), (end, *amount)],
+                    }));
+                }
+                if cliff > duration {
+                    return Err(CurveError::UnorderedSteps {});
+                }
+                // start + cliff <= end, cannot overflow
+                let unlocked = amount.multiply_ratio(cliff, duration);
+                let mut steps = vec![(start + cliff, Uint128::zero()), (start + cliff, unlocked)];
+                if cliff < duration {
+                    steps.push((end, *amount));
+                }
+                Ok(Curve::PiecewiseLinear(PiecewiseLinear { steps }))
+            }
+            VestingSchedule::Periodic {
+                amount,
+                period,
+                tranches,
+                ..
+            } => {
+                if *tranches == 0 {
+                    return Err(CurveError::MissingSteps {});
+                }
+                let period = self.length(period)?;
+                let mut steps = vec![(start, Uint128::zero())];
+                for i in 1..=*tranches {
+                    let x = period
+                        .checked_mul(i as u64)
+                        .and_then(|offset| start.checked_add(offset))
+                        .ok_or(CurveError::Overflow {})?;
+                    let before = steps[steps.len() - 1].1;
+                    let after = amount.multiply_ratio(i, *tranches);
+                    steps.push((x, before));
+                    steps.push((x, after));
+                }
+                Ok(Curve::PiecewiseLinear(PiecewiseLinear { steps }))
+            }
+        }
+    }
+
+    fn start(&self) -> &Scheduled {
+        match self {
+            VestingSchedule::Linear { start, .. } | VestingSchedule::Periodic { start, .. } => {
+                start
+            }
+        }
+    }
+
+    // the duration in the unit of the schedule
+    fn length(&self, duration: &Duration) -> Result<u64, CurveError> {
+        match (self.start().is_height(), duration) {
+            (true, Duration::Height(h)) => Ok(*h),
+            (false, Duration::Time(t)) => Ok(*t),
+            _ => Err(CurveError::MismatchedUnits {}),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::testing::mock_env;
+    use cosmwasm_std::Timestamp;
+
+    fn at_height(height: u64) -> BlockInfo {
+        let mut block = mock_env().block;
+        block.height = height;
+        block
+    }
+
+    fn at_time(seconds: u64) -> BlockInfo {
+        let mut block = mock_env().block;
+        block.time = Timestamp::from_seconds(seconds);
+        block
+    }
+
+    #[test]
+    fn linear_with_cliff() {
+        let schedule = VestingSchedule::Linear {
+            amount: Uint128::new(1200),
+            start: Scheduled::AtTime(Timestamp::from_seconds(1000)),
+            duration: Duration::Time(1200),
+            cliff: Some(Duration::Time(300)),
+        };
+        schedule.validate().unwrap();
+        let value = |t| schedule.value_at(&at_time(t)).unwrap().u128();
+        assert_eq!(value(0), 0);
+        assert_eq!(value(1299), 0);
+        assert_eq!(value(1300), 300);
+        assert_eq!(value(1600), 600);
+        assert_eq!(value(2200), 1200);
+        assert_eq!(value(5000), 1200);
+        assert_eq!(
+            schedule.locked_at(&at_time(1600)).unwrap(),
+            Uint128::new(600)
+        );
+
+        let no_cliff = VestingSchedule::Linear {
+            amount: Uint128::new(100),
+            start: Scheduled::AtHeight(10),
+            duration: Duration::Height(10),
+            cliff: None,
+        };
+        assert_eq!(no_cliff.value_at(&at_height(15)).unwrap().u128(), 50);
+    }
+
+    #[test]
+    fn periodic_tranches() {
+        let schedule = VestingSchedule::Periodic {
+            amount: Uint128::new(100),
+            start: Scheduled::AtHeight(100),
+            period: Duration::Height(10),
+            tranches: 3,
+        };
+        let value = |h| schedule.value_at(&at_height(h)).unwrap().u128();
+        assert_eq!(value(100), 0);
+        assert_eq!(value(109), 0);
+        assert_eq!(value(110), 33);
+        assert_eq!(value(119), 33);
+        assert_eq!(value(120), 66);
+        assert_eq!(value(130), 100);
+        assert_eq!(value(1000), 100);
+        schedule
+            .to_curve()
+            .unwrap()
+            .validate_monotonic_increasing()
+            .unwrap();
+    }
+
+    #[test]
+    fn invalid_schedules() {
+        let mismatched = VestingSchedule::Linear {
+            amount: Uint128::new(100),
+            start: Scheduled::AtHeight(10),
+            duration: Duration::Time(10),
+            cliff: None,
+        };
+        assert_eq!(mismatched.validate(), Err(CurveError::MismatchedUnits {}));
+
+        let long_cliff = VestingSchedule::Linear {
+            amount: Uint128::new(100),
+            start: Scheduled::AtHeight(10),
+            duration: Duration::Height(10),
+            cliff: Some(Duration::Height(11)),
+        };
+        assert_eq!(long_cliff.validate(), Err(CurveError::UnorderedSteps {}));
+
+        let overflow = VestingSchedule::Periodic {
+            amount: Uint128::new(100),
+            start: Scheduled::AtHeight(10),
+            period: Duration::Height(u64::MAX / 2),
+            tranches: 3,
+        };
+        assert_eq!(overflow.validate(), Err(CurveError::Overflow {}));
+    }
+
+    #[test]
+    fn combine_schedules() {
+        let team = VestingSchedule::Linear {
+            amount: Uint128::new(1000),
+            start: Scheduled::AtHeight(0),
+            duration: Duration::Height(100),
+            cliff: Some(Duration::Height(50)),
+        };
+        let bonus = VestingSchedule::Periodic {
+            amount: Uint128::new(100),
+            start: Scheduled::AtHeight(0),
+            period: Duration::Height(40),
+            tranches: 2,
+        };
+        assert!(team.same_unit(&bonus));
+        let total = team
+            .to_curve()
+            .unwrap()
+            .checked_add(&bonus.to_curve().unwrap())
+            .unwrap();
+        assert_eq!(total.value(39).u128(), 0);
+        assert_eq!(total.value(40).u128(), 50);
+        assert_eq!(total.value(50).u128(), 550);
+        assert_eq!(total.value(80).u128(), 900);
+        assert_eq!(total.value(100).u128(), 1100);
+
+        // taking one schedule out again
+        let without_bonus = total.checked_sub(&bonus.to_curve().unwrap()).unwrap();
+        for h in [0, 40, 49, 50, 80, 100, 200] {
+            assert_eq!(
+                without_bonus.value(h),
+                team.value_at(&at_height(h)).unwrap()
+            );
+        }
+        assert_eq!(
+            bonus.to_curve().unwrap().checked_sub(&total),
+            Err(CurveError::Underflow {})
+        );
+    }
+}